@@ -1,12 +1,17 @@
-use std::{rc::Rc, time::Instant};
+use std::{collections::BTreeMap, process::Command, rc::Rc, time::Instant};
 
+use camino::{Utf8Path, Utf8PathBuf};
+use ecow::{EcoString, eco_format};
 use gleam_core::{
-    Result,
-    build::{Built, Codegen, NullTelemetry, Options, ProjectCompiler, Telemetry},
-    manifest::Manifest,
+    Error, Result,
+    build::{Built, Codegen, NullTelemetry, Options, ProjectCompiler, Target, Telemetry},
+    line_numbers::LineNumbers,
+    manifest::{Manifest, ManifestPackage},
+    parse::{lexer, token::Token},
     paths::ProjectPaths,
-    warning::WarningEmitterIO,
+    warning::{NullWarningEmitterIO, WarningEmitterIO},
 };
+use itertools::Itertools;
 
 use crate::{
     build_lock::BuildLock,
@@ -28,6 +33,34 @@ pub(crate) fn main_with_warnings(
     options: Options,
     manifest: Manifest,
     warnings: Rc<dyn WarningEmitterIO>,
+) -> Result<Built> {
+    let dependency_cache = dependency_cache(std::env::var_os(
+        DISABLE_DEPENDENCY_CACHE_ENVIRONMENT_VARIABLE,
+    ));
+    main_with_dependency_cache(paths, options, manifest, warnings, dependency_cache)
+}
+
+/// Setting this stops the compiled artefacts of Hex dependencies from being
+/// shared with other projects, so they are always compiled in the project.
+const DISABLE_DEPENDENCY_CACHE_ENVIRONMENT_VARIABLE: &str = "GLEAM_NO_DEPENDENCY_CACHE";
+
+/// The dependency cache to use, which is the global one unless disabled by
+/// the `GLEAM_NO_DEPENDENCY_CACHE` environment variable having been set.
+fn dependency_cache(disabled: Option<std::ffi::OsString>) -> Option<Utf8PathBuf> {
+    match disabled {
+        Some(_) => None,
+        None => Some(gleam_core::paths::global_dependency_cache()),
+    }
+}
+
+/// Builds the project, copying the compiled artefacts of Hex dependencies
+/// from and to the given dependency cache if there is one.
+fn main_with_dependency_cache(
+    paths: &ProjectPaths,
+    options: Options,
+    manifest: Manifest,
+    warnings: Rc<dyn WarningEmitterIO>,
+    dependency_cache: Option<Utf8PathBuf>,
 ) -> Result<Built> {
     let perform_codegen = options.codegen;
     let root_config = crate::config::root_config(paths)?;
@@ -38,16 +71,23 @@ pub(crate) fn main_with_warnings(
     };
     let io = fs::ProjectIO::new();
     let start = Instant::now();
-    let lock = BuildLock::new_target(
-        paths,
-        options.mode,
-        options.target.unwrap_or(root_config.target),
-    )?;
+    let target = options.target.unwrap_or(root_config.target);
+    let lock = BuildLock::new_target(paths, options.mode, target)?;
+
+    // Erlang dependencies are only shared with builds using the same versions
+    // of Erlang and Elixir, so if they can't be found the cache isn't used.
+    let (dependency_cache, dependency_cache_runtime) = match (dependency_cache, target) {
+        (Some(cache), Target::Erlang) => match erlang_runtime_versions(&manifest.packages) {
+            Some(runtime) => (Some(cache), runtime),
+            None => (None, EcoString::new()),
+        },
+        (cache, _) => (cache, EcoString::new()),
+    };
 
     tracing::info!("Compiling packages");
     let result = {
         let _guard = lock.lock(telemetry);
-        let compiler = ProjectCompiler::new(
+        let mut compiler = ProjectCompiler::new(
             root_config,
             options,
             manifest.packages,
@@ -56,6 +96,8 @@ pub(crate) fn main_with_warnings(
             paths.clone(),
             io,
         );
+        compiler.dependency_cache = dependency_cache;
+        compiler.dependency_cache_runtime = dependency_cache_runtime;
         compiler.compile()?
     };
 
@@ -66,3 +108,485 @@ pub(crate) fn main_with_warnings(
 
     Ok(result)
 }
+
+/// The versions of Erlang/OTP and, if any of the packages is built with Mix,
+/// of Elixir that are installed, or `None` if they can't be found.
+fn erlang_runtime_versions(packages: &[ManifestPackage]) -> Option<EcoString> {
+    let erlang = command_output(Command::new("erl").args([
+        "-noshell",
+        "-eval",
+        "io:put_chars([erlang:system_info(otp_release), \" \", erlang:system_info(version)]), halt().",
+    ]))?;
+    if !packages
+        .iter()
+        .any(|package| package.build_tools.iter().any(|tool| tool == "mix"))
+    {
+        return Some(eco_format!("erlang {erlang}"));
+    }
+    let elixir = command_output(Command::new("elixir").args(["-e", "IO.write(System.version())"]))?;
+    Some(eco_format!("erlang {erlang} elixir {elixir}"))
+}
+
+fn command_output(command: &mut Command) -> Option<String> {
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok()
+}
+
+/// Builds the project twice from scratch, checking that the manifest and the
+/// generated code are byte-for-byte the same each time.
+///
+/// The manifest is read back from disc and written again for each build so
+/// that any ordering that depends on hash map iteration shows up as a
+/// difference. Compiled BEAM bytecode and the compiler's caches are not
+/// compared.
+pub fn check_determinism(paths: &ProjectPaths, options: Options) -> Result<()> {
+    let target = match options.target {
+        Some(target) => target,
+        None => crate::config::root_config(paths)?.target,
+    };
+    let output_directory = paths.build_directory_for_target(options.mode, target);
+    let first = build_from_scratch(paths, &options, &output_directory)?;
+    let second = build_from_scratch(paths, &options, &output_directory)?;
+
+    let files = first
+        .keys()
+        .chain(second.keys())
+        .unique()
+        .filter(|path| first.get(*path) != second.get(*path))
+        .map(|path| {
+            path.strip_prefix(paths.root())
+                .unwrap_or(path)
+                .to_path_buf()
+        })
+        .collect_vec();
+    if files.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::NonDeterministicBuild { files })
+    }
+}
+
+/// Fails if any module in the project's `src` directory still contains a
+/// `todo`, or a `panic` if `include_panic` is set, listing where each one is.
+/// Both are keywords so the source only needs to be tokenised to find them.
+pub fn check_unfinished_code(paths: &ProjectPaths, include_panic: bool) -> Result<()> {
+    let mut locations = vec![];
+    for path in fs::gleam_files(&paths.src_directory()).sorted() {
+        let src = fs::read(&path)?;
+        let line_numbers = LineNumbers::new(&src);
+        let path = path.strip_prefix(paths.root()).unwrap_or(&path);
+        for (start, token, _) in lexer::make_tokenizer(&src).filter_map(Result::ok) {
+            let keyword = match token {
+                Token::Todo => "todo",
+                Token::Panic if include_panic => "panic",
+                _ => continue,
+            };
+            let position = line_numbers.line_and_column_number(start);
+            locations.push(eco_format!(
+                "{keyword} at {path}:{}:{}",
+                position.line,
+                position.column
+            ));
+        }
+    }
+    if locations.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::UnfinishedCode { locations })
+    }
+}
+
+fn build_from_scratch(
+    paths: &ProjectPaths,
+    options: &Options,
+    output_directory: &Utf8Path,
+) -> Result<BTreeMap<Utf8PathBuf, Vec<u8>>> {
+    fs::delete_directory(output_directory)?;
+    let manifest = crate::dependencies::read_manifest_from_disc(paths)?;
+    let manifest_toml = manifest.to_toml(paths.root());
+    // Every package is compiled rather than copied from the dependency cache,
+    // otherwise differences in how dependencies are compiled would be hidden.
+    let _ = main_with_dependency_cache(
+        paths,
+        options.clone(),
+        manifest,
+        Rc::new(NullWarningEmitterIO),
+        None,
+    )?;
+    let mut files = generated_files(output_directory)?;
+    let _ = files.insert(paths.manifest(), manifest_toml.into_bytes());
+    Ok(files)
+}
+
+fn generated_files(directory: &Utf8Path) -> Result<BTreeMap<Utf8PathBuf, Vec<u8>>> {
+    let mut files = BTreeMap::new();
+    for path in fs::private_files(directory) {
+        let extension = path.extension().unwrap_or_default();
+        if matches!(
+            extension,
+            "beam" | "cache" | "cache_meta" | "cache_warnings"
+        ) {
+            continue;
+        }
+        let contents = fs::read_bytes(&path)?;
+        let _ = files.insert(path, contents);
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ecow::EcoString;
+    use gleam_core::{
+        analyse::TargetSupport,
+        build::{Compile, Mode, Target},
+        manifest::{Base16Checksum, ManifestPackage, ManifestPackageSource},
+    };
+    use hexpm::version::Version;
+
+    fn write_project(root: &Utf8Path, files: &[(&str, &str)]) -> (ProjectPaths, Manifest) {
+        let paths = ProjectPaths::new(root.to_path_buf());
+        for (path, contents) in files {
+            fs::write(&root.join(path), contents).unwrap();
+        }
+        let manifest = Manifest {
+            requirements: Default::default(),
+            packages: vec![],
+            checksum_algorithm: Default::default(),
+            published_before: None,
+        };
+        fs::write(&paths.manifest(), &manifest.to_toml(root)).unwrap();
+        (paths, manifest)
+    }
+
+    fn javascript_options(features: Vec<EcoString>) -> Options {
+        Options {
+            root_target_support: TargetSupport::Enforced,
+            warnings_as_errors: false,
+            codegen: Codegen::All,
+            compile: Compile::All,
+            mode: Mode::Dev,
+            target: Some(Target::JavaScript),
+            no_print_progress: true,
+            compile_tests: false,
+            changed_modules: None,
+            features,
+            javascript_target_version: None,
+            coverage: false,
+        }
+    }
+
+    #[test]
+    fn building_a_project_is_deterministic() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = Utf8PathBuf::from_path_buf(tmp.path().to_path_buf()).expect("Non Utf8 Path");
+        let (paths, _) = write_project(
+            &root,
+            &[
+                ("gleam.toml", "name = \"wibble\"\nversion = \"1.0.0\"\n"),
+                (
+                    "src/wibble.gleam",
+                    "import wibble/wobble
+
+pub type Wibble {
+  Wibble(a: Int, b: String)
+  Wobble
+}
+
+pub fn main() {
+  let Wibble(a:, b:) = Wibble(1, wobble.name())
+  #(a, b, wobble.values())
+}
+",
+                ),
+                (
+                    "src/wibble/wobble.gleam",
+                    "pub fn name() -> String {
+  \"wobble\"
+}
+
+@external(erlang, \"wobble_ffi\", \"values\")
+@external(javascript, \"./wobble_ffi.mjs\", \"values\")
+pub fn values() -> List(Int)
+",
+                ),
+                (
+                    "src/wibble/wobble_ffi.mjs",
+                    "export function values() { return []; }\n",
+                ),
+            ],
+        );
+
+        assert_eq!(
+            check_determinism(&paths, javascript_options(vec![])),
+            Ok(())
+        );
+        let generated = paths
+            .build_directory_for_package(Mode::Dev, Target::JavaScript, "wibble")
+            .join("wibble/wobble.mjs");
+        assert!(generated.is_file());
+    }
+
+    #[test]
+    fn changing_the_enabled_features_recompiles_the_features_module() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = Utf8PathBuf::from_path_buf(tmp.path().to_path_buf()).expect("Non Utf8 Path");
+        let (paths, manifest) = write_project(
+            &root,
+            &[
+                (
+                    "gleam.toml",
+                    "name = \"wibble\"\nversion = \"1.0.0\"\nfeatures = [\"debug\"]\n",
+                ),
+                (
+                    "src/wibble.gleam",
+                    "import wibble/features
+
+pub fn main() {
+  features.debug
+}
+",
+                ),
+            ],
+        );
+        let features = paths
+            .build_directory_for_package(Mode::Dev, Target::JavaScript, "wibble")
+            .join("wibble/features.mjs");
+        let build = |features: Vec<EcoString>| {
+            let _ = main_with_warnings(
+                &paths,
+                javascript_options(features),
+                manifest.clone(),
+                Rc::new(NullWarningEmitterIO),
+            )
+            .unwrap();
+        };
+
+        build(vec![]);
+        assert!(
+            fs::read(&features)
+                .unwrap()
+                .contains("export const debug = false;")
+        );
+
+        build(vec!["debug".into()]);
+        assert!(
+            fs::read(&features)
+                .unwrap()
+                .contains("export const debug = true;")
+        );
+
+        build(vec![]);
+        assert!(
+            fs::read(&features)
+                .unwrap()
+                .contains("export const debug = false;")
+        );
+    }
+
+    #[test]
+    fn dependencies_with_no_imported_modules_are_unused() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = Utf8PathBuf::from_path_buf(tmp.path().to_path_buf()).expect("Non Utf8 Path");
+        for name in ["used", "unused"] {
+            let _ = write_project(
+                &root.join(name),
+                &[
+                    (
+                        "gleam.toml",
+                        &format!("name = \"{name}\"\nversion = \"1.0.0\"\n"),
+                    ),
+                    (&format!("src/{name}.gleam"), "pub fn value() { 1 }\n"),
+                ],
+            );
+        }
+        let (paths, mut manifest) = write_project(
+            &root.join("wibble"),
+            &[
+                (
+                    "gleam.toml",
+                    "name = \"wibble\"
+version = \"1.0.0\"
+
+[dependencies]
+used = { path = \"../used\" }
+unused = { path = \"../unused\" }
+",
+                ),
+                (
+                    "src/wibble.gleam",
+                    "import used
+
+pub fn main() {
+  used.value()
+}
+",
+                ),
+            ],
+        );
+        manifest.packages = ["used", "unused"]
+            .into_iter()
+            .map(|name| ManifestPackage {
+                name: name.into(),
+                version: Version::new(1, 0, 0),
+                build_tools: vec!["gleam".into()],
+                otp_app: None,
+                requirements: vec![],
+                source: ManifestPackageSource::Local {
+                    path: root.join(name),
+                },
+                retired: false,
+            })
+            .collect();
+
+        let built = main_with_warnings(
+            &paths,
+            Options {
+                codegen: Codegen::None,
+                ..javascript_options(vec![])
+            },
+            manifest,
+            Rc::new(NullWarningEmitterIO),
+        )
+        .unwrap();
+        assert_eq!(built.unused_dependencies(), vec![EcoString::from("unused")]);
+    }
+
+    #[test]
+    fn unfinished_code_only_fails_the_build_when_checked_for() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = Utf8PathBuf::from_path_buf(tmp.path().to_path_buf()).expect("Non Utf8 Path");
+        let (paths, manifest) = write_project(
+            &root,
+            &[
+                ("gleam.toml", "name = \"wibble\"\nversion = \"1.0.0\"\n"),
+                (
+                    "src/wibble.gleam",
+                    "pub fn main() {
+  wobble()
+  todo as \"not done yet\"
+}
+
+fn wobble() {
+  panic
+}
+",
+                ),
+            ],
+        );
+
+        let built = main_with_warnings(
+            &paths,
+            javascript_options(vec![]),
+            manifest,
+            Rc::new(NullWarningEmitterIO),
+        );
+        assert!(built.is_ok());
+        assert_eq!(
+            check_unfinished_code(&paths, false),
+            Err(Error::UnfinishedCode {
+                locations: vec!["todo at src/wibble.gleam:3:3".into()]
+            })
+        );
+        assert_eq!(
+            check_unfinished_code(&paths, true),
+            Err(Error::UnfinishedCode {
+                locations: vec![
+                    "todo at src/wibble.gleam:3:3".into(),
+                    "panic at src/wibble.gleam:7:3".into(),
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn dependency_cache_can_be_disabled() {
+        assert_eq!(
+            dependency_cache(None),
+            Some(gleam_core::paths::global_dependency_cache())
+        );
+        assert_eq!(dependency_cache(Some("1".into())), None);
+    }
+
+    #[test]
+    fn hex_dependencies_are_copied_from_the_dependency_cache() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = Utf8PathBuf::from_path_buf(tmp.path().to_path_buf()).expect("Non Utf8 Path");
+        let cache = root.join("cache");
+        let build_project = |name: &str| {
+            let (paths, mut manifest) = write_project(
+                &root.join(name),
+                &[
+                    (
+                        "gleam.toml",
+                        &format!(
+                            "name = \"{name}\"
+version = \"1.0.0\"
+
+[dependencies]
+wobble = \">= 1.0.0 and < 2.0.0\"
+"
+                        ),
+                    ),
+                    (
+                        &format!("src/{name}.gleam"),
+                        "import wobble\n\npub fn main() { wobble.value() }\n",
+                    ),
+                    (
+                        "build/packages/wobble/gleam.toml",
+                        "name = \"wobble\"\nversion = \"1.0.0\"\n",
+                    ),
+                    (
+                        "build/packages/wobble/src/wobble.gleam",
+                        "pub fn value() { 1 }\n",
+                    ),
+                ],
+            );
+            manifest.packages = vec![ManifestPackage {
+                name: "wobble".into(),
+                version: Version::new(1, 0, 0),
+                build_tools: vec!["gleam".into()],
+                otp_app: None,
+                requirements: vec![],
+                source: ManifestPackageSource::Hex {
+                    outer_checksum: Base16Checksum(vec![1, 2, 3]),
+                },
+                retired: false,
+            }];
+            let _ = main_with_dependency_cache(
+                &paths,
+                javascript_options(vec![]),
+                manifest,
+                Rc::new(NullWarningEmitterIO),
+                Some(cache.clone()),
+            )
+            .unwrap();
+            paths
+                .build_directory_for_package(Mode::Dev, Target::JavaScript, "wobble")
+                .join("wobble.mjs")
+        };
+
+        // The first project compiles the dependency and stores it in the cache
+        let first = build_project("wibble");
+        assert!(first.is_file());
+        let cached = fs::private_files(&cache)
+            .find(|path| path.file_name() == Some("wobble.mjs"))
+            .unwrap();
+        // The artefacts were copied to a temporary directory that has been
+        // renamed to be the cache entry, so none is left behind.
+        assert!(
+            !fs::private_files(&cache).any(|path| path.as_str().contains(".tmp-")),
+            "temporary directory left in the dependency cache"
+        );
+
+        // The second project uses the artefacts from the cache rather than
+        // compiling the dependency again, so a change made to them shows up.
+        fs::write(&cached, "// From the cache\n").unwrap();
+        let second = build_project("wubble");
+        assert_eq!(fs::read(&second).unwrap(), "// From the cache\n");
+    }
+}
@@ -0,0 +1,76 @@
+use std::{collections::HashSet, process::Command};
+
+use camino::Utf8Path;
+use ecow::EcoString;
+use gleam_core::{Result, paths::ProjectPaths};
+
+use crate::dependencies::execute_command;
+
+#[cfg(test)]
+mod tests;
+
+/// The Gleam modules of the project that have been changed, added, or removed
+/// since the given git ref, including any changes not yet committed.
+///
+/// Returns `None` if there are changes that could affect every module, such
+/// as to `gleam.toml` or to a native file, in which case everything has to be
+/// compiled.
+///
+pub fn changed_modules_since(
+    paths: &ProjectPaths,
+    reference: &str,
+) -> Result<Option<HashSet<EcoString>>> {
+    let changed = execute_command(
+        Command::new("git")
+            .arg("diff")
+            .arg("--name-only")
+            .arg("--relative")
+            .arg(reference)
+            .arg("--")
+            .current_dir(paths.root()),
+    )?;
+    let untracked = execute_command(
+        Command::new("git")
+            .arg("ls-files")
+            .arg("--others")
+            .arg("--exclude-standard")
+            .current_dir(paths.root()),
+    )?;
+
+    let changed = String::from_utf8_lossy(&changed.stdout);
+    let untracked = String::from_utf8_lossy(&untracked.stdout);
+    let files = changed.lines().chain(untracked.lines()).map(Utf8Path::new);
+    let modules = modules_for_changed_files(files);
+    tracing::debug!(reference, ?modules, "changed_modules");
+    Ok(modules)
+}
+
+/// The Gleam modules for the given changed files, with paths relative to the
+/// root of the project. Changes to files that are not used to build the
+/// project, such as the readme, are ignored.
+///
+fn modules_for_changed_files<'a>(
+    files: impl IntoIterator<Item = &'a Utf8Path>,
+) -> Option<HashSet<EcoString>> {
+    let mut modules = HashSet::new();
+    for file in files {
+        if file == "gleam.toml" || file == "manifest.toml" {
+            return None;
+        }
+
+        let Some(path) = file
+            .strip_prefix("src")
+            .or_else(|_| file.strip_prefix("test"))
+            .ok()
+        else {
+            continue;
+        };
+
+        if path.extension() != Some("gleam") {
+            return None;
+        }
+        let module = path.with_extension("");
+        let _ = modules.insert(module.as_str().replace('\\', "/").into());
+    }
+    Some(modules)
+}
@@ -0,0 +1,43 @@
+use std::collections::HashSet;
+
+use camino::Utf8Path;
+use ecow::EcoString;
+
+use super::modules_for_changed_files;
+
+fn modules(files: &[&str]) -> Option<HashSet<EcoString>> {
+    modules_for_changed_files(files.iter().map(Utf8Path::new))
+}
+
+#[test]
+fn changed_gleam_modules() {
+    assert_eq!(
+        modules(&[
+            "src/wibble.gleam",
+            "src/wibble/wobble.gleam",
+            "test/wibble_test.gleam",
+            "README.md",
+        ]),
+        Some(
+            ["wibble", "wibble/wobble", "wibble_test"]
+                .into_iter()
+                .map(EcoString::from)
+                .collect()
+        )
+    );
+}
+
+#[test]
+fn no_changes() {
+    assert_eq!(modules(&["README.md"]), Some(HashSet::new()));
+}
+
+#[test]
+fn changed_native_file_affects_everything() {
+    assert_eq!(modules(&["src/wibble.gleam", "src/wibble_ffi.erl"]), None);
+}
+
+#[test]
+fn changed_config_affects_everything() {
+    assert_eq!(modules(&["src/wibble.gleam", "gleam.toml"]), None);
+}
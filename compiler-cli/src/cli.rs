@@ -1,3 +1,4 @@
+use clap::ValueEnum;
 use gleam_core::{
     build::Telemetry,
     error::{Error, StandardIoAction},
@@ -5,10 +6,61 @@ use gleam_core::{
 use hexpm::version::Version;
 use std::{
     io::{IsTerminal, Write},
+    sync::{
+        OnceLock,
+        atomic::{AtomicUsize, Ordering},
+    },
     time::{Duration, Instant},
 };
 use termcolor::{BufferWriter, Color, ColorChoice, ColorSpec, WriteColor};
 
+/// How the progress of downloading packages is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "lower")]
+pub enum ProgressStyle {
+    /// A line for each package downloaded
+    Plain,
+    /// A progress bar, updated as each package is downloaded
+    Bar,
+    /// Nothing is printed
+    None,
+}
+
+impl ProgressStyle {
+    /// A progress bar if standard error is a terminal, otherwise a line for
+    /// each package.
+    pub fn detect() -> Self {
+        if std::io::stderr().is_terminal() {
+            Self::Bar
+        } else {
+            Self::Plain
+        }
+    }
+}
+
+/// How download progress is shown, detected from standard error if it has
+/// not been set.
+static PROGRESS_STYLE: OnceLock<ProgressStyle> = OnceLock::new();
+
+/// How many packages there are to download, and how many have been so far.
+static PACKAGES_TO_DOWNLOAD: AtomicUsize = AtomicUsize::new(0);
+static PACKAGES_DOWNLOADED: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets how download progress is shown. A progress bar can only be drawn in
+/// a terminal, so a line is printed for each package instead if standard
+/// error isn't one.
+pub fn set_progress_style(progress: ProgressStyle) {
+    let progress = match progress {
+        ProgressStyle::Bar if !std::io::stderr().is_terminal() => ProgressStyle::Plain,
+        progress => progress,
+    };
+    let _ = PROGRESS_STYLE.set(progress);
+}
+
+fn progress_style() -> ProgressStyle {
+    *PROGRESS_STYLE.get_or_init(ProgressStyle::detect)
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct Reporter;
 
@@ -35,16 +87,51 @@ impl Telemetry for Reporter {
         print_checking(name);
     }
 
-    fn downloading_package(&self, name: &str) {
-        print_downloading(name)
+    fn downloading_packages(&self, count: usize) {
+        PACKAGES_DOWNLOADED.store(0, Ordering::Relaxed);
+        PACKAGES_TO_DOWNLOAD.store(count, Ordering::Relaxed);
+        match progress_style() {
+            ProgressStyle::Plain => print_downloading("packages"),
+            ProgressStyle::Bar => print_progress_bar(0, count),
+            ProgressStyle::None => (),
+        }
+    }
+
+    fn downloaded_package(&self, name: &str, version: &Version) {
+        let downloaded = PACKAGES_DOWNLOADED.fetch_add(1, Ordering::Relaxed) + 1;
+        let to_download = PACKAGES_TO_DOWNLOAD.load(Ordering::Relaxed);
+        let buffer_writer = stderr_buffer_writer();
+        let mut buffer = buffer_writer.buffer();
+        write_download_progress(
+            &mut buffer,
+            progress_style(),
+            name,
+            version,
+            downloaded,
+            to_download,
+        )
+        .expect("print_download_progress");
+        buffer_writer
+            .print(&buffer)
+            .expect("print_download_progress");
     }
 
     fn packages_downloaded(&self, start: Instant, count: usize) {
-        print_packages_downloaded(start, count)
+        match progress_style() {
+            ProgressStyle::Plain => print_packages_downloaded(start, count),
+            ProgressStyle::Bar => {
+                // Replace the progress bar with the summary
+                eprint!("{CLEAR_LINE}");
+                print_packages_downloaded(start, count)
+            }
+            ProgressStyle::None => (),
+        }
     }
 
     fn resolving_package_versions(&self) {
-        print_resolving_versions()
+        if progress_style() != ProgressStyle::None {
+            print_resolving_versions()
+        }
     }
 
     fn running(&self, name: &str) {
@@ -92,6 +179,17 @@ pub fn ask_password(question: &str) -> Result<String, Error> {
         .map(|s| s.trim().to_string())
 }
 
+pub fn print_test_seed(seed: u64) {
+    let buffer_writer = stderr_buffer_writer();
+    let mut buffer = buffer_writer.buffer();
+    write_test_seed(&mut buffer, seed).expect("print_test_seed");
+    buffer_writer.print(&buffer).expect("print_test_seed");
+}
+
+fn write_test_seed(buffer: &mut impl WriteColor, seed: u64) -> std::io::Result<()> {
+    write_colourful_prefix(buffer, "Seed", &seed.to_string())
+}
+
 pub fn print_publishing(name: &str, version: &Version) {
     print_colourful_prefix("Publishing", &format!("{name} v{version}"))
 }
@@ -144,6 +242,10 @@ pub(crate) fn print_checked(duration: Duration) {
     print_colourful_prefix("Checked", &format!("in {}", seconds(duration)))
 }
 
+pub(crate) fn print_checked_determinism() {
+    print_colourful_prefix("Checked", "the build is deterministic")
+}
+
 pub(crate) fn print_running(text: &str) {
     print_colourful_prefix("Running", text)
 }
@@ -173,21 +275,82 @@ pub fn seconds(duration: Duration) -> String {
     format!("{:.2}s", duration.as_millis() as f32 / 1000.)
 }
 
+/// Moves the cursor to the start of the line and erases it.
+const CLEAR_LINE: &str = "\r\x1b[2K";
+
+/// Writes the progress of downloading packages once one more has been
+/// downloaded, `downloaded` being how many have been so far.
+fn write_download_progress(
+    buffer: &mut impl WriteColor,
+    progress: ProgressStyle,
+    name: &str,
+    version: &Version,
+    downloaded: usize,
+    to_download: usize,
+) -> std::io::Result<()> {
+    match progress {
+        ProgressStyle::Plain => {
+            write_colourful_prefix(buffer, "Downloaded", &format!("{name} v{version}"))
+        }
+        ProgressStyle::Bar => write_progress_bar(buffer, downloaded, to_download),
+        ProgressStyle::None => Ok(()),
+    }
+}
+
+fn print_progress_bar(downloaded: usize, to_download: usize) {
+    let buffer_writer = stderr_buffer_writer();
+    let mut buffer = buffer_writer.buffer();
+    write_progress_bar(&mut buffer, downloaded, to_download).expect("print_progress_bar");
+    buffer_writer.print(&buffer).expect("print_progress_bar");
+}
+
+/// Writes a progress bar over the current line, without ending it so that
+/// it can be replaced by the next one.
+fn write_progress_bar(
+    buffer: &mut impl WriteColor,
+    downloaded: usize,
+    to_download: usize,
+) -> std::io::Result<()> {
+    const WIDTH: usize = 30;
+    let filled = (downloaded * WIDTH)
+        .checked_div(to_download)
+        .unwrap_or(WIDTH)
+        .min(WIDTH);
+    write!(buffer, "{CLEAR_LINE}")?;
+    buffer.set_color(
+        ColorSpec::new()
+            .set_intense(true)
+            .set_fg(Some(Color::Magenta)),
+    )?;
+    write!(buffer, "{: >11}", "Downloading")?;
+    buffer.set_color(&ColorSpec::new())?;
+    write!(
+        buffer,
+        " [{}{}] {downloaded}/{to_download}",
+        "=".repeat(filled),
+        " ".repeat(WIDTH - filled)
+    )
+}
+
+fn write_colourful_prefix(
+    buffer: &mut impl WriteColor,
+    prefix: &str,
+    text: &str,
+) -> std::io::Result<()> {
+    buffer.set_color(
+        ColorSpec::new()
+            .set_intense(true)
+            .set_fg(Some(Color::Magenta)),
+    )?;
+    write!(buffer, "{prefix: >11}")?;
+    buffer.set_color(&ColorSpec::new())?;
+    writeln!(buffer, " {text}")
+}
+
 pub fn print_colourful_prefix(prefix: &str, text: &str) {
     let buffer_writer = stderr_buffer_writer();
     let mut buffer = buffer_writer.buffer();
-    buffer
-        .set_color(
-            ColorSpec::new()
-                .set_intense(true)
-                .set_fg(Some(Color::Magenta)),
-        )
-        .expect("print_green_prefix");
-    write!(buffer, "{prefix: >11}").expect("print_green_prefix");
-    buffer
-        .set_color(&ColorSpec::new())
-        .expect("print_green_prefix");
-    writeln!(buffer, " {text}").expect("print_green_prefix");
+    write_colourful_prefix(&mut buffer, prefix, text).expect("print_green_prefix");
     buffer_writer.print(&buffer).expect("print_green_prefix");
 }
 
@@ -213,3 +376,57 @@ fn color_choice() -> ColorChoice {
         ColorChoice::Never
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use termcolor::Buffer;
+
+    fn download_progress(progress: ProgressStyle) -> String {
+        let mut buffer = Buffer::no_color();
+        let packages = [
+            ("wibble", "1.0.0"),
+            ("wobble", "2.1.0"),
+            ("wubble", "0.3.0"),
+        ];
+        for (index, (name, version)) in packages.iter().enumerate() {
+            let version = Version::parse(version).expect("version");
+            write_download_progress(&mut buffer, progress, name, &version, index + 1, 3)
+                .expect("write download progress");
+        }
+        String::from_utf8(buffer.into_inner()).expect("utf8")
+    }
+
+    #[test]
+    fn plain_download_progress_is_a_line_per_package() {
+        assert_eq!(
+            download_progress(ProgressStyle::Plain),
+            " Downloaded wibble v1.0.0
+ Downloaded wobble v2.1.0
+ Downloaded wubble v0.3.0
+"
+        );
+    }
+
+    #[test]
+    fn no_download_progress_is_silent() {
+        assert_eq!(download_progress(ProgressStyle::None), "");
+    }
+
+    #[test]
+    fn test_seed_is_printed_as_given() {
+        let mut buffer = Buffer::no_color();
+        write_test_seed(&mut buffer, 18446744073709551615).expect("write test seed");
+        assert_eq!(
+            String::from_utf8(buffer.into_inner()).expect("utf8"),
+            "       Seed 18446744073709551615\n"
+        );
+    }
+
+    #[test]
+    fn download_progress_bar_is_redrawn_on_one_line() {
+        let output = download_progress(ProgressStyle::Bar);
+        assert!(!output.contains('\n'));
+        assert!(output.ends_with(&format!("{CLEAR_LINE}Downloading [{}] 3/3", "=".repeat(30))));
+    }
+}
@@ -26,12 +26,17 @@ pub fn command(options: CompilePackage) -> Result<()> {
     let config = config::read(paths.root_config())?;
 
     let target = match options.target {
-        Target::Erlang => TargetCodegenConfiguration::Erlang { app_file: None },
+        Target::Erlang => TargetCodegenConfiguration::Erlang {
+            app_file: None,
+            module_prefix: None,
+        },
         Target::JavaScript => TargetCodegenConfiguration::JavaScript {
             emit_typescript_definitions: false,
             prelude_location: options
                 .javascript_prelude
                 .ok_or_else(|| Error::JavaScriptPreludeRequired)?,
+            ecmascript_version: config.javascript.target_version,
+            coverage: false,
         },
     };
 
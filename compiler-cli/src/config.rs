@@ -1,8 +1,11 @@
 use camino::Utf8PathBuf;
+use serde::Serialize;
 
 use gleam_core::{
-    config::PackageConfig,
+    build::{Mode, Target},
+    config::{Dependencies, PackageConfig},
     error::{Error, FileIoAction, FileKind},
+    io::ordered_map,
     manifest::{Manifest, ManifestPackage, ManifestPackageSource},
     paths::ProjectPaths,
 };
@@ -53,12 +56,20 @@ fn package_root(package: &ManifestPackage, project_paths: &ProjectPaths) -> Utf8
 }
 
 pub fn root_config(paths: &ProjectPaths) -> Result<PackageConfig, Error> {
-    read(paths.root_config())
+    let config_path = paths.root_config();
+    let toml = crate::fs::read(&config_path)?;
+    let config = parse(&toml, config_path)?;
+    config.check_duplicate_dependencies(&toml)?;
+    Ok(config)
 }
 
 pub fn read(config_path: Utf8PathBuf) -> Result<PackageConfig, Error> {
     let toml = crate::fs::read(&config_path)?;
-    let config: PackageConfig = toml::from_str(&toml).map_err(|e| Error::FileIo {
+    parse(&toml, config_path)
+}
+
+fn parse(toml: &str, config_path: Utf8PathBuf) -> Result<PackageConfig, Error> {
+    let config: PackageConfig = toml::from_str(toml).map_err(|e| Error::FileIo {
         action: FileIoAction::Parse,
         kind: FileKind::File,
         path: config_path,
@@ -81,11 +92,89 @@ pub fn ensure_config_exists(paths: &ProjectPaths) -> Result<(), Error> {
     Ok(())
 }
 
+/// The configuration in effect for a build once defaults, command line flags,
+/// and environment variables have been applied.
+#[derive(Debug, Serialize)]
+pub struct ResolvedConfig<'a> {
+    #[serde(flatten)]
+    pub package: &'a PackageConfig,
+    #[serde(rename = "selected-target")]
+    pub selected_target: Target,
+    pub mode: String,
+    #[serde(rename = "all-dependencies", serialize_with = "ordered_map")]
+    pub all_dependencies: Dependencies,
+    #[serde(rename = "hex-api-url")]
+    pub hex_api_url: String,
+    #[serde(rename = "hex-repository-url")]
+    pub hex_repository_url: String,
+}
+
+impl<'a> ResolvedConfig<'a> {
+    pub fn new(
+        package: &'a PackageConfig,
+        target: Option<Target>,
+        mode: Mode,
+        hex_config: &hexpm::Config,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            package,
+            selected_target: target.unwrap_or(package.target),
+            mode: mode.to_string(),
+            all_dependencies: package.all_direct_dependencies()?,
+            hex_api_url: hex_config.api_base.to_string(),
+            hex_repository_url: hex_config.repository_base.to_string(),
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("config JSON serialisation")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use gleam_core::manifest::Base16Checksum;
 
+    #[test]
+    fn resolved_config_uses_repository_url_override() {
+        let package = PackageConfig {
+            name: "my_project".into(),
+            ..Default::default()
+        };
+        let hex_config =
+            crate::hex::config_with_repository_url(Some("https://mirror.example.com/hex".into()))
+                .expect("valid repository URL");
+        let config = ResolvedConfig::new(&package, None, Mode::Dev, &hex_config).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&config.to_json()).unwrap();
+
+        assert_eq!(
+            json["hex-repository-url"],
+            "https://mirror.example.com/hex/"
+        );
+        assert_eq!(json["hex-api-url"], "https://hex.pm/api/");
+        assert_eq!(json["name"], "my_project");
+        assert_eq!(json["mode"], "dev");
+    }
+
+    #[test]
+    fn resolved_config_without_repository_url_override() {
+        let package = PackageConfig::default();
+        let hex_config = crate::hex::config_with_repository_url(None).unwrap();
+        let config =
+            ResolvedConfig::new(&package, Some(Target::JavaScript), Mode::Dev, &hex_config)
+                .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&config.to_json()).unwrap();
+
+        assert_eq!(json["hex-repository-url"], "https://repo.hex.pm/");
+        assert_eq!(json["selected-target"], "javascript");
+    }
+
+    #[test]
+    fn invalid_repository_url_override() {
+        assert!(crate::hex::config_with_repository_url(Some("not a url".into())).is_err());
+    }
+
     #[test]
     fn package_root_hex() {
         let paths = ProjectPaths::new(Utf8PathBuf::from("/app"));
@@ -98,6 +187,7 @@ mod tests {
             source: ManifestPackageSource::Hex {
                 outer_checksum: Base16Checksum(vec![]),
             },
+            retired: false,
         };
         assert_eq!(
             package_root(&package, &paths),
@@ -118,6 +208,7 @@ mod tests {
                 repo: "repo".into(),
                 commit: "commit".into(),
             },
+            retired: false,
         };
         assert_eq!(
             package_root(&package, &paths),
@@ -137,6 +228,7 @@ mod tests {
             source: ManifestPackageSource::Local {
                 path: Utf8PathBuf::from("../wibble"),
             },
+            retired: false,
         };
         assert_eq!(
             package_root(&package, &paths),
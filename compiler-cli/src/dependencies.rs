@@ -1,6 +1,8 @@
 use std::{
     collections::{HashMap, HashSet},
+    num::NonZeroUsize,
     process::Command,
+    rc::Rc,
     time::Instant,
 };
 
@@ -9,30 +11,35 @@ use ecow::{EcoString, eco_format};
 use flate2::read::GzDecoder;
 use futures::future;
 use gleam_core::{
-    Error, Result,
+    Error, Result, STDLIB_PACKAGE_NAME, Warning,
     build::{Mode, Target, Telemetry},
     config::PackageConfig,
-    dependency,
+    dependency::{self, RegistrySnapshot, Timestamp},
     error::{FileIoAction, FileKind, ShellCommandFailureReason, StandardIoAction},
-    hex::{self, HEXPM_PUBLIC_KEY},
-    io::{HttpClient as _, TarUnpacker, WrappedReader},
-    manifest::{Base16Checksum, Manifest, ManifestPackage, ManifestPackageSource},
-    paths::ProjectPaths,
+    hex::{self, DownloadPlan, DownloadSummary, HEXPM_PUBLIC_KEY, PackageDownload},
+    io::{HttpClient as _, PackageSizeLimitExceeded, TarUnpacker, WrappedReader},
+    manifest::{
+        Base16Checksum, ChecksumAlgorithm, Manifest, ManifestPackage, ManifestPackageSource,
+    },
+    paths::{self, ProjectPaths},
     requirement::Requirement,
+    version::COMPILER_VERSION,
+    warning::WarningEmitterIO,
 };
 use hexpm::version::Version;
 use itertools::Itertools;
 use same_file::is_same_file;
 use strum::IntoEnumIterator;
 
+mod policy;
 #[cfg(test)]
 mod tests;
 
 use crate::{
-    TreeOptions,
+    DownloadOptions, TreeOptions,
     build_lock::{BuildLock, Guard},
     cli,
-    fs::{self, ProjectIO},
+    fs::{self, ConsoleWarningEmitter, ProjectIO},
     http::HttpClient,
 };
 
@@ -68,6 +75,7 @@ pub fn tree(paths: &ProjectPaths, options: TreeOptions) -> Result<()> {
             path: paths.root().to_path_buf(),
         },
         otp_app: None,
+        retired: false,
     };
 
     // Get the manifest packages and add the root package to the vec
@@ -77,7 +85,12 @@ pub fn tree(paths: &ProjectPaths, options: TreeOptions) -> Result<()> {
     list_package_and_dependencies_tree(std::io::stdout(), options, packages.clone(), config.name)
 }
 
-fn get_manifest_details(paths: &ProjectPaths) -> Result<(PackageConfig, Manifest)> {
+pub fn version(paths: &ProjectPaths, package: &str) -> Result<()> {
+    let (_, manifest) = get_manifest_details(paths)?;
+    print_package_version(std::io::stdout(), &manifest, package)
+}
+
+pub(crate) fn get_manifest_details(paths: &ProjectPaths) -> Result<(PackageConfig, Manifest)> {
     let runtime = tokio::runtime::Runtime::new().expect("Unable to start Tokio async runtime");
     let config = crate::config::root_config(paths)?;
     let (_, manifest) = get_manifest(
@@ -88,6 +101,7 @@ fn get_manifest_details(paths: &ProjectPaths) -> Result<(PackageConfig, Manifest
         &cli::Reporter::new(),
         UseManifest::Yes,
         Vec::new(),
+        ChecksumAlgorithm::default(),
     )?;
     Ok((config, manifest))
 }
@@ -103,6 +117,22 @@ fn list_manifest_packages<W: std::io::Write>(mut buffer: W, manifest: Manifest)
         })
 }
 
+fn print_package_version<W: std::io::Write>(
+    mut buffer: W,
+    manifest: &Manifest,
+    package: &str,
+) -> Result<()> {
+    let version = manifest
+        .package_version(package)
+        .ok_or_else(|| Error::PackageNotInManifest {
+            package: package.into(),
+        })?;
+    writeln!(buffer, "{version}").map_err(|e| Error::StandardIo {
+        action: StandardIoAction::Write,
+        err: Some(e.kind()),
+    })
+}
+
 fn list_package_and_dependencies_tree<W: std::io::Write>(
     mut buffer: W,
     options: TreeOptions,
@@ -246,7 +276,7 @@ pub fn cleanup<Telem: Telemetry>(paths: &ProjectPaths, telemetry: Telem) -> Resu
 
     // Record new state of the packages directory
     tracing::debug!("writing_manifest_toml");
-    write_manifest_to_disc(paths, &manifest)?;
+    write_manifest_to_disc(paths, &config, &manifest)?;
     LocalPackages::from_manifest(&manifest).write_to_disc(paths)?;
 
     Ok(manifest)
@@ -292,6 +322,188 @@ fn remove_extra_requirements(config: &PackageConfig, manifest: &mut Manifest) ->
     Ok(())
 }
 
+/// Warn about packages that are required by both `dependencies` and
+/// `dev-dependencies` with ranges no version could satisfy. This is much
+/// faster than waiting for version resolution to fail.
+/// A warning if the config sets a maximum dependency depth and the resolved
+/// dependencies include a longer chain than that.
+fn deep_dependency_chain_warning(config: &PackageConfig, manifest: &Manifest) -> Option<Warning> {
+    let max_depth = config.max_dependency_depth?;
+    let chain = manifest.deepest_dependency_chain();
+    (chain.len() > max_depth).then_some(Warning::DeepDependencyChain { max_depth, chain })
+}
+
+/// Warnings for the requirements with no upper bound, if the config asks for
+/// them.
+fn unbounded_requirement_warnings(config: &PackageConfig) -> Vec<Warning> {
+    if !config.warn_unbounded_requirements {
+        return vec![];
+    }
+    config
+        .unbounded_requirements()
+        .into_iter()
+        .map(|requirement| Warning::UnboundedRequirement {
+            package: requirement.package,
+            range: requirement.range,
+            suggestion: requirement.suggestion,
+        })
+        .collect()
+}
+
+/// Retired versions are only resolved if they were already locked or if no
+/// other version satisfies the requirements. If `no_retired` is set any
+/// retired version in the manifest is an error, otherwise newly resolved
+/// manifests with retired versions get a warning.
+fn check_retired_packages(
+    manifest: &Manifest,
+    manifest_updated: bool,
+    no_retired: bool,
+) -> Result<Option<Warning>> {
+    let packages = manifest
+        .packages
+        .iter()
+        .filter(|package| package.retired)
+        .sorted_by(|a, b| a.name.cmp(&b.name))
+        .map(|package| eco_format!("{} {}", package.name, package.version))
+        .collect_vec();
+    if packages.is_empty() {
+        Ok(None)
+    } else if no_retired {
+        Err(Error::RetiredPackages { packages })
+    } else {
+        Ok(manifest_updated.then_some(Warning::RetiredPackages { packages }))
+    }
+}
+
+/// With strict checksums every Hex package must have a checksum recorded in
+/// the manifest, so that it is verified when it is downloaded.
+fn check_recorded_checksums(manifest: &Manifest, strict_checksums: bool) -> Result<()> {
+    if !strict_checksums {
+        return Ok(());
+    }
+    let packages = manifest.packages_without_checksums();
+    if packages.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::MissingChecksums { packages })
+    }
+}
+
+/// Fails if the config names a dependency policy and the packages in the
+/// manifest break it. This is done once versions have been resolved, before
+/// anything is downloaded. The licences and owners of Hex packages are looked
+/// up with the Hex API.
+fn check_dependency_policy(
+    paths: &ProjectPaths,
+    config: &PackageConfig,
+    manifest: &Manifest,
+) -> Result<()> {
+    if config.dependency_policy.is_none() {
+        return Ok(());
+    }
+    let http = HttpClient::new();
+    let api_base = crate::hex::config()?.api_base;
+    let runtime = tokio::runtime::Runtime::new().expect("Unable to start Tokio async runtime");
+    check_dependency_policy_with(paths, config, manifest, |package| {
+        runtime.block_on(policy::HexMetadata::fetch(&http, &api_base, package))
+    })
+}
+
+fn check_dependency_policy_with(
+    paths: &ProjectPaths,
+    config: &PackageConfig,
+    manifest: &Manifest,
+    hex_metadata: impl Fn(&str) -> Result<policy::HexMetadata>,
+) -> Result<()> {
+    let Some(path) = &config.dependency_policy else {
+        return Ok(());
+    };
+    let path = paths.root().join(path);
+    let policy = policy::DependencyPolicy::read(&path)?;
+
+    let packages: Vec<_> = manifest
+        .packages
+        .iter()
+        .filter(|package| package.name != config.name)
+        .sorted_by(|a, b| a.name.cmp(&b.name))
+        .collect();
+    let metadata = packages
+        .iter()
+        .map(|package| match &package.source {
+            ManifestPackageSource::Hex { .. } if policy.checks_metadata() => {
+                hex_metadata(&package.name).map(Some)
+            }
+            _ => Ok(None),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let violations = policy.violations(
+        packages
+            .into_iter()
+            .zip(metadata.iter().map(Option::as_ref)),
+    );
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::DependencyPolicyViolations { path, violations })
+    }
+}
+
+pub fn check_for_incompatible_duplicates(config: &PackageConfig) {
+    for incompatible in config.incompatible_duplicate_requirements() {
+        ConsoleWarningEmitter.emit_warning(Warning::IncompatibleDuplicateRequirements {
+            package: incompatible.package,
+            dependency_range: incompatible.dependency_range,
+            dev_dependency_range: incompatible.dev_dependency_range,
+        });
+    }
+}
+
+/// Hermetic builds can set this to the path of a registry snapshot file, which
+/// is then used to resolve versions instead of the Hex API. See
+/// `dependency::RegistrySnapshot` for its format.
+pub const REGISTRY_SNAPSHOT_ENVIRONMENT_VARIABLE: &str = "GLEAM_REGISTRY_SNAPSHOT";
+
+/// The registry snapshot to resolve versions with, if one is set.
+fn registry_snapshot() -> Result<Option<Rc<RegistrySnapshot>>> {
+    let Ok(path) = std::env::var(REGISTRY_SNAPSHOT_ENVIRONMENT_VARIABLE) else {
+        return Ok(None);
+    };
+    let path = Utf8PathBuf::from(path);
+    let snapshot = RegistrySnapshot::from_json(&fs::read(&path)?).map_err(|e| Error::FileIo {
+        action: FileIoAction::Parse,
+        kind: FileKind::File,
+        path,
+        err: Some(e.to_string()),
+    })?;
+    Ok(Some(Rc::new(snapshot)))
+}
+
+/// Contributors to the standard library can set this to the path of a local
+/// checkout to build projects against it instead of the published version.
+const STDLIB_PATH_ENVIRONMENT_VARIABLE: &str = "GLEAM_STDLIB_PATH";
+
+/// Replace any requirement on the standard library with a requirement on the
+/// local checkout at `path`. The standard library is added as a dependency if
+/// the project doesn't already depend on it directly, so that the override
+/// also applies when it is only a transitive dependency.
+fn override_stdlib_with_local_path(config: &mut PackageConfig, path: Utf8PathBuf) -> Warning {
+    let requirement = Requirement::Path { path: path.clone() };
+    if config.dev_dependencies.contains_key(STDLIB_PACKAGE_NAME)
+        && !config.dependencies.contains_key(STDLIB_PACKAGE_NAME)
+    {
+        _ = config
+            .dev_dependencies
+            .insert(STDLIB_PACKAGE_NAME.into(), requirement);
+    } else {
+        _ = config
+            .dependencies
+            .insert(STDLIB_PACKAGE_NAME.into(), requirement);
+        _ = config.dev_dependencies.remove(STDLIB_PACKAGE_NAME);
+    }
+    Warning::LocalStdlibOverride { path }
+}
+
 pub fn parse_gleam_add_specifier(package: &str) -> Result<(EcoString, Requirement)> {
     let Some((package, version)) = package.split_once('@') else {
         // Default to the latest version available.
@@ -352,10 +564,59 @@ pub fn download<Telem: Telemetry>(
     // manifest which will result in the latest versions of the dependency
     // packages being resolved (not the locked ones).
     use_manifest: UseManifest,
+) -> Result<Manifest> {
+    download_with_options(
+        paths,
+        telemetry,
+        new_package,
+        packages_to_update,
+        use_manifest,
+        &DownloadOptions::default(),
+    )
+}
+
+pub fn download_with_options<Telem: Telemetry>(
+    paths: &ProjectPaths,
+    telemetry: Telem,
+    new_package: Option<(Vec<(EcoString, Requirement)>, bool)>,
+    packages_to_update: Vec<EcoString>,
+    use_manifest: UseManifest,
+    options: &DownloadOptions,
+) -> Result<Manifest> {
+    let local_stdlib = match std::env::var(STDLIB_PATH_ENVIRONMENT_VARIABLE) {
+        Ok(path) => Some(fs::canonicalise(Utf8Path::new(&path))?),
+        Err(_) => None,
+    };
+    download_with_local_stdlib(
+        paths,
+        telemetry,
+        new_package,
+        packages_to_update,
+        use_manifest,
+        options,
+        local_stdlib,
+    )
+}
+
+/// Download the dependencies, resolving versions with the standard library
+/// replaced by the local checkout at `local_stdlib` if one is given.
+///
+/// The override only applies to this build: manifest.toml is not written
+/// with it, so it keeps describing the project's own requirements.
+fn download_with_local_stdlib<Telem: Telemetry>(
+    paths: &ProjectPaths,
+    telemetry: Telem,
+    new_package: Option<(Vec<(EcoString, Requirement)>, bool)>,
+    packages_to_update: Vec<EcoString>,
+    use_manifest: UseManifest,
+    options: &DownloadOptions,
+    local_stdlib: Option<Utf8PathBuf>,
 ) -> Result<Manifest> {
     let span = tracing::info_span!("download_deps");
     let _enter = span.enter();
 
+    let hex_config = configure_network(options)?;
+
     let mode = Mode::Dev;
 
     // We do this before acquiring the build lock so that we don't create the
@@ -371,6 +632,8 @@ pub fn download<Telem: Telemetry>(
     let mut config = crate::root_config(paths)?;
     let project_name = config.name.clone();
 
+    let adding_packages = new_package.is_some();
+
     // Insert the new packages to add, if it exists
     if let Some((packages, dev)) = new_package {
         for (package, requirement) in packages {
@@ -382,6 +645,37 @@ pub fn download<Telem: Telemetry>(
         }
     }
 
+    let overriding_stdlib = local_stdlib.is_some();
+    let mut resolution_config = config.clone();
+    if let Some(path) = local_stdlib {
+        let warning = override_stdlib_with_local_path(&mut resolution_config, path);
+        ConsoleWarningEmitter.emit_warning(warning);
+    }
+    // When asked to prefer the cached state the manifest is used as-is, without
+    // any requests to the package repository, if it is up to date with the
+    // config and all of its packages have already been downloaded.
+    if options.prefer_cached
+        && !adding_packages
+        && packages_to_update.is_empty()
+        && matches!(use_manifest, UseManifest::Yes)
+        && paths.manifest().exists()
+    {
+        let manifest = read_manifest_from_disc(paths)?;
+        let local = LocalPackages::read_from_disc(paths)?;
+        if is_satisfied_by_cache(
+            &manifest,
+            &resolution_config,
+            &local,
+            options.checksum_algo,
+            paths.root(),
+        )? {
+            tracing::debug!("using_cached_packages");
+            check_dependency_policy(paths, &config, &manifest)?;
+            write_resolved_manifest(paths, &config, &manifest, false, overriding_stdlib, options)?;
+            return Ok(manifest);
+        }
+    }
+
     // Start event loop so we can run async functions to call the Hex API
     let runtime = tokio::runtime::Runtime::new().expect("Unable to start Tokio async runtime");
 
@@ -390,86 +684,375 @@ pub fn download<Telem: Telemetry>(
         paths,
         runtime.handle().clone(),
         mode,
-        &config,
+        &resolution_config,
         &telemetry,
         use_manifest,
         packages_to_update,
+        options.checksum_algo,
     )?;
+    check_recorded_checksums(&manifest, options.strict_checksums)?;
+    // Only newly resolved versions are checked so that the warning isn't
+    // repeated on every build.
+    let deep_chain_warning = manifest_updated
+        .then(|| deep_dependency_chain_warning(&config, &manifest))
+        .flatten();
+    if let Some(warning) = deep_chain_warning {
+        ConsoleWarningEmitter.emit_warning(warning);
+    }
+    if manifest_updated {
+        for warning in unbounded_requirement_warnings(&config) {
+            ConsoleWarningEmitter.emit_warning(warning);
+        }
+    }
+    if let Some(warning) = check_retired_packages(&manifest, manifest_updated, options.no_retired)?
+    {
+        ConsoleWarningEmitter.emit_warning(warning);
+    }
+    check_dependency_policy(paths, &config, &manifest)?;
+
+    if options.json_plan_only {
+        write_resolved_manifest(
+            paths,
+            &config,
+            &manifest,
+            manifest_updated,
+            overriding_stdlib,
+            options,
+        )?;
+        let plan = DownloadPlan::new(&manifest, &project_name, &hex_config.repository_base);
+        println!("{}", plan.to_json());
+        return Ok(manifest);
+    }
+
+    // A manifest written elsewhere doesn't describe the packages in the build
+    // directory, so they are left as they are.
+    if options.output_manifest.is_some() {
+        write_resolved_manifest(
+            paths,
+            &config,
+            &manifest,
+            manifest_updated,
+            overriding_stdlib,
+            options,
+        )?;
+        return Ok(manifest);
+    }
+
     let local = LocalPackages::read_from_disc(paths)?;
 
-    // Remove any packages that are no longer required due to gleam.toml changes
-    remove_extra_packages(paths, &local, &manifest, &telemetry)?;
+    if options.print_plan {
+        print_download_plan(
+            std::io::stdout(),
+            &manifest,
+            &local,
+            &project_name,
+            |package| {
+                paths::global_package_cache_package_tarball(
+                    &package.name,
+                    &package.version.to_string(),
+                )
+                .exists()
+            },
+        )?;
+        if !options.yes && !cli::confirm("\nDo you wish to download these packages?")? {
+            println!("Not downloading.");
+            return Ok(manifest);
+        }
+    }
+
+    let (missing_packages, new_local) = if options.since_manifest {
+        let added = local.added_local_packages(&manifest, &project_name);
+        let new_local = local.with_packages(&added);
+        (added, new_local)
+    } else {
+        // Remove any packages that are no longer required due to gleam.toml changes
+        remove_extra_packages(paths, &local, &manifest, &telemetry)?;
+        let missing = local.missing_local_packages(&manifest, &project_name);
+        (missing, LocalPackages::from_manifest(&manifest))
+    };
 
     // Download them from Hex to the local cache
-    runtime.block_on(add_missing_packages(
+    let start = Instant::now();
+    let downloads = runtime.block_on(add_missing_packages(
         paths,
         fs,
-        &manifest,
-        &local,
-        project_name,
+        missing_packages,
+        project_name.clone(),
         &telemetry,
+        Untar::boxed(
+            options.skip_unneeded_files,
+            options.max_package_size_in_bytes(),
+        ),
+        options.concurrency_per_host(),
     ))?;
 
+    if let Some(path) = &options.summary_json {
+        let summary = download_summary(&manifest, downloads, &project_name, start.elapsed());
+        let json = summary.to_json();
+        if path == "-" {
+            println!("{json}");
+        } else {
+            fs::write(path, &json)?;
+        }
+    }
+
     if manifest_updated {
         // Record new state of the packages directory
         // TODO: test
         tracing::debug!("writing_manifest_toml");
-        write_manifest_to_disc(paths, &manifest)?;
+        write_resolved_manifest(
+            paths,
+            &config,
+            &manifest,
+            manifest_updated,
+            overriding_stdlib,
+            options,
+        )?;
+    } else if config.deps_lock && !overriding_stdlib && !paths.deps_lock().exists() {
+        write_deps_lock_to_disc(paths, &manifest)?;
     }
-    LocalPackages::from_manifest(&manifest).write_to_disc(paths)?;
+    new_local.write_to_disc(paths)?;
 
     Ok(manifest)
 }
 
+/// Applies the network options to the HTTP client, returning the Hex config
+/// for the package repository to use.
+fn configure_network(options: &DownloadOptions) -> Result<hexpm::Config> {
+    crate::http::set_network_retries(options.network_retries);
+    let hex_config = crate::hex::config()?;
+    let require_https = crate::hex::https_required(options.require_https);
+    crate::hex::check_download_url(&hex_config.repository_base.to_string(), require_https)?;
+    crate::http::set_require_https(require_https);
+    if crate::hex::check_repository_scheme(&hex_config, options.allow_insecure_http)? {
+        ConsoleWarningEmitter.emit_warning(Warning::InsecureRepositoryUrl {
+            url: hex_config.repository_base.to_string(),
+        });
+    }
+    if let Some(hosts) = &options.allow_network {
+        crate::http::set_allowed_hosts(allowed_hosts(&hex_config, hosts));
+    }
+    Ok(hex_config)
+}
+
+/// Downloads the Hex packages of a plan printed by
+/// `gleam deps download --json-plan-only` to the global package cache,
+/// without resolving versions.
+pub fn download_from_plan(plan_path: &Utf8Path, options: &DownloadOptions) -> Result<()> {
+    let span = tracing::info_span!("download_from_plan");
+    let _enter = span.enter();
+
+    if let Some(progress) = options.progress {
+        cli::set_progress_style(progress);
+    }
+    let hex_config = configure_network(options)?;
+    let plan: DownloadPlan =
+        serde_json::from_str(&fs::read(plan_path)?).map_err(|e| Error::FileIo {
+            action: FileIoAction::Parse,
+            kind: FileKind::File,
+            path: plan_path.to_path_buf(),
+            err: Some(e.to_string()),
+        })?;
+    let require_https = crate::hex::https_required(options.require_https);
+    for package in &plan.packages {
+        crate::hex::check_download_url(&package.url, require_https)?;
+    }
+
+    let io = ProjectIO::boxed();
+    let downloader = hex::Downloader::new(
+        io.clone(),
+        io,
+        HttpClient::boxed(),
+        Untar::boxed(
+            options.skip_unneeded_files,
+            options.max_package_size_in_bytes(),
+        ),
+        hex_config,
+        ProjectPaths::new(fs::get_current_directory()?),
+    )
+    .with_concurrency_per_host(options.concurrency_per_host());
+    let telemetry = cli::Reporter::new();
+    let runtime = tokio::runtime::Runtime::new().expect("Unable to start Tokio async runtime");
+    let start = Instant::now();
+    telemetry.downloading_packages(plan.packages.len());
+    let downloads = runtime.block_on(downloader.download_plan_to_cache(&plan, &telemetry))?;
+    telemetry.packages_downloaded(start, plan.packages.len());
+
+    if let Some(path) = &options.summary_json {
+        let json = DownloadSummary::new(downloads, start.elapsed()).to_json();
+        if path == "-" {
+            println!("{json}");
+        } else {
+            fs::write(path, &json)?;
+        }
+    }
+    Ok(())
+}
+
 async fn add_missing_packages<Telem: Telemetry>(
     paths: &ProjectPaths,
     fs: Box<ProjectIO>,
-    manifest: &Manifest,
-    local: &LocalPackages,
+    missing_packages: Vec<&ManifestPackage>,
     project_name: EcoString,
     telemetry: &Telem,
-) -> Result<(), Error> {
-    let missing_packages = local.missing_local_packages(manifest, &project_name);
-
-    let mut num_to_download = 0;
-
+    untar: Box<Untar>,
+    concurrency_per_host: NonZeroUsize,
+) -> Result<Vec<PackageDownload>, Error> {
     let missing_git_packages = missing_packages
         .iter()
         .copied()
         .filter(|package| package.is_git())
-        .inspect(|_| {
-            num_to_download += 1;
-        })
         .collect_vec();
 
     let mut missing_hex_packages = missing_packages
         .iter()
         .copied()
         .filter(|package| package.is_hex())
-        .inspect(|_| {
-            num_to_download += 1;
-        })
         .peekable();
 
+    let num_to_download = missing_git_packages.len()
+        + missing_packages
+            .iter()
+            .filter(|package| package.is_hex())
+            .count();
+
+    let mut downloads = Vec::new();
+
     // If we need to download at-least one package
     if missing_hex_packages.peek().is_some() || !missing_git_packages.is_empty() {
         let http = HttpClient::boxed();
-        let downloader = hex::Downloader::new(fs.clone(), fs, http, Untar::boxed(), paths.clone());
+        let downloader = hex::Downloader::new(
+            fs.clone(),
+            fs,
+            http,
+            untar,
+            crate::hex::config()?,
+            paths.clone(),
+        )
+        .with_concurrency_per_host(concurrency_per_host);
         let start = Instant::now();
-        telemetry.downloading_package("packages");
-        downloader
-            .download_hex_packages(missing_hex_packages, &project_name)
+        telemetry.downloading_packages(num_to_download);
+        downloads = downloader
+            .download_hex_packages(missing_hex_packages, &project_name, telemetry)
             .await?;
         for package in missing_git_packages {
             let ManifestPackageSource::Git { repo, commit } = &package.source else {
                 continue;
             };
             let _ = download_git_package(&package.name, repo, commit, paths)?;
+            telemetry.downloaded_package(&package.name, &package.version);
         }
         telemetry.packages_downloaded(start, num_to_download);
     }
 
-    Ok(())
+    Ok(downloads)
+}
+
+/// Summarise the Hex packages of the manifest, using the given downloads for
+/// those that were just fetched and treating the rest as already present.
+fn download_summary(
+    manifest: &Manifest,
+    mut downloads: Vec<PackageDownload>,
+    project_name: &str,
+    elapsed: std::time::Duration,
+) -> DownloadSummary {
+    let present = manifest
+        .packages
+        .iter()
+        .filter(|package| package.name != project_name)
+        .filter(|package| {
+            !downloads
+                .iter()
+                .any(|download| download.name == package.name)
+        })
+        .filter_map(PackageDownload::already_present)
+        .collect_vec();
+    downloads.extend(present);
+    DownloadSummary::new(downloads, elapsed)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PlannedDownload {
+    /// The package is already in the build directory at the right version.
+    Present,
+    /// The package is provided by a local path and is never downloaded.
+    Local,
+    /// The package tarball is in the global cache and will be unpacked from
+    /// there.
+    Cached,
+    /// The package will be fetched from its source.
+    Fetch,
+}
+
+impl PlannedDownload {
+    fn for_package(
+        package: &ManifestPackage,
+        local: &LocalPackages,
+        is_cached: &impl Fn(&ManifestPackage) -> bool,
+    ) -> Self {
+        if package.is_local() {
+            Self::Local
+        } else if local.packages.get(package.name.as_str()) == Some(&package.version) {
+            Self::Present
+        } else if package.is_hex() && is_cached(package) {
+            Self::Cached
+        } else {
+            Self::Fetch
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Self::Present => "already downloaded",
+            Self::Local => "local",
+            Self::Cached => "cached",
+            Self::Fetch => "to fetch",
+        }
+    }
+}
+
+fn print_download_plan<W: std::io::Write>(
+    mut buffer: W,
+    manifest: &Manifest,
+    local: &LocalPackages,
+    root: &str,
+    is_cached: impl Fn(&ManifestPackage) -> bool,
+) -> Result<()> {
+    let repository_base = crate::hex::config()?.repository_base;
+    let mut write = || -> std::io::Result<()> {
+        writeln!(buffer, "Download plan:")?;
+        for package in manifest.packages.iter().filter(|p| p.name != root) {
+            let planned = PlannedDownload::for_package(package, local, &is_cached);
+            writeln!(
+                buffer,
+                "  {} {} ({})",
+                package.name,
+                package.version,
+                planned.description()
+            )?;
+            match &package.source {
+                ManifestPackageSource::Hex { outer_checksum } => {
+                    writeln!(
+                        buffer,
+                        "    source: {repository_base}tarballs/{}-{}.tar",
+                        package.name, package.version
+                    )?;
+                    writeln!(buffer, "    checksum: {}", outer_checksum.to_string())?;
+                }
+                ManifestPackageSource::Git { repo, commit } => {
+                    writeln!(buffer, "    source: {repo} at commit {commit}")?;
+                }
+                ManifestPackageSource::Local { path } => {
+                    writeln!(buffer, "    source: {path}")?;
+                }
+            }
+        }
+        Ok(())
+    };
+    write().map_err(|e| Error::StandardIo {
+        action: StandardIoAction::Write,
+        err: Some(e.kind()),
+    })
 }
 
 fn remove_extra_packages<Telem: Telemetry>(
@@ -510,7 +1093,7 @@ fn remove_extra_packages<Telem: Telemetry>(
     Ok(())
 }
 
-fn read_manifest_from_disc(paths: &ProjectPaths) -> Result<Manifest> {
+pub(crate) fn read_manifest_from_disc(paths: &ProjectPaths) -> Result<Manifest> {
     tracing::debug!("reading_manifest_toml");
     let manifest_path = paths.manifest();
     let toml = fs::read(&manifest_path)?;
@@ -523,9 +1106,49 @@ fn read_manifest_from_disc(paths: &ProjectPaths) -> Result<Manifest> {
     Ok(manifest)
 }
 
-fn write_manifest_to_disc(paths: &ProjectPaths, manifest: &Manifest) -> Result<()> {
+fn write_manifest_to_disc(
+    paths: &ProjectPaths,
+    config: &PackageConfig,
+    manifest: &Manifest,
+) -> Result<()> {
     let path = paths.manifest();
-    fs::write(&path, &manifest.to_toml(paths.root()))
+    fs::write(&path, &manifest.to_toml(paths.root()))?;
+    if config.deps_lock {
+        write_deps_lock_to_disc(paths, manifest)?;
+    }
+    Ok(())
+}
+
+/// Writes the manifest to the path given with `--output-manifest`, whether or
+/// not it has changed, leaving the project's manifest.toml untouched.
+/// Otherwise manifest.toml is written if the manifest has changed.
+///
+/// A manifest resolved with the standard library overridden is not written
+/// anywhere, as it doesn't describe the project's own requirements.
+fn write_resolved_manifest(
+    paths: &ProjectPaths,
+    config: &PackageConfig,
+    manifest: &Manifest,
+    manifest_updated: bool,
+    overriding_stdlib: bool,
+    options: &DownloadOptions,
+) -> Result<()> {
+    if overriding_stdlib {
+        return Ok(());
+    }
+    match &options.output_manifest {
+        Some(path) => {
+            tracing::debug!(path = path.as_str(), "writing_output_manifest");
+            fs::write(path, &manifest.to_toml(paths.root()))
+        }
+        None if manifest_updated => write_manifest_to_disc(paths, config, manifest),
+        None => Ok(()),
+    }
+}
+
+fn write_deps_lock_to_disc(paths: &ProjectPaths, manifest: &Manifest) -> Result<()> {
+    tracing::debug!("writing_deps_lock");
+    fs::write(&paths.deps_lock(), &manifest.to_deps_lock(paths.root()))
 }
 
 // This is the container for locally pinned packages, representing the current contents of
@@ -568,6 +1191,34 @@ impl LocalPackages {
             .collect()
     }
 
+    /// The packages of the manifest that are not present at any version, the
+    /// counterpart of `extra_local_packages`. Unlike `missing_local_packages`
+    /// this doesn't include packages that are present at another version.
+    pub fn added_local_packages<'a>(
+        &self,
+        manifest: &'a Manifest,
+        root: &str,
+    ) -> Vec<&'a ManifestPackage> {
+        manifest
+            .packages
+            .iter()
+            .filter(|p| p.name != root)
+            .filter(|p| !p.is_local())
+            .filter(|p| !self.packages.contains_key(p.name.as_str()))
+            .collect()
+    }
+
+    /// These local packages with the given packages added to them.
+    pub fn with_packages(&self, packages: &[&ManifestPackage]) -> Self {
+        let mut local = self.clone();
+        for package in packages {
+            _ = local
+                .packages
+                .insert(package.name.to_string(), package.version.clone());
+        }
+        local
+    }
+
     pub fn read_from_disc(paths: &ProjectPaths) -> Result<Self> {
         let path = paths.build_packages_toml();
         if !path.exists() {
@@ -601,6 +1252,7 @@ impl LocalPackages {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn get_manifest<Telem: Telemetry>(
     paths: &ProjectPaths,
     runtime: tokio::runtime::Handle,
@@ -609,6 +1261,7 @@ fn get_manifest<Telem: Telemetry>(
     telemetry: &Telem,
     use_manifest: UseManifest,
     packages_to_update: Vec<EcoString>,
+    checksum_algorithm: ChecksumAlgorithm,
 ) -> Result<(bool, Manifest)> {
     // If there's no manifest (or we have been asked not to use it) then resolve
     // the versions anew
@@ -625,15 +1278,62 @@ fn get_manifest<Telem: Telemetry>(
     };
 
     if should_resolve {
-        let manifest = resolve_versions(runtime, mode, paths, config, None, telemetry, Vec::new())?;
+        let manifest = resolve_versions(
+            runtime,
+            mode,
+            paths,
+            config,
+            None,
+            telemetry,
+            Vec::new(),
+            checksum_algorithm,
+        )?;
         return Ok((true, manifest));
     }
 
     let manifest = read_manifest_from_disc(paths)?;
 
+    // If the checksums were recorded with a different algorithm (or one this
+    // version of Gleam does not know) then the locked versions are resolved
+    // again so their checksums are fetched and recorded afresh.
+    if manifest.checksum_algorithm != checksum_algorithm {
+        tracing::debug!(
+            recorded = manifest.checksum_algorithm.as_str(),
+            requested = checksum_algorithm.as_str(),
+            "manifest_checksum_algorithm_changed"
+        );
+    }
+
+    // A package locked without a recorded checksum can't be verified when it
+    // is downloaded, so the versions are resolved again to record one.
+    let missing_checksums = !manifest.packages_without_checksums().is_empty();
+    if missing_checksums {
+        tracing::debug!("manifest_missing_checksums");
+    }
+
+    // If the versions are to be resolved as at a different time then the
+    // locked versions may not have been published yet, so they are all
+    // resolved anew.
+    if manifest.published_before != config.published_before {
+        tracing::debug!("manifest_published_before_changed");
+        let manifest = resolve_versions(
+            runtime,
+            mode,
+            paths,
+            config,
+            None,
+            telemetry,
+            Vec::new(),
+            checksum_algorithm,
+        )?;
+        return Ok((true, manifest));
+    }
+
     // If there are no requested updates, and the config is unchanged
     // since the manifest was written then it is up to date so we can return it unmodified.
     if packages_to_update.is_empty()
+        && manifest.checksum_algorithm == checksum_algorithm
+        && !missing_checksums
         && is_same_requirements(
             &manifest.requirements,
             &config.all_direct_dependencies()?,
@@ -652,11 +1352,46 @@ fn get_manifest<Telem: Telemetry>(
             Some(&manifest),
             telemetry,
             packages_to_update,
+            checksum_algorithm,
         )?;
         Ok((true, manifest))
     }
 }
 
+/// The hosts of the Hex API and package repository, followed by any other
+/// hosts that have been allowed.
+fn allowed_hosts(hex_config: &hexpm::Config, hosts: &[String]) -> Vec<String> {
+    [&hex_config.api_base, &hex_config.repository_base]
+        .into_iter()
+        .filter_map(|uri| uri.host())
+        .map(String::from)
+        .chain(hosts.iter().cloned())
+        .collect()
+}
+
+/// Whether the manifest is up to date with the config, has a checksum for
+/// every Hex package, and every one of its packages is already in the build
+/// directory, so there is nothing to download.
+fn is_satisfied_by_cache(
+    manifest: &Manifest,
+    config: &PackageConfig,
+    local: &LocalPackages,
+    checksum_algorithm: ChecksumAlgorithm,
+    root_path: &Utf8Path,
+) -> Result<bool> {
+    Ok(manifest.checksum_algorithm == checksum_algorithm
+        && manifest.published_before == config.published_before
+        && manifest.packages_without_checksums().is_empty()
+        && is_same_requirements(
+            &manifest.requirements,
+            &config.all_direct_dependencies()?,
+            root_path,
+        )?
+        && local
+            .missing_local_packages(manifest, &config.name)
+            .is_empty())
+}
+
 fn is_same_requirements(
     requirements1: &HashMap<EcoString, Requirement>,
     requirements2: &HashMap<EcoString, Requirement>,
@@ -745,6 +1480,7 @@ impl ProvidedPackage {
             build_tools: vec!["gleam".into()],
             requirements: self.requirements.keys().cloned().collect(),
             source: self.source.to_manifest_package_source(),
+            retired: false,
         };
         package.requirements.sort();
         package
@@ -799,6 +1535,7 @@ impl PartialEq for ProvidedPackageSource {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn resolve_versions<Telem: Telemetry>(
     runtime: tokio::runtime::Handle,
     mode: Mode,
@@ -807,6 +1544,7 @@ fn resolve_versions<Telem: Telemetry>(
     manifest: Option<&Manifest>,
     telemetry: &Telem,
     packages_to_update: Vec<EcoString>,
+    checksum_algorithm: ChecksumAlgorithm,
 ) -> Result<Manifest, Error> {
     telemetry.resolving_package_versions();
     let dependencies = config.dependencies_for(mode)?;
@@ -851,24 +1589,36 @@ fn resolve_versions<Telem: Telemetry>(
         .map(|(name, package)| (name.clone(), package.to_hex_package(name)))
         .collect();
 
-    let resolved = dependency::resolve_versions(
-        PackageFetcher::boxed(runtime.clone()),
+    let snapshot = registry_snapshot()?;
+    let fetcher: Box<dyn dependency::PackageFetcher> = match &snapshot {
+        Some(snapshot) => Box::new(snapshot.clone()),
+        None => PackageFetcher::boxed(runtime.clone()),
+    };
+    let fetcher: Box<dyn dependency::PackageFetcher> = match config.published_before {
+        Some(cutoff) => Box::new(dependency::PublishedBefore::new(fetcher, cutoff)),
+        None => fetcher,
+    };
+    let resolved = dependency::resolve_versions_with_step_limit(
+        fetcher,
         provided_hex_packages,
         config.name.clone(),
         root_requirements.into_iter(),
         &locked,
+        config
+            .max_resolution_steps
+            .unwrap_or(dependency::DEFAULT_MAX_RESOLUTION_STEPS),
     )?;
 
     // Convert the hex packages and local packages into manifest packages
-    let manifest_packages = runtime.block_on(future::try_join_all(
-        resolved
-            .into_iter()
-            .map(|(name, version)| lookup_package(name, version, &provided_packages)),
-    ))?;
+    let manifest_packages = runtime.block_on(future::try_join_all(resolved.into_iter().map(
+        |(name, version)| lookup_package(name, version, &provided_packages, snapshot.as_deref()),
+    )))?;
 
     let manifest = Manifest {
         packages: manifest_packages,
         requirements: config.all_direct_dependencies()?,
+        checksum_algorithm,
+        published_before: config.published_before,
     };
 
     Ok(manifest)
@@ -901,7 +1651,7 @@ fn provide_local_package(
     )
 }
 
-fn execute_command(command: &mut Command) -> Result<std::process::Output> {
+pub(crate) fn execute_command(command: &mut Command) -> Result<std::process::Output> {
     let output = command.output().map_err(|error| Error::ShellCommand {
         program: "git".into(),
         reason: ShellCommandFailureReason::IoError(error.kind()),
@@ -1145,13 +1895,20 @@ async fn lookup_package(
     name: String,
     version: Version,
     provided: &HashMap<EcoString, ProvidedPackage>,
+    snapshot: Option<&RegistrySnapshot>,
 ) -> Result<ManifestPackage> {
     match provided.get(name.as_str()) {
         Some(provided_package) => Ok(provided_package.to_manifest_package(name.as_str())),
         None => {
-            let config = hexpm::Config::new();
-            let release =
-                hex::get_package_release(&name, &version, &config, &HttpClient::new()).await?;
+            let release = match snapshot {
+                Some(snapshot) => snapshot.release(&name, &version).cloned().ok_or_else(|| {
+                    Error::Hex(format!("{name} v{version} is not in the registry snapshot"))
+                })?,
+                None => {
+                    let config = crate::hex::config()?;
+                    hex::get_package_release(&name, &version, &config, &HttpClient::new()).await?
+                }
+            };
             let build_tools = release
                 .meta
                 .build_tools
@@ -1163,6 +1920,7 @@ async fn lookup_package(
                 .keys()
                 .map(|s| EcoString::from(s.as_str()))
                 .collect_vec();
+            let retired = release.is_retired();
             Ok(ManifestPackage {
                 name: name.into(),
                 version,
@@ -1172,6 +1930,7 @@ async fn lookup_package(
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(release.outer_checksum),
                 },
+                retired,
             })
         }
     }
@@ -1192,11 +1951,49 @@ impl PackageFetcher {
 }
 
 #[derive(Debug)]
-pub struct Untar;
+pub struct Untar {
+    /// If set then files that are never needed to build a package, such as its
+    /// documentation and tests, are not extracted.
+    skip_unneeded_files: bool,
+    /// The most bytes the files of a package may take up once extracted.
+    max_package_size: u64,
+}
 
 impl Untar {
-    pub fn boxed() -> Box<Self> {
-        Box::new(Self)
+    pub fn boxed(skip_unneeded_files: bool, max_package_size: u64) -> Box<Self> {
+        Box::new(Self {
+            skip_unneeded_files,
+            max_package_size,
+        })
+    }
+
+    fn unpack_files<R: std::io::Read>(
+        &self,
+        path: &Utf8Path,
+        mut archive: tar::Archive<R>,
+    ) -> std::io::Result<()> {
+        std::fs::create_dir_all(path)?;
+        let mut unpacked_size: u64 = 0;
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if self.skip_unneeded_files
+                && !gleam_core::io::is_needed_to_build_package(&entry.path()?)
+            {
+                tracing::trace!(path = ?entry.path()?, "skipping_unneeded_package_file");
+                continue;
+            }
+
+            // The size is checked before anything is written so a package
+            // claiming to be huge can't fill up the disk.
+            unpacked_size = unpacked_size.saturating_add(entry.size());
+            if unpacked_size > self.max_package_size {
+                return Err(std::io::Error::other(PackageSizeLimitExceeded {
+                    limit: self.max_package_size,
+                }));
+            }
+            let _ = entry.unpack_in(path)?;
+        }
+        Ok(())
     }
 }
 
@@ -1211,9 +2008,9 @@ impl TarUnpacker for Untar {
     fn io_result_unpack(
         &self,
         path: &Utf8Path,
-        mut archive: tar::Archive<GzDecoder<tar::Entry<'_, WrappedReader>>>,
+        archive: tar::Archive<GzDecoder<tar::Entry<'_, WrappedReader>>>,
     ) -> std::io::Result<()> {
-        archive.unpack(path)
+        self.unpack_files(path, archive)
     }
 }
 
@@ -1223,7 +2020,7 @@ impl dependency::PackageFetcher for PackageFetcher {
         package: &str,
     ) -> Result<hexpm::Package, Box<dyn std::error::Error>> {
         tracing::debug!(package = package, "looking_up_hex_package");
-        let config = hexpm::Config::new();
+        let config = crate::hex::config().map_err(Box::new)?;
         let request = hexpm::get_package_request(package, None, &config);
         let response = self
             .runtime
@@ -1240,4 +2037,57 @@ impl dependency::PackageFetcher for PackageFetcher {
             },
         }
     }
+
+    fn get_publication_times(
+        &self,
+        package: &str,
+    ) -> Result<HashMap<Version, Timestamp>, Box<dyn std::error::Error>> {
+        // The signed registry doesn't record when releases were published so
+        // the Hex API is asked instead.
+        #[derive(serde::Deserialize)]
+        struct ApiPackage {
+            releases: Vec<ApiRelease>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct ApiRelease {
+            version: Version,
+            inserted_at: Timestamp,
+        }
+
+        tracing::debug!(
+            package = package,
+            "looking_up_hex_package_publication_times"
+        );
+        let config = crate::hex::config().map_err(Box::new)?;
+        let request = http::Request::get(format!("{}packages/{package}", config.api_base))
+            .header("accept", "application/json")
+            .header("user-agent", format!("gleam/{COMPILER_VERSION}"))
+            .body(vec![])?;
+        let response = self
+            .runtime
+            .block_on(self.http.send(request))
+            .map_err(Box::new)?;
+
+        match response.status() {
+            http::StatusCode::OK => (),
+            http::StatusCode::NOT_FOUND => {
+                return Err(format!("I couldn't find a package called `{package}`").into());
+            }
+            status => {
+                return Err(format!(
+                    "I couldn't find when the releases of `{package}` were published, \
+Hex responded with status {status}"
+                )
+                .into());
+            }
+        }
+
+        let package: ApiPackage = serde_json::from_slice(response.body())?;
+        Ok(package
+            .releases
+            .into_iter()
+            .map(|release| (release.version, release.inserted_at))
+            .collect())
+    }
 }
@@ -0,0 +1,197 @@
+//! A dependency policy lets a project restrict which packages its
+//! dependencies may resolve to, such as to keep out copyleft licences or
+//! packages from unknown publishers. It is read from the TOML file named by
+//! `dependency-policy` in gleam.toml:
+//!
+//! ```toml
+//! denied-licences = ["GPL-3.0-only", "AGPL-3.0-only"]
+//! allowed-licences = ["Apache-2.0", "MIT"]
+//! allowed-publishers = ["gleam-lang"]
+//!
+//! [minimum-versions]
+//! gleam_stdlib = "0.40.0"
+//! ```
+//!
+//! Licences and publishers are taken from what Hex records for each package
+//! rather than from its gleam.toml, so that a package can't claim to be
+//! published by someone else. The publishers of a package are the Hex users
+//! that own it, any of whom could publish a new release. Path and Git
+//! dependencies are chosen by the project itself and only their versions are
+//! checked.
+
+use std::collections::HashMap;
+
+use camino::Utf8Path;
+use ecow::{EcoString, eco_format};
+use gleam_core::{
+    Error, Result,
+    error::{FileIoAction, FileKind},
+    manifest::ManifestPackage,
+    version::COMPILER_VERSION,
+};
+use hexpm::version::Version;
+use itertools::Itertools;
+
+use crate::fs;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct DependencyPolicy {
+    /// Licences no dependency may be published under.
+    #[serde(default)]
+    pub denied_licences: Vec<String>,
+    /// If set every dependency must be published under one of these licences.
+    pub allowed_licences: Option<Vec<String>>,
+    /// If set every owner of each dependency on Hex must be one of these
+    /// users.
+    pub allowed_publishers: Option<Vec<String>>,
+    /// The oldest version of each package that may be used.
+    #[serde(default)]
+    pub minimum_versions: HashMap<EcoString, Version>,
+}
+
+impl DependencyPolicy {
+    pub fn read(path: &Utf8Path) -> Result<Self> {
+        let toml = fs::read(path)?;
+        toml::from_str(&toml).map_err(|e| Error::FileIo {
+            action: FileIoAction::Parse,
+            kind: FileKind::File,
+            path: path.to_path_buf(),
+            err: Some(e.to_string()),
+        })
+    }
+
+    /// Whether the Hex metadata of packages is needed to check them.
+    pub fn checks_metadata(&self) -> bool {
+        !self.denied_licences.is_empty()
+            || self.allowed_licences.is_some()
+            || self.allowed_publishers.is_some()
+    }
+
+    /// Each way the resolved packages break the policy, in the order of the
+    /// packages given. A package's metadata is `None` if it isn't from Hex,
+    /// in which case its licences and publishers aren't checked.
+    pub fn violations<'a>(
+        &self,
+        packages: impl IntoIterator<Item = (&'a ManifestPackage, Option<&'a HexMetadata>)>,
+    ) -> Vec<EcoString> {
+        let mut violations = vec![];
+        for (package, metadata) in packages {
+            let name = eco_format!("{} {}", package.name, package.version);
+
+            if let Some(minimum) = self.minimum_versions.get(&package.name)
+                && &package.version < minimum
+            {
+                violations.push(eco_format!(
+                    "{name} is older than the minimum version of {minimum}"
+                ));
+            }
+
+            let Some(metadata) = metadata else {
+                continue;
+            };
+
+            let licences: Vec<_> = metadata.licences.iter().map(String::as_str).collect();
+            for licence in &licences {
+                if self.denied_licences.iter().any(|denied| denied == licence) {
+                    violations.push(eco_format!(
+                        "{name} is published under the denied licence {licence}"
+                    ));
+                }
+            }
+            if let Some(allowed) = &self.allowed_licences
+                && !licences
+                    .iter()
+                    .any(|licence| allowed.iter().any(|allowed| allowed == licence))
+            {
+                violations.push(match licences.as_slice() {
+                    [] => eco_format!("{name} doesn't name its licence"),
+                    _ => eco_format!(
+                        "{name} is published under {}, none of which are allowed",
+                        licences.join(", ")
+                    ),
+                });
+            }
+
+            if let Some(allowed) = &self.allowed_publishers {
+                if metadata.owners.is_empty() {
+                    violations.push(eco_format!("{name} has no owners on Hex"));
+                }
+                for owner in &metadata.owners {
+                    if !allowed.iter().any(|allowed| allowed == owner) {
+                        violations.push(eco_format!(
+                            "{name} is published by {owner}, who is not an allowed publisher"
+                        ));
+                    }
+                }
+            }
+        }
+        violations
+    }
+}
+
+/// What Hex records about a package that a policy checks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HexMetadata {
+    /// The licences in the package's metadata.
+    pub licences: Vec<String>,
+    /// The usernames of the Hex users that own the package, sorted.
+    pub owners: Vec<String>,
+}
+
+impl HexMetadata {
+    /// Looks up a package's licences and owners with the Hex API.
+    pub async fn fetch(
+        http: &impl gleam_core::io::HttpClient,
+        api_base: &http::Uri,
+        package: &str,
+    ) -> Result<Self> {
+        #[derive(serde::Deserialize)]
+        struct ApiPackage {
+            meta: ApiMeta,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct ApiMeta {
+            #[serde(default)]
+            licenses: Vec<String>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct ApiOwner {
+            username: String,
+        }
+
+        let package_info: ApiPackage =
+            get_json(http, format!("{api_base}packages/{package}")).await?;
+        let owners: Vec<ApiOwner> =
+            get_json(http, format!("{api_base}packages/{package}/owners")).await?;
+        Ok(Self {
+            licences: package_info.meta.licenses,
+            owners: owners
+                .into_iter()
+                .map(|owner| owner.username)
+                .sorted()
+                .collect(),
+        })
+    }
+}
+
+async fn get_json<T: serde::de::DeserializeOwned>(
+    http: &impl gleam_core::io::HttpClient,
+    url: String,
+) -> Result<T> {
+    let request = http::Request::get(&url)
+        .header("accept", "application/json")
+        .header("user-agent", format!("gleam/{COMPILER_VERSION}"))
+        .body(vec![])
+        .map_err(Error::http)?;
+    let response = http.send(request).await?;
+    if !response.status().is_success() {
+        return Err(Error::Http(format!(
+            "{url} responded with status {}",
+            response.status()
+        )));
+    }
+    serde_json::from_slice(response.body()).map_err(Error::http)
+}
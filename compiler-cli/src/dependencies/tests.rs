@@ -6,19 +6,23 @@ use hexpm::version::Version;
 use pretty_assertions::assert_eq;
 
 use gleam_core::{
-    Error,
-    build::Runtime,
-    config::{DenoConfig, DenoFlag, Docs, ErlangConfig, JavaScriptConfig, Repository},
-    manifest::{Base16Checksum, Manifest, ManifestPackage, ManifestPackageSource},
+    Error, Warning,
+    build::{EcmaScriptVersion, NullTelemetry, Runtime},
+    config::{DenoConfig, DenoFlag, Docs, ErlangConfig, JavaScriptConfig, PackageKind, Repository},
+    manifest::{
+        Base16Checksum, ChecksumAlgorithm, Manifest, ManifestPackage, ManifestPackageSource,
+    },
     requirement::Requirement,
 };
 
-use crate::dependencies::*;
+use crate::{DownloadOptions, dependencies::*};
 
 #[test]
 fn list_manifest_format() {
     let mut buffer = vec![];
     let manifest = Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
         requirements: HashMap::new(),
         packages: vec![
             ManifestPackage {
@@ -30,6 +34,7 @@ fn list_manifest_format() {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![1, 2, 3, 4]),
                 },
+                retired: false,
             },
             ManifestPackage {
                 name: "aaa".into(),
@@ -40,6 +45,7 @@ fn list_manifest_format() {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![3, 22]),
                 },
+                retired: false,
             },
             ManifestPackage {
                 name: "zzz".into(),
@@ -50,6 +56,7 @@ fn list_manifest_format() {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![3, 22]),
                 },
+                retired: false,
             },
         ],
     };
@@ -63,10 +70,122 @@ zzz 0.4.0
     )
 }
 
+#[test]
+fn print_package_version_for_present_package() {
+    let mut buffer = vec![];
+    let manifest = Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
+        requirements: HashMap::new(),
+        packages: vec![ManifestPackage {
+            name: "gleam_stdlib".into(),
+            version: Version::new(0, 25, 0),
+            build_tools: ["gleam".into()].into(),
+            otp_app: None,
+            requirements: vec![],
+            source: ManifestPackageSource::Hex {
+                outer_checksum: Base16Checksum(vec![3, 22]),
+            },
+            retired: false,
+        }],
+    };
+    print_package_version(&mut buffer, &manifest, "gleam_stdlib").unwrap();
+    assert_eq!(std::str::from_utf8(&buffer).unwrap(), "0.25.0\n");
+}
+
+#[test]
+fn print_package_version_for_absent_package() {
+    let mut buffer = vec![];
+    let manifest = Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
+        requirements: HashMap::new(),
+        packages: vec![],
+    };
+    let result = print_package_version(&mut buffer, &manifest, "gleam_stdlib");
+    assert_eq!(
+        result,
+        Err(Error::PackageNotInManifest {
+            package: "gleam_stdlib".into()
+        })
+    );
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn download_plan_format() {
+    let mut buffer = vec![];
+    let manifest = Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
+        requirements: HashMap::new(),
+        packages: vec![
+            manifest_package("root", "1.0.0", vec![]),
+            manifest_package("cached", "1.0.0", vec![]),
+            manifest_package("present", "2.0.0", vec![]),
+            manifest_package("fetched", "0.1.0", vec![]),
+            ManifestPackage {
+                name: "from_git".into(),
+                version: Version::new(0, 0, 0),
+                build_tools: ["gleam".into()].into(),
+                otp_app: None,
+                requirements: vec![],
+                source: ManifestPackageSource::Git {
+                    repo: "https://github.com/gleam-lang/gleam.git".into(),
+                    commit: "bd9fe02f72250e6a136967917bcb1bdccaffa3c8".into(),
+                },
+                retired: false,
+            },
+            ManifestPackage {
+                name: "from_path".into(),
+                version: Version::new(0, 1, 0),
+                build_tools: ["gleam".into()].into(),
+                otp_app: None,
+                requirements: vec![],
+                source: ManifestPackageSource::Local {
+                    path: "/path/to/from_path".into(),
+                },
+                retired: false,
+            },
+        ],
+    };
+    let local = LocalPackages {
+        packages: [
+            ("present".into(), Version::new(2, 0, 0)),
+            ("fetched".into(), Version::new(0, 0, 1)),
+        ]
+        .into(),
+    };
+    print_download_plan(&mut buffer, &manifest, &local, "root", |package| {
+        package.name == "cached"
+    })
+    .unwrap();
+    assert_eq!(
+        std::str::from_utf8(&buffer).unwrap(),
+        r#"Download plan:
+  cached 1.0.0 (cached)
+    source: https://repo.hex.pm/tarballs/cached-1.0.0.tar
+    checksum: 01020304
+  present 2.0.0 (already downloaded)
+    source: https://repo.hex.pm/tarballs/present-2.0.0.tar
+    checksum: 01020304
+  fetched 0.1.0 (to fetch)
+    source: https://repo.hex.pm/tarballs/fetched-0.1.0.tar
+    checksum: 01020304
+  from_git 0.0.0 (to fetch)
+    source: https://github.com/gleam-lang/gleam.git at commit bd9fe02f72250e6a136967917bcb1bdccaffa3c8
+  from_path 0.1.0 (local)
+    source: /path/to/from_path
+"#
+    )
+}
+
 #[test]
 fn tree_format() {
     let mut buffer = vec![];
     let manifest = Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
         requirements: HashMap::new(),
         packages: vec![
             ManifestPackage {
@@ -78,6 +197,7 @@ fn tree_format() {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![1, 2, 3, 4]),
                 },
+                retired: false,
             },
             ManifestPackage {
                 name: "gleam_stdlib".into(),
@@ -88,6 +208,7 @@ fn tree_format() {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![3, 22]),
                 },
+                retired: false,
             },
             ManifestPackage {
                 name: "gleam_regexp".into(),
@@ -98,6 +219,7 @@ fn tree_format() {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![3, 22]),
                 },
+                retired: false,
             },
         ],
     };
@@ -130,6 +252,8 @@ fn tree_format() {
 fn tree_package_format() {
     let mut buffer = vec![];
     let manifest = Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
         requirements: HashMap::new(),
         packages: vec![
             ManifestPackage {
@@ -141,6 +265,7 @@ fn tree_package_format() {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![3, 22]),
                 },
+                retired: false,
             },
             ManifestPackage {
                 name: "deps_proj".into(),
@@ -151,6 +276,7 @@ fn tree_package_format() {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![1, 2, 3, 4]),
                 },
+                retired: false,
             },
             ManifestPackage {
                 name: "gleam_regexp".into(),
@@ -161,6 +287,7 @@ fn tree_package_format() {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![3, 22]),
                 },
+                retired: false,
             },
         ],
     };
@@ -190,6 +317,8 @@ fn tree_package_format() {
 fn tree_invert_format() {
     let mut buffer = vec![];
     let manifest = Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
         requirements: HashMap::new(),
         packages: vec![
             ManifestPackage {
@@ -201,6 +330,7 @@ fn tree_invert_format() {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![3, 22]),
                 },
+                retired: false,
             },
             ManifestPackage {
                 name: "deps_proj".into(),
@@ -211,6 +341,7 @@ fn tree_invert_format() {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![1, 2, 3, 4]),
                 },
+                retired: false,
             },
             ManifestPackage {
                 name: "gleam_regexp".into(),
@@ -221,6 +352,7 @@ fn tree_invert_format() {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![3, 22]),
                 },
+                retired: false,
             },
         ],
     };
@@ -252,6 +384,8 @@ fn tree_invert_format() {
 fn list_tree_invalid_package_format() {
     let mut buffer = vec![];
     let manifest = Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
         requirements: HashMap::new(),
         packages: vec![
             ManifestPackage {
@@ -263,6 +397,7 @@ fn list_tree_invalid_package_format() {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![3, 22]),
                 },
+                retired: false,
             },
             ManifestPackage {
                 name: "gleam_regexp".into(),
@@ -273,6 +408,7 @@ fn list_tree_invalid_package_format() {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![3, 22]),
                 },
+                retired: false,
             },
             ManifestPackage {
                 name: "root".into(),
@@ -283,6 +419,7 @@ fn list_tree_invalid_package_format() {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![1, 2, 3, 4]),
                 },
+                retired: false,
             },
         ],
     };
@@ -380,6 +517,8 @@ fn parse_gleam_add_specifier_major_minor_and_patch() {
 #[test]
 fn missing_local_packages() {
     let manifest = Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
         requirements: HashMap::new(),
         packages: vec![
             ManifestPackage {
@@ -391,6 +530,7 @@ fn missing_local_packages() {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![1, 2, 3, 4]),
                 },
+                retired: false,
             },
             ManifestPackage {
                 name: "local1".into(),
@@ -401,6 +541,7 @@ fn missing_local_packages() {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![1, 2, 3, 4, 5]),
                 },
+                retired: false,
             },
             ManifestPackage {
                 name: "local2".into(),
@@ -411,6 +552,7 @@ fn missing_local_packages() {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![1, 2, 3, 4, 5]),
                 },
+                retired: false,
             },
         ],
     };
@@ -435,6 +577,7 @@ fn missing_local_packages() {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![1, 2, 3, 4, 5]),
                 },
+                retired: false,
             },
             &ManifestPackage {
                 name: "local2".into(),
@@ -445,6 +588,7 @@ fn missing_local_packages() {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![1, 2, 3, 4, 5]),
                 },
+                retired: false,
             },
         ]
     )
@@ -461,6 +605,8 @@ fn extra_local_packages() {
         .into(),
     }
     .extra_local_packages(&Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
         requirements: HashMap::new(),
         packages: vec![
             ManifestPackage {
@@ -472,6 +618,7 @@ fn extra_local_packages() {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![1, 2, 3, 4, 5]),
                 },
+                retired: false,
             },
             ManifestPackage {
                 name: "local2".into(),
@@ -482,6 +629,7 @@ fn extra_local_packages() {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![4, 5]),
                 },
+                retired: false,
             },
         ],
     });
@@ -495,6 +643,40 @@ fn extra_local_packages() {
     )
 }
 
+#[test]
+fn added_local_packages() {
+    let local = LocalPackages {
+        packages: [
+            ("wibble".into(), Version::new(1, 0, 0)),
+            ("wobble".into(), Version::new(1, 0, 0)),
+        ]
+        .into(),
+    };
+    // The manifest gains `wubble`, and `wobble` is at a new version.
+    let manifest = Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
+        requirements: HashMap::new(),
+        packages: vec![
+            manifest_package("root", "1.0.0", vec![]),
+            manifest_package("wibble", "1.0.0", vec![]),
+            manifest_package("wobble", "2.0.0", vec![]),
+            manifest_package("wubble", "1.0.0", vec![]),
+        ],
+    };
+
+    let added = local.added_local_packages(&manifest, "root");
+    assert_eq!(added, [&manifest_package("wubble", "1.0.0", vec![])]);
+    assert_eq!(
+        local.with_packages(&added).packages,
+        HashMap::from([
+            ("wibble".into(), Version::new(1, 0, 0)),
+            ("wobble".into(), Version::new(1, 0, 0)),
+            ("wubble".into(), Version::new(1, 0, 0)),
+        ])
+    );
+}
+
 #[test]
 fn provide_wrong_package() {
     let mut provided = HashMap::new();
@@ -770,6 +952,7 @@ fn provided_local_to_manifest() {
         source: ManifestPackageSource::Local {
             path: "canonical/path/to/package".into(),
         },
+        retired: false,
     };
 
     assert_eq!(
@@ -809,6 +992,7 @@ fn provided_git_to_manifest() {
             repo: "https://github.com/gleam-lang/gleam.git".into(),
             commit: "bd9fe02f72250e6a136967917bcb1bdccaffa3c8".into(),
         },
+        retired: false,
     };
 
     assert_eq!(
@@ -866,6 +1050,7 @@ fn create_testable_unlock_manifest(
             source: ManifestPackageSource::Hex {
                 outer_checksum: Base16Checksum(vec![]),
             },
+            retired: false,
         })
         .collect();
 
@@ -882,6 +1067,8 @@ fn create_testable_unlock_manifest(
         .collect();
 
     Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
         packages: manifest_packages,
         requirements: root_requirements,
     }
@@ -1172,6 +1359,7 @@ fn manifest_package(name: &str, version: &str, requirements: Vec<EcoString>) ->
         source: ManifestPackageSource::Hex {
             outer_checksum: Base16Checksum(vec![1, 2, 3, 4]),
         },
+        retired: false,
     }
 }
 
@@ -1193,6 +1381,7 @@ fn package_config(
         erlang: ErlangConfig {
             application_start_module: None,
             extra_applications: vec![],
+            module_prefix: None,
         },
         javascript: JavaScriptConfig {
             typescript_declarations: false,
@@ -1210,9 +1399,20 @@ fn package_config(
                 unstable: true,
                 location: None,
             },
+            target_version: EcmaScriptVersion::Es2022,
         },
         target: Target::Erlang,
         internal_modules: None,
+        run_target: None,
+        deps_lock: false,
+        max_dependency_depth: None,
+        warn_unbounded_requirements: false,
+        max_resolution_steps: None,
+        published_before: None,
+        generated_line_length: None,
+        dependency_policy: None,
+        kind: PackageKind::Library,
+        features: vec![],
     }
 }
 
@@ -1224,6 +1424,8 @@ fn test_remove_do_nothing() {
     );
 
     let mut manifest = Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
         requirements: HashMap::from([
             ("a".into(), Requirement::hex("~>1")),
             ("b".into(), Requirement::hex("~>2")),
@@ -1247,6 +1449,8 @@ fn test_remove_simple() {
     let config = package_config(HashMap::new(), HashMap::new());
 
     let mut manifest = Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
         requirements: HashMap::from([("a".into(), Requirement::hex("~>1"))]),
         packages: vec![manifest_package("a", "1.0.0", vec![])],
     };
@@ -1262,6 +1466,8 @@ fn test_remove_package_with_transitive_dependencies() {
     let config = package_config(HashMap::new(), HashMap::new());
 
     let mut manifest = Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
         requirements: HashMap::from([("a".into(), Requirement::hex("~>1"))]),
         packages: vec![
             manifest_package("a", "1.0.0", vec!["b".into()]),
@@ -1284,6 +1490,8 @@ fn test_remove_package_with_shared_transitive_dependencies() {
     );
 
     let mut manifest = Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
         requirements: HashMap::from([
             ("a".into(), Requirement::hex("~>1")),
             ("b".into(), Requirement::hex("~>1")),
@@ -1316,6 +1524,8 @@ fn test_remove_package_that_is_also_a_transitive_dependency() {
     );
 
     let mut manifest = Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
         requirements: HashMap::from([
             ("a".into(), Requirement::hex("~>1")),
             ("b".into(), Requirement::hex("~>1")),
@@ -1335,3 +1545,850 @@ fn test_remove_package_that_is_also_a_transitive_dependency() {
     assert_eq!(manifest.requirements, config.dependencies);
     assert_eq!(manifest.packages, manifest_copy.packages);
 }
+
+#[test]
+fn local_stdlib_override_replaces_hex_requirement() {
+    let mut config = package_config(
+        HashMap::from([
+            ("gleam_stdlib".into(), Requirement::hex("~> 0.44")),
+            ("a".into(), Requirement::hex("~> 1.0")),
+        ]),
+        HashMap::new(),
+    );
+
+    let warning = override_stdlib_with_local_path(&mut config, "/stdlib".into());
+
+    assert_eq!(
+        warning,
+        Warning::LocalStdlibOverride {
+            path: "/stdlib".into()
+        }
+    );
+    assert_eq!(
+        config.dependencies,
+        HashMap::from([
+            (
+                "gleam_stdlib".into(),
+                Requirement::Path {
+                    path: "/stdlib".into()
+                }
+            ),
+            ("a".into(), Requirement::hex("~> 1.0")),
+        ])
+    );
+    assert!(config.dev_dependencies.is_empty());
+}
+
+#[test]
+fn local_stdlib_override_keeps_dev_dependency() {
+    let mut config = package_config(
+        HashMap::new(),
+        HashMap::from([("gleam_stdlib".into(), Requirement::hex("~> 0.44"))]),
+    );
+
+    _ = override_stdlib_with_local_path(&mut config, "/stdlib".into());
+
+    assert!(config.dependencies.is_empty());
+    assert_eq!(
+        config.dev_dependencies,
+        HashMap::from([(
+            "gleam_stdlib".into(),
+            Requirement::Path {
+                path: "/stdlib".into()
+            }
+        )])
+    );
+}
+
+#[test]
+fn local_stdlib_override_applies_to_transitive_dependency() {
+    let mut config = package_config(
+        HashMap::from([("a".into(), Requirement::hex("~> 1.0"))]),
+        HashMap::new(),
+    );
+
+    _ = override_stdlib_with_local_path(&mut config, "/stdlib".into());
+
+    assert_eq!(
+        config.dependencies.get("gleam_stdlib"),
+        Some(&Requirement::Path {
+            path: "/stdlib".into()
+        })
+    );
+}
+
+fn tarball(files: &[(&str, &str)]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for (path, contents) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, path, contents.as_bytes())
+            .unwrap();
+    }
+    builder.into_inner().unwrap()
+}
+
+#[test]
+fn unpack_files_needed_to_build_skips_docs_and_tests() {
+    let tarball = tarball(&[
+        ("gleam.toml", "name = \"wibble\""),
+        ("src/wibble.gleam", "pub fn main() { Nil }"),
+        ("src/wibble_ffi.erl", "-module(wibble_ffi)."),
+        ("priv/data.txt", "data"),
+        ("test/wibble_test.gleam", "pub fn main_test() { Nil }"),
+        ("docs/guide.md", "# Guide"),
+    ]);
+
+    let temp_dir = tempfile::tempdir().expect("Failed to create a temp directory");
+    let destination = Utf8PathBuf::from_path_buf(temp_dir.path().join("wibble")).unwrap();
+    Untar::boxed(true, u64::MAX)
+        .unpack_files(&destination, tar::Archive::new(tarball.as_slice()))
+        .unwrap();
+
+    assert!(destination.join("gleam.toml").is_file());
+    assert!(destination.join("src/wibble.gleam").is_file());
+    assert!(destination.join("src/wibble_ffi.erl").is_file());
+    assert!(destination.join("priv/data.txt").is_file());
+    assert!(!destination.join("test").exists());
+    assert!(!destination.join("docs").exists());
+}
+
+#[test]
+fn unpack_files_within_max_package_size() {
+    let tarball = tarball(&[
+        ("gleam.toml", "name = \"wibble\""),
+        ("src/wibble.gleam", ""),
+    ]);
+    let temp_dir = tempfile::tempdir().expect("Failed to create a temp directory");
+    let destination = Utf8PathBuf::from_path_buf(temp_dir.path().join("wibble")).unwrap();
+    Untar::boxed(false, 15)
+        .unpack_files(&destination, tar::Archive::new(tarball.as_slice()))
+        .unwrap();
+
+    assert!(destination.join("gleam.toml").is_file());
+    assert!(destination.join("src/wibble.gleam").is_file());
+}
+
+#[test]
+fn package_larger_than_max_package_size_is_rejected_and_cleaned_up() {
+    use flate2::{Compression, write::GzEncoder};
+    use gleam_core::{
+        io::{FileSystemWriter, memory::InMemoryFileSystem},
+        paths::ProjectPaths,
+    };
+    use std::io::Write;
+
+    let mut contents = GzEncoder::new(Vec::new(), Compression::default());
+    contents
+        .write_all(&tarball(&[
+            ("gleam.toml", "name = \"wibble\""),
+            ("src/wibble.gleam", &"// padding\n".repeat(100)),
+        ]))
+        .unwrap();
+    let contents = contents.finish().unwrap();
+    let mut builder = tar::Builder::new(Vec::new());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, "contents.tar.gz", contents.as_slice())
+        .unwrap();
+    let outer = builder.into_inner().unwrap();
+
+    // The tarball is read from the in-memory global cache and extracted to a
+    // real temporary project directory.
+    let cache = InMemoryFileSystem::new();
+    let version = Version::new(1, 0, 0);
+    cache
+        .write_bytes(
+            &paths::global_package_cache_package_tarball("wibble", "1.0.0"),
+            &outer,
+        )
+        .unwrap();
+    let temp_dir = tempfile::tempdir().expect("Failed to create a temp directory");
+    let paths = ProjectPaths::new(Utf8PathBuf::from_path_buf(temp_dir.path().into()).unwrap());
+    let downloader = hex::Downloader::new(
+        Box::new(cache),
+        Box::new(ProjectIO::new()),
+        HttpClient::boxed(),
+        Untar::boxed(false, 100),
+        hexpm::Config::new(),
+        paths.clone(),
+    );
+
+    let result = downloader.extract_package_from_cache("wibble", &version);
+
+    assert_eq!(
+        result,
+        Err(Error::PackageTooLarge {
+            package: "wibble".into(),
+            limit: 100
+        })
+    );
+    assert!(!paths.build_packages_package("wibble").exists());
+}
+
+fn cached_manifest() -> Manifest {
+    Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
+        requirements: HashMap::from([("wibble".into(), Requirement::hex(">= 1.0.0"))]),
+        packages: vec![manifest_package("wibble", "1.2.0", vec![])],
+    }
+}
+
+fn cached_local_packages() -> LocalPackages {
+    LocalPackages {
+        packages: [("wibble".into(), Version::parse("1.2.0").unwrap())].into(),
+    }
+}
+
+#[test]
+fn satisfied_by_cache_when_up_to_date_and_downloaded() {
+    let config = package_config(
+        HashMap::from([("wibble".into(), Requirement::hex(">= 1.0.0"))]),
+        HashMap::new(),
+    );
+    assert!(
+        is_satisfied_by_cache(
+            &cached_manifest(),
+            &config,
+            &cached_local_packages(),
+            ChecksumAlgorithm::Sha256,
+            Utf8Path::new("/"),
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn not_satisfied_by_cache_when_requirements_changed() {
+    let config = package_config(
+        HashMap::from([("wibble".into(), Requirement::hex(">= 2.0.0"))]),
+        HashMap::new(),
+    );
+    assert!(
+        !is_satisfied_by_cache(
+            &cached_manifest(),
+            &config,
+            &cached_local_packages(),
+            ChecksumAlgorithm::Sha256,
+            Utf8Path::new("/"),
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn not_satisfied_by_cache_when_package_not_downloaded() {
+    let config = package_config(
+        HashMap::from([("wibble".into(), Requirement::hex(">= 1.0.0"))]),
+        HashMap::new(),
+    );
+    let local = LocalPackages {
+        packages: [("wibble".into(), Version::parse("1.1.0").unwrap())].into(),
+    };
+    assert!(
+        !is_satisfied_by_cache(
+            &cached_manifest(),
+            &config,
+            &local,
+            ChecksumAlgorithm::Sha256,
+            Utf8Path::new("/"),
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn not_satisfied_by_cache_when_a_checksum_is_missing() {
+    let config = package_config(
+        HashMap::from([("wibble".into(), Requirement::hex(">= 1.0.0"))]),
+        HashMap::new(),
+    );
+    let mut manifest = cached_manifest();
+    for package in manifest.packages.iter_mut() {
+        package.source = ManifestPackageSource::Hex {
+            outer_checksum: Base16Checksum(vec![]),
+        };
+    }
+    // Even without strict checksums a package without a checksum can't be
+    // verified, so the versions are resolved again rather than trusting it.
+    assert!(
+        !is_satisfied_by_cache(
+            &manifest,
+            &config,
+            &cached_local_packages(),
+            ChecksumAlgorithm::Sha256,
+            Utf8Path::new("/"),
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn download_prefer_cached_does_nothing_when_satisfied_by_cache() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create a temp directory");
+    let root = Utf8PathBuf::from_path_buf(temp_dir.path().to_path_buf()).unwrap();
+    let paths = ProjectPaths::new(root.clone());
+    fs::write(
+        &paths.root_config(),
+        "name = \"the_package\"\n\n[dependencies]\nwibble = \">= 1.0.0\"\n",
+    )
+    .unwrap();
+    let manifest = cached_manifest();
+    fs::write(&paths.manifest(), &manifest.to_toml(&root)).unwrap();
+
+    // The package is not on Hex, so any request to the package repository
+    // would fail, and the packages file is left as it was written.
+    let packages_toml = "# Written by the test\n[packages]\nwibble = \"1.2.0\"\n";
+    fs::write(&paths.build_packages_toml(), packages_toml).unwrap();
+
+    let options = DownloadOptions {
+        prefer_cached: true,
+        ..Default::default()
+    };
+    let downloaded = download_with_options(
+        &paths,
+        NullTelemetry,
+        None,
+        Vec::new(),
+        UseManifest::Yes,
+        &options,
+    )
+    .unwrap();
+
+    assert_eq!(downloaded, manifest);
+    assert_eq!(
+        fs::read(paths.build_packages_toml()).unwrap(),
+        packages_toml
+    );
+}
+
+#[test]
+fn download_writes_the_manifest_to_the_output_path() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create a temp directory");
+    let root = Utf8PathBuf::from_path_buf(temp_dir.path().to_path_buf()).unwrap();
+    let paths = ProjectPaths::new(root.join("the_package"));
+    fs::write(
+        &paths.root_config(),
+        "name = \"the_package\"\n\n[dependencies]\nwibble = { path = \"../wibble\" }\n",
+    )
+    .unwrap();
+    fs::write(
+        &root.join("wibble/gleam.toml"),
+        "name = \"wibble\"\nversion = \"1.0.0\"\n",
+    )
+    .unwrap();
+    let manifest_toml = "# Written by the test\npackages = []\n\n[requirements]\n";
+    fs::write(&paths.manifest(), manifest_toml).unwrap();
+    let packages_toml = "# Written by the test\n[packages]\n";
+    fs::write(&paths.build_packages_toml(), packages_toml).unwrap();
+
+    let output = root.join("resolved/manifest.toml");
+    let options = DownloadOptions {
+        output_manifest: Some(output.clone()),
+        ..Default::default()
+    };
+    let downloaded = download_with_options(
+        &paths,
+        NullTelemetry,
+        None,
+        Vec::new(),
+        UseManifest::Yes,
+        &options,
+    )
+    .unwrap();
+
+    assert_eq!(
+        downloaded
+            .packages
+            .iter()
+            .map(|package| package.name.as_str())
+            .collect_vec(),
+        vec!["wibble"]
+    );
+    assert_eq!(fs::read(&output).unwrap(), downloaded.to_toml(paths.root()));
+    assert_eq!(fs::read(paths.manifest()).unwrap(), manifest_toml);
+    // The build directory still matches manifest.toml.
+    assert_eq!(
+        fs::read(paths.build_packages_toml()).unwrap(),
+        packages_toml
+    );
+    assert!(!paths.build_packages_package("wibble").exists());
+}
+
+#[test]
+fn download_with_local_stdlib_leaves_the_manifest_as_it_is() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create a temp directory");
+    let root = Utf8PathBuf::from_path_buf(temp_dir.path().to_path_buf()).unwrap();
+    let paths = ProjectPaths::new(root.join("the_package"));
+    fs::write(
+        &paths.root_config(),
+        "name = \"the_package\"\n\n[dependencies]\nwibble = { path = \"../wibble\" }\n",
+    )
+    .unwrap();
+    fs::write(
+        &root.join("wibble/gleam.toml"),
+        "name = \"wibble\"\nversion = \"1.0.0\"\n",
+    )
+    .unwrap();
+    fs::write(
+        &root.join("gleam_stdlib/gleam.toml"),
+        "name = \"gleam_stdlib\"\nversion = \"0.60.0\"\n",
+    )
+    .unwrap();
+    let manifest_toml = "# Written by the test\npackages = []\n\n[requirements]\n";
+    fs::write(&paths.manifest(), manifest_toml).unwrap();
+
+    let downloaded = download_with_local_stdlib(
+        &paths,
+        NullTelemetry,
+        None,
+        Vec::new(),
+        UseManifest::Yes,
+        &DownloadOptions::default(),
+        Some(root.join("gleam_stdlib")),
+    )
+    .unwrap();
+
+    // The local standard library is used for this build, but it is neither
+    // locked in the manifest nor added to its requirements.
+    assert_eq!(
+        downloaded
+            .packages
+            .iter()
+            .map(|package| package.name.as_str())
+            .sorted()
+            .collect_vec(),
+        vec!["gleam_stdlib", "wibble"]
+    );
+    assert_eq!(fs::read(paths.manifest()).unwrap(), manifest_toml);
+}
+
+#[test]
+fn download_with_local_stdlib_does_not_write_the_output_manifest() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create a temp directory");
+    let root = Utf8PathBuf::from_path_buf(temp_dir.path().to_path_buf()).unwrap();
+    let paths = ProjectPaths::new(root.join("the_package"));
+    fs::write(&paths.root_config(), "name = \"the_package\"\n").unwrap();
+    fs::write(
+        &root.join("gleam_stdlib/gleam.toml"),
+        "name = \"gleam_stdlib\"\nversion = \"0.60.0\"\n",
+    )
+    .unwrap();
+
+    let output = root.join("resolved/manifest.toml");
+    let options = DownloadOptions {
+        output_manifest: Some(output.clone()),
+        ..Default::default()
+    };
+    let _ = download_with_local_stdlib(
+        &paths,
+        NullTelemetry,
+        None,
+        Vec::new(),
+        UseManifest::Yes,
+        &options,
+        Some(root.join("gleam_stdlib")),
+    )
+    .unwrap();
+
+    // Like manifest.toml, the output manifest is never written with the
+    // local standard library in it.
+    assert!(!output.exists());
+    assert!(!paths.manifest().exists());
+}
+
+#[test]
+fn allowed_hosts_include_the_hex_repository() {
+    let hex_config =
+        crate::hex::config_with_repository_url(Some("https://mirror.example.com/hex".into()))
+            .unwrap();
+    assert_eq!(
+        allowed_hosts(&hex_config, &["git.example.com".into()]),
+        vec!["hex.pm", "mirror.example.com", "git.example.com"]
+    );
+}
+
+fn chain_manifest() -> Manifest {
+    Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
+        requirements: HashMap::from([("wibble".into(), Requirement::hex(">= 1.0.0"))]),
+        packages: vec![
+            manifest_package("wibble", "1.0.0", vec!["wobble".into()]),
+            manifest_package("wobble", "1.0.0", vec!["wubble".into()]),
+            manifest_package("wubble", "1.0.0", vec![]),
+        ],
+    }
+}
+
+#[test]
+fn deep_dependency_chain_warns() {
+    let mut config = package_config(HashMap::new(), HashMap::new());
+    config.max_dependency_depth = Some(2);
+    assert_eq!(
+        deep_dependency_chain_warning(&config, &chain_manifest()),
+        Some(Warning::DeepDependencyChain {
+            max_depth: 2,
+            chain: vec!["wibble".into(), "wobble".into(), "wubble".into()],
+        })
+    );
+}
+
+#[test]
+fn shallow_dependency_chain_does_not_warn() {
+    let mut config = package_config(HashMap::new(), HashMap::new());
+    config.max_dependency_depth = Some(3);
+    assert_eq!(
+        deep_dependency_chain_warning(&config, &chain_manifest()),
+        None
+    );
+}
+
+#[test]
+fn dependency_depth_is_not_checked_by_default() {
+    let config = package_config(HashMap::new(), HashMap::new());
+    assert_eq!(
+        deep_dependency_chain_warning(&config, &chain_manifest()),
+        None
+    );
+}
+
+fn retired_manifest() -> Manifest {
+    let mut manifest = chain_manifest();
+    for package in manifest.packages.iter_mut() {
+        package.retired = package.name != "wobble";
+    }
+    manifest
+}
+
+#[test]
+fn unbounded_requirements_warn_when_enabled() {
+    let mut config = package_config(
+        HashMap::from([
+            ("wibble".into(), Requirement::hex(">= 1.0.0")),
+            ("wobble".into(), Requirement::hex(">= 1.0.0 and < 2.0.0")),
+        ]),
+        HashMap::new(),
+    );
+    config.warn_unbounded_requirements = true;
+    assert_eq!(
+        unbounded_requirement_warnings(&config),
+        vec![Warning::UnboundedRequirement {
+            package: "wibble".into(),
+            range: ">= 1.0.0".into(),
+            suggestion: ">= 1.0.0 and < 2.0.0".into(),
+        }]
+    );
+}
+
+#[test]
+fn unbounded_requirements_do_not_warn_by_default() {
+    let config = package_config(
+        HashMap::from([("wibble".into(), Requirement::hex(">= 1.0.0"))]),
+        HashMap::new(),
+    );
+    assert_eq!(unbounded_requirement_warnings(&config), vec![]);
+}
+
+#[test]
+fn newly_resolved_retired_packages_warn() {
+    assert_eq!(
+        check_retired_packages(&retired_manifest(), true, false),
+        Ok(Some(Warning::RetiredPackages {
+            packages: vec!["wibble 1.0.0".into(), "wubble 1.0.0".into()],
+        }))
+    );
+}
+
+#[test]
+fn already_locked_retired_packages_do_not_warn_again() {
+    assert_eq!(
+        check_retired_packages(&retired_manifest(), false, false),
+        Ok(None)
+    );
+}
+
+#[test]
+fn retired_packages_are_an_error_when_disallowed() {
+    assert_eq!(
+        check_retired_packages(&retired_manifest(), false, true),
+        Err(Error::RetiredPackages {
+            packages: vec!["wibble 1.0.0".into(), "wubble 1.0.0".into()],
+        })
+    );
+}
+
+#[test]
+fn no_retired_packages_is_fine_when_disallowed() {
+    assert_eq!(
+        check_retired_packages(&chain_manifest(), true, true),
+        Ok(None)
+    );
+}
+
+fn manifest_without_checksum() -> Manifest {
+    let mut manifest = chain_manifest();
+    for package in manifest.packages.iter_mut() {
+        if package.name == "wibble" {
+            package.source = ManifestPackageSource::Hex {
+                outer_checksum: Base16Checksum(vec![]),
+            };
+        }
+    }
+    manifest
+}
+
+#[test]
+fn packages_without_checksums_are_rejected_with_strict_checksums() {
+    assert_eq!(
+        check_recorded_checksums(&manifest_without_checksum(), true),
+        Err(Error::MissingChecksums {
+            packages: vec!["wibble 1.0.0".into()],
+        })
+    );
+}
+
+#[test]
+fn packages_without_checksums_are_used_without_strict_checksums() {
+    assert_eq!(
+        check_recorded_checksums(&manifest_without_checksum(), false),
+        Ok(())
+    );
+}
+
+#[test]
+fn packages_with_checksums_are_used_with_strict_checksums() {
+    assert_eq!(check_recorded_checksums(&chain_manifest(), true), Ok(()));
+}
+
+#[test]
+fn lookup_package_from_registry_snapshot() {
+    let snapshot = RegistrySnapshot::from_json(
+        r#"{
+  "packages": {
+    "wibble": [
+      {
+        "version": "1.2.0",
+        "requirements": {
+          "wobble": { "requirement": ">= 1.0.0", "optional": false }
+        },
+        "outer_checksum": "0A0B",
+        "meta": { "app": "wibble_app", "build_tools": ["gleam"] }
+      }
+    ]
+  }
+}"#,
+    )
+    .expect("snapshot");
+    let runtime = tokio::runtime::Runtime::new().expect("runtime");
+    let package = runtime
+        .block_on(lookup_package(
+            "wibble".into(),
+            Version::new(1, 2, 0),
+            &HashMap::new(),
+            Some(&snapshot),
+        ))
+        .expect("package");
+    assert_eq!(
+        package,
+        ManifestPackage {
+            name: "wibble".into(),
+            version: Version::new(1, 2, 0),
+            build_tools: vec!["gleam".into()],
+            otp_app: Some("wibble_app".into()),
+            requirements: vec!["wobble".into()],
+            source: ManifestPackageSource::Hex {
+                outer_checksum: Base16Checksum(vec![10, 11]),
+            },
+            retired: false,
+        }
+    );
+}
+
+fn write_policy_project(root: &Utf8Path, policy: &str) -> ProjectPaths {
+    let paths = ProjectPaths::new(root.join("app"));
+    fs::write(
+        &paths.root_config(),
+        "name = \"app\"\nversion = \"1.0.0\"\ndependency-policy = \"policy.toml\"\n",
+    )
+    .unwrap();
+    fs::write(&paths.root().join("policy.toml"), policy).unwrap();
+    paths
+}
+
+fn policy_manifest(root: &Utf8Path) -> Manifest {
+    let local = ManifestPackage {
+        source: ManifestPackageSource::Local {
+            path: root.join("wubble"),
+        },
+        ..manifest_package("wubble", "0.1.0", vec![])
+    };
+    Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
+        requirements: HashMap::new(),
+        packages: vec![
+            manifest_package("wobble", "0.2.0", vec![]),
+            manifest_package("wibble", "1.0.0", vec![]),
+            local,
+        ],
+    }
+}
+
+/// What Hex records for the packages of the policy manifest. The gleam.toml
+/// of a package plays no part, so it can't claim another publisher.
+fn policy_hex_metadata(package: &str) -> Result<policy::HexMetadata> {
+    Ok(match package {
+        "wibble" => policy::HexMetadata {
+            licences: vec!["GPL-3.0-only".into()],
+            owners: vec!["someone".into()],
+        },
+        "wobble" => policy::HexMetadata {
+            licences: vec!["Apache-2.0".into()],
+            owners: vec!["gleam-lang".into()],
+        },
+        _ => panic!("{package} is not from Hex"),
+    })
+}
+
+#[test]
+fn dependency_policy_violations_are_itemised() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create a temp directory");
+    let root = Utf8PathBuf::from_path_buf(temp_dir.path().to_path_buf()).unwrap();
+    let paths = write_policy_project(
+        &root,
+        r#"denied-licences = ["GPL-3.0-only"]
+allowed-publishers = ["gleam-lang"]
+
+[minimum-versions]
+wobble = "1.0.0"
+wubble = "1.0.0"
+"#,
+    );
+    let config = crate::config::root_config(&paths).unwrap();
+
+    assert_eq!(
+        check_dependency_policy_with(
+            &paths,
+            &config,
+            &policy_manifest(&root),
+            policy_hex_metadata
+        ),
+        Err(Error::DependencyPolicyViolations {
+            path: paths.root().join("policy.toml"),
+            violations: vec![
+                "wibble 1.0.0 is published under the denied licence GPL-3.0-only".into(),
+                "wibble 1.0.0 is published by someone, who is not an allowed publisher".into(),
+                "wobble 0.2.0 is older than the minimum version of 1.0.0".into(),
+                "wubble 0.1.0 is older than the minimum version of 1.0.0".into(),
+            ],
+        })
+    );
+}
+
+#[test]
+fn dependencies_following_the_policy_are_accepted() {
+    let temp_dir = tempfile::tempdir().expect("Failed to create a temp directory");
+    let root = Utf8PathBuf::from_path_buf(temp_dir.path().to_path_buf()).unwrap();
+    let paths = write_policy_project(
+        &root,
+        r#"allowed-licences = ["Apache-2.0", "GPL-3.0-only"]
+allowed-publishers = ["gleam-lang", "someone"]
+
+[minimum-versions]
+wobble = "0.1.0"
+"#,
+    );
+    let config = crate::config::root_config(&paths).unwrap();
+
+    assert_eq!(
+        check_dependency_policy_with(
+            &paths,
+            &config,
+            &policy_manifest(&root),
+            policy_hex_metadata
+        ),
+        Ok(())
+    );
+}
+
+#[test]
+fn dependency_policy_checks_every_hex_owner() {
+    let policy = policy::DependencyPolicy {
+        allowed_publishers: Some(vec!["gleam-lang".into()]),
+        ..Default::default()
+    };
+    let package = manifest_package("wibble", "1.0.0", vec![]);
+    let metadata = policy::HexMetadata {
+        licences: vec![],
+        owners: vec!["gleam-lang".into(), "someone".into()],
+    };
+    assert_eq!(
+        policy.violations([(&package, Some(&metadata))]),
+        vec![EcoString::from(
+            "wibble 1.0.0 is published by someone, who is not an allowed publisher"
+        )]
+    );
+}
+
+#[test]
+fn dependency_policy_reports_a_missing_licence() {
+    let policy = policy::DependencyPolicy {
+        allowed_licences: Some(vec!["MIT".into()]),
+        ..Default::default()
+    };
+    let package = manifest_package("wibble", "1.0.0", vec![]);
+    assert_eq!(
+        policy.violations([(&package, Some(&policy::HexMetadata::default()))]),
+        vec![EcoString::from("wibble 1.0.0 doesn't name its licence")]
+    );
+}
+
+struct MockHexApi;
+
+#[async_trait::async_trait]
+impl gleam_core::io::HttpClient for MockHexApi {
+    async fn send(
+        &self,
+        request: http::Request<Vec<u8>>,
+    ) -> Result<http::Response<Vec<u8>>, Error> {
+        let body = match request.uri().path() {
+            "/api/packages/wibble" => {
+                r#"{"name": "wibble", "meta": {"licenses": ["MIT"], "links": {}}}"#
+            }
+            "/api/packages/wibble/owners" => {
+                r#"[{"username": "someone", "email": "someone@example.com"}, {"username": "gleam-lang"}]"#
+            }
+            _ => return Ok(http::Response::builder().status(404).body(vec![]).unwrap()),
+        };
+        Ok(http::Response::builder()
+            .status(200)
+            .body(body.as_bytes().to_vec())
+            .unwrap())
+    }
+}
+
+#[test]
+fn hex_metadata_is_looked_up_with_the_hex_api() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let api_base: http::Uri = "https://hex.pm/api/".parse().unwrap();
+    assert_eq!(
+        runtime.block_on(policy::HexMetadata::fetch(&MockHexApi, &api_base, "wibble")),
+        Ok(policy::HexMetadata {
+            licences: vec!["MIT".into()],
+            owners: vec!["gleam-lang".into(), "someone".into()],
+        })
+    );
+    assert!(
+        runtime
+            .block_on(policy::HexMetadata::fetch(&MockHexApi, &api_base, "wobble"))
+            .is_err()
+    );
+}
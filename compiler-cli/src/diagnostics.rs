@@ -0,0 +1,307 @@
+use std::{
+    cell::{Cell, RefCell},
+    io::Write,
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use gleam_core::{
+    Error, Result, Warning,
+    diagnostic::{Diagnostic, Level},
+    error::StandardIoAction,
+    line_numbers::LineNumbers,
+    warning::WarningEmitterIO,
+};
+
+use crate::fs::{self, ConsoleWarningEmitter};
+
+#[cfg(test)]
+mod tests;
+
+/// How the warnings and errors from a build are to be reported, in addition
+/// to the default of pretty printing them.
+#[derive(Debug, Clone, Default)]
+pub struct Reporting {
+    /// Write the diagnostics as JSON to this file, or to stdout if it is `-`.
+    /// Warnings are not pretty printed when this is set.
+    pub json_path: Option<Utf8PathBuf>,
+    /// Stream each diagnostic to stdout as a JSON object on its own line as
+    /// soon as it is produced, followed by a line with the totals. Warnings
+    /// are neither kept in memory nor pretty printed when this is set.
+    pub json_lines: bool,
+    /// Print a one line summary of how many errors and warnings there were.
+    pub summary: bool,
+    /// The format to print the diagnostics in once the build has finished,
+    /// in addition to pretty printing them.
+    pub format: ErrorFormat,
+}
+
+/// A format diagnostics can be printed in for other tools to read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+#[clap(rename_all = "lower")]
+pub enum ErrorFormat {
+    /// Only pretty printed for people to read
+    #[default]
+    Human,
+    /// As GitHub Actions workflow commands, so they are shown as annotations
+    /// on the lines of code they are for
+    Github,
+}
+
+impl Reporting {
+    fn prints_warnings(&self) -> bool {
+        self.json_path.is_none() && !self.json_lines
+    }
+
+    /// Report the warnings collected during a build along with the error it
+    /// failed with, if any.
+    pub fn report(&self, warnings: &CollectingWarningEmitter, error: Option<&Error>) -> Result<()> {
+        let diagnostics = diagnostics(&warnings.take(), error);
+
+        if let Some(stream) = &warnings.stream {
+            for diagnostic in &diagnostics {
+                stream.write(diagnostic);
+            }
+            stream.finish()?;
+        }
+
+        if let Some(path) = &self.json_path {
+            let json = json(&diagnostics);
+            if path == "-" {
+                println!("{json}");
+            } else {
+                fs::write(path, &json)?;
+            }
+        }
+
+        if self.format == ErrorFormat::Github {
+            let directory = fs::get_current_directory()?;
+            let mut stdout = std::io::stdout();
+            for diagnostic in &diagnostics {
+                let annotation = github_annotation(diagnostic, &directory);
+                writeln!(stdout, "{annotation}").map_err(|e| Error::StandardIo {
+                    action: StandardIoAction::Write,
+                    err: Some(e.kind()),
+                })?;
+            }
+        }
+
+        if self.summary {
+            let mut stdout = std::io::stdout();
+            writeln!(stdout, "{}", summary(&diagnostics)).map_err(|e| Error::StandardIo {
+                action: StandardIoAction::Write,
+                err: Some(e.kind()),
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A warning emitter that keeps hold of every warning so they can be reported
+/// once the build has finished, pretty printing them as they are emitted
+/// unless they are to be reported in another format. When streaming JSON
+/// lines the warnings are written as they are emitted instead of being kept.
+#[derive(Debug)]
+pub struct CollectingWarningEmitter {
+    print: bool,
+    warnings: RefCell<Vec<Warning>>,
+    stream: Option<JsonLines<std::io::Stdout>>,
+}
+
+impl CollectingWarningEmitter {
+    pub fn new(reporting: &Reporting) -> Self {
+        Self {
+            print: reporting.prints_warnings(),
+            warnings: RefCell::new(Vec::new()),
+            stream: reporting
+                .json_lines
+                .then(|| JsonLines::new(std::io::stdout())),
+        }
+    }
+
+    pub fn take(&self) -> Vec<Warning> {
+        self.warnings.take()
+    }
+}
+
+impl WarningEmitterIO for CollectingWarningEmitter {
+    fn emit_warning(&self, warning: Warning) {
+        if let Some(stream) = &self.stream {
+            stream.write(&warning.to_diagnostic());
+            return;
+        }
+        if self.print {
+            ConsoleWarningEmitter.emit_warning(warning.clone());
+        }
+        self.warnings.borrow_mut().push(warning);
+    }
+}
+
+fn diagnostics(warnings: &[Warning], error: Option<&Error>) -> Vec<Diagnostic> {
+    warnings
+        .iter()
+        .map(Warning::to_diagnostic)
+        .chain(error.into_iter().flat_map(Error::to_diagnostics))
+        .collect()
+}
+
+fn json(diagnostics: &[Diagnostic]) -> String {
+    let diagnostics: Vec<_> = diagnostics.iter().map(Diagnostic::to_json).collect();
+    serde_json::to_string_pretty(&diagnostics).expect("diagnostics JSON serialisation")
+}
+
+fn summary(diagnostics: &[Diagnostic]) -> String {
+    Counts::of(diagnostics).summary()
+}
+
+/// A diagnostic as a GitHub Actions workflow command, such as:
+///
+/// ```text
+/// ::warning file=src/wibble.gleam,line=2,col=3,endLine=2,endColumn=9,title=Unused variable::This variable is never used
+/// ```
+///
+/// GitHub expects paths relative to the repository, so paths are made
+/// relative to the directory the build is run in. The location is left out if
+/// the diagnostic doesn't have one, and newlines in the message are escaped so
+/// the command stays on a single line.
+fn github_annotation(diagnostic: &Diagnostic, directory: &Utf8Path) -> String {
+    let command = match diagnostic.level {
+        Level::Error => "error",
+        Level::Warning => "warning",
+    };
+
+    let mut properties = vec![];
+    if let Some(location) = &diagnostic.location {
+        let line_numbers = LineNumbers::new(&location.src);
+        let start = line_numbers.line_and_column_number(location.label.span.start);
+        let end = line_numbers.line_and_column_number(location.label.span.end);
+        let path = location
+            .path
+            .strip_prefix(directory)
+            .unwrap_or(&location.path);
+        properties.push(format!("file={}", escape_property(path.as_str())));
+        properties.push(format!("line={}", start.line));
+        properties.push(format!("col={}", start.column));
+        properties.push(format!("endLine={}", end.line));
+        properties.push(format!("endColumn={}", end.column));
+    }
+    properties.push(format!("title={}", escape_property(&diagnostic.title)));
+
+    let mut message = diagnostic.text.trim().to_string();
+    if let Some(hint) = &diagnostic.hint {
+        message.push_str(&format!("\nHint: {hint}"));
+    }
+    if message.is_empty() {
+        message = diagnostic.title.clone();
+    }
+    format!(
+        "::{command} {}::{}",
+        properties.join(","),
+        escape_data(message.trim())
+    )
+}
+
+fn escape_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(property: &str) -> String {
+    escape_data(property)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+/// How many diagnostics of each level there have been.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+struct Counts {
+    errors: usize,
+    warnings: usize,
+}
+
+impl Counts {
+    fn of(diagnostics: &[Diagnostic]) -> Self {
+        let mut counts = Self::default();
+        for diagnostic in diagnostics {
+            counts.add(diagnostic);
+        }
+        counts
+    }
+
+    fn add(&mut self, diagnostic: &Diagnostic) {
+        match diagnostic.level {
+            Level::Error => self.errors += 1,
+            Level::Warning => self.warnings += 1,
+        }
+    }
+
+    fn summary(&self) -> String {
+        let plural = |count: usize, noun: &str| match count {
+            1 => format!("1 {noun}"),
+            _ => format!("{count} {noun}s"),
+        };
+        format!(
+            "{}, {}",
+            plural(self.errors, "error"),
+            plural(self.warnings, "warning")
+        )
+    }
+}
+
+/// Writes each diagnostic as a JSON object on its own line as soon as it is
+/// produced, only keeping count of them, and then a final line with the
+/// totals:
+///
+/// ```json
+/// {"summary":{"errors":1,"warnings":2}}
+/// ```
+///
+/// Writing to the output can't fail part way through a build, so the first
+/// error is kept and returned once the stream is finished.
+#[derive(Debug)]
+struct JsonLines<W> {
+    output: RefCell<W>,
+    counts: Cell<Counts>,
+    write_error: Cell<Option<std::io::ErrorKind>>,
+}
+
+impl<W: Write> JsonLines<W> {
+    fn new(output: W) -> Self {
+        Self {
+            output: RefCell::new(output),
+            counts: Cell::new(Counts::default()),
+            write_error: Cell::new(None),
+        }
+    }
+
+    fn write(&self, diagnostic: &Diagnostic) {
+        let mut counts = self.counts.get();
+        counts.add(diagnostic);
+        self.counts.set(counts);
+        let json =
+            serde_json::to_string(&diagnostic.to_json()).expect("diagnostic JSON serialisation");
+        self.write_line(&json);
+    }
+
+    fn finish(&self) -> Result<()> {
+        let summary = serde_json::json!({ "summary": self.counts.get() });
+        self.write_line(&summary.to_string());
+        match self.write_error.take() {
+            Some(kind) => Err(Error::StandardIo {
+                action: StandardIoAction::Write,
+                err: Some(kind),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn write_line(&self, line: &str) {
+        let mut output = self.output.borrow_mut();
+        if let Err(error) = writeln!(output, "{line}").and_then(|_| output.flush())
+            && self.write_error.get().is_none()
+        {
+            self.write_error.set(Some(error.kind()));
+        }
+    }
+}
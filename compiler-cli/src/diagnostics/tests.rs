@@ -0,0 +1,115 @@
+use camino::{Utf8Path, Utf8PathBuf};
+use gleam_core::{Error, Warning};
+
+use super::{JsonLines, diagnostics, github_annotation, json, summary};
+
+fn warning(package: &str) -> Warning {
+    Warning::IncompatibleDuplicateRequirements {
+        package: package.into(),
+        dependency_range: "~> 1.0".into(),
+        dev_dependency_range: "~> 2.0".into(),
+    }
+}
+
+fn error() -> Error {
+    Error::NonUtf8File {
+        path: Utf8PathBuf::from("src/wibble.gleam"),
+        src: "pub fn main() {\n  \"caf\u{FFFD}\"\n}\n".into(),
+        byte_offset: 22,
+    }
+}
+
+#[test]
+fn summary_counts() {
+    let diagnostics = diagnostics(&[warning("a"), warning("b")], Some(&error()));
+    assert_eq!(summary(&diagnostics), "1 error, 2 warnings");
+}
+
+#[test]
+fn summary_without_diagnostics() {
+    assert_eq!(summary(&diagnostics(&[], None)), "0 errors, 0 warnings");
+}
+
+#[test]
+fn json_and_summary_agree() {
+    let diagnostics = diagnostics(&[warning("a"), warning("b")], Some(&error()));
+    let json: serde_json::Value =
+        serde_json::from_str(&json(&diagnostics)).expect("diagnostics JSON");
+    let json = json.as_array().expect("JSON array of diagnostics");
+    let levels: Vec<_> = json
+        .iter()
+        .map(|diagnostic| diagnostic["level"].as_str().expect("level"))
+        .collect();
+
+    assert_eq!(levels, vec!["warning", "warning", "error"]);
+    assert_eq!(summary(&diagnostics), "1 error, 2 warnings");
+}
+
+#[test]
+fn json_format() {
+    let diagnostics = diagnostics(&[], Some(&error()));
+    insta::assert_snapshot!(json(&diagnostics));
+}
+
+#[test]
+fn json_lines_are_written_as_diagnostics_are_produced() {
+    let stream = JsonLines::new(Vec::new());
+    let lines = || {
+        String::from_utf8(stream.output.borrow().clone())
+            .expect("UTF-8 output")
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("JSON line"))
+            .collect::<Vec<serde_json::Value>>()
+    };
+
+    stream.write(&warning("a").to_diagnostic());
+    assert_eq!(lines().len(), 1);
+    assert_eq!(
+        lines().first().map(|line| &line["level"]),
+        Some(&"warning".into())
+    );
+
+    stream.write(&warning("b").to_diagnostic());
+    for diagnostic in error().to_diagnostics() {
+        stream.write(&diagnostic);
+    }
+    assert_eq!(lines().len(), 3);
+    assert_eq!(
+        lines().last().map(|line| &line["level"]),
+        Some(&"error".into())
+    );
+
+    stream.finish().expect("finish");
+    let lines = lines();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(
+        lines.last(),
+        Some(&serde_json::json!({ "summary": { "errors": 1, "warnings": 2 } }))
+    );
+}
+
+#[test]
+fn github_annotation_format() {
+    let error = Error::NonUtf8File {
+        path: Utf8PathBuf::from("/project/src/wibble.gleam"),
+        src: "pub fn main() {\n  \"caf\u{FFFD}\"\n}\n".into(),
+        byte_offset: 22,
+    };
+    let diagnostics = diagnostics(&[warning("a")], Some(&error));
+    let annotations: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| github_annotation(diagnostic, Utf8Path::new("/project")))
+        .collect();
+    assert_eq!(
+        annotations,
+        vec![
+            "::warning title=Incompatible requirements::The package `a` is required with the \
+version range `~> 1.0` in%0A`dependencies` and `~> 2.0` in `dev-dependencies`. No version can \
+satisfy%0Aboth of these ranges, so dependency resolution will fail.%0AHint: Remove one of the \
+requirements for `a` from your gleam.toml.",
+            "::error file=src/wibble.gleam,line=2,col=7,endLine=2,endColumn=10,\
+title=Invalid UTF-8::The file is not valid UTF-8. The first invalid byte is at offset 22. \
+Gleam%0Aonly supports UTF-8 encoded source files.",
+        ]
+    );
+}
@@ -2,6 +2,7 @@ use std::time::{Instant, SystemTime};
 
 use camino::{Utf8Path, Utf8PathBuf};
 use ecow::EcoString;
+use hexpm::version::Version;
 
 use crate::{cli, fs::ProjectIO, http::HttpClient};
 use gleam_core::{
@@ -12,7 +13,7 @@ use gleam_core::{
     docs::DocContext,
     error::Error,
     hex,
-    io::HttpClient as _,
+    io::{HttpClient as _, OutputFile},
     paths::ProjectPaths,
     type_,
 };
@@ -59,6 +60,11 @@ pub fn build(paths: &ProjectPaths, options: BuildOptions) -> Result<()> {
             warnings_as_errors: false,
             root_target_support: TargetSupport::Enforced,
             no_print_progress: false,
+            compile_tests: false,
+            changed_modules: None,
+            features: vec![],
+            javascript_target_version: None,
+            coverage: false,
         },
         crate::build::download_dependencies(paths, cli::Reporter::new())?,
     )?;
@@ -109,7 +115,7 @@ pub(crate) fn build_documentation(
     compiled: &mut Package,
     is_hex_publish: DocContext,
     cached_modules: &im::HashMap<EcoString, type_::ModuleInterface>,
-) -> Result<Vec<gleam_core::io::OutputFile>, Error> {
+) -> Result<Vec<OutputFile>, Error> {
     compiled.attach_doc_and_module_comments();
     cli::print_generating_documentation();
     let mut pages = vec![DocsPage {
@@ -136,13 +142,25 @@ pub(crate) fn build_documentation(
     Ok(outputs)
 }
 
-pub fn publish(paths: &ProjectPaths) -> Result<()> {
+pub fn publish(paths: &ProjectPaths, dry_run: bool) -> Result<()> {
     let config = crate::config::root_config(paths)?;
+    crate::publish::check_config_for_publishing(&config)?;
 
     let runtime = tokio::runtime::Runtime::new().expect("Unable to start Tokio async runtime");
     let hex_config = hexpm::Config::new();
-    let api_key =
-        crate::hex::HexAuthentication::new(&runtime, hex_config.clone()).get_or_create_api_key()?;
+    let api_key = if dry_run {
+        None
+    } else {
+        let api_key = crate::hex::HexAuthentication::new(&runtime, hex_config.clone())
+            .get_or_create_api_key()?;
+        runtime.block_on(check_version_published(
+            &config.name,
+            &config.version,
+            &hex_config,
+            &HttpClient::new(),
+        ))?;
+        Some(api_key)
+    };
 
     // Reset the build directory so we know the state of the project
     crate::fs::delete_directory(&paths.build_directory_for_target(Mode::Prod, config.target))?;
@@ -157,6 +175,11 @@ pub fn publish(paths: &ProjectPaths) -> Result<()> {
             mode: Mode::Prod,
             target: None,
             no_print_progress: false,
+            compile_tests: false,
+            changed_modules: None,
+            features: vec![],
+            javascript_target_version: None,
+            coverage: false,
         },
         crate::build::download_dependencies(paths, cli::Reporter::new())?,
     )?;
@@ -167,6 +190,17 @@ pub fn publish(paths: &ProjectPaths) -> Result<()> {
         DocContext::HexPublish,
         &built.module_interfaces,
     )?;
+
+    let Some(api_key) = api_key else {
+        let path = write_docs_tarball(paths, &config, outputs)?;
+        println!(
+            "
+The documentation tarball has been written to {path}
+It has not been published.
+"
+        );
+        return Ok(());
+    };
     let archive = crate::fs::create_tar_archive(outputs)?;
 
     let start = Instant::now();
@@ -182,3 +216,128 @@ pub fn publish(paths: &ProjectPaths) -> Result<()> {
     cli::print_published(start.elapsed());
     Ok(())
 }
+
+/// HexDocs only accepts documentation for versions that have been published.
+async fn check_version_published(
+    name: &str,
+    version: &Version,
+    config: &hexpm::Config,
+    http: &impl gleam_core::io::HttpClient,
+) -> Result<()> {
+    let request = hexpm::get_package_release_request(name, &version.to_string(), None, config);
+    let response = http.send(request).await?;
+    match hexpm::get_package_release_response(response) {
+        Ok(_) => Ok(()),
+        Err(hexpm::ApiError::NotFound) => Err(Error::DocsVersionNotPublished {
+            package: name.into(),
+            version: version.to_string(),
+        }),
+        Err(error) => Err(Error::hex(error)),
+    }
+}
+
+/// Write the tarball that would be uploaded to HexDocs to the build directory.
+fn write_docs_tarball(
+    paths: &ProjectPaths,
+    config: &PackageConfig,
+    outputs: Vec<OutputFile>,
+) -> Result<Utf8PathBuf> {
+    let archive = crate::fs::create_tar_archive(outputs)?;
+    let path = paths.build_export_docs_tarball(&config.name, &config.version.to_string());
+    crate::fs::write_bytes(&path, &archive)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use gleam_core::io::Content;
+    use http::Response;
+    use std::io::Read;
+
+    struct MockHex {
+        status: u16,
+    }
+
+    #[async_trait]
+    impl gleam_core::io::HttpClient for MockHex {
+        async fn send(&self, _request: http::Request<Vec<u8>>) -> Result<Response<Vec<u8>>> {
+            Ok(Response::builder()
+                .status(self.status)
+                .body(vec![])
+                .expect("mock response"))
+        }
+    }
+
+    #[test]
+    fn dry_run_docs_tarball_contents() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let paths = ProjectPaths::new(Utf8PathBuf::from_path_buf(dir.path().into()).unwrap());
+        let config = PackageConfig {
+            name: "wibble".into(),
+            version: Version::new(1, 2, 3),
+            ..Default::default()
+        };
+        let outputs = vec![
+            OutputFile {
+                path: "index.html".into(),
+                content: Content::Text("<h1>wibble</h1>".into()),
+            },
+            OutputFile {
+                path: "wibble/wobble.html".into(),
+                content: Content::Text("<h1>wibble/wobble</h1>".into()),
+            },
+            OutputFile {
+                path: "package-interface.json".into(),
+                content: Content::Text("{}".into()),
+            },
+        ];
+
+        let path = write_docs_tarball(&paths, &config, outputs).unwrap();
+        assert_eq!(
+            path,
+            paths.build_directory().join("wibble-1.2.3-docs.tar.gz")
+        );
+
+        let tarball = std::fs::read(&path).unwrap();
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(tarball.as_slice()));
+        let contents: Vec<_> = archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let path = entry.path().unwrap().to_string_lossy().to_string();
+                let mut content = String::new();
+                let _ = entry.read_to_string(&mut content).unwrap();
+                (path, content)
+            })
+            .collect();
+        assert_eq!(
+            contents,
+            vec![
+                ("index.html".into(), "<h1>wibble</h1>".into()),
+                ("wibble/wobble.html".into(), "<h1>wibble/wobble</h1>".into()),
+                ("package-interface.json".into(), "{}".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn docs_cannot_be_published_for_unpublished_version() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let result = runtime.block_on(check_version_published(
+            "wibble",
+            &Version::new(1, 2, 3),
+            &hexpm::Config::new(),
+            &MockHex { status: 404 },
+        ));
+        assert_eq!(
+            result,
+            Err(Error::DocsVersionNotPublished {
+                package: "wibble".into(),
+                version: "1.2.3".into(),
+            })
+        );
+    }
+}
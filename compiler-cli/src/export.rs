@@ -6,6 +6,8 @@ use gleam_core::{
     paths::ProjectPaths,
 };
 
+use crate::fs::ProjectIO;
+
 static ENTRYPOINT_FILENAME_POWERSHELL: &str = "entrypoint.ps1";
 static ENTRYPOINT_FILENAME_POSIX_SHELL: &str = "entrypoint.sh";
 
@@ -48,6 +50,11 @@ pub(crate) fn erlang_shipment(paths: &ProjectPaths) -> Result<()> {
             mode,
             target: Some(target),
             no_print_progress: false,
+            compile_tests: false,
+            changed_modules: None,
+            features: vec![],
+            javascript_target_version: None,
+            coverage: false,
         },
         crate::build::download_dependencies(paths, crate::cli::Reporter::new())?,
     )?;
@@ -106,6 +113,63 @@ one of the following scripts:
     Ok(())
 }
 
+/// Bundle the generated JavaScript of a module and all the modules it imports,
+/// including those of dependencies and the prelude, into a single script that
+/// runs the module's main function. Suitable for loading in a browser.
+pub fn javascript_bundle(paths: &ProjectPaths, module: Option<String>) -> Result<()> {
+    let target = Target::JavaScript;
+    let mode = Mode::Prod;
+
+    // Build project in production mode
+    let built = crate::build::main(
+        paths,
+        Options {
+            root_target_support: TargetSupport::Enforced,
+            warnings_as_errors: false,
+            codegen: Codegen::All,
+            compile: Compile::All,
+            mode,
+            target: Some(target),
+            no_print_progress: false,
+            compile_tests: false,
+            changed_modules: None,
+            features: vec![],
+            javascript_target_version: None,
+            coverage: false,
+        },
+        crate::build::download_dependencies(paths, crate::cli::Reporter::new())?,
+    )?;
+
+    let name = built.root_package.config.name.clone();
+    let module = module.unwrap_or_else(|| name.to_string());
+    let main_function = built.get_main_function(&module.as_str().into(), target)?;
+
+    let entry = Utf8PathBuf::from(main_function.package.as_str()).join(format!("{module}.mjs"));
+    let bundle = gleam_core::javascript::bundle::bundle(
+        &ProjectIO::new(),
+        &paths.build_directory_for_target(mode, target),
+        &entry,
+    )?;
+
+    let out = paths
+        .javascript_bundle_directory()
+        .join(format!("{name}.js"));
+    crate::fs::write(&out, &bundle)?;
+
+    crate::cli::print_exported(&name);
+
+    println!(
+        "
+Your JavaScript bundle has been generated to {out}.
+
+It runs the main function of the {module} module and can be loaded in a
+browser with a script tag.
+",
+    );
+
+    Ok(())
+}
+
 fn write_entrypoint_script(
     entrypoint_output_path: &Utf8PathBuf,
     entrypoint_template_path: &str,
@@ -154,6 +218,11 @@ pub fn package_interface(paths: &ProjectPaths, out: Utf8PathBuf) -> Result<()> {
             warnings_as_errors: false,
             root_target_support: TargetSupport::Enforced,
             no_print_progress: false,
+            compile_tests: false,
+            changed_modules: None,
+            features: vec![],
+            javascript_target_version: None,
+            coverage: false,
         },
         crate::build::download_dependencies(paths, crate::cli::Reporter::new())?,
     )?;
@@ -174,3 +243,14 @@ pub fn package_information(paths: &ProjectPaths, out: Utf8PathBuf) -> Result<()>
     crate::fs::write_outputs_under(&[out], paths.root())?;
     Ok(())
 }
+
+pub fn requirements_lock(paths: &ProjectPaths, out: Utf8PathBuf, separator: String) -> Result<()> {
+    let (_, manifest) = crate::dependencies::get_manifest_details(paths)?;
+    crate::fs::write(&out, &manifest.to_requirements_lock(&separator))?;
+    println!(
+        "
+Your requirements lock has been generated to {out}.
+"
+    );
+    Ok(())
+}
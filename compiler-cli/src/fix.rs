@@ -28,6 +28,11 @@ pub fn run(paths: &ProjectPaths) -> Result<()> {
             mode: Mode::Dev,
             target: None,
             no_print_progress: false,
+            compile_tests: true,
+            changed_modules: None,
+            features: vec![],
+            javascript_target_version: None,
+            coverage: false,
         },
         build::download_dependencies(paths, cli::Reporter::new())?,
         warnings.clone(),
@@ -7,28 +7,45 @@ use std::{io::Read, str::FromStr};
 
 use camino::{Utf8Path, Utf8PathBuf};
 
-pub fn run(stdin: bool, check: bool, files: Vec<String>) -> Result<()> {
-    if stdin {
-        process_stdin(check)
+#[cfg(test)]
+mod tests;
+
+pub fn run(
+    stdin: bool,
+    stdin_filepath: Option<Utf8PathBuf>,
+    check: bool,
+    files: Vec<String>,
+) -> Result<()> {
+    if stdin || stdin_filepath.is_some() {
+        process_stdin(check, stdin_filepath.as_deref())
     } else {
         process_files(check, files)
     }
 }
 
-fn process_stdin(check: bool) -> Result<()> {
-    let src = read_stdin()?.into();
-    let mut out = String::new();
-    gleam_core::format::pretty(&mut out, &src, Utf8Path::new("<stdin>"))?;
-
+fn process_stdin(check: bool, path: Option<&Utf8Path>) -> Result<()> {
+    let src = read_stdin()?;
+    let out = format_stdin_source(src, path, check)?;
     if !check {
         print!("{out}");
-        return Ok(());
     }
+    Ok(())
+}
 
-    if src != out {
+/// Formats source code read from stdin. The path of the file the source
+/// belongs to, if known, is only used when reporting errors.
+fn format_stdin_source(src: String, path: Option<&Utf8Path>, check: bool) -> Result<String> {
+    let src = src.into();
+    let mut out = String::new();
+    gleam_core::format::pretty(&mut out, &src, path.unwrap_or(Utf8Path::new("<stdin>")))?;
+
+    if check && src != out {
         return Err(Error::Format {
             problem_files: vec![Unformatted {
-                source: Utf8PathBuf::from("<standard input>"),
+                source: path.map_or_else(
+                    || Utf8PathBuf::from("<standard input>"),
+                    Utf8Path::to_path_buf,
+                ),
                 destination: Utf8PathBuf::from("<standard output>"),
                 input: src,
                 output: out,
@@ -36,7 +53,7 @@ fn process_stdin(check: bool) -> Result<()> {
         });
     }
 
-    Ok(())
+    Ok(out)
 }
 
 fn process_files(check: bool, files: Vec<String>) -> Result<()> {
@@ -0,0 +1,46 @@
+use camino::{Utf8Path, Utf8PathBuf};
+use gleam_core::Error;
+
+use super::format_stdin_source;
+
+#[test]
+fn format_stdin_with_filepath() {
+    let path = Utf8Path::new("src/wibble.gleam");
+    let output = format_stdin_source("pub fn main(){ 1 }".into(), Some(path), false).unwrap();
+    assert_eq!(output, "pub fn main() {\n  1\n}\n");
+}
+
+#[test]
+fn format_stdin_with_filepath_syntax_error() {
+    let path = Utf8Path::new("src/wibble.gleam");
+    let result = format_stdin_source("pub fn main( {".into(), Some(path), false);
+    match result {
+        Err(Error::Parse { path, .. }) => assert_eq!(path, Utf8PathBuf::from("src/wibble.gleam")),
+        other => panic!("expected a parse error, got {other:?}"),
+    }
+}
+
+#[test]
+fn check_stdin_with_filepath() {
+    let path = Utf8Path::new("src/wibble.gleam");
+    let result = format_stdin_source("pub fn main(){ 1 }".into(), Some(path), true);
+    match result {
+        Err(Error::Format { problem_files }) => assert_eq!(
+            problem_files
+                .into_iter()
+                .map(|file| file.source)
+                .collect::<Vec<_>>(),
+            vec![Utf8PathBuf::from("src/wibble.gleam")]
+        ),
+        other => panic!("expected a format error, got {other:?}"),
+    }
+}
+
+#[test]
+fn format_stdin_without_filepath_syntax_error() {
+    let result = format_stdin_source("pub fn main( {".into(), None, false);
+    match result {
+        Err(Error::Parse { path, .. }) => assert_eq!(path, Utf8PathBuf::from("<stdin>")),
+        other => panic!("expected a parse error, got {other:?}"),
+    }
+}
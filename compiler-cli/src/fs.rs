@@ -156,6 +156,10 @@ impl FileSystemWriter for ProjectIO {
         copy_dir(from, to)
     }
 
+    fn rename_directory(&self, from: &Utf8Path, to: &Utf8Path) -> Result<()> {
+        rename_directory(from, to)
+    }
+
     fn mkdir(&self, path: &Utf8Path) -> Result<(), Error> {
         mkdir(path)
     }
@@ -187,38 +191,69 @@ impl FileSystemWriter for ProjectIO {
 
 impl CommandExecutor for ProjectIO {
     fn exec(&self, command: Command) -> Result<i32, Error> {
-        let Command {
-            program,
-            args,
-            env,
-            cwd,
-            stdio,
-        } = command;
-        tracing::trace!(program=program, args=?args.join(" "), env=?env, cwd=?cwd, "command_exec");
-        let result = std::process::Command::new(&program)
-            .args(args)
-            .stdin(stdio.get_process_stdio())
-            .stdout(stdio.get_process_stdio())
-            .envs(env.iter().map(|pair| (&pair.0, &pair.1)))
-            .current_dir(cwd.unwrap_or_else(|| Utf8Path::new("./").to_path_buf()))
-            .status();
-
-        match result {
-            Ok(status) => Ok(status.code().unwrap_or_default()),
-
-            Err(error) => Err(match error.kind() {
-                io::ErrorKind::NotFound => Error::ShellProgramNotFound {
-                    program,
-                    os: get_os(),
-                },
-
-                other => Error::ShellCommand {
-                    program,
-                    reason: ShellCommandFailureReason::IoError(other),
-                },
-            }),
+        let (program, mut process) = process_command(command);
+        match process.status() {
+            Ok(status) => Ok(exit_code(status)),
+            Err(error) => Err(command_error(program, error)),
+        }
+    }
+}
+
+/// The exit code to exit with for a command that finished with the given
+/// status. A command killed by a signal has no exit code, so the code a shell
+/// would give is used instead: 128 plus the signal number.
+pub fn exit_code(status: std::process::ExitStatus) -> i32 {
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
         }
     }
+    status.code().unwrap_or(1)
+}
+
+impl ProjectIO {
+    /// Start a command without waiting for it to finish.
+    pub fn spawn(&self, command: Command) -> Result<std::process::Child, Error> {
+        let (program, mut process) = process_command(command);
+        process
+            .spawn()
+            .map_err(|error| command_error(program, error))
+    }
+}
+
+fn process_command(command: Command) -> (String, std::process::Command) {
+    let Command {
+        program,
+        args,
+        env,
+        cwd,
+        stdio,
+    } = command;
+    tracing::trace!(program=program, args=?args.join(" "), env=?env, cwd=?cwd, "command_exec");
+    let mut process = std::process::Command::new(&program);
+    let _ = process
+        .args(args)
+        .stdin(stdio.get_process_stdio())
+        .stdout(stdio.get_process_stdio())
+        .envs(env.iter().map(|pair| (&pair.0, &pair.1)))
+        .current_dir(cwd.unwrap_or_else(|| Utf8Path::new("./").to_path_buf()));
+    (program, process)
+}
+
+fn command_error(program: String, error: io::Error) -> Error {
+    match error.kind() {
+        io::ErrorKind::NotFound => Error::ShellProgramNotFound {
+            program,
+            os: get_os(),
+        },
+
+        other => Error::ShellCommand {
+            program,
+            reason: ShellCommandFailureReason::IoError(other),
+        },
+    }
 }
 
 impl BeamCompiler for ProjectIO {
@@ -324,6 +359,19 @@ pub fn make_executable(_path: impl AsRef<Utf8Path>) -> Result<(), Error> {
     Ok(())
 }
 
+#[cfg(target_family = "unix")]
+pub fn is_executable(path: impl AsRef<Utf8Path>) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path.as_ref())
+        .map(|metadata| metadata.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(target_family = "unix"))]
+pub fn is_executable(_path: impl AsRef<Utf8Path>) -> bool {
+    false
+}
+
 pub fn write_bytes(path: &Utf8Path, bytes: &[u8]) -> Result<(), Error> {
     tracing::trace!(path=?path, "writing_file");
 
@@ -334,29 +382,37 @@ pub fn write_bytes(path: &Utf8Path, bytes: &[u8]) -> Result<(), Error> {
         err: None,
     })?;
 
-    std::fs::create_dir_all(dir_path).map_err(|e| Error::FileIo {
-        action: FileIoAction::Create,
-        kind: FileKind::Directory,
-        path: dir_path.to_path_buf(),
-        err: Some(e.to_string()),
-    })?;
+    std::fs::create_dir_all(dir_path)
+        .map_err(|e| write_error(FileIoAction::Create, FileKind::Directory, dir_path, &e))?;
 
-    let mut f = File::create(path).map_err(|e| Error::FileIo {
-        action: FileIoAction::Create,
-        kind: FileKind::File,
-        path: path.to_path_buf(),
-        err: Some(e.to_string()),
-    })?;
+    let mut f = File::create(path)
+        .map_err(|e| write_error(FileIoAction::Create, FileKind::File, path, &e))?;
 
-    f.write_all(bytes).map_err(|e| Error::FileIo {
-        action: FileIoAction::WriteTo,
-        kind: FileKind::File,
-        path: path.to_path_buf(),
-        err: Some(e.to_string()),
-    })?;
+    f.write_all(bytes)
+        .map_err(|e| write_error(FileIoAction::WriteTo, FileKind::File, path, &e))?;
     Ok(())
 }
 
+/// The error for a failed attempt to write to a path. Permission being
+/// denied is a common problem in CI, where the build directory may belong to
+/// another user, so in that case the message says what to check.
+fn write_error(action: FileIoAction, kind: FileKind, path: &Utf8Path, error: &io::Error) -> Error {
+    let err = match error.kind() {
+        io::ErrorKind::PermissionDenied => format!(
+            "{error}
+    This path isn't writable, please check that you have permission to
+    write to it and to the directories that contain it."
+        ),
+        _ => error.to_string(),
+    };
+    Error::FileIo {
+        action,
+        kind,
+        path: path.to_path_buf(),
+        err: Some(err),
+    }
+}
+
 fn is_gleam_path(path: &Utf8Path, dir: impl AsRef<Utf8Path>) -> bool {
     use regex::Regex;
 
@@ -539,12 +595,13 @@ pub fn module_caches_paths(
 pub fn read(path: impl AsRef<Utf8Path> + Debug) -> Result<String, Error> {
     tracing::trace!(path=?path,"reading_file");
 
-    std::fs::read_to_string(path.as_ref()).map_err(|err| Error::FileIo {
+    let bytes = std::fs::read(path.as_ref()).map_err(|err| Error::FileIo {
         action: FileIoAction::Read,
         kind: FileKind::File,
         path: Utf8PathBuf::from(path.as_ref()),
         err: Some(err.to_string()),
-    })
+    })?;
+    String::from_utf8(bytes).map_err(|error| Error::non_utf8_file(path.as_ref().into(), error))
 }
 
 pub fn read_bytes(path: impl AsRef<Utf8Path> + Debug) -> Result<Vec<u8>, Error> {
@@ -599,19 +656,22 @@ pub fn copy(
         .map(|_| ())
 }
 
-// pub fn rename(path: impl AsRef<Utf8Path> + Debug, to: impl AsRef<Utf8Path> + Debug) -> Result<(), Error> {
-//     tracing::trace!(from=?path, to=?to, "renaming_file");
+/// Moves a directory to a new path. This fails rather than replacing a
+/// non-empty directory already at that path.
+pub fn rename_directory(
+    path: impl AsRef<Utf8Path> + Debug,
+    to: impl AsRef<Utf8Path> + Debug,
+) -> Result<(), Error> {
+    tracing::trace!(from=?path, to=?to, "renaming_directory");
 
-//     // TODO: include the destination in the error message
-//     std::fs::rename(&path, &to)
-//         .map_err(|err| Error::FileIo {
-//             action: FileIoAction::Rename,
-//             kind: FileKind::File,
-//             path: Utf8PathBuf::from(path.as_ref()),
-//             err: Some(err.to_string()),
-//         })
-//         .map(|_| ())
-// }
+    // TODO: include the destination in the error message
+    std::fs::rename(path.as_ref(), to.as_ref()).map_err(|err| Error::FileIo {
+        action: FileIoAction::Rename,
+        kind: FileKind::Directory,
+        path: Utf8PathBuf::from(path.as_ref()),
+        err: Some(err.to_string()),
+    })
+}
 
 pub fn copy_dir(
     path: impl AsRef<Utf8Path> + Debug,
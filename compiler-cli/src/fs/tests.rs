@@ -187,3 +187,70 @@ HOME_URL=\"https://www.ubuntu.com/\"
         "id first"
     );
 }
+
+#[test]
+fn read_non_utf8_file() {
+    let tmp_dir = tempfile::tempdir().unwrap();
+    let path = Utf8Path::from_path(tmp_dir.path())
+        .expect("Non Utf-8 Path")
+        .join("wibble.gleam");
+    std::fs::write(&path, b"pub fn main() {\n  \"caf\xe9\"\n}\n").unwrap();
+
+    match super::read(&path) {
+        Err(gleam_core::Error::NonUtf8File {
+            path: error_path,
+            byte_offset,
+            ..
+        }) => {
+            assert_eq!(error_path, path);
+            assert_eq!(byte_offset, 22);
+        }
+        result => panic!("expected a non UTF-8 file error, got {result:?}"),
+    }
+}
+
+#[test]
+fn writing_to_a_read_only_directory_says_to_check_permissions() {
+    let path = Utf8Path::new("/read-only/manifest.toml");
+    let error = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+
+    assert_eq!(
+        super::write_error(
+            gleam_core::error::FileIoAction::Create,
+            gleam_core::error::FileKind::File,
+            path,
+            &error,
+        ),
+        gleam_core::Error::FileIo {
+            action: gleam_core::error::FileIoAction::Create,
+            kind: gleam_core::error::FileKind::File,
+            path: path.to_path_buf(),
+            err: Some(
+                "permission denied
+    This path isn't writable, please check that you have permission to
+    write to it and to the directories that contain it."
+                    .into()
+            ),
+        }
+    );
+}
+
+#[cfg(target_family = "unix")]
+#[test]
+fn exit_code_of_a_command() {
+    let status = std::process::Command::new("sh")
+        .args(["-c", "exit 3"])
+        .status()
+        .unwrap();
+    assert_eq!(super::exit_code(status), 3);
+}
+
+#[cfg(target_family = "unix")]
+#[test]
+fn exit_code_of_a_command_killed_by_a_signal() {
+    let status = std::process::Command::new("sh")
+        .args(["-c", "kill -TERM $$"])
+        .status()
+        .unwrap();
+    assert_eq!(super::exit_code(status), 143);
+}
@@ -1,5 +1,7 @@
 mod auth;
 
+use std::ffi::OsString;
+
 use crate::{cli, http::HttpClient};
 use gleam_core::{
     Error, Result,
@@ -10,6 +12,96 @@ use gleam_core::{
 
 pub use auth::HexAuthentication;
 
+const REPOSITORY_URL_ENV_NAME: &str = "HEXPM_REPOSITORY_URL";
+
+/// When set, plain HTTP repository URLs are permitted without passing
+/// `--allow-insecure-http`, such as for `gleam build`.
+const ALLOW_INSECURE_HTTP_ENV_NAME: &str = "HEXPM_ALLOW_INSECURE_HTTP";
+
+/// When set, packages may only be downloaded over HTTPS, as if
+/// `--require-https` had been passed.
+pub const REQUIRE_HTTPS_ENV_NAME: &str = "HEXPM_REQUIRE_HTTPS";
+
+/// The Hex configuration to use when fetching packages, with the repository
+/// URL replaced by `HEXPM_REPOSITORY_URL` if it is set, such as to use a
+/// mirror.
+pub fn config() -> Result<hexpm::Config> {
+    config_with_repository_url(std::env::var(REPOSITORY_URL_ENV_NAME).ok())
+}
+
+pub fn config_with_repository_url(url: Option<String>) -> Result<hexpm::Config> {
+    let mut config = hexpm::Config::new();
+    let Some(url) = url else {
+        return Ok(config);
+    };
+
+    // Request paths are appended directly to the repository URL, so it must
+    // end with a slash.
+    let normalised = if url.ends_with('/') {
+        url.clone()
+    } else {
+        format!("{url}/")
+    };
+    config.repository_base = match normalised.parse::<http::Uri>() {
+        Ok(uri) if uri.scheme().is_some() && uri.host().is_some() => uri,
+        _ => {
+            return Err(Error::InvalidUrlEnvironmentVariable {
+                name: REPOSITORY_URL_ENV_NAME.into(),
+                value: url,
+            });
+        }
+    };
+    Ok(config)
+}
+
+/// Returns an error if the repository URL uses plain HTTP and that has not
+/// been opted into with `--allow-insecure-http` or `HEXPM_ALLOW_INSECURE_HTTP`.
+/// Returns whether an insecure URL is being used, so a warning can be shown.
+pub fn check_repository_scheme(config: &hexpm::Config, allow_insecure_http: bool) -> Result<bool> {
+    if config.repository_base.scheme_str() != Some("http") {
+        return Ok(false);
+    }
+    if allow_insecure_http || env_flag_enabled(std::env::var_os(ALLOW_INSECURE_HTTP_ENV_NAME)) {
+        return Ok(true);
+    }
+    Err(Error::InsecureRepositoryUrl {
+        url: config.repository_base.to_string(),
+    })
+}
+
+/// Whether HTTPS is required for the package repository, with
+/// `--require-https` or `HEXPM_REQUIRE_HTTPS`.
+pub fn https_required(require_https: bool) -> bool {
+    require_https || env_flag_enabled(std::env::var_os(REQUIRE_HTTPS_ENV_NAME))
+}
+
+/// Whether the value of a boolean environment variable turns it on. Values
+/// such as `false`, `no`, `off` and `0` turn it off, as does an empty value.
+pub fn env_flag_enabled(value: Option<OsString>) -> bool {
+    let Some(value) = value else {
+        return false;
+    };
+    let value = value.to_string_lossy().trim().to_ascii_lowercase();
+    !matches!(
+        value.as_str(),
+        "" | "0" | "false" | "no" | "off" | "n" | "f"
+    )
+}
+
+/// Returns an error if HTTPS is required and the URL packages are to be
+/// downloaded from doesn't use it. This applies to URLs that don't come from
+/// the configuration, such as those in a download plan, so that they can't be
+/// used to downgrade a secure repository to plain HTTP.
+pub fn check_download_url(url: &str, require_https: bool) -> Result<()> {
+    let is_https = url
+        .parse::<http::Uri>()
+        .is_ok_and(|uri| uri.scheme_str() == Some("https"));
+    if !require_https || is_https {
+        return Ok(());
+    }
+    Err(Error::InsecureDownloadUrl { url: url.into() })
+}
+
 pub fn retire(
     package: String,
     version: String,
@@ -119,3 +211,61 @@ with a new one?";
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_flag_is_enabled_when_set() {
+        for value in ["1", "true", "TRUE", "yes", "on"] {
+            assert!(env_flag_enabled(Some(value.into())), "{value}");
+        }
+    }
+
+    #[test]
+    fn env_flag_is_disabled_when_unset_or_false() {
+        assert!(!env_flag_enabled(None));
+        for value in ["", "0", "false", "False", "no", "off"] {
+            assert!(!env_flag_enabled(Some(value.into())), "{value}");
+        }
+    }
+
+    #[test]
+    fn http_repository_url_requires_opt_in() {
+        let hex_config =
+            config_with_repository_url(Some("http://mirror.example.com".into())).unwrap();
+        assert_eq!(
+            check_repository_scheme(&hex_config, false),
+            Err(Error::InsecureRepositoryUrl {
+                url: "http://mirror.example.com/".into()
+            })
+        );
+        assert_eq!(check_repository_scheme(&hex_config, true), Ok(true));
+    }
+
+    #[test]
+    fn https_repository_url_needs_no_opt_in() {
+        let hex_config =
+            config_with_repository_url(Some("https://mirror.example.com".into())).unwrap();
+        assert_eq!(check_repository_scheme(&hex_config, false), Ok(false));
+    }
+
+    #[test]
+    fn http_download_url_rejected_when_https_required() {
+        let url = "http://mirror.example.com/tarballs/wibble-1.0.0.tar";
+        assert_eq!(
+            check_download_url(url, true),
+            Err(Error::InsecureDownloadUrl { url: url.into() })
+        );
+        assert_eq!(check_download_url(url, false), Ok(()));
+    }
+
+    #[test]
+    fn https_download_url_accepted_when_https_required() {
+        assert_eq!(
+            check_download_url("https://mirror.example.com/tarballs/wibble-1.0.0.tar", true),
+            Ok(())
+        );
+    }
+}
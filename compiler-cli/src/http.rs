@@ -1,5 +1,8 @@
 use std::convert::TryInto;
+use std::future::Future;
 use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use camino::Utf8PathBuf;
@@ -8,12 +11,53 @@ use gleam_core::{
     error::{FileIoAction, FileKind},
 };
 use http::{Request, Response};
-use reqwest::{Certificate, Client};
+use reqwest::{Certificate, Client, ClientBuilder, redirect::Policy};
 
 use crate::fs;
 
 static REQWEST_CLIENT: OnceLock<Client> = OnceLock::new();
 
+/// The environment variable that sets how many idle connections to each host
+/// are kept open to be reused by later requests. The single client is shared
+/// by all requests so that connections and their TLS handshakes are reused.
+pub const POOL_SIZE_ENVIRONMENT_VARIABLE: &str = "GLEAM_HTTP_POOL_SIZE";
+
+/// How many times a failed request is retried before giving up. Requests
+/// fail when the connection can't be made or the server returns a 5xx status.
+static NETWORK_RETRIES: AtomicU32 = AtomicU32::new(0);
+
+/// The delay before the first retry, doubled on each retry after that.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// The only hosts that requests may be made to, including when following
+/// redirects. Any host may be contacted if this has not been set.
+static ALLOWED_HOSTS: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Whether requests, including redirects, may only be made over HTTPS.
+static REQUIRE_HTTPS: AtomicBool = AtomicBool::new(false);
+
+pub fn set_network_retries(retries: u32) {
+    NETWORK_RETRIES.store(retries, Ordering::Relaxed);
+}
+
+/// Restricts requests to the given hosts. This can only be done once, before
+/// the first request is made, as the client is created with the redirect
+/// policy that enforces it.
+pub fn set_allowed_hosts(hosts: Vec<String>) {
+    let _ = ALLOWED_HOSTS.set(hosts);
+}
+
+/// Rejects requests and redirects that don't use HTTPS. Like the allowed
+/// hosts, this must be done before the first request is made.
+pub fn set_require_https(require_https: bool) {
+    REQUIRE_HTTPS.store(require_https, Ordering::Relaxed);
+}
+
+/// Whether requests have been restricted to particular hosts or to HTTPS.
+pub fn network_restricted() -> bool {
+    ALLOWED_HOSTS.get().is_some() || REQUIRE_HTTPS.load(Ordering::Relaxed)
+}
+
 #[derive(Debug)]
 pub struct HttpClient;
 
@@ -30,20 +74,140 @@ impl HttpClient {
 #[async_trait]
 impl gleam_core::io::HttpClient for HttpClient {
     async fn send(&self, request: Request<Vec<u8>>) -> Result<Response<Vec<u8>>> {
-        let request = request
+        let request: reqwest::Request = request
             .try_into()
             .expect("Unable to convert HTTP request for use by reqwest library");
+        if let Some(hosts) = ALLOWED_HOSTS.get() {
+            check_host_allowed(hosts, request.url())?;
+        }
+        crate::hex::check_download_url(
+            request.url().as_str(),
+            REQUIRE_HTTPS.load(Ordering::Relaxed),
+        )?;
         let client = init_client().map_err(Error::http)?;
-        let mut response = client.execute(request).await.map_err(Error::http)?;
-        let mut builder = Response::builder()
-            .status(response.status())
-            .version(response.version());
-        if let Some(headers) = builder.headers_mut() {
-            std::mem::swap(headers, response.headers_mut());
+        let retries = NETWORK_RETRIES.load(Ordering::Relaxed);
+        with_retries(retries, RETRY_DELAY, || {
+            let request = request
+                .try_clone()
+                .expect("Unable to clone HTTP request with a streaming body");
+            execute(client, request)
+        })
+        .await
+    }
+}
+
+async fn execute(client: &Client, request: reqwest::Request) -> Result<Response<Vec<u8>>> {
+    let method = request.method().clone();
+    let url = request.url().clone();
+    let start = Instant::now();
+    let mut response = match client.execute(request).await {
+        Ok(response) => response,
+        Err(error) => {
+            if let Some(HostNotAllowed(host)) = disallowed_redirect(&error) {
+                return Err(Error::HostNotAllowed { host: host.clone() });
+            }
+            if let Some(InsecureRedirect(url)) = disallowed_redirect(&error) {
+                return Err(Error::InsecureDownloadUrl { url: url.clone() });
+            }
+            tracing::debug!(
+                %method,
+                %url,
+                duration_ms = start.elapsed().as_millis(),
+                %error,
+                "http_request_failed"
+            );
+            return Err(Error::http(error));
         }
-        builder
-            .body(response.bytes().await.map_err(Error::http)?.to_vec())
-            .map_err(Error::http)
+    };
+    tracing::debug!(
+        %method,
+        %url,
+        status = response.status().as_u16(),
+        duration_ms = start.elapsed().as_millis(),
+        "http_request"
+    );
+    let mut builder = Response::builder()
+        .status(response.status())
+        .version(response.version());
+    if let Some(headers) = builder.headers_mut() {
+        std::mem::swap(headers, response.headers_mut());
+    }
+    builder
+        .body(response.bytes().await.map_err(Error::http)?.to_vec())
+        .map_err(Error::http)
+}
+
+/// The error a redirect is stopped with when it goes to a host that is not in
+/// the allowlist.
+#[derive(Debug)]
+struct HostNotAllowed(String);
+
+impl std::fmt::Display for HostNotAllowed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the host {} is not allowed", self.0)
+    }
+}
+
+impl std::error::Error for HostNotAllowed {}
+
+/// The error a redirect is stopped with when it goes to a URL that doesn't use
+/// HTTPS while HTTPS is required.
+#[derive(Debug)]
+struct InsecureRedirect(String);
+
+impl std::fmt::Display for InsecureRedirect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the redirect to {} does not use HTTPS", self.0)
+    }
+}
+
+impl std::error::Error for InsecureRedirect {}
+
+fn is_host_allowed(hosts: &[String], url: &reqwest::Url) -> bool {
+    url.host_str().is_some_and(|host| {
+        hosts
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(host))
+    })
+}
+
+fn check_host_allowed(hosts: &[String], url: &reqwest::Url) -> Result<()> {
+    if is_host_allowed(hosts, url) {
+        Ok(())
+    } else {
+        Err(Error::HostNotAllowed {
+            host: url.host_str().unwrap_or_default().into(),
+        })
+    }
+}
+
+fn disallowed_redirect<E: std::error::Error + 'static>(error: &reqwest::Error) -> Option<&E> {
+    std::iter::successors(std::error::Error::source(error), |error| error.source())
+        .find_map(|error| error.downcast_ref::<E>())
+}
+
+/// Makes a request, trying again up to `retries` more times if it fails or
+/// the server responds with a 5xx status. The last outcome is returned if
+/// every attempt fails.
+async fn with_retries<F, Fut>(
+    retries: u32,
+    delay: Duration,
+    mut attempt: F,
+) -> Result<Response<Vec<u8>>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Response<Vec<u8>>>>,
+{
+    let mut failures = 0;
+    loop {
+        match attempt().await {
+            Ok(response) if !response.status().is_server_error() => return Ok(response),
+            result if failures >= retries => return result,
+            _ => (),
+        }
+        failures += 1;
+        tracing::debug!(retry = failures, retries, "retrying_http_request");
+        tokio::time::sleep(delay.saturating_mul(2u32.saturating_pow(failures - 1))).await;
     }
 }
 
@@ -52,11 +216,14 @@ fn init_client() -> Result<&'static Client, Error> {
         return Ok(client);
     }
 
+    let pool_size = pool_size(std::env::var(POOL_SIZE_ENVIRONMENT_VARIABLE).ok())?;
+    let require_https = REQUIRE_HTTPS.load(Ordering::Relaxed);
+
     let certificate_path = match std::env::var("GLEAM_CACERTS_PATH") {
         Ok(path) => path,
         Err(_) => {
             return Ok(REQWEST_CLIENT.get_or_init(|| {
-                Client::builder()
+                client_builder(pool_size, ALLOWED_HOSTS.get().cloned(), require_https)
                     .build()
                     .expect("Failed to create reqwest client")
             }));
@@ -72,9 +239,267 @@ fn init_client() -> Result<&'static Client, Error> {
     })?;
 
     Ok(REQWEST_CLIENT.get_or_init(|| {
-        Client::builder()
+        client_builder(pool_size, ALLOWED_HOSTS.get().cloned(), require_https)
             .add_root_certificate(certificate)
             .build()
             .expect("Failed to create reqwest client")
     }))
 }
+
+fn client_builder(
+    pool_size: Option<usize>,
+    allowed_hosts: Option<Vec<String>>,
+    require_https: bool,
+) -> ClientBuilder {
+    let builder = if allowed_hosts.is_some() || require_https {
+        Client::builder().redirect(redirect_policy(allowed_hosts, require_https))
+    } else {
+        Client::builder()
+    };
+    match pool_size {
+        Some(size) => builder.pool_max_idle_per_host(size),
+        None => builder,
+    }
+}
+
+/// Follows redirects like the default policy, but only to the allowed hosts,
+/// and only over HTTPS if it is required.
+fn redirect_policy(hosts: Option<Vec<String>>, require_https: bool) -> Policy {
+    let default = Policy::default();
+    Policy::custom(move |attempt| {
+        if require_https && attempt.url().scheme() != "https" {
+            let url = attempt.url().to_string();
+            attempt.error(InsecureRedirect(url))
+        } else if hosts
+            .as_ref()
+            .is_some_and(|hosts| !is_host_allowed(hosts, attempt.url()))
+        {
+            let host = attempt.url().host_str().unwrap_or_default().to_string();
+            attempt.error(HostNotAllowed(host))
+        } else {
+            default.redirect(attempt)
+        }
+    })
+}
+
+fn pool_size(value: Option<String>) -> Result<Option<usize>> {
+    value
+        .map(|value| {
+            value
+                .trim()
+                .parse()
+                .map_err(|_| Error::InvalidNumberEnvironmentVariable {
+                    name: POOL_SIZE_ENVIRONMENT_VARIABLE.into(),
+                    value,
+                })
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+
+    async fn failing_then_ok(calls: &AtomicU32, failures: u32) -> Result<Response<Vec<u8>>> {
+        let call = calls.fetch_add(1, Ordering::SeqCst);
+        if call < failures {
+            Err(Error::Http("connection reset".into()))
+        } else {
+            Ok(Response::builder().status(200).body(vec![]).unwrap())
+        }
+    }
+
+    fn run<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    #[test]
+    fn retries_until_success() {
+        let calls = AtomicU32::new(0);
+        let result = run(with_retries(3, Duration::ZERO, || {
+            failing_then_ok(&calls, 3)
+        }));
+        assert!(result.is_ok());
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn gives_up_after_configured_retries() {
+        let calls = AtomicU32::new(0);
+        let result = run(with_retries(2, Duration::ZERO, || {
+            failing_then_ok(&calls, 3)
+        }));
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn retries_server_errors() {
+        let calls = AtomicU32::new(0);
+        let result = run(with_retries(1, Duration::ZERO, || async {
+            let status = if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                503
+            } else {
+                200
+            };
+            Ok(Response::builder().status(status).body(vec![]).unwrap())
+        }));
+        assert_eq!(result.unwrap().status(), 200);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn does_not_retry_by_default() {
+        let calls = AtomicU32::new(0);
+        let result = run(with_retries(0, Duration::ZERO, || {
+            failing_then_ok(&calls, 1)
+        }));
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn pool_size_from_environment_variable() {
+        assert_eq!(pool_size(None), Ok(None));
+        assert_eq!(pool_size(Some("4".into())), Ok(Some(4)));
+        assert_eq!(
+            pool_size(Some("many".into())),
+            Err(Error::InvalidNumberEnvironmentVariable {
+                name: POOL_SIZE_ENVIRONMENT_VARIABLE.into(),
+                value: "many".into(),
+            })
+        );
+    }
+
+    /// Serves `requests` keep-alive HTTP requests, returning how many
+    /// connections were opened to make them.
+    fn serve_requests(listener: std::net::TcpListener, requests: usize) -> usize {
+        use std::io::{BufRead, BufReader, Write};
+
+        let mut connections = 0;
+        let mut served = 0;
+        while served < requests {
+            let (stream, _) = listener.accept().unwrap();
+            connections += 1;
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            while served < requests {
+                let mut line = String::new();
+                // Read the request line and headers, up to the blank line
+                loop {
+                    line.clear();
+                    if reader.read_line(&mut line).unwrap() == 0 || line == "\r\n" {
+                        break;
+                    }
+                }
+                if line.is_empty() {
+                    break;
+                }
+                writer
+                    .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok")
+                    .unwrap();
+                served += 1;
+            }
+        }
+        connections
+    }
+
+    #[test]
+    fn connections_are_reused() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || serve_requests(listener, 3));
+
+        let client = client_builder(Some(1), None, false).build().unwrap();
+        run(async {
+            for _ in 0..3 {
+                let request = client.get(format!("http://{address}/")).build().unwrap();
+                let response = execute(&client, request).await.unwrap();
+                assert_eq!(response.body(), b"ok");
+            }
+        });
+
+        assert_eq!(server.join().unwrap(), 1);
+    }
+
+    #[test]
+    fn request_to_disallowed_host_is_rejected() {
+        let hosts = vec!["repo.hex.pm".to_string()];
+        let allowed = reqwest::Url::parse("https://REPO.hex.pm/tarballs/x.tar").unwrap();
+        let disallowed = reqwest::Url::parse("https://example.com/x.tar").unwrap();
+        assert_eq!(check_host_allowed(&hosts, &allowed), Ok(()));
+        assert_eq!(
+            check_host_allowed(&hosts, &disallowed),
+            Err(Error::HostNotAllowed {
+                host: "example.com".into()
+            })
+        );
+    }
+
+    /// Responds to a single request with a redirect to the given location.
+    fn serve_redirect(
+        listener: std::net::TcpListener,
+        location: String,
+    ) -> std::thread::JoinHandle<()> {
+        use std::io::{BufRead, BufReader, Write};
+
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                line.clear();
+            }
+            let mut writer = stream;
+            write!(
+                writer,
+                "HTTP/1.1 302 Found\r\nlocation: {location}\r\ncontent-length: 0\r\n\r\n",
+            )
+            .unwrap();
+        })
+    }
+
+    #[test]
+    fn redirect_to_disallowed_host_is_rejected() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = serve_redirect(listener, format!("http://localhost:{}/", address.port()));
+
+        let client = client_builder(None, Some(vec!["127.0.0.1".into()]), false)
+            .build()
+            .unwrap();
+        let request = client.get(format!("http://{address}/")).build().unwrap();
+        let result = run(execute(&client, request));
+
+        server.join().unwrap();
+        assert_eq!(
+            result.map(|_| ()),
+            Err(Error::HostNotAllowed {
+                host: "localhost".into()
+            })
+        );
+    }
+
+    #[test]
+    fn redirect_to_http_is_rejected_when_https_is_required() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let location = format!("http://{address}/tarballs/wibble-1.0.0.tar");
+        let server = serve_redirect(listener, location.clone());
+
+        let client = client_builder(None, None, true).build().unwrap();
+        let request = client.get(format!("http://{address}/")).build().unwrap();
+        let result = run(execute(&client, request));
+
+        server.join().unwrap();
+        assert_eq!(
+            result.map(|_| ()),
+            Err(Error::InsecureDownloadUrl { url: location })
+        );
+    }
+}
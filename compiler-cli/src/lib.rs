@@ -56,12 +56,14 @@ mod add;
 mod beam_compiler;
 mod build;
 mod build_lock;
+mod changes;
 mod cli;
 mod compile_package;
 mod config;
 mod dependencies;
+mod diagnostics;
 mod docs;
-mod export;
+pub mod export;
 mod fix;
 mod format;
 pub mod fs;
@@ -74,6 +76,7 @@ mod publish;
 mod remove;
 pub mod run;
 mod shell;
+mod version_check;
 
 use config::root_config;
 use dependencies::UseManifest;
@@ -82,14 +85,17 @@ pub use gleam_core::error::{Error, Result};
 
 use gleam_core::{
     analyse::TargetSupport,
-    build::{Codegen, Compile, Mode, NullTelemetry, Options, Runtime, Target},
-    hex::RetirementReason,
+    build::{Codegen, Compile, EcmaScriptVersion, Mode, NullTelemetry, Options, Runtime, Target},
+    hex::{DEFAULT_CONCURRENCY_PER_HOST, RetirementReason},
+    manifest::{ChecksumAlgorithm, Manifest},
     paths::ProjectPaths,
     version::COMPILER_VERSION,
+    warning::{Warning, WarningEmitterIO},
 };
-use std::str::FromStr;
+use std::{collections::HashSet, num::NonZeroUsize, rc::Rc, str::FromStr};
 
 use camino::Utf8PathBuf;
+use ecow::EcoString;
 
 use clap::{
     Args, Parser, Subcommand,
@@ -105,6 +111,144 @@ struct UpdateOptions {
     packages: Vec<String>,
 }
 
+#[derive(Args, Debug, Clone, Default)]
+struct DownloadOptions {
+    /// Warn about incompatible requirements for the same package before
+    /// resolving versions
+    #[arg(long)]
+    dedupe_check: bool,
+
+    /// Print the packages that will be fetched, where from, and their
+    /// expected checksums, asking for confirmation before downloading
+    #[arg(long)]
+    print_plan: bool,
+
+    /// Download without asking for confirmation after printing the plan
+    #[arg(long, requires = "print_plan")]
+    yes: bool,
+
+    /// How many times to retry a failed request to the package repository
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    network_retries: u32,
+
+    /// The hash algorithm to record package checksums with in the manifest.
+    /// Packages locked with a different algorithm are checked again
+    #[arg(
+        long,
+        value_name = "ALGORITHM",
+        default_value = "sha256",
+        value_parser = PossibleValuesParser::new(ChecksumAlgorithm::SUPPORTED)
+            .map(|s| ChecksumAlgorithm::from_str(&s).unwrap()),
+    )]
+    checksum_algo: ChecksumAlgorithm,
+
+    /// Refuse to use any Hex package without a checksum recorded in the
+    /// manifest, such as one locked by an older manifest. Versions are
+    /// resolved again to record their checksums, failing if any still lack one
+    #[arg(long)]
+    strict_checksums: bool,
+
+    /// Write a JSON summary of the Hex packages fetched and already cached,
+    /// with their sizes and checksums, to this file or to stdout if it is `-`
+    #[arg(long, value_name = "PATH")]
+    summary_json: Option<Utf8PathBuf>,
+
+    /// Don't extract the documentation and tests of downloaded packages, as
+    /// they are not needed to build them
+    #[arg(long)]
+    skip_unneeded_files: bool,
+
+    /// Trust manifest.toml and the downloaded packages, doing nothing if the
+    /// manifest is up to date with gleam.toml and every package has already
+    /// been downloaded
+    #[arg(long)]
+    prefer_cached: bool,
+
+    /// Only download packages that are not in the build directory at any
+    /// version, such as those just added to gleam.toml. Packages already
+    /// downloaded are left as they are, even if the manifest has a different
+    /// version of them
+    #[arg(long)]
+    since_manifest: bool,
+
+    /// Only make requests to the Hex package repository and these hosts,
+    /// failing if any other host is contacted, such as by a redirect
+    #[arg(long, value_name = "HOST", num_args = 0.., value_delimiter = ',')]
+    allow_network: Option<Vec<String>>,
+
+    /// How to show the progress of downloading packages. Defaults to `bar`
+    /// when printing to a terminal and `plain` otherwise
+    #[arg(long, value_name = "STYLE")]
+    progress: Option<cli::ProgressStyle>,
+
+    /// The most megabytes a downloaded package may take up once extracted.
+    /// Packages larger than this are rejected. Defaults to 256
+    #[arg(long, value_name = "MEGABYTES")]
+    max_package_size: Option<u64>,
+
+    /// The most packages to download at once from the same host, so as to
+    /// not overwhelm a shared repository or mirror. Defaults to 4
+    #[arg(long, value_name = "N")]
+    concurrency_per_host: Option<NonZeroUsize>,
+
+    /// Allow fetching packages from a repository URL that uses plain HTTP
+    /// rather than HTTPS. Anyone between you and the repository could alter
+    /// the packages downloaded
+    #[arg(long)]
+    allow_insecure_http: bool,
+
+    /// Only download packages over HTTPS, rejecting a repository URL, a
+    /// redirect, or a URL in a download plan that uses plain HTTP. This can
+    /// also be set with HEXPM_REQUIRE_HTTPS
+    #[arg(long, conflicts_with = "allow_insecure_http")]
+    require_https: bool,
+
+    /// Resolve versions and print the Hex packages to download, with where
+    /// they are fetched from and their checksums, as JSON without
+    /// downloading them
+    #[arg(long, conflicts_with_all = ["print_plan", "from_plan"])]
+    json_plan_only: bool,
+
+    /// Download the Hex packages of a plan printed by `--json-plan-only` to
+    /// the package cache, without resolving versions. They are fetched from
+    /// the configured repository, such as a mirror set with
+    /// HEXPM_REPOSITORY_URL, and must have the checksums in the plan
+    #[arg(long, value_name = "PATH")]
+    from_plan: Option<Utf8PathBuf>,
+
+    /// Fail rather than warn if any package is locked to a version that has
+    /// been retired on Hex. Retired versions are otherwise only used if they
+    /// are already locked or no other version satisfies the requirements
+    #[arg(long, alias = "no-yanked")]
+    no_retired: bool,
+
+    /// After downloading, type check the project and its tests and warn
+    /// about any dependencies none of whose modules are imported
+    #[arg(long, conflicts_with_all = ["json_plan_only", "from_plan"])]
+    report_unused: bool,
+
+    /// Write the resolved manifest to this file rather than manifest.toml,
+    /// which is left as it is along with the downloaded packages. Versions
+    /// already locked in manifest.toml are still used when resolving
+    #[arg(long, value_name = "PATH", conflicts_with = "from_plan")]
+    output_manifest: Option<Utf8PathBuf>,
+}
+
+impl DownloadOptions {
+    const DEFAULT_MAX_PACKAGE_SIZE: u64 = 256;
+
+    fn max_package_size_in_bytes(&self) -> u64 {
+        self.max_package_size
+            .unwrap_or(Self::DEFAULT_MAX_PACKAGE_SIZE)
+            .saturating_mul(1024 * 1024)
+    }
+
+    fn concurrency_per_host(&self) -> NonZeroUsize {
+        self.concurrency_per_host
+            .unwrap_or(DEFAULT_CONCURRENCY_PER_HOST)
+    }
+}
+
 #[derive(Args, Debug, Clone)]
 struct TreeOptions {
     /// Name of the package to get the dependency tree for
@@ -155,12 +299,72 @@ enum Command {
         /// Don't print progress information
         #[clap(long)]
         no_print_progress: bool,
+
+        /// Write the warnings and errors as JSON to this file, or to stdout
+        /// if it is `-`, instead of printing them
+        #[arg(long, value_name = "PATH")]
+        json_diagnostics: Option<Utf8PathBuf>,
+
+        /// Stream each warning and error to stdout as a JSON object on its
+        /// own line as soon as it is produced, followed by a line with the
+        /// totals, instead of printing them
+        #[arg(long, conflicts_with_all = ["json_diagnostics", "pretty_summary"])]
+        json_lines_diagnostics: bool,
+
+        /// Print a summary of how many errors and warnings there were
+        #[arg(long)]
+        pretty_summary: bool,
+
+        /// Also print the warnings and errors in this format once the build
+        /// has finished, such as `github` for annotations in GitHub Actions
+        #[arg(
+            long,
+            value_name = "FORMAT",
+            default_value = "human",
+            conflicts_with = "json_lines_diagnostics"
+        )]
+        error_format: diagnostics::ErrorFormat,
+
+        /// Print the configuration in effect as JSON instead of building,
+        /// including the package repository set with HEXPM_REPOSITORY_URL
+        #[arg(long)]
+        print_config: bool,
+
+        /// Only compile the modules affected by the changes made since this
+        /// git ref, such as a branch or a commit, and the modules they import
+        #[arg(long, value_name = "REF")]
+        since: Option<String>,
+
+        /// Build the project a second time from scratch and fail if the
+        /// manifest or the generated code differ from the first build
+        #[arg(long, conflicts_with = "since")]
+        check_determinism: bool,
+
+        /// Fail if any `todo` remains in the project's `src` directory,
+        /// listing where each one is
+        #[arg(long)]
+        fail_on_todo: bool,
+
+        /// Also fail if any `panic` remains in the project's `src` directory
+        #[arg(long, requires = "fail_on_todo")]
+        fail_on_panic: bool,
+
+        /// The features listed in gleam.toml to enable, separated by commas
+        #[arg(long, value_name = "FEATURES", value_delimiter = ',')]
+        features: Vec<EcoString>,
+
+        #[arg(long, ignore_case = true, help = target_version_doc())]
+        target_version: Option<EcmaScriptVersion>,
     },
 
     /// Type check the project
     Check {
         #[arg(short, long, ignore_case = true, help = target_doc())]
         target: Option<Target>,
+
+        /// Also type check the modules in the `test` directory
+        #[arg(long)]
+        tests: bool,
     },
 
     /// Publish the project to the Hex package manager
@@ -206,6 +410,11 @@ enum Command {
         #[arg(long)]
         stdin: bool,
 
+        /// Read source from STDIN, using this path for the file it belongs to
+        /// when reporting errors
+        #[arg(long, value_name = "PATH", conflicts_with = "stdin")]
+        stdin_filepath: Option<Utf8PathBuf>,
+
         /// Check if inputs are formatted without changing them
         #[arg(long)]
         check: bool,
@@ -233,6 +442,12 @@ enum Command {
         #[clap(long)]
         no_print_progress: bool,
 
+        /// Recompile the project when its source files change, loading the
+        /// changed modules into the running program without restarting it.
+        /// Erlang target only
+        #[clap(long)]
+        reload: bool,
+
         arguments: Vec<String>,
     },
 
@@ -245,6 +460,23 @@ enum Command {
         #[arg(long, ignore_case = true, help = runtime_doc())]
         runtime: Option<Runtime>,
 
+        /// Seed for any randomisation of the test order, given to the test
+        /// runner in the `GLEAM_TEST_SEED` environment variable.
+        /// If omitted, a random seed is used and printed
+        #[arg(long, verbatim_doc_comment)]
+        seed: Option<u64>,
+
+        /// Only compile the modules affected by the changes made since this
+        /// git ref, such as a branch or a commit, and the modules they import
+        #[arg(long, value_name = "REF")]
+        since: Option<String>,
+
+        /// Record which lines of the `src` modules are run by the tests,
+        /// writing an LCOV report to build/coverage/lcov.info.
+        /// Only supported on the JavaScript target when running on Node.js
+        #[arg(long, verbatim_doc_comment)]
+        coverage: bool,
+
         arguments: Vec<String>,
     },
 
@@ -277,6 +509,13 @@ enum Command {
     /// Clean build artifacts
     Clean,
 
+    /// Check whether a newer version of Gleam is available
+    ///
+    /// `gleam build` and `gleam check` also check for a newer version once a
+    /// day. Set the GLEAM_NO_UPDATE_CHECK environment variable to disable this.
+    #[command(verbatim_doc_comment)]
+    CheckUpdate,
+
     /// Run the language server, to be used by editors
     #[command(name = "lsp")]
     LanguageServer,
@@ -298,6 +537,14 @@ fn runtime_doc() -> String {
     format!("The runtime to target ({})", Runtime::VARIANTS.join("|"))
 }
 
+fn target_version_doc() -> String {
+    format!(
+        "The version of ECMAScript to generate JavaScript for, overriding the \
+`target_version` in gleam.toml ({})",
+        EcmaScriptVersion::VARIANTS.join("|")
+    )
+}
+
 #[derive(Subcommand, Debug, Clone)]
 pub enum ExportTarget {
     /// Precompiled Erlang, suitable for deployment
@@ -308,6 +555,14 @@ pub enum ExportTarget {
     JavascriptPrelude,
     /// The TypeScript prelude module
     TypescriptPrelude,
+    /// The project's JavaScript and that of its dependencies combined into a
+    /// single script, suitable for loading in a browser
+    JavascriptBundle {
+        /// The module whose main function the bundle runs. Defaults to the
+        /// module named after the project
+        #[arg(short, long)]
+        module: Option<String>,
+    },
     /// Information on the modules, functions, and types in the project in JSON format
     PackageInterface {
         #[arg(long = "out", required = true)]
@@ -320,6 +575,16 @@ pub enum ExportTarget {
         /// The path to write the JSON file to
         output: Utf8PathBuf,
     },
+    /// The resolved dependencies as a flat list of `name==version` lines,
+    /// for tools that don't understand manifest.toml
+    RequirementsLock {
+        #[arg(long = "out", required = true)]
+        /// The path to write the list to
+        output: Utf8PathBuf,
+        /// The text to put between the name and the version of each package
+        #[arg(long, default_value = "==")]
+        separator: String,
+    },
 }
 
 #[derive(Args, Debug, Clone)]
@@ -383,13 +648,19 @@ enum Dependencies {
     List,
 
     /// Download all dependency packages
-    Download,
+    Download(DownloadOptions),
 
     /// Update dependency packages to their latest versions
     Update(UpdateOptions),
 
     /// Tree of all the dependency packages
     Tree(TreeOptions),
+
+    /// Print the resolved version of a dependency package
+    Version {
+        /// The name of the package
+        package: String,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -463,7 +734,11 @@ enum Docs {
     /// - HEXPM_PASS: (optional) The Hex password to authenticate with.
     /// - HEXPM_API_KEY: (optional) A Hex API key to use instead of authenticating.
     #[command(verbatim_doc_comment)]
-    Publish,
+    Publish {
+        /// Write the docs tarball to the build directory instead of uploading it
+        #[arg(long)]
+        dry_run: bool,
+    },
 
     /// Remove HTML docs from HexDocs
     ///
@@ -505,18 +780,59 @@ pub fn main() {
 
 fn parse_and_run_command() -> Result<(), Error> {
     match Command::parse() {
+        Command::Build {
+            target,
+            print_config: true,
+            ..
+        } => {
+            let paths = find_project_paths()?;
+            print_resolved_config(&paths, target)
+        }
+
         Command::Build {
             target,
             warnings_as_errors,
             no_print_progress,
+            json_diagnostics,
+            json_lines_diagnostics,
+            pretty_summary,
+            error_format,
+            print_config: false,
+            since,
+            check_determinism,
+            fail_on_todo,
+            fail_on_panic,
+            features,
+            target_version,
         } => {
             let paths = find_project_paths()?;
-            command_build(&paths, target, warnings_as_errors, no_print_progress)
+            let reporting = diagnostics::Reporting {
+                json_path: json_diagnostics,
+                json_lines: json_lines_diagnostics,
+                summary: pretty_summary,
+                format: error_format,
+            };
+            let changed_modules = changed_modules_since(&paths, since)?;
+            let options = BuildOptions {
+                target,
+                warnings_as_errors,
+                no_print_progress,
+                changed_modules,
+                check_determinism,
+                fail_on_unfinished_code: fail_on_todo.then_some(fail_on_panic),
+                features,
+                javascript_target_version: target_version,
+            };
+            command_build(&paths, options, reporting)?;
+            version_check::check_daily();
+            Ok(())
         }
 
-        Command::Check { target } => {
+        Command::Check { target, tests } => {
             let paths = find_project_paths()?;
-            command_check(&paths, target)
+            command_check(&paths, target, tests)?;
+            version_check::check_daily();
+            Ok(())
         }
 
         Command::Docs(Docs::Build { open, target }) => {
@@ -524,18 +840,19 @@ fn parse_and_run_command() -> Result<(), Error> {
             docs::build(&paths, docs::BuildOptions { open, target })
         }
 
-        Command::Docs(Docs::Publish) => {
+        Command::Docs(Docs::Publish { dry_run }) => {
             let paths = find_project_paths()?;
-            docs::publish(&paths)
+            docs::publish(&paths, dry_run)
         }
 
         Command::Docs(Docs::Remove { package, version }) => docs::remove(package, version),
 
         Command::Format {
             stdin,
+            stdin_filepath,
             files,
             check,
-        } => format::run(stdin, check, files),
+        } => format::run(stdin, stdin_filepath, check, files),
 
         Command::Fix => {
             let paths = find_project_paths()?;
@@ -547,10 +864,13 @@ fn parse_and_run_command() -> Result<(), Error> {
             dependencies::list(&paths)
         }
 
-        Command::Deps(Dependencies::Download) => {
-            let paths = find_project_paths()?;
-            download_dependencies(&paths)
-        }
+        Command::Deps(Dependencies::Download(options)) => match &options.from_plan {
+            Some(plan) => dependencies::download_from_plan(plan, &options),
+            None => {
+                let paths = find_project_paths()?;
+                download_dependencies(&paths, options)
+            }
+        },
 
         Command::Deps(Dependencies::Update(options)) => {
             let paths = find_project_paths()?;
@@ -562,6 +882,11 @@ fn parse_and_run_command() -> Result<(), Error> {
             dependencies::tree(&paths, options)
         }
 
+        Command::Deps(Dependencies::Version { package }) => {
+            let paths = find_project_paths()?;
+            dependencies::version(&paths, &package)
+        }
+
         Command::Hex(Hex::Authenticate) => hex::authenticate(),
 
         Command::New(options) => new::create(options, COMPILER_VERSION),
@@ -577,6 +902,7 @@ fn parse_and_run_command() -> Result<(), Error> {
             runtime,
             module,
             no_print_progress,
+            reload,
         } => {
             let paths = find_project_paths()?;
             run::command(
@@ -587,6 +913,8 @@ fn parse_and_run_command() -> Result<(), Error> {
                 module,
                 run::Which::Src,
                 no_print_progress,
+                None,
+                reload,
             )
         }
 
@@ -594,15 +922,21 @@ fn parse_and_run_command() -> Result<(), Error> {
             target,
             arguments,
             runtime,
+            seed,
+            since,
+            coverage,
         } => {
             let paths = find_project_paths()?;
+            let changed_modules = changed_modules_since(&paths, since)?;
             run::command(
                 &paths,
                 arguments,
                 target,
                 runtime,
                 None,
-                run::Which::Test,
+                run::Which::Test { seed, coverage },
+                false,
+                changed_modules,
                 false,
             )
         }
@@ -653,6 +987,8 @@ fn parse_and_run_command() -> Result<(), Error> {
             clean(&paths)
         }
 
+        Command::CheckUpdate => version_check::command(),
+
         Command::LanguageServer => lsp::main(),
 
         Command::Export(ExportTarget::ErlangShipment) => {
@@ -663,6 +999,10 @@ fn parse_and_run_command() -> Result<(), Error> {
             let paths = find_project_paths()?;
             export::hex_tarball(&paths)
         }
+        Command::Export(ExportTarget::JavascriptBundle { module }) => {
+            let paths = find_project_paths()?;
+            export::javascript_bundle(&paths, module)
+        }
         Command::Export(ExportTarget::JavascriptPrelude) => export::javascript_prelude(),
         Command::Export(ExportTarget::TypescriptPrelude) => export::typescript_prelude(),
         Command::Export(ExportTarget::PackageInterface { output }) => {
@@ -673,10 +1013,14 @@ fn parse_and_run_command() -> Result<(), Error> {
             let paths = find_project_paths()?;
             export::package_information(&paths, output)
         }
+        Command::Export(ExportTarget::RequirementsLock { output, separator }) => {
+            let paths = find_project_paths()?;
+            export::requirements_lock(&paths, output, separator)
+        }
     }
 }
 
-fn command_check(paths: &ProjectPaths, target: Option<Target>) -> Result<()> {
+fn command_check(paths: &ProjectPaths, target: Option<Target>, tests: bool) -> Result<()> {
     let _ = build::main(
         paths,
         Options {
@@ -687,36 +1031,91 @@ fn command_check(paths: &ProjectPaths, target: Option<Target>) -> Result<()> {
             mode: Mode::Dev,
             target,
             no_print_progress: false,
+            compile_tests: tests,
+            changed_modules: None,
+            features: vec![],
+            javascript_target_version: None,
+            coverage: false,
         },
         build::download_dependencies(paths, cli::Reporter::new())?,
     )?;
     Ok(())
 }
 
-fn command_build(
+fn changed_modules_since(
     paths: &ProjectPaths,
+    since: Option<String>,
+) -> Result<Option<HashSet<EcoString>>> {
+    match since {
+        Some(reference) => changes::changed_modules_since(paths, &reference),
+        None => Ok(None),
+    }
+}
+
+/// The options for `gleam build`, other than how its diagnostics are reported.
+#[derive(Debug)]
+struct BuildOptions {
     target: Option<Target>,
     warnings_as_errors: bool,
     no_print_progress: bool,
+    /// Only the modules affected by these changes are compiled, if given.
+    changed_modules: Option<HashSet<EcoString>>,
+    /// Build the project again from scratch afterwards, failing if any file
+    /// generated differs.
+    check_determinism: bool,
+    /// Fail if the project has any `todo`, and any `panic` as well if this
+    /// is `Some(true)`.
+    fail_on_unfinished_code: Option<bool>,
+    features: Vec<EcoString>,
+    javascript_target_version: Option<EcmaScriptVersion>,
+}
+
+fn command_build(
+    paths: &ProjectPaths,
+    options: BuildOptions,
+    reporting: diagnostics::Reporting,
 ) -> Result<()> {
+    let BuildOptions {
+        target,
+        warnings_as_errors,
+        no_print_progress,
+        changed_modules,
+        check_determinism,
+        fail_on_unfinished_code,
+        features,
+        javascript_target_version,
+    } = options;
     let manifest = if no_print_progress {
         build::download_dependencies(paths, NullTelemetry)?
     } else {
         build::download_dependencies(paths, cli::Reporter::new())?
     };
-    let _ = build::main(
-        paths,
-        Options {
-            root_target_support: TargetSupport::Enforced,
-            warnings_as_errors,
-            codegen: Codegen::All,
-            compile: Compile::All,
-            mode: Mode::Dev,
-            target,
-            no_print_progress,
-        },
-        manifest,
-    )?;
+    let warnings = Rc::new(diagnostics::CollectingWarningEmitter::new(&reporting));
+    let options = Options {
+        root_target_support: TargetSupport::Enforced,
+        warnings_as_errors,
+        codegen: Codegen::All,
+        compile: Compile::All,
+        mode: Mode::Dev,
+        target,
+        no_print_progress,
+        compile_tests: false,
+        changed_modules,
+        features,
+        javascript_target_version,
+        coverage: false,
+    };
+    let result =
+        build::main_with_warnings(paths, options.clone(), manifest.clone(), warnings.clone());
+    reporting.report(&warnings, result.as_ref().err())?;
+    let _ = result?;
+    if check_determinism {
+        build::check_determinism(paths, options)?;
+        cli::print_checked_determinism();
+    }
+    if let Some(include_panic) = fail_on_unfinished_code {
+        build::check_unfinished_code(paths, include_panic)?;
+    }
     Ok(())
 }
 
@@ -726,6 +1125,14 @@ fn print_config(paths: &ProjectPaths) -> Result<()> {
     Ok(())
 }
 
+fn print_resolved_config(paths: &ProjectPaths, target: Option<Target>) -> Result<()> {
+    let config = root_config(paths)?;
+    let hex_config = hex::config()?;
+    let resolved = config::ResolvedConfig::new(&config, target, Mode::Dev, &hex_config)?;
+    println!("{}", resolved.to_json());
+    Ok(())
+}
+
 fn clean(paths: &ProjectPaths) -> Result<()> {
     fs::delete_directory(&paths.build_directory())
 }
@@ -752,13 +1159,51 @@ fn project_paths_at_current_directory_without_toml() -> ProjectPaths {
     ProjectPaths::new(current_dir)
 }
 
-fn download_dependencies(paths: &ProjectPaths) -> Result<()> {
-    _ = dependencies::download(
+fn download_dependencies(paths: &ProjectPaths, options: DownloadOptions) -> Result<()> {
+    if options.dedupe_check {
+        // The config is read without rejecting duplicated dependencies so the
+        // incompatible ranges are warned about before that error is shown.
+        dependencies::check_for_incompatible_duplicates(&config::read(paths.root_config())?);
+    }
+    if let Some(progress) = options.progress {
+        cli::set_progress_style(progress);
+    }
+    let manifest = dependencies::download_with_options(
         paths,
         cli::Reporter::new(),
         None,
         Vec::new(),
         UseManifest::Yes,
+        &options,
     )?;
+    if options.report_unused {
+        report_unused_dependencies(paths, manifest)?;
+    }
+    Ok(())
+}
+
+fn report_unused_dependencies(paths: &ProjectPaths, manifest: Manifest) -> Result<()> {
+    let built = build::main(
+        paths,
+        Options {
+            root_target_support: TargetSupport::Enforced,
+            warnings_as_errors: false,
+            codegen: Codegen::DepsOnly,
+            compile: Compile::All,
+            mode: Mode::Dev,
+            target: None,
+            no_print_progress: false,
+            compile_tests: true,
+            changed_modules: None,
+            features: vec![],
+            javascript_target_version: None,
+            coverage: false,
+        },
+        manifest,
+    )?;
+    let packages = built.unused_dependencies();
+    if !packages.is_empty() {
+        fs::ConsoleWarningEmitter.emit_warning(Warning::UnusedDependencies { packages });
+    }
     Ok(())
 }
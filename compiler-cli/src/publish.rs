@@ -289,6 +289,11 @@ fn do_build_hex_tarball(paths: &ProjectPaths, config: &mut PackageConfig) -> Res
             codegen: Codegen::All,
             compile: Compile::All,
             no_print_progress: false,
+            compile_tests: false,
+            changed_modules: None,
+            features: vec![],
+            javascript_target_version: None,
+            coverage: false,
         },
         build::download_dependencies(paths, cli::Reporter::new())?,
     )?;
@@ -362,8 +367,9 @@ fn do_build_hex_tarball(paths: &ProjectPaths, config: &mut PackageConfig) -> Res
         Target::Erlang => generated_erlang_files(paths, &built.root_package)?,
         Target::JavaScript => vec![],
     };
-    let src_files = project_files(Utf8Path::new(""))?;
-    let contents_tar_gz = contents_tarball(&src_files, &generated_files)?;
+    let root = Utf8Path::new("");
+    let src_files = project_files(root)?;
+    let contents_tar_gz = contents_tarball(root, &src_files, &generated_files)?;
     let version = "3";
     let metadata = metadata_config(&built.root_package.config, &src_files, &generated_files)?;
 
@@ -395,7 +401,7 @@ fn do_build_hex_tarball(paths: &ProjectPaths, config: &mut PackageConfig) -> Res
     })
 }
 
-fn check_config_for_publishing(config: &PackageConfig) -> Result<()> {
+pub(crate) fn check_config_for_publishing(config: &PackageConfig) -> Result<()> {
     // These fields are required to publish a Hex package. Hex will reject
     // packages without them.
     if config.description.is_empty() || config.licences.is_empty() {
@@ -448,19 +454,39 @@ fn metadata_config<'a>(
     Ok(metadata)
 }
 
+/// Builds the `contents.tar.gz` of a package.
+///
+/// The tarball is reproducible: entries are sorted by path and each has a zero
+/// timestamp, zero uid and gid, and a fixed mode, so the same source files
+/// always produce the same bytes, and so the same checksum, on any machine.
+///
+/// The `files` are read relative to `root`.
 fn contents_tarball(
+    root: &Utf8Path,
     files: &[Utf8PathBuf],
     data_files: &[(Utf8PathBuf, String)],
 ) -> Result<Vec<u8>, Error> {
+    let mut entries = Vec::with_capacity(files.len() + data_files.len());
+    for path in files {
+        let full_path = root.join(path);
+        let mode = if fs::is_executable(&full_path) {
+            0o755
+        } else {
+            0o644
+        };
+        entries.push((path.as_path(), fs::read_bytes(&full_path)?, mode));
+    }
+    for (path, contents) in data_files {
+        entries.push((path.as_path(), contents.as_bytes().to_vec(), 0o644));
+    }
+    entries.sort_by_key(|(path, _, _)| *path);
+
     let mut contents_tar_gz = Vec::new();
     {
         let mut tarball =
             tar::Builder::new(GzEncoder::new(&mut contents_tar_gz, Compression::default()));
-        for path in files {
-            add_path_to_tar(&mut tarball, path)?;
-        }
-        for (path, contents) in data_files {
-            add_to_tar(&mut tarball, path, contents.as_bytes())?;
+        for (path, data, mode) in entries {
+            add_to_tar_with_mode(&mut tarball, path, &data, mode)?;
         }
         tarball.finish().map_err(Error::finish_tar)?;
     }
@@ -518,7 +544,7 @@ fn generated_erlang_files(
         if module.is_test() {
             continue;
         }
-        let name = module.compiled_erlang_path();
+        let name = module.compiled_erlang_path(package.config.erlang.module_prefix.as_deref());
         files.push((tar_src.join(&name), fs::read(build.join(name))?));
     }
 
@@ -543,26 +569,30 @@ where
     P: AsRef<Utf8Path>,
     W: Write,
 {
-    let path = path.as_ref();
-    tracing::info!(file=?path, "Adding file to tarball");
-    let mut header = tar::Header::new_gnu();
-    header.set_mode(0o600);
-    header.set_size(data.len() as u64);
-    header.set_cksum();
-    tarball
-        .append_data(&mut header, path, data)
-        .map_err(|e| Error::add_tar(path, e))
+    add_to_tar_with_mode(tarball, path, data, 0o600)
 }
 
-fn add_path_to_tar<P, W>(tarball: &mut tar::Builder<W>, path: P) -> Result<()>
+fn add_to_tar_with_mode<P, W>(
+    tarball: &mut tar::Builder<W>,
+    path: P,
+    data: &[u8],
+    mode: u32,
+) -> Result<()>
 where
     P: AsRef<Utf8Path>,
     W: Write,
 {
     let path = path.as_ref();
     tracing::info!(file=?path, "Adding file to tarball");
+    let mut header = tar::Header::new_gnu();
+    header.set_mode(mode);
+    header.set_mtime(0);
+    header.set_uid(0);
+    header.set_gid(0);
+    header.set_size(data.len() as u64);
+    header.set_cksum();
     tarball
-        .append_path(path)
+        .append_data(&mut header, path, data)
         .map_err(|e| Error::add_tar(path, e))
 }
 
@@ -879,3 +909,38 @@ src/also-ignored.gleam";
 
     assert_eq!(expected_exported_files, chosen_exported_files);
 }
+
+#[test]
+fn contents_tarball_is_reproducible() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = Utf8PathBuf::from_path_buf(tmp.path().to_path_buf()).expect("Non Utf8 Path");
+    let files = ["src/wibble.gleam", "src/wobble_ffi.erl", "priv/data.txt"]
+        .iter()
+        .map(|file| {
+            fs::write(&root.join(file), "wibble").unwrap();
+            Utf8PathBuf::from(file)
+        })
+        .collect_vec();
+    let generated = [(
+        Utf8PathBuf::from("src/wibble.app.src"),
+        "{application, wibble, []}.".to_string(),
+    )];
+
+    let first = contents_tarball(&root, &files, &generated).unwrap();
+
+    // The files being touched again and listed in a different order doesn't
+    // change the contents of the tarball.
+    let later = std::time::SystemTime::now() + std::time::Duration::from_secs(60 * 60);
+    for file in &files {
+        std::fs::File::options()
+            .write(true)
+            .open(root.join(file))
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+    }
+    let reversed = files.iter().rev().cloned().collect_vec();
+    let second = contents_tarball(&root, &reversed, &generated).unwrap();
+
+    assert_eq!(first, second);
+}
@@ -1,12 +1,20 @@
-use std::sync::OnceLock;
+use std::{
+    collections::HashSet,
+    sync::OnceLock,
+    time::{Duration, SystemTime},
+};
 
 use camino::Utf8PathBuf;
 use ecow::EcoString;
 use gleam_core::{
     analyse::TargetSupport,
-    build::{Built, Codegen, Compile, Mode, NullTelemetry, Options, Runtime, Target, Telemetry},
+    build::{
+        Built, Codegen, Compile, Mode, NullTelemetry, Options, Runtime, Target, Telemetry,
+        prefixed_module_erlang_name,
+    },
     config::{DenoFlag, PackageConfig},
-    error::Error,
+    erlang::escape_atom_string,
+    error::{Error, ShellCommandFailureReason},
     io::{Command, CommandExecutor, Stdio},
     paths::ProjectPaths,
     type_::ModuleFunction,
@@ -17,10 +25,28 @@ use crate::{config::PackageKind, fs::ProjectIO};
 #[derive(Debug, Clone, Copy)]
 pub enum Which {
     Src,
-    Test,
+    Test {
+        /// The seed the test runner should use for any randomisation of the
+        /// order tests are run in. A random one is used if none is given.
+        seed: Option<u64>,
+        /// Whether to record which lines of the `src` modules are run,
+        /// writing a coverage report once the tests have finished.
+        coverage: bool,
+    },
+}
+
+/// The environment variable used to tell the test runner which seed to use.
+pub const TEST_SEED_ENVIRONMENT_VARIABLE: &str = "GLEAM_TEST_SEED";
+
+fn random_test_seed() -> u64 {
+    use std::hash::{BuildHasher, Hasher};
+    std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish()
 }
 
 // TODO: test
+#[allow(clippy::too_many_arguments)]
 pub fn command(
     paths: &ProjectPaths,
     arguments: Vec<String>,
@@ -29,6 +55,8 @@ pub fn command(
     module: Option<String>,
     which: Which,
     no_print_progress: bool,
+    changed_modules: Option<HashSet<EcoString>>,
+    reload: bool,
 ) -> Result<(), Error> {
     // Don't exit on ctrl+c as it is used by child erlang shell
     ctrlc::set_handler(move || {}).expect("Error setting Ctrl-C handler");
@@ -40,11 +68,87 @@ pub fn command(
         module,
         which,
         no_print_progress,
+        changed_modules,
+        reload,
     )?;
-    let status = ProjectIO::new().exec(command)?;
+    let status = if reload {
+        run_reloading(paths, command, no_print_progress)?
+    } else {
+        ProjectIO::new().exec(command)?
+    };
     std::process::exit(status);
 }
 
+/// Runs the program, recompiling the project whenever one of its source files
+/// changes. The Erlang entrypoint loads the recompiled modules into the running
+/// program.
+fn run_reloading(
+    paths: &ProjectPaths,
+    command: Command,
+    no_print_progress: bool,
+) -> Result<i32, Error> {
+    let mut program = ProjectIO::new().spawn(command)?;
+    let mut last_change = latest_source_change(paths);
+
+    loop {
+        if let Some(status) = program.try_wait().map_err(|error| Error::ShellCommand {
+            program: "erl".into(),
+            reason: ShellCommandFailureReason::IoError(error.kind()),
+        })? {
+            return Ok(crate::fs::exit_code(status));
+        }
+
+        std::thread::sleep(Duration::from_millis(200));
+        let change = latest_source_change(paths);
+        if change <= last_change {
+            continue;
+        }
+        last_change = change;
+
+        // Errors are printed rather than returned so that the program keeps
+        // running with its current code until the problem is fixed.
+        if let Err(error) = rebuild(paths, no_print_progress) {
+            let stderr = crate::cli::stderr_buffer_writer();
+            let mut buffer = stderr.buffer();
+            error.pretty(&mut buffer);
+            stderr.print(&buffer).expect("Reload error writing");
+        }
+    }
+}
+
+fn rebuild(paths: &ProjectPaths, no_print_progress: bool) -> Result<(), Error> {
+    let manifest = crate::build::download_dependencies(paths, NullTelemetry)?;
+    let options = Options {
+        warnings_as_errors: false,
+        compile: Compile::All,
+        codegen: Codegen::All,
+        mode: Mode::Dev,
+        target: Some(Target::Erlang),
+        root_target_support: TargetSupport::Enforced,
+        no_print_progress,
+        compile_tests: false,
+        changed_modules: None,
+        features: vec![],
+        javascript_target_version: None,
+        coverage: false,
+    };
+    let _ = crate::build::main(paths, options, manifest)?;
+    Ok(())
+}
+
+/// The most recent time a file in the project's source directory was changed.
+fn latest_source_change(paths: &ProjectPaths) -> Option<SystemTime> {
+    crate::fs::gleam_files(&paths.src_directory())
+        .chain(crate::fs::native_files(&paths.src_directory()))
+        .filter_map(|path| {
+            path.metadata()
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .max()
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn setup(
     paths: &ProjectPaths,
     arguments: Vec<String>,
@@ -53,6 +157,8 @@ pub fn setup(
     module: Option<String>,
     which: Which,
     no_print_progress: bool,
+    mut changed_modules: Option<HashSet<EcoString>>,
+    reload: bool,
 ) -> Result<Command, Error> {
     // Validate the module path
     if let Some(mod_path) = &module {
@@ -89,13 +195,48 @@ pub fn setup(
     // The root config is required to run the project.
     let root_config = crate::config::root_config(paths)?;
 
+    // Test modules are only compiled when running the tests, or when a module
+    // has been given as it may be one of the test modules.
+    let compile_tests = matches!(which, Which::Test { .. }) || module.is_some();
+
     // Determine which module to run
     let module = module.unwrap_or(match which {
         Which::Src => root_config.name.to_string(),
-        Which::Test => format!("{}_test", &root_config.name),
+        Which::Test { .. } => format!("{}_test", &root_config.name),
     });
 
-    let target = target.unwrap_or(mod_config.target);
+    // The module being run is always compiled, even if it has not been
+    // affected by any changes.
+    if let Some(changed_modules) = &mut changed_modules {
+        let _ = changed_modules.insert(module.clone().into());
+    }
+
+    let target = target_to_run(which, target, &mod_config);
+
+    // Modules can only be reloaded into a running BEAM program.
+    if reload && target != Target::Erlang {
+        return Err(Error::ReloadUnsupportedTarget { target });
+    }
+
+    // Coverage is only recorded by the JavaScript target, running on Node.js.
+    let coverage = matches!(which, Which::Test { coverage: true, .. });
+    if coverage {
+        match (target, runtime.unwrap_or(mod_config.javascript.runtime)) {
+            (Target::JavaScript, Runtime::NodeJs) => (),
+            (Target::Erlang, _) => {
+                return Err(Error::CoverageUnsupported {
+                    target,
+                    runtime: None,
+                });
+            }
+            (Target::JavaScript, runtime) => {
+                return Err(Error::CoverageUnsupported {
+                    target,
+                    runtime: Some(runtime),
+                });
+            }
+        }
+    }
 
     let options = Options {
         warnings_as_errors: false,
@@ -118,23 +259,43 @@ pub fn setup(
             PackageKind::Dependency => TargetSupport::NotEnforced,
         },
         no_print_progress,
+        compile_tests,
+        changed_modules,
+        features: vec![],
+        javascript_target_version: None,
+        coverage,
     };
 
     let built = crate::build::main(paths, options, manifest)?;
 
+    let seed = match which {
+        Which::Test { seed, .. } => Some(seed.unwrap_or_else(random_test_seed)),
+        Which::Src => None,
+    };
+
     // A module can not be run if it does not exist or does not have a public main function.
     let main_function = get_or_suggest_main_function(built, &module, target)?;
 
+    if let (Some(seed), false) = (seed, no_print_progress) {
+        crate::cli::print_test_seed(seed);
+    }
     telemetry.running(&format!("{module}.main"));
 
     // Get the command to run the project.
-    match target {
+    let mut command = match target {
         Target::Erlang => match runtime {
             Some(r) => Err(Error::InvalidRuntime {
                 target: Target::Erlang,
                 invalid_runtime: r,
             }),
-            _ => run_erlang_command(paths, &root_config.name, &module, arguments),
+            _ => run_erlang_command(
+                paths,
+                &root_config.name,
+                &module,
+                root_config.erlang.module_prefix.as_deref(),
+                arguments,
+                reload,
+            ),
         },
         Target::JavaScript => match runtime.unwrap_or(mod_config.javascript.runtime) {
             Runtime::Deno => run_javascript_deno_command(
@@ -144,13 +305,36 @@ pub fn setup(
                 &module,
                 arguments,
             ),
-            Runtime::NodeJs => {
-                run_javascript_node_command(paths, &main_function.package, &module, arguments)
-            }
+            Runtime::NodeJs => run_javascript_node_command(
+                paths,
+                &main_function.package,
+                &module,
+                arguments,
+                coverage.then(|| paths.coverage_report()),
+            ),
             Runtime::Bun => {
                 run_javascript_bun_command(paths, &main_function.package, &module, arguments)
             }
         },
+    }?;
+
+    if let Some(seed) = seed {
+        command
+            .env
+            .push((TEST_SEED_ENVIRONMENT_VARIABLE.into(), seed.to_string()));
+    }
+
+    Ok(command)
+}
+
+/// The target given on the command line is used if there is one. Otherwise
+/// `gleam run` uses the package's `run-target` if it has one, falling back
+/// to the package's default target.
+fn target_to_run(which: Which, target: Option<Target>, config: &PackageConfig) -> Target {
+    match (target, which) {
+        (Some(target), _) => target,
+        (None, Which::Src) => config.run_target.unwrap_or(config.target),
+        (None, Which::Test { .. }) => config.target,
     }
 }
 
@@ -158,7 +342,9 @@ fn run_erlang_command(
     paths: &ProjectPaths,
     package: &str,
     module: &str,
+    module_prefix: Option<&str>,
     arguments: Vec<String>,
+    reload: bool,
 ) -> Result<Command, Error> {
     let mut args = vec![];
 
@@ -171,10 +357,11 @@ fn run_erlang_command(
     }
 
     // gleam modules are separated by `/`. Erlang modules are separated by `@`.
-    let module = module.replace('/', "@");
+    let module = escape_atom_string(prefixed_module_erlang_name(module_prefix, module).into());
 
+    let entrypoint = if reload { "run_reloading" } else { "run" };
     args.push("-eval".into());
-    args.push(format!("{package}@@main:run({module})"));
+    args.push(format!("{package}@@main:{entrypoint}({module})"));
 
     // Don't run the Erlang shell
     args.push("-noshell".into());
@@ -201,7 +388,7 @@ fn run_javascript_bun_command(
     arguments: Vec<String>,
 ) -> Result<Command, Error> {
     let mut args = vec!["run".to_string()];
-    let entry = write_javascript_entrypoint(paths, package, module)?;
+    let entry = write_javascript_entrypoint(paths, package, module, None)?;
 
     args.push(entry.to_string());
 
@@ -223,9 +410,10 @@ fn run_javascript_node_command(
     package: &str,
     module: &str,
     arguments: Vec<String>,
+    coverage_report: Option<Utf8PathBuf>,
 ) -> Result<Command, Error> {
     let mut args = vec![];
-    let entry = write_javascript_entrypoint(paths, package, module)?;
+    let entry = write_javascript_entrypoint(paths, package, module, coverage_report)?;
 
     args.push(entry.to_string());
 
@@ -242,24 +430,61 @@ fn run_javascript_node_command(
     })
 }
 
+/// Writes the module that runs the main function. When a coverage report is
+/// to be written it also writes the lines run by the instrumented modules to
+/// the report as the program exits.
 fn write_javascript_entrypoint(
     paths: &ProjectPaths,
     package: &str,
     module: &str,
+    coverage_report: Option<Utf8PathBuf>,
 ) -> Result<Utf8PathBuf, Error> {
     let path = paths
         .build_directory_for_package(Mode::Dev, Target::JavaScript, package)
         .to_path_buf()
         .join("gleam.main.mjs");
+    let coverage = match coverage_report {
+        Some(report) => COVERAGE_REPORTER.replace(
+            "$REPORT_PATH",
+            &serde_json::to_string(report.as_str()).expect("coverage report path JSON"),
+        ),
+        None => String::new(),
+    };
     let module = format!(
         r#"import {{ main }} from "./{module}.mjs";
-main();
+{coverage}main();
 "#,
     );
     crate::fs::write(&path, &module)?;
     Ok(path)
 }
 
+/// Writes the lines recorded by the modules instrumented for coverage to an
+/// LCOV report when the process exits.
+const COVERAGE_REPORTER: &str = r#"import * as $fs from "node:fs";
+import * as $path from "node:path";
+process.on("exit", () => {
+  const files = globalThis.__gleam_coverage || {};
+  let report = "";
+  for (const file of Object.keys(files).sort()) {
+    const lines = files[file];
+    const numbers = Object.keys(lines);
+    report += `TN:
+SF:${file}
+`;
+    for (const line of numbers) report += `DA:${line},${lines[line]}
+`;
+    const hit = numbers.filter((line) => lines[line] > 0).length;
+    report += `LF:${numbers.length}
+LH:${hit}
+end_of_record
+`;
+  }
+  $fs.mkdirSync($path.dirname($REPORT_PATH), { recursive: true });
+  $fs.writeFileSync($REPORT_PATH, report);
+});
+"#;
+
 fn run_javascript_deno_command(
     paths: &ProjectPaths,
     config: &PackageConfig,
@@ -326,7 +551,7 @@ fn run_javascript_deno_command(
         );
     }
 
-    let entrypoint = write_javascript_entrypoint(paths, package, module)?;
+    let entrypoint = write_javascript_entrypoint(paths, package, module, None)?;
     args.push(entrypoint.to_string());
 
     for argument in arguments.into_iter() {
@@ -419,3 +644,130 @@ fn valid_module_names() {
         assert!(is_gleam_module(mod_name));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(target: Target, run_target: Option<Target>) -> PackageConfig {
+        PackageConfig {
+            target,
+            run_target,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn run_uses_run_target() {
+        let config = config(Target::Erlang, Some(Target::JavaScript));
+        assert_eq!(target_to_run(Which::Src, None, &config), Target::JavaScript);
+    }
+
+    #[test]
+    fn run_uses_default_target_without_run_target() {
+        let config = config(Target::JavaScript, None);
+        assert_eq!(target_to_run(Which::Src, None, &config), Target::JavaScript);
+    }
+
+    #[test]
+    fn run_target_flag_overrides_run_target() {
+        let config = config(Target::Erlang, Some(Target::JavaScript));
+        assert_eq!(
+            target_to_run(Which::Src, Some(Target::Erlang), &config),
+            Target::Erlang
+        );
+    }
+
+    #[test]
+    fn test_ignores_run_target() {
+        let config = config(Target::Erlang, Some(Target::JavaScript));
+        assert_eq!(
+            target_to_run(
+                Which::Test {
+                    seed: Some(1),
+                    coverage: false
+                },
+                None,
+                &config
+            ),
+            Target::Erlang
+        );
+    }
+
+    fn erlang_eval_argument(module_prefix: Option<&str>, reload: bool) -> String {
+        let temp_dir = tempfile::tempdir().expect("Failed to create a temp directory");
+        let root = Utf8PathBuf::from_path_buf(temp_dir.path().to_path_buf()).expect("Path");
+        let paths = ProjectPaths::new(root);
+        let packages = paths.build_directory_for_target(Mode::Dev, Target::Erlang);
+        crate::fs::mkdir(packages.join("wibble")).expect("Create package directory");
+
+        let command = run_erlang_command(
+            &paths,
+            "wibble",
+            "wibble/wobble",
+            module_prefix,
+            vec![],
+            reload,
+        )
+        .expect("Erlang command");
+        let position = command
+            .args
+            .iter()
+            .position(|argument| argument == "-eval")
+            .expect("Eval argument");
+        command
+            .args
+            .get(position + 1)
+            .cloned()
+            .expect("Eval expression")
+    }
+
+    #[test]
+    fn erlang_command_runs_main() {
+        assert_eq!(
+            erlang_eval_argument(None, false),
+            "wibble@@main:run(wibble@wobble)"
+        );
+    }
+
+    #[test]
+    fn erlang_command_runs_main_of_prefixed_module() {
+        assert_eq!(
+            erlang_eval_argument(Some("app_"), false),
+            "wibble@@main:run(app_wibble@wobble)"
+        );
+    }
+
+    #[test]
+    fn erlang_command_with_reload_runs_main_reloading_modules() {
+        assert_eq!(
+            erlang_eval_argument(None, true),
+            "wibble@@main:run_reloading(wibble@wobble)"
+        );
+    }
+
+    #[test]
+    fn latest_source_change_includes_new_files() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create a temp directory");
+        let root = Utf8PathBuf::from_path_buf(temp_dir.path().to_path_buf()).expect("Path");
+        let paths = ProjectPaths::new(root);
+        assert_eq!(latest_source_change(&paths), None);
+
+        let module = paths.src_directory().join("wibble.gleam");
+        crate::fs::write(&module, "pub fn main() { 1 }").expect("Write module");
+        let change = latest_source_change(&paths);
+        assert!(change.is_some());
+
+        std::thread::sleep(Duration::from_millis(10));
+        crate::fs::write(&paths.src_directory().join("wibble_ffi.erl"), "").expect("Write FFI");
+        assert!(latest_source_change(&paths) > change);
+    }
+
+    #[test]
+    fn reload_is_not_supported_on_javascript() {
+        let error = Error::ReloadUnsupportedTarget {
+            target: Target::JavaScript,
+        };
+        insta::assert_snapshot!(error.pretty_string());
+    }
+}
@@ -18,6 +18,11 @@ pub fn command(paths: &ProjectPaths) -> Result<(), Error> {
             mode: Mode::Dev,
             target: Some(Target::Erlang),
             no_print_progress: false,
+            compile_tests: true,
+            changed_modules: None,
+            features: vec![],
+            javascript_target_version: None,
+            coverage: false,
         },
         crate::build::download_dependencies(paths, crate::cli::Reporter::new())?,
     )?;
@@ -0,0 +1,293 @@
+use std::{
+    ffi::OsString,
+    io::IsTerminal,
+    time::{Duration, SystemTime},
+};
+
+use camino::Utf8PathBuf;
+use gleam_core::{Error, Result, paths, version::COMPILER_VERSION};
+use hexpm::version::Version;
+
+use crate::{
+    dependencies::REGISTRY_SNAPSHOT_ENVIRONMENT_VARIABLE,
+    fs,
+    hex::{REQUIRE_HTTPS_ENV_NAME, env_flag_enabled},
+    http::HttpClient,
+};
+
+/// The environment variable that sets the URL the latest release of Gleam is
+/// looked up from. It must respond with a JSON object with a `tag_name` field,
+/// like the GitHub releases API does.
+pub const RELEASES_URL_ENVIRONMENT_VARIABLE: &str = "GLEAM_RELEASES_URL";
+
+/// When this environment variable is set the build tool will not check for
+/// new versions of Gleam by itself. `gleam check-update` can still be used.
+pub const DISABLE_ENVIRONMENT_VARIABLE: &str = "GLEAM_NO_UPDATE_CHECK";
+
+const DEFAULT_RELEASES_URL: &str = "https://api.github.com/repos/gleam-lang/gleam/releases/latest";
+
+/// How long to wait between checks made without being asked for.
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// Checks made without being asked for give up after this long, so that being
+/// offline or on a slow connection doesn't hold up the command that was run.
+const DAILY_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// `gleam check-update`: looks up the latest release of Gleam and prints
+/// whether it is newer than the one in use.
+pub fn command() -> Result<()> {
+    let current = current_version();
+    let runtime = tokio::runtime::Runtime::new().expect("Unable to start Tokio async runtime");
+    let latest = runtime.block_on(latest_version(&HttpClient::new(), &releases_url()))?;
+    record_check(&latest);
+    match upgrade_hint(&current, &latest) {
+        Some(hint) => println!("{hint}"),
+        None => println!("You are using the latest version of Gleam, v{current}."),
+    }
+    Ok(())
+}
+
+/// Prints a hint to stderr if a newer version of Gleam has been released.
+///
+/// This is done at most once a day and only when printing to a terminal. It is
+/// never done when the `GLEAM_NO_UPDATE_CHECK` environment variable is set, or
+/// when the network has been restricted to particular hosts or to HTTPS, or a
+/// registry snapshot is used, as those builds expect to make no requests other
+/// than the ones they asked for. Any failure, such as there being no network
+/// connection, is silently ignored.
+pub fn check_daily() {
+    if check_disabled(
+        |name| std::env::var_os(name),
+        crate::http::network_restricted(),
+    ) || !std::io::stderr().is_terminal()
+    {
+        return;
+    }
+    let last_checked = check_path()
+        .metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    if !daily_check_due(last_checked, SystemTime::now()) {
+        return;
+    }
+
+    let Ok(runtime) = tokio::runtime::Runtime::new() else {
+        return;
+    };
+    let url = releases_url();
+    let latest = runtime.block_on(async {
+        tokio::time::timeout(
+            DAILY_CHECK_TIMEOUT,
+            latest_version(&HttpClient::new(), &url),
+        )
+        .await
+    });
+    let latest = match latest {
+        Ok(Ok(latest)) => latest,
+        Ok(Err(error)) => {
+            tracing::debug!(?error, "update_check_failed");
+            // Still record the attempt so we don't try again on every command
+            // while offline.
+            record_check(&current_version());
+            return;
+        }
+        Err(_) => {
+            tracing::debug!("update_check_timed_out");
+            record_check(&current_version());
+            return;
+        }
+    };
+    record_check(&latest);
+    if let Some(hint) = upgrade_hint(&current_version(), &latest) {
+        eprintln!(
+            "\n{hint}\nSet {DISABLE_ENVIRONMENT_VARIABLE} to stop checking for new versions."
+        );
+    }
+}
+
+fn check_disabled(env: impl Fn(&str) -> Option<OsString>, network_restricted: bool) -> bool {
+    network_restricted
+        || [
+            DISABLE_ENVIRONMENT_VARIABLE,
+            REGISTRY_SNAPSHOT_ENVIRONMENT_VARIABLE,
+        ]
+        .into_iter()
+        .any(|name| env(name).is_some())
+        || env_flag_enabled(env(REQUIRE_HTTPS_ENV_NAME))
+}
+
+fn daily_check_due(last_checked: Option<SystemTime>, now: SystemTime) -> bool {
+    match last_checked.map(|time| now.duration_since(time)) {
+        None => true,
+        Some(Ok(elapsed)) => elapsed >= CHECK_INTERVAL,
+        // The last check is in the future, the clock must have changed.
+        Some(Err(_)) => true,
+    }
+}
+
+async fn latest_version(http: &impl gleam_core::io::HttpClient, url: &str) -> Result<Version> {
+    let request = http::Request::get(url)
+        .header("accept", "application/json")
+        .header("user-agent", format!("gleam/{COMPILER_VERSION}"))
+        .body(vec![])
+        .map_err(Error::http)?;
+    let response = http.send(request).await?;
+    if !response.status().is_success() {
+        return Err(Error::Http(format!(
+            "{url} responded with status {}",
+            response.status()
+        )));
+    }
+    parse_latest_release(response.body())
+}
+
+#[derive(serde::Deserialize)]
+struct Release {
+    tag_name: String,
+}
+
+fn parse_latest_release(body: &[u8]) -> Result<Version> {
+    let release: Release = serde_json::from_slice(body).map_err(Error::http)?;
+    let tag = release.tag_name.trim_start_matches('v');
+    Version::parse(tag).map_err(|error| Error::InvalidVersionFormat {
+        input: release.tag_name.clone(),
+        error: error.to_string(),
+    })
+}
+
+fn upgrade_hint(current: &Version, latest: &Version) -> Option<String> {
+    (latest > current).then(|| {
+        format!(
+            "Gleam v{latest} is available, you are using v{current}.
+See https://gleam.run/getting-started/installing/ for how to upgrade."
+        )
+    })
+}
+
+fn current_version() -> Version {
+    Version::parse(COMPILER_VERSION).expect("Compiler version is a valid version")
+}
+
+fn releases_url() -> String {
+    std::env::var(RELEASES_URL_ENVIRONMENT_VARIABLE).unwrap_or(DEFAULT_RELEASES_URL.into())
+}
+
+/// The file whose modification time is when the latest version was last
+/// looked up, containing that version.
+fn check_path() -> Utf8PathBuf {
+    paths::default_global_gleam_cache().join("latest_version")
+}
+
+fn record_check(latest: &Version) {
+    if let Err(error) = fs::write(&check_path(), &latest.to_string()) {
+        tracing::debug!(?error, "update_check_not_recorded");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use http::Response;
+
+    struct MockReleases(&'static str);
+
+    #[async_trait]
+    impl gleam_core::io::HttpClient for MockReleases {
+        async fn send(&self, _request: http::Request<Vec<u8>>) -> Result<Response<Vec<u8>>> {
+            Ok(Response::builder()
+                .status(200)
+                .body(self.0.as_bytes().to_vec())
+                .expect("mock response"))
+        }
+    }
+
+    fn latest_from_mock(body: &'static str) -> Result<Version> {
+        let runtime = tokio::runtime::Runtime::new().expect("runtime");
+        runtime.block_on(latest_version(&MockReleases(body), "http://releases"))
+    }
+
+    #[test]
+    fn latest_version_from_release_tag() {
+        assert_eq!(
+            latest_from_mock(r#"{"tag_name": "v1.42.0", "name": "v1.42.0"}"#),
+            Ok(Version::new(1, 42, 0))
+        );
+    }
+
+    #[test]
+    fn latest_version_from_invalid_release() {
+        assert!(latest_from_mock(r#"{"tag_name": "nightly"}"#).is_err());
+    }
+
+    #[test]
+    fn newer_version_is_reported() {
+        let latest = latest_from_mock(r#"{"tag_name": "v1.42.0"}"#).expect("latest");
+        assert_eq!(
+            upgrade_hint(&Version::new(1, 9, 1), &latest),
+            Some(
+                "Gleam v1.42.0 is available, you are using v1.9.1.
+See https://gleam.run/getting-started/installing/ for how to upgrade."
+                    .into()
+            )
+        );
+    }
+
+    #[test]
+    fn same_or_older_version_is_not_reported() {
+        let current = Version::new(1, 9, 1);
+        assert_eq!(upgrade_hint(&current, &Version::new(1, 9, 1)), None);
+        assert_eq!(upgrade_hint(&current, &Version::new(1, 8, 0)), None);
+    }
+
+    fn only_set(set: &'static str) -> impl Fn(&str) -> Option<OsString> {
+        move |name: &str| (name == set).then(|| "1".into())
+    }
+
+    #[test]
+    fn daily_check_is_enabled_by_default() {
+        assert!(!check_disabled(|_| None, false));
+    }
+
+    #[test]
+    fn daily_check_can_be_disabled() {
+        assert!(check_disabled(only_set("GLEAM_NO_UPDATE_CHECK"), false));
+    }
+
+    #[test]
+    fn daily_check_is_disabled_with_a_registry_snapshot() {
+        assert!(check_disabled(only_set("GLEAM_REGISTRY_SNAPSHOT"), false));
+    }
+
+    #[test]
+    fn daily_check_is_disabled_when_https_is_required() {
+        assert!(check_disabled(only_set("HEXPM_REQUIRE_HTTPS"), false));
+    }
+
+    #[test]
+    fn daily_check_is_enabled_when_https_is_not_required() {
+        assert!(!check_disabled(
+            |name: &str| (name == "HEXPM_REQUIRE_HTTPS").then(|| "false".into()),
+            false
+        ));
+    }
+
+    #[test]
+    fn daily_check_is_disabled_when_the_network_is_restricted() {
+        assert!(check_disabled(|_| None, true));
+    }
+
+    #[test]
+    fn daily_check_when_never_checked() {
+        assert!(daily_check_due(None, SystemTime::now()));
+    }
+
+    #[test]
+    fn daily_check_at_most_once_a_day() {
+        let now = SystemTime::now();
+        let an_hour_ago = now - Duration::from_secs(60 * 60);
+        let two_days_ago = now - Duration::from_secs(60 * 60 * 48);
+        assert!(!daily_check_due(Some(an_hour_ago), now));
+        assert!(daily_check_due(Some(two_days_ago), now));
+    }
+}
@@ -7,12 +7,14 @@ mod tests;
 use crate::{
     GLEAM_CORE_PACKAGE_NAME,
     ast::{
-        self, Arg, BitArrayOption, CustomType, Definition, DefinitionLocation, Function,
-        GroupedStatements, Import, ModuleConstant, Publicity, RecordConstructor,
-        RecordConstructorArg, SrcSpan, Statement, TypeAlias, TypeAst, TypeAstConstructor,
-        TypeAstFn, TypeAstHole, TypeAstTuple, TypeAstVar, TypedDefinition, TypedExpr,
-        TypedFunction, TypedModule, UntypedArg, UntypedCustomType, UntypedFunction, UntypedImport,
-        UntypedModule, UntypedModuleConstant, UntypedStatement, UntypedTypeAlias,
+        self, Arg, ArgNames, BitArrayOption, CustomType, Definition, DefinitionLocation, Function,
+        FunctionLiteralKind, GroupedStatements, Import, ModuleConstant, Publicity,
+        RecordConstructor, RecordConstructorArg, SrcSpan, Statement, TodoKind, TypeAlias, TypeAst,
+        TypeAstConstructor, TypeAstFn, TypeAstHole, TypeAstTuple, TypeAstVar, TypedArg,
+        TypedDefinition, TypedExpr, TypedFunction, TypedModule, TypedStatement, UntypedArg,
+        UntypedCustomType, UntypedFunction, UntypedImport, UntypedModule, UntypedModuleConstant,
+        UntypedStatement, UntypedTypeAlias,
+        visit::{self, Visit},
     },
     build::{Origin, Outcome, Target},
     call_graph::{CallGraphNode, into_dependency_order},
@@ -24,8 +26,8 @@ use crate::{
     type_::{
         self, AccessorsMap, Deprecation, ModuleInterface, Opaque, PatternConstructor,
         RecordAccessor, References, Type, TypeAliasConstructor, TypeConstructor,
-        TypeValueConstructor, TypeValueConstructorField, TypeVariantConstructors, ValueConstructor,
-        ValueConstructorVariant, Warning,
+        TypeValueConstructor, TypeValueConstructorField, TypeVariantConstructors, TypedCallArg,
+        ValueConstructor, ValueConstructorVariant, Warning,
         environment::*,
         error::{Error, FeatureKind, MissingAnnotation, Named, Problems, convert_unify_error},
         expression::{ExprTyper, FunctionDefinition, Implementations},
@@ -485,6 +487,7 @@ impl<'a, A> ModuleAnalyzer<'a, A> {
             external_javascript,
             return_type: (),
             implementations: _,
+            allow_deprecated,
         } = f;
         let (name_location, name) = name.expect("Function in a definition must be named");
         let target = environment.target;
@@ -540,6 +543,8 @@ impl<'a, A> ModuleAnalyzer<'a, A> {
         // we reference in the body of it can be tracked properly in the call graph.
         environment.references.set_current_node(name.clone());
 
+        let warnings_before_body = self.problems.warnings_count();
+
         // Infer the type using the preregistered args + return types as a starting point
         let result = environment.in_new_scope(&mut self.problems, |environment, problems| {
             let mut expr_typer = ExprTyper::new(environment, definition, problems);
@@ -586,6 +591,18 @@ impl<'a, A> ModuleAnalyzer<'a, A> {
             }
         };
 
+        if allow_deprecated {
+            self.problems
+                .remove_deprecated_item_warnings_since(warnings_before_body);
+        }
+
+        if let Some(location) =
+            unconditional_self_call(&environment.current_module, &name, &typed_args, &body)
+        {
+            self.problems
+                .warning(Warning::InfiniteRecursion { location });
+        }
+
         if required_version > self.minimum_required_version {
             self.minimum_required_version = required_version;
         }
@@ -682,6 +699,7 @@ impl<'a, A> ModuleAnalyzer<'a, A> {
             external_erlang,
             external_javascript,
             implementations,
+            allow_deprecated,
         })
     }
 
@@ -1398,6 +1416,7 @@ impl<'a, A> ModuleAnalyzer<'a, A> {
             body: _,
             return_type: _,
             implementations,
+            allow_deprecated,
         } = f;
         let (name_location, name) = name.as_ref().expect("A module's function must be named");
 
@@ -1421,6 +1440,7 @@ impl<'a, A> ModuleAnalyzer<'a, A> {
         }
         let field_map = builder.finish();
         let mut hydrator = Hydrator::new();
+        let warnings_before_signature = self.problems.warnings_count();
 
         // When external implementations are present then the type annotations
         // must be given in full, so we disallow holes in the annotations.
@@ -1437,6 +1457,11 @@ impl<'a, A> ModuleAnalyzer<'a, A> {
         let type_ = fn_(arg_types, return_type);
         let _ = self.hydrators.insert(name.clone(), hydrator);
 
+        if *allow_deprecated {
+            self.problems
+                .remove_deprecated_item_warnings_since(warnings_before_signature);
+        }
+
         let variant = ValueConstructorVariant::ModuleFn {
             documentation: documentation.as_ref().map(|(_, doc)| doc.clone()),
             name: name.clone(),
@@ -1720,6 +1745,7 @@ fn generalise_function(
         external_erlang,
         external_javascript,
         implementations,
+        allow_deprecated,
     } = function;
 
     let (name_location, name) = name.expect("Function in a definition must be named");
@@ -1780,9 +1806,159 @@ fn generalise_function(
         external_erlang,
         external_javascript,
         implementations,
+        allow_deprecated,
     })
 }
 
+/// Finds a call a function makes to itself with exactly the arguments it was
+/// called with, before it does anything that could stop the recursion. Such a
+/// call can never return.
+///
+/// This is deliberately conservative to avoid false positives: only calls
+/// that are a statement of the function's body, or the value assigned by one,
+/// are considered, and an argument that has been shadowed makes the call
+/// different. Any statement before the call that could have an effect, such
+/// as calling a function, stops the search: a loop that receives a message
+/// each time round waits rather than recursing forever.
+///
+fn unconditional_self_call(
+    module: &str,
+    name: &str,
+    args: &[TypedArg],
+    body: &[TypedStatement],
+) -> Option<SrcSpan> {
+    let arg_locations: Vec<SrcSpan> = args
+        .iter()
+        .map(|arg| match &arg.names {
+            ArgNames::Named { location, .. }
+            | ArgNames::NamedLabelled {
+                name_location: location,
+                ..
+            } => Some(*location),
+            ArgNames::Discard { .. } | ArgNames::LabelledDiscard { .. } => None,
+        })
+        .collect::<Option<_>>()?;
+
+    let self_call = |statement: &TypedStatement| {
+        let expression = match statement {
+            Statement::Expression(expression) => expression,
+            Statement::Assignment(assignment) => assignment.value.as_ref(),
+            Statement::Use(_) => return None,
+        };
+        let TypedExpr::Call {
+            location,
+            fun,
+            args: call_args,
+            ..
+        } = expression
+        else {
+            return None;
+        };
+        let calls_itself = matches!(
+            fun.as_ref(),
+            TypedExpr::Var { constructor: ValueConstructor {
+                variant: ValueConstructorVariant::ModuleFn { name: called, module: called_module, .. },
+                ..
+            }, .. } if called == name && called_module == module
+        );
+        let same_args = call_args.len() == arg_locations.len()
+            && call_args
+                .iter()
+                .zip(&arg_locations)
+                .all(|(call_arg, arg_location)| {
+                    matches!(
+                        &call_arg.value,
+                        TypedExpr::Var { constructor: ValueConstructor {
+                            variant: ValueConstructorVariant::LocalVariable { location, .. },
+                            ..
+                        }, .. } if location == arg_location
+                    )
+                });
+        (calls_itself && same_args).then_some(*location)
+    };
+
+    for statement in body {
+        if let Some(location) = self_call(statement) {
+            return Some(location);
+        }
+        let mut effects = SideEffects { found: false };
+        effects.visit_typed_statement(statement);
+        if effects.found {
+            return None;
+        }
+    }
+    None
+}
+
+/// Finds whether evaluating some code could have an effect, or stop it from
+/// continuing: calling a function, `echo`, `panic`, `todo`, or a `let assert`.
+/// Anonymous functions are not looked into, as creating one does nothing.
+struct SideEffects {
+    found: bool,
+}
+
+impl<'ast> Visit<'ast> for SideEffects {
+    fn visit_typed_expr_call(
+        &mut self,
+        location: &'ast SrcSpan,
+        type_: &'ast Arc<Type>,
+        fun: &'ast TypedExpr,
+        args: &'ast [TypedCallArg],
+    ) {
+        // Building a record is the only call that does nothing else.
+        if !fun.is_record_builder() {
+            self.found = true;
+        }
+        visit::visit_typed_expr_call(self, location, type_, fun, args);
+    }
+
+    fn visit_typed_expr_echo(
+        &mut self,
+        _location: &'ast SrcSpan,
+        _type_: &'ast Arc<Type>,
+        _expression: &'ast Option<Box<TypedExpr>>,
+    ) {
+        self.found = true;
+    }
+
+    fn visit_typed_expr_todo(
+        &mut self,
+        _location: &'ast SrcSpan,
+        _message: &'ast Option<Box<TypedExpr>>,
+        _kind: &'ast TodoKind,
+        _type_: &'ast Arc<Type>,
+    ) {
+        self.found = true;
+    }
+
+    fn visit_typed_expr_panic(
+        &mut self,
+        _location: &'ast SrcSpan,
+        _message: &'ast Option<Box<TypedExpr>>,
+        _type_: &'ast Arc<Type>,
+    ) {
+        self.found = true;
+    }
+
+    fn visit_typed_assignment(&mut self, assignment: &'ast ast::TypedAssignment) {
+        if let ast::AssignmentKind::Assert { .. } = assignment.kind {
+            self.found = true;
+        }
+        visit::visit_typed_assignment(self, assignment);
+    }
+
+    fn visit_typed_expr_fn(
+        &mut self,
+        _location: &'ast SrcSpan,
+        _type_: &'ast Arc<Type>,
+        _kind: &'ast FunctionLiteralKind,
+        _args: &'ast [TypedArg],
+        _body: &'ast Vec1<TypedStatement>,
+        _return_annotation: &'ast Option<TypeAst>,
+    ) {
+    }
+}
+
 fn assert_unique_name(
     names: &mut HashMap<EcoString, SrcSpan>,
     name: &EcoString,
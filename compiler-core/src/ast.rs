@@ -687,6 +687,9 @@ pub struct Function<T, Expr> {
     pub external_erlang: Option<(EcoString, EcoString, SrcSpan)>,
     pub external_javascript: Option<(EcoString, EcoString, SrcSpan)>,
     pub implementations: Implementations,
+    /// Whether the function has an `@allow(deprecated)` attribute, silencing
+    /// the warnings for any deprecated values and types used within it.
+    pub allow_deprecated: bool,
 }
 
 pub type TypedFunction = Function<Arc<Type>, TypedExpr>;
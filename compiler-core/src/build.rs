@@ -31,7 +31,7 @@ use crate::{
     type_,
 };
 use camino::Utf8PathBuf;
-use ecow::EcoString;
+use ecow::{EcoString, eco_format};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
@@ -138,14 +138,59 @@ impl Default for Runtime {
     }
 }
 
+/// The version of the ECMAScript standard the generated JavaScript is written
+/// for. The prelude only uses ES2020 features, so that is the oldest version
+/// that can be targeted.
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Display,
+    EnumString,
+    EnumVariantNames,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Default,
+)]
+pub enum EcmaScriptVersion {
+    #[strum(serialize = "es2020")]
+    #[serde(rename = "es2020")]
+    Es2020,
+    #[strum(serialize = "es2021")]
+    #[serde(rename = "es2021")]
+    Es2021,
+    #[default]
+    #[strum(serialize = "es2022")]
+    #[serde(rename = "es2022")]
+    Es2022,
+}
+
+impl EcmaScriptVersion {
+    /// Numeric separators, such as in `1_000`, were added in ES2021.
+    pub fn supports_numeric_separators(self) -> bool {
+        self >= Self::Es2021
+    }
+}
+
 #[derive(Debug)]
 pub enum TargetCodegenConfiguration {
     JavaScript {
         emit_typescript_definitions: bool,
         prelude_location: Utf8PathBuf,
+        ecmascript_version: EcmaScriptVersion,
+        /// Whether the package's `src` modules record which of their lines
+        /// are run, so a test coverage report can be written.
+        coverage: bool,
     },
     Erlang {
         app_file: Option<ErlangAppCodegenConfiguration>,
+        /// Prepended to the name of every generated Erlang module so that
+        /// they cannot clash with other Erlang modules loaded in the same VM.
+        module_prefix: Option<EcoString>,
     },
 }
 
@@ -246,12 +291,12 @@ pub struct Module {
 }
 
 impl Module {
-    pub fn erlang_name(&self) -> EcoString {
-        module_erlang_name(&self.name)
+    pub fn erlang_name(&self, module_prefix: Option<&str>) -> EcoString {
+        prefixed_module_erlang_name(module_prefix, &self.name)
     }
 
-    pub fn compiled_erlang_path(&self) -> Utf8PathBuf {
-        let mut path = Utf8PathBuf::from(&module_erlang_name(&self.name));
+    pub fn compiled_erlang_path(&self, module_prefix: Option<&str>) -> Utf8PathBuf {
+        let mut path = Utf8PathBuf::from(&self.erlang_name(module_prefix));
         assert!(path.set_extension("erl"), "Couldn't set file extension");
         path
     }
@@ -329,6 +374,17 @@ pub fn module_erlang_name(gleam_name: &EcoString) -> EcoString {
     gleam_name.replace("/", "@")
 }
 
+/// The name of the Erlang module generated for a Gleam module, starting with
+/// the `module_prefix` configured in the `[erlang]` section of the root
+/// package's `gleam.toml`, if there is one.
+pub fn prefixed_module_erlang_name(module_prefix: Option<&str>, gleam_name: &str) -> EcoString {
+    let name = gleam_name.replace('/', "@");
+    match module_prefix {
+        Some(prefix) => eco_format!("{prefix}{name}"),
+        None => name.into(),
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct UnqualifiedImport<'a> {
     pub name: &'a EcoString,
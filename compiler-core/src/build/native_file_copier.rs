@@ -20,6 +20,8 @@ pub(crate) struct CopiedNativeFiles {
 pub(crate) struct NativeFileCopier<'a, IO> {
     io: IO,
     root: &'a Utf8Path,
+    /// Whether native files in the `test` directory are copied too.
+    pub(crate) include_tests: bool,
     destination_dir: &'a Utf8Path,
     seen_native_files: HashSet<Utf8PathBuf>,
     seen_modules: HashMap<EcoString, Utf8PathBuf>,
@@ -35,6 +37,7 @@ where
         Self {
             io,
             root,
+            include_tests: true,
             destination_dir: out,
             to_compile: Vec::new(),
             seen_native_files: HashSet::new(),
@@ -56,7 +59,7 @@ where
         self.copy_files(&src)?;
 
         let test = self.root.join("test");
-        if self.io.is_directory(&test) {
+        if self.include_tests && self.io.is_directory(&test) {
             self.copy_files(&test)?;
         }
 
@@ -7,18 +7,20 @@ use crate::{
     Error, Result, Warning,
     ast::{SrcSpan, TypedModule, UntypedModule},
     build::{
-        Mode, Module, Origin, Outcome, Package, SourceFingerprint, Target,
+        EcmaScriptVersion, Mode, Module, Origin, Outcome, Package, SourceFingerprint, Target,
         elixir_libraries::ElixirLibraries,
         native_file_copier::NativeFileCopier,
         package_loader::{CodegenRequired, PackageLoader, StaleTracker},
     },
-    codegen::{Erlang, ErlangApp, JavaScript, TypeScriptDeclarations},
-    config::PackageConfig,
+    codegen::{DEFAULT_LINE_LENGTH, Erlang, ErlangApp, JavaScript, TypeScriptDeclarations},
+    config::{PackageConfig, PackageKind},
     dep_tree, error,
     io::{BeamCompiler, CommandExecutor, FileSystemReader, FileSystemWriter, Stdio},
     metadata::ModuleEncoder,
     parse::extra::ModuleExtra,
-    paths, type_,
+    paths,
+    reference::ReferenceKind,
+    type_::{self, ValueConstructorVariant},
     uid::UniqueIdGenerator,
     warning::{TypeWarningEmitter, WarningEmitter},
 };
@@ -58,12 +60,24 @@ pub struct PackageCompiler<'a, IO> {
     /// entry point nonetheless.
     ///
     pub compile_modules: bool,
+    /// If set to false the modules and native files in the package's `test`
+    /// directory are not compiled, even if the mode includes tests.
+    pub compile_tests: bool,
+    /// If set only the modules affected by changes to these modules, and the
+    /// modules they import, are compiled.
+    pub changed_modules: Option<&'a HashSet<EcoString>>,
+    /// If set a `<name>/features` module is generated for the package, with a
+    /// `Bool` constant for each of the features in its config that is `True`
+    /// for the features given here.
+    pub features: Option<&'a [EcoString]>,
     pub write_entrypoint: bool,
     pub copy_native_files: bool,
     pub compile_beam_bytecode: bool,
     pub subprocess_stdio: Stdio,
     pub target_support: TargetSupport,
     pub cached_warnings: CachedWarnings,
+    /// The width generated Erlang and JavaScript is wrapped at where possible.
+    pub line_length: isize,
 }
 
 impl<'a, IO> PackageCompiler<'a, IO>
@@ -92,12 +106,16 @@ where
             write_metadata: true,
             perform_codegen: true,
             compile_modules: true,
+            compile_tests: true,
+            changed_modules: None,
+            features: None,
             write_entrypoint: false,
             copy_native_files: true,
             compile_beam_bytecode: true,
             subprocess_stdio: Stdio::Inherit,
             target_support: TargetSupport::NotEnforced,
             cached_warnings: CachedWarnings::Ignore,
+            line_length: DEFAULT_LINE_LENGTH,
         }
     }
 
@@ -129,11 +147,25 @@ where
             CodegenRequired::No
         };
 
+        let generated_directory = self.out.join(paths::GENERATED_DIRECTORY_NAME);
+        let generated_directory = match self.features {
+            Some(enabled) if !self.config.features.is_empty() => {
+                if let Err(error) = self.write_features_module(&generated_directory, enabled) {
+                    return error.into();
+                }
+                Some(generated_directory.as_path())
+            }
+            _ => None,
+        };
+
         let loader = PackageLoader::new(
             self.io.clone(),
             self.ids.clone(),
             self.mode,
+            self.compile_tests,
+            self.changed_modules,
             self.root,
+            generated_directory,
             self.cached_warnings,
             warnings,
             codegen_required,
@@ -209,6 +241,18 @@ where
             Outcome::TotalFailure(error) => return Outcome::TotalFailure(error),
         };
 
+        if self.config.kind == PackageKind::Application {
+            let module_names = modules
+                .iter()
+                .map(|module| &module.name)
+                .chain(&cached_module_names);
+            if let Err(error) =
+                self.emit_unused_public_function_warnings(warnings, existing_modules, module_names)
+            {
+                return error.into();
+            }
+        }
+
         tracing::debug!("performing_code_generation");
 
         if let Err(error) = self.perform_codegen(&modules) {
@@ -256,7 +300,8 @@ where
             self.io.symlink_dir(&priv_source, &priv_build)?;
         }
 
-        let copier = NativeFileCopier::new(self.io.clone(), self.root.clone(), destination_dir);
+        let mut copier = NativeFileCopier::new(self.io.clone(), self.root.clone(), destination_dir);
+        copier.include_tests = self.compile_tests;
         let copied = copier.run()?;
 
         to_compile_modules.extend(copied.to_compile.into_iter());
@@ -274,6 +319,22 @@ where
         Ok(())
     }
 
+    fn write_features_module(&self, directory: &Utf8Path, enabled: &[EcoString]) -> Result<()> {
+        let path = directory
+            .join(self.config.name.as_str())
+            .join("features.gleam");
+        let source = features_module(&self.config.features, enabled);
+
+        // The module is only written when the enabled features change so that
+        // the modules that import it are not needlessly recompiled.
+        if self.io.is_file(&path) && self.io.read(&path)? == source {
+            tracing::debug!("features_module_unchanged");
+            return Ok(());
+        }
+        tracing::debug!("writing_features_module");
+        self.io.write(&path, &source)
+    }
+
     fn encode_and_write_metadata(&mut self, modules: &[Module]) -> Result<()> {
         if !self.write_metadata {
             tracing::debug!("package_metadata_writing_disabled");
@@ -327,14 +388,19 @@ where
             TargetCodegenConfiguration::JavaScript {
                 emit_typescript_definitions,
                 prelude_location,
+                ecmascript_version,
+                coverage,
             } => self.perform_javascript_codegen(
                 modules,
                 *emit_typescript_definitions,
                 prelude_location,
+                *ecmascript_version,
+                *coverage,
             ),
-            TargetCodegenConfiguration::Erlang { app_file } => {
-                self.perform_erlang_codegen(modules, app_file.as_ref())
-            }
+            TargetCodegenConfiguration::Erlang {
+                app_file,
+                module_prefix,
+            } => self.perform_erlang_codegen(modules, app_file.as_ref(), module_prefix.as_deref()),
         }
     }
 
@@ -342,6 +408,7 @@ where
         &mut self,
         modules: &[Module],
         app_file_config: Option<&ErlangAppCodegenConfiguration>,
+        module_prefix: Option<&str>,
     ) -> Result<(), Error> {
         let mut written = HashSet::new();
         let build_dir = self.out.join(paths::ARTEFACT_DIRECTORY_NAME);
@@ -366,10 +433,18 @@ where
         // we overwrite any precompiled Erlang that was included in the Hex
         // package. Otherwise we will build the potentially outdated precompiled
         // version and not the newly compiled version.
-        Erlang::new(&build_dir, &include_dir).render(io.clone(), modules, self.root)?;
+        Erlang::new(&build_dir, &include_dir, module_prefix, self.line_length).render(
+            io.clone(),
+            modules,
+            self.root,
+        )?;
 
         let native_modules: Vec<EcoString> = if self.compile_beam_bytecode {
-            written.extend(modules.iter().map(Module::compiled_erlang_path));
+            written.extend(
+                modules
+                    .iter()
+                    .map(|module| module.compiled_erlang_path(module_prefix)),
+            );
             self.compile_erlang_to_beam(&written)?
         } else {
             tracing::debug!("skipping_erlang_bytecode_compilation");
@@ -377,7 +452,7 @@ where
         };
 
         if let Some(config) = app_file_config {
-            ErlangApp::new(&self.out.join("ebin"), config).render(
+            ErlangApp::new(&self.out.join("ebin"), config, module_prefix).render(
                 io,
                 &self.config,
                 modules,
@@ -392,6 +467,8 @@ where
         modules: &[Module],
         typescript: bool,
         prelude_location: &Utf8Path,
+        ecmascript_version: EcmaScriptVersion,
+        coverage: bool,
     ) -> Result<(), Error> {
         let mut written = HashSet::new();
         let typescript = if typescript {
@@ -406,7 +483,10 @@ where
             prelude_location,
             &self.root,
             self.target_support,
+            ecmascript_version,
+            self.line_length,
         )
+        .with_coverage(coverage)
         .render(&self.io, modules, self.stdlib_package())?;
 
         if self.copy_native_files {
@@ -460,6 +540,70 @@ where
         Ok(())
     }
 
+    /// Applications are not depended upon by other packages, so a public
+    /// function in the `src` directory that none of the package's modules use
+    /// is dead code. The references of every module are needed to tell, so
+    /// this is checked after analysis rather than for each module.
+    fn emit_unused_public_function_warnings<'b>(
+        &self,
+        warnings: &WarningEmitter,
+        existing_modules: &im::HashMap<EcoString, type_::ModuleInterface>,
+        module_names: impl Iterator<Item = &'b EcoString>,
+    ) -> Result<()> {
+        let interfaces: Vec<_> = module_names
+            .filter_map(|name| existing_modules.get(name))
+            .collect();
+
+        let used: HashSet<_> = interfaces
+            .iter()
+            .flat_map(|interface| &interface.references.value_references)
+            .filter(|(_, references)| {
+                references.iter().any(|reference| match reference.kind {
+                    ReferenceKind::Qualified
+                    | ReferenceKind::Unqualified
+                    | ReferenceKind::Alias => true,
+                    ReferenceKind::Import | ReferenceKind::Definition => false,
+                })
+            })
+            .map(|(key, _)| key)
+            .collect();
+
+        for interface in interfaces.iter().filter(|i| i.origin == Origin::Src) {
+            let mut unused: Vec<_> = interface
+                .values
+                .iter()
+                .filter_map(|(name, value)| match &value.variant {
+                    ValueConstructorVariant::ModuleFn {
+                        module, location, ..
+                    } if value.publicity.is_public()
+                        && *module == interface.name
+                        // `main` is run by `gleam run` rather than called.
+                        && name != "main"
+                        && !used.contains(&(module.clone(), name.clone())) =>
+                    {
+                        Some((name, *location))
+                    }
+                    _ => None,
+                })
+                .collect();
+            if unused.is_empty() {
+                continue;
+            }
+
+            unused.sort_by_key(|(_, location)| location.start);
+            let src: EcoString = self.io.read(&interface.src_path)?.into();
+            for (name, location) in unused {
+                let warning = type_::Warning::UnusedPublicFunction {
+                    location,
+                    name: name.clone(),
+                };
+                warnings.emit(warning.into_warning(interface.src_path.clone(), src.clone()));
+            }
+        }
+
+        Ok(())
+    }
+
     fn stdlib_package(&self) -> StdlibPackage {
         if self.config.dependencies.contains_key("gleam_stdlib")
             || self.config.dev_dependencies.contains_key("gleam_stdlib")
@@ -477,6 +621,26 @@ pub enum StdlibPackage {
     Missing,
 }
 
+/// The source of the `<name>/features` module, with a constant for each of the
+/// package's features.
+fn features_module(features: &[EcoString], enabled: &[EcoString]) -> String {
+    let mut source = String::from(
+        "//// The features this package was built with. This module is generated by
+//// the build tool from the `features` in `gleam.toml`, enable them with
+//// `gleam build --features`.
+",
+    );
+    for feature in features {
+        let value = if enabled.contains(feature) {
+            "True"
+        } else {
+            "False"
+        };
+        source.push_str(&format!("\npub const {feature} = {value}\n"));
+    }
+    source
+}
+
 fn analyse(
     package_config: &PackageConfig,
     target: Target,
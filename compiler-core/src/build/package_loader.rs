@@ -56,7 +56,12 @@ pub struct PackageLoader<'a, IO> {
     io: IO,
     ids: UniqueIdGenerator,
     mode: Mode,
+    include_tests: bool,
+    changed_modules: Option<&'a HashSet<EcoString>>,
     root: &'a Utf8Path,
+    /// A directory of Gleam modules generated by the build tool, such as the
+    /// `<name>/features` module, loaded as if they were in `src`.
+    generated_directory: Option<&'a Utf8Path>,
     warnings: &'a WarningEmitter,
     codegen: CodegenRequired,
     artefact_directory: &'a Utf8Path,
@@ -76,7 +81,10 @@ where
         io: IO,
         ids: UniqueIdGenerator,
         mode: Mode,
+        include_tests: bool,
+        changed_modules: Option<&'a HashSet<EcoString>>,
         root: &'a Utf8Path,
+        generated_directory: Option<&'a Utf8Path>,
         cached_warnings: CachedWarnings,
         warnings: &'a WarningEmitter,
         codegen: CodegenRequired,
@@ -91,7 +99,10 @@ where
             io,
             ids,
             mode,
+            include_tests,
+            changed_modules,
             root,
+            generated_directory,
             warnings,
             codegen,
             target,
@@ -110,11 +121,16 @@ where
         // which should be loaded.
         let mut inputs = self.read_sources_and_caches()?;
 
+        // Test modules are only loaded when the tests are to be compiled. The
+        // caches of the ones that weren't loaded are kept for the next build
+        // that compiles the tests.
+        let unloaded_test_modules = self.unloaded_test_modules();
+
         // Check for any removed modules, by looking at cache files that don't exist in inputs.
         // Delete the cache files for removed modules and mark them as stale
         // to trigger refreshing dependent modules.
         for module in CacheFiles::modules_with_meta_files(&self.io, &self.artefact_directory) {
-            if (!inputs.contains_key(&module)) {
+            if !inputs.contains_key(&module) && !unloaded_test_modules.contains(&module) {
                 tracing::debug!(%module, "module_removed");
                 CacheFiles::new(&self.artefact_directory, &module).delete(&self.io)?;
                 self.stale_modules.add(module);
@@ -135,7 +151,14 @@ where
             // any bugged outcomes, though not any where the compiler is working correctly, so it's
             // mostly to aid debugging.
             .sorted_by(|(a, _), (b, _)| a.cmp(b))
-            .collect();
+            .collect_vec();
+
+        // If only the modules affected by some changes are to be compiled then
+        // any other modules are skipped.
+        let needed = self
+            .changed_modules
+            .map(|changed| modules_needed_for_changes(changed, &deps));
+
         let sequence = dep_tree::toposort_deps(deps)
             .map_err(|e| self.convert_deps_tree_error(e, dep_location_map))?;
 
@@ -148,6 +171,14 @@ where
                 .remove(&name)
                 .expect("Getting parsed module for name");
 
+            if needed
+                .as_ref()
+                .is_some_and(|needed| !needed.contains(&name))
+            {
+                tracing::debug!(module = %name, "module_not_affected_by_changes");
+                continue;
+            }
+
             match input {
                 // A new uncached module is to be compiled
                 Input::New(module) => {
@@ -176,13 +207,68 @@ where
             }
         }
 
+        self.remove_stale_test_caches(unloaded_test_modules)?;
+
         Ok(loaded)
     }
 
+    /// The names of the modules in the test directory when they are not to be
+    /// loaded.
+    fn unloaded_test_modules(&self) -> HashSet<EcoString> {
+        if self.mode.includes_tests() && self.include_tests {
+            return HashSet::new();
+        }
+        GleamFile::iterate_files_in_directory(&self.io, &self.root.join("test"))
+            .filter_map(Result::ok)
+            .map(|file| file.module_name)
+            .collect()
+    }
+
+    /// Deletes the caches of the test modules that weren't loaded if they
+    /// depend on a module that is being recompiled, directly or through other
+    /// test modules, as they could have been compiled against a previous
+    /// version of it. They are compiled anew the next time tests are.
+    fn remove_stale_test_caches(&mut self, modules: HashSet<EcoString>) -> Result<()> {
+        let mut cached = Vec::new();
+        for module in modules {
+            let meta_path = CacheFiles::new(self.artefact_directory, &module).meta_path;
+            if !self.io.is_file(&meta_path) {
+                continue;
+            }
+            let binary = self.io.read_bytes(&meta_path)?;
+            let metadata = CacheMetadata::from_binary(&binary).map_err(|e| Error::FileIo {
+                action: FileIoAction::Parse,
+                kind: FileKind::File,
+                path: meta_path,
+                err: Some(e),
+            })?;
+            cached.push((module, metadata.dependencies));
+        }
+
+        loop {
+            let (stale, fresh): (Vec<_>, Vec<_>) = cached
+                .into_iter()
+                .partition(|(_, dependencies)| self.stale_modules.includes_any(dependencies));
+            if stale.is_empty() {
+                return Ok(());
+            }
+            for (module, _) in stale {
+                tracing::debug!(%module, "test_module_cache_stale");
+                CacheFiles::new(self.artefact_directory, &module).delete(&self.io)?;
+                self.stale_modules.add(module);
+            }
+            cached = fresh;
+        }
+    }
+
     fn load_cached_module(&self, info: CachedModule) -> Result<type_::ModuleInterface, Error> {
         let cache_files = CacheFiles::new(&self.artefact_directory, &info.name);
         let bytes = self.io.read_bytes(&cache_files.cache_path)?;
         let mut module = metadata::ModuleDecoder::new(self.ids.clone()).read(bytes.as_slice())?;
+        // The cache may have been written in another directory, such as when
+        // a dependency's artefacts are copied from the dependency cache, so
+        // the source is wherever it has been found this time.
+        module.src_path = info.source_path;
 
         // Load warnings
         if self.cached_warnings.should_use() {
@@ -231,8 +317,21 @@ where
             }
         }
 
+        // Generated
+        if let Some(generated) = self.generated_directory {
+            for file in GleamFile::iterate_files_in_directory(&self.io, generated) {
+                match file {
+                    Ok(file) => {
+                        let input = loader.load(file)?;
+                        inputs.insert(input)?;
+                    }
+                    Err(warning) => self.warnings.emit(warning),
+                }
+            }
+        }
+
         // Test
-        if self.mode.includes_tests() {
+        if self.mode.includes_tests() && self.include_tests {
             let test = self.root.join("test");
             loader.origin = Origin::Test;
 
@@ -345,6 +444,43 @@ where
     }
 }
 
+/// The modules affected by changes to the given modules, that is the modules
+/// themselves and those that import them directly or indirectly, along with
+/// all the modules they import as those are needed to compile them.
+fn modules_needed_for_changes(
+    changed: &HashSet<EcoString>,
+    deps: &[(EcoString, Vec<EcoString>)],
+) -> HashSet<EcoString> {
+    let reachability = dep_tree::Reachability::new(deps.to_vec());
+
+    // A module importing a changed module is affected even if the changed
+    // module has been removed, and so is not part of the graph.
+    let mut needed: HashSet<EcoString> = deps
+        .iter()
+        .filter(|(name, imports)| {
+            changed.contains(name) || imports.iter().any(|import| changed.contains(import))
+        })
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    let dependents = needed
+        .iter()
+        .flat_map(|module| reachability.dependents_of(module))
+        .cloned()
+        .collect_vec();
+    needed.extend(dependents);
+
+    let dependencies = needed
+        .iter()
+        .filter_map(|module| reachability.dependencies_of(module))
+        .flatten()
+        .cloned()
+        .collect_vec();
+    needed.extend(dependencies);
+
+    needed
+}
+
 fn ensure_gleam_module_does_not_overwrite_standard_erlang_module(input: &Input) -> Result<()> {
     // We only need to check uncached modules as it's not possible for these
     // to have compiled successfully.
@@ -1715,7 +1851,14 @@ impl GleamFile {
             if (Self::is_gleam_path(&path, &dir)) {
                 Ok(Self::new(dir, path))
             } else {
-                Err(crate::Warning::InvalidSource { path })
+                let module_name = Self::module_name(&path, &dir);
+                let suggested_path = Self::suggested_module_name(&module_name)
+                    .map(|name| dir.join(format!("{name}.gleam")));
+                Err(crate::Warning::InvalidSource {
+                    path,
+                    module_name,
+                    suggested_path,
+                })
             }
         })
     }
@@ -1743,6 +1886,28 @@ impl GleamFile {
         name.replace("\\", "/").into()
     }
 
+    /// A valid module name close to an invalid one, for use in suggestions.
+    /// For example, `My-Module/Wibble` becomes `my_module/wibble`. There's no
+    /// suggestion if a part of the name doesn't start with a letter.
+    fn suggested_module_name(module_name: &str) -> Option<EcoString> {
+        use heck::ToSnakeCase;
+
+        let is_valid = |segment: &str| {
+            segment.starts_with(|char: char| char.is_ascii_lowercase())
+                && segment
+                    .chars()
+                    .all(|char| char == '_' || char.is_ascii_lowercase() || char.is_ascii_digit())
+        };
+        module_name
+            .split('/')
+            .map(|segment| {
+                let segment = segment.to_snake_case();
+                is_valid(&segment).then_some(segment)
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(|segments| segments.join("/").into())
+    }
+
     fn is_gleam_path(path: &Utf8Path, dir: &Utf8Path) -> bool {
         use regex::Regex;
         use std::cell::OnceCell;
@@ -77,6 +77,15 @@ fn write_cache(
 }
 
 fn run_loader(fs: InMemoryFileSystem, root: &Utf8Path, artefact: &Utf8Path) -> LoaderTestOutput {
+    run_loader_for_changes(fs, root, artefact, None)
+}
+
+fn run_loader_for_changes(
+    fs: InMemoryFileSystem,
+    root: &Utf8Path,
+    artefact: &Utf8Path,
+    changed_modules: Option<&HashSet<EcoString>>,
+) -> LoaderTestOutput {
     let mut defined = im::HashMap::new();
     let ids = UniqueIdGenerator::new();
     let (emitter, warnings) = WarningEmitter::vector();
@@ -85,7 +94,10 @@ fn run_loader(fs: InMemoryFileSystem, root: &Utf8Path, artefact: &Utf8Path) -> L
         io: fs.clone(),
         ids,
         mode: Mode::Dev,
+        include_tests: true,
+        changed_modules,
         root: &root,
+        generated_directory: None,
         warnings: &emitter,
         codegen: CodegenRequired::Yes,
         artefact_directory: &artefact,
@@ -164,6 +176,53 @@ fn importing() {
     assert!(loaded.cached.is_empty());
 }
 
+#[test]
+fn only_modules_affected_by_changes() {
+    let fs = InMemoryFileSystem::new();
+    let root = Utf8Path::new("/");
+    let artefact = Utf8Path::new("/artefact");
+
+    // one <- two <- three
+    //   ^
+    //   +-- four    five <- six
+    write_src(&fs, "/src/one.gleam", 0, "");
+    write_src(&fs, "/src/two.gleam", 0, "import one");
+    write_src(&fs, "/src/three.gleam", 0, "import two");
+    write_src(&fs, "/src/four.gleam", 0, "import one");
+    write_src(&fs, "/src/five.gleam", 0, "");
+    write_src(&fs, "/test/six.gleam", 0, "import five");
+
+    let changed = HashSet::from([EcoString::from("two")]);
+    let loaded = run_loader_for_changes(fs, root, artefact, Some(&changed));
+    assert_eq!(
+        loaded.to_compile,
+        vec![
+            EcoString::from("one"),
+            EcoString::from("two"),
+            EcoString::from("three")
+        ]
+    );
+    assert!(loaded.cached.is_empty());
+}
+
+#[test]
+fn modules_importing_removed_module_are_affected() {
+    let fs = InMemoryFileSystem::new();
+    let root = Utf8Path::new("/");
+    let artefact = Utf8Path::new("/artefact");
+
+    write_src(&fs, "/src/one.gleam", 0, "");
+    write_src(&fs, "/src/two.gleam", 0, "import removed");
+    write_src(&fs, "/src/three.gleam", 0, "import two");
+
+    let changed = HashSet::from([EcoString::from("removed")]);
+    let loaded = run_loader_for_changes(fs, root, artefact, Some(&changed));
+    assert_eq!(
+        loaded.to_compile,
+        vec![EcoString::from("two"), EcoString::from("three")]
+    );
+}
+
 #[test]
 fn reading_cache() {
     let fs = InMemoryFileSystem::new();
@@ -300,6 +359,8 @@ fn invalid_module_name() {
         loaded.warnings,
         vec![Warning::InvalidSource {
             path: Utf8PathBuf::from("/src/One.gleam"),
+            module_name: "One".into(),
+            suggested_path: Some(Utf8PathBuf::from("/src/one.gleam")),
         }],
     );
 }
@@ -320,6 +381,28 @@ fn invalid_nested_module_name() {
         loaded.warnings,
         vec![Warning::InvalidSource {
             path: Utf8PathBuf::from("/src/1/one.gleam"),
+            module_name: "1/one".into(),
+            suggested_path: None,
+        }],
+    );
+}
+
+#[test]
+fn invalid_module_name_suggests_a_valid_path() {
+    let fs = InMemoryFileSystem::new();
+    let root = Utf8Path::new("/");
+    let artefact = Utf8Path::new("/artefact");
+
+    write_src(&fs, "/src/My-Module/WibbleWobble.gleam", 1, TEST_SOURCE_2);
+
+    let loaded = run_loader(fs, root, artefact);
+    assert!(loaded.to_compile.is_empty());
+    assert_eq!(
+        loaded.warnings,
+        vec![Warning::InvalidSource {
+            path: Utf8PathBuf::from("/src/My-Module/WibbleWobble.gleam"),
+            module_name: "My-Module/WibbleWobble".into(),
+            suggested_path: Some(Utf8PathBuf::from("/src/my_module/wibble_wobble.gleam")),
         }],
     );
 }
@@ -340,6 +423,8 @@ fn invalid_module_name_in_test() {
         loaded.warnings,
         vec![Warning::InvalidSource {
             path: Utf8PathBuf::from("/test/One.gleam"),
+            module_name: "One".into(),
+            suggested_path: Some(Utf8PathBuf::from("/test/one.gleam")),
         }],
     );
 }
@@ -360,6 +445,8 @@ fn invalid_nested_module_name_in_test() {
         loaded.warnings,
         vec![Warning::InvalidSource {
             path: Utf8PathBuf::from("/test/1/one.gleam"),
+            module_name: "1/one".into(),
+            suggested_path: None,
         }],
     );
 }
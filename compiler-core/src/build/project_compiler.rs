@@ -2,7 +2,7 @@ use crate::{
     Error, Result, Warning,
     analyse::TargetSupport,
     build::{
-        Mode, Module, Origin, Package, Target,
+        EcmaScriptVersion, Mode, Module, Origin, Package, Target,
         package_compiler::{self, PackageCompiler},
         package_loader::StaleTracker,
         project_compiler,
@@ -15,13 +15,14 @@ use crate::{
     io::{BeamCompiler, Command, CommandExecutor, FileSystemReader, FileSystemWriter, Stdio},
     manifest::{ManifestPackage, ManifestPackageSource},
     metadata,
+    parse::lexer::str_to_keyword,
     paths::{self, ProjectPaths},
     type_::{self, ModuleFunction},
     uid::UniqueIdGenerator,
     version::COMPILER_VERSION,
     warning::{self, WarningEmitter, WarningEmitterIO},
 };
-use ecow::EcoString;
+use ecow::{EcoString, eco_format};
 use hexpm::version::Version;
 use itertools::Itertools;
 use pubgrub::range::Range;
@@ -50,12 +51,16 @@ const REBAR_EXECUTABLE: &str = "rebar3";
 #[cfg(target_os = "windows")]
 const REBAR_EXECUTABLE: &str = "rebar3.cmd";
 
+/// The file written to an entry in the dependency cache once all of the
+/// package's artefacts have been copied into it.
+const DEPENDENCY_CACHE_COMPLETE: &str = "complete";
+
 #[cfg(not(target_os = "windows"))]
 const ELIXIR_EXECUTABLE: &str = "elixir";
 #[cfg(target_os = "windows")]
 const ELIXIR_EXECUTABLE: &str = "elixir.bat";
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Options {
     pub mode: Mode,
     pub target: Option<Target>,
@@ -64,6 +69,21 @@ pub struct Options {
     pub warnings_as_errors: bool,
     pub root_target_support: TargetSupport,
     pub no_print_progress: bool,
+    /// Whether the modules in the root package's `test` directory are
+    /// compiled. This only has an effect when the mode includes tests.
+    pub compile_tests: bool,
+    /// If set only the root package's modules affected by changes to these
+    /// modules are compiled, that is the modules themselves and those that
+    /// import them directly or indirectly, along with the modules they import.
+    pub changed_modules: Option<HashSet<EcoString>>,
+    /// The features of the root package that are enabled, making their
+    /// constants in the generated `<name>/features` module `True`.
+    pub features: Vec<EcoString>,
+    /// Overrides the ECMAScript version set in the root package's config.
+    pub javascript_target_version: Option<EcmaScriptVersion>,
+    /// Whether the JavaScript generated for the root package's `src` modules
+    /// records which lines are run, so a test coverage report can be written.
+    pub coverage: bool,
 }
 
 #[derive(Debug)]
@@ -96,6 +116,40 @@ impl Built {
             .map(|minimum_required_version| minimum_required_version.clone())
             .unwrap_or(Version::new(0, 1, 0))
     }
+
+    /// The root package's `dependencies` and `dev-dependencies` that have
+    /// Gleam modules, none of which are imported by the root package.
+    /// Packages without any Gleam modules are never included, as they can
+    /// only be used through FFI.
+    pub fn unused_dependencies(&self) -> Vec<EcoString> {
+        let root = &self.root_package.config.name;
+        let imported_packages: HashSet<&EcoString> = self
+            .module_interfaces
+            .values()
+            .filter(|interface| &interface.package == root)
+            .flat_map(|interface| &interface.references.imported_modules)
+            .filter_map(|module| self.module_interfaces.get(module))
+            .map(|interface| &interface.package)
+            .collect();
+        let gleam_packages: HashSet<&EcoString> = self
+            .module_interfaces
+            .values()
+            .map(|interface| &interface.package)
+            .collect();
+
+        let config = &self.root_package.config;
+        config
+            .dependencies
+            .keys()
+            .chain(config.dev_dependencies.keys())
+            .filter(|package| {
+                gleam_packages.contains(package) && !imported_packages.contains(package)
+            })
+            .cloned()
+            .sorted()
+            .dedup()
+            .collect()
+    }
 }
 
 #[derive(Debug)]
@@ -118,6 +172,17 @@ pub struct ProjectCompiler<IO> {
     /// We may want to silence subprocess stdout if we are running in LSP mode.
     /// The language server talks over stdio so printing would break that.
     pub subprocess_stdio: Stdio,
+    /// A directory shared by every project on the machine in which the
+    /// compiled artefacts of Hex dependencies are kept, so that each one is
+    /// compiled once and then copied into any project that uses it.
+    pub dependency_cache: Option<Utf8PathBuf>,
+    /// The versions of Erlang/OTP and Elixir that Erlang dependencies are
+    /// compiled with. Compiled BEAM files and the artefacts of Rebar3 and Mix
+    /// depend on them, so they are part of each cached dependency's key.
+    pub dependency_cache_runtime: EcoString,
+    /// The key each dependency's artefacts are kept under in the dependency
+    /// cache, if they can be shared.
+    dependency_cache_keys: HashMap<EcoString, u64>,
 }
 
 // TODO: test that tests cannot be imported into src
@@ -149,6 +214,9 @@ where
             ids: UniqueIdGenerator::new(),
             warnings: WarningEmitter::new(warning_emitter),
             subprocess_stdio: Stdio::Inherit,
+            dependency_cache: None,
+            dependency_cache_runtime: EcoString::new(),
+            dependency_cache_keys: HashMap::new(),
             telemetry,
             packages,
             options,
@@ -170,6 +238,24 @@ where
         self.options.target.unwrap_or(self.config.target)
     }
 
+    /// The version of ECMAScript the JavaScript of every package is
+    /// generated for, which is decided by the root package.
+    pub fn ecmascript_version(&self) -> EcmaScriptVersion {
+        self.options
+            .javascript_target_version
+            .unwrap_or(self.config.javascript.target_version)
+    }
+
+    /// The width the generated code of every package is wrapped at, which is
+    /// decided by the root package.
+    fn line_length(&self) -> isize {
+        self.config
+            .generated_line_length
+            .map_or(codegen::DEFAULT_LINE_LENGTH, |length| {
+                isize::try_from(length).unwrap_or(isize::MAX)
+            })
+    }
+
     pub fn reset_state_for_new_compile_run(&mut self) {
         // We make sure the stale module tracker is empty before we start, to
         // avoid mistakenly thinking a module is stale due to outdated state
@@ -188,6 +274,9 @@ where
         // verify that this version is appropriate.
         self.check_gleam_version()?;
 
+        // The features that are enabled must be ones the root package has.
+        self.check_features()?;
+
         // The JavaScript target requires a prelude module to be written.
         self.write_prelude()?;
 
@@ -229,10 +318,63 @@ where
             )
     }
 
+    /// Checks that the features of the root package can be used as the names
+    /// of constants, and that the enabled features are all ones it has.
+    pub fn check_features(&self) -> Result<(), Error> {
+        for feature in &self.config.features {
+            let valid = feature.starts_with(|c: char| c.is_ascii_lowercase())
+                && feature
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+                && str_to_keyword(feature).is_none();
+            if !valid {
+                return Err(Error::InvalidFeatureName {
+                    name: feature.clone(),
+                });
+            }
+        }
+        match self
+            .options
+            .features
+            .iter()
+            .find(|feature| !self.config.features.contains(feature))
+        {
+            Some(feature) => Err(Error::UnknownFeature {
+                feature: feature.clone(),
+                features: self.config.features.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn build_version(&self) -> EcoString {
+        let version = match self.target() {
+            Target::Erlang => COMPILER_VERSION.into(),
+            Target::JavaScript if self.options.coverage => {
+                eco_format!("{COMPILER_VERSION} {} coverage", self.ecmascript_version())
+            }
+            Target::JavaScript => eco_format!("{COMPILER_VERSION} {}", self.ecmascript_version()),
+        };
+        let version = match self.config.generated_line_length {
+            Some(length) => eco_format!("{version} line-length {length}"),
+            None => version,
+        };
+        match (self.target(), &self.config.erlang.module_prefix) {
+            (Target::Erlang, Some(prefix)) => eco_format!("{version} module-prefix {prefix}"),
+            _ => version,
+        }
+    }
+
     /// Checks that version file found in the build directory matches the
     /// current version of gleam. If not, we will clear the build directory
     /// before continuing. This will ensure that upgrading gleam will not leave
     /// one with confusing or hard to debug states.
+    ///
+    /// For the JavaScript target the file also records the ECMAScript version
+    /// the code was generated for, so changing it rebuilds every module. The
+    /// same goes for the configured line length of generated code, for the
+    /// prefix of generated Erlang module names, and for whether the code is
+    /// instrumented for test coverage.
     pub fn check_gleam_version(&self) -> Result<(), Error> {
         let build_path = self
             .paths
@@ -240,7 +382,7 @@ where
         let version_path = self.paths.build_gleam_version(self.mode(), self.target());
         if self.io.is_file(&version_path) {
             let version = self.io.read(&version_path)?;
-            if version == COMPILER_VERSION {
+            if version == self.build_version() {
                 return Ok(());
             }
         }
@@ -252,7 +394,7 @@ where
         // Recreate build directory with new updated version file
         self.io.mkdir(&build_path)?;
         self.io
-            .write(&version_path, COMPILER_VERSION)
+            .write(&version_path, &self.build_version())
             .map_err(|e| Error::FileIo {
                 action: FileIoAction::WriteTo,
                 kind: FileKind::File,
@@ -310,6 +452,11 @@ where
         // packages into their own classes and then only mutate self after we no
         // longer need to have the package borrowed from self.packages.
         let package = self.packages.get(name).expect("Missing package").clone();
+        let cached_artefacts = self.cached_dependency_artefacts(&package);
+        if let Some(cached) = &cached_artefacts {
+            self.restore_dependency_artefacts(&package, cached)?;
+        }
+
         let result = match usable_build_tools(&package)?.as_slice() {
             &[BuildTool::Gleam] => self.compile_gleam_dep_package(&package),
             &[BuildTool::Rebar3] => self.compile_rebar3_dep_package(&package).map(|_| vec![]),
@@ -335,11 +482,142 @@ where
                 package.application_name(),
             );
             self.io.delete_directory(&path)?;
+        } else if let Some(cached) = &cached_artefacts {
+            self.store_dependency_artefacts(&package, cached)?;
         }
 
         result
     }
 
+    /// The directory in the dependency cache the compiled artefacts of a
+    /// package are kept in. Only packages from Hex can be shared between
+    /// projects, as they cannot change once published, and only if all the
+    /// packages they depend on can be too.
+    ///
+    /// Besides the package's own checksum the key covers everything the
+    /// compiled code depends on: the compiler version, the target, the code
+    /// generation settings of the root package, the Erlang and Elixir versions
+    /// when compiling to Erlang, and the keys of the package's dependencies,
+    /// as their modules may have been inlined.
+    fn cached_dependency_artefacts(&mut self, package: &ManifestPackage) -> Option<Utf8PathBuf> {
+        let cache = self.dependency_cache.as_ref()?;
+        let ManifestPackageSource::Hex { outer_checksum } = &package.source else {
+            return None;
+        };
+
+        let mut key = format!(
+            "{}\n{}\n{}\n{COMPILER_VERSION}\n{}\n{}\n",
+            package.name,
+            package.version,
+            outer_checksum.to_string(),
+            self.target(),
+            self.line_length(),
+        );
+        match self.target() {
+            Target::Erlang => {
+                let _ = writeln!(key, "{:?}", self.config.erlang.module_prefix);
+                let _ = writeln!(key, "{}", self.dependency_cache_runtime);
+                for (name, otp_app) in self
+                    .packages
+                    .values()
+                    .filter_map(|p| Some((&p.name, p.otp_app.as_ref()?)))
+                    .sorted()
+                {
+                    let _ = writeln!(key, "{name}={otp_app}");
+                }
+            }
+            Target::JavaScript => {
+                let _ = writeln!(
+                    key,
+                    "{} {:?}",
+                    self.config.javascript.typescript_declarations,
+                    self.ecmascript_version()
+                );
+            }
+        }
+        for requirement in package.requirements.iter().sorted() {
+            let requirement_key = self.dependency_cache_keys.get(requirement)?;
+            let _ = writeln!(key, "{requirement}={requirement_key:016x}");
+        }
+
+        let key = xxhash_rust::xxh3::xxh3_64(key.as_bytes());
+        let _ = self.dependency_cache_keys.insert(package.name.clone(), key);
+        Some(
+            cache
+                .join(COMPILER_VERSION)
+                .join(self.target().to_string())
+                .join(format!("{}-{}-{key:016x}", package.name, package.version)),
+        )
+    }
+
+    /// Copies a dependency's artefacts from the dependency cache into the
+    /// build directory if it has not been compiled in this project yet.
+    fn restore_dependency_artefacts(
+        &self,
+        package: &ManifestPackage,
+        cached: &Utf8Path,
+    ) -> Result<(), Error> {
+        let path = self.paths.build_directory_for_package(
+            self.mode(),
+            self.target(),
+            package.application_name(),
+        );
+        if self.io.is_directory(&path) || !self.io.is_file(&cached.join(DEPENDENCY_CACHE_COMPLETE))
+        {
+            return Ok(());
+        }
+        tracing::debug!(package=%package.name, "restoring_cached_dependency_artefacts");
+        self.io.mkdir(&path)?;
+        self.io.copy_dir(&cached.join("artefacts"), &path)
+    }
+
+    /// Copies a freshly compiled dependency's artefacts into the dependency
+    /// cache so other projects can use them. The artefacts are only complete
+    /// if code generation was performed for dependencies.
+    fn store_dependency_artefacts(
+        &self,
+        package: &ManifestPackage,
+        cached: &Utf8Path,
+    ) -> Result<(), Error> {
+        let path = self.paths.build_directory_for_package(
+            self.mode(),
+            self.target(),
+            package.application_name(),
+        );
+        let complete = cached.join(DEPENDENCY_CACHE_COMPLETE);
+        if !self.options.codegen.should_codegen(false)
+            || !self.io.is_directory(&path)
+            || self.io.is_file(&complete)
+        {
+            return Ok(());
+        }
+        tracing::debug!(package=%package.name, "storing_dependency_artefacts_in_cache");
+        // The cache is shared by every project, so other builds may be
+        // reading or storing this entry at the same time. The artefacts are
+        // copied to a directory of this process's own and then renamed into
+        // place, so the entry appears all at once and is never partly written.
+        let temporary = Utf8PathBuf::from(format!("{cached}.tmp-{}", std::process::id()));
+        self.io.delete_directory(&temporary)?;
+        let artefacts = temporary.join("artefacts");
+        self.io.mkdir(&artefacts)?;
+        self.io.copy_dir(&path, &artefacts)?;
+        self.io
+            .write(&temporary.join(DEPENDENCY_CACHE_COMPLETE), "")?;
+
+        // An entry without the marker was left by an interrupted copy made by
+        // an older version of Gleam, and it is replaced.
+        if self.io.is_directory(cached) && !self.io.is_file(&complete) {
+            self.io.delete_directory(cached)?;
+        }
+        let result = self.io.rename_directory(&temporary, cached);
+        self.io.delete_directory(&temporary)?;
+        match result {
+            // Another build stored the same artefacts first.
+            Err(_) if self.io.is_file(&complete) => Ok(()),
+            result => result,
+        }
+    }
+
     // TODO: extract and unit test
     fn compile_rebar3_dep_package(&mut self, package: &ManifestPackage) -> Result<(), Error> {
         let application_name = package.application_name();
@@ -573,6 +851,7 @@ where
                         include_dev_deps: is_root && self.mode().includes_dev_dependencies(),
                         package_name_overrides,
                     }),
+                    module_prefix: self.config.erlang.module_prefix.clone(),
                 }
             }
 
@@ -580,6 +859,8 @@ where
                 emit_typescript_definitions: self.config.javascript.typescript_declarations,
                 // This path is relative to each package output directory
                 prelude_location: Utf8PathBuf::from("../prelude.mjs"),
+                ecmascript_version: self.ecmascript_version(),
+                coverage: is_root && self.options.coverage,
             },
         };
 
@@ -598,7 +879,19 @@ where
         compiler.perform_codegen = self.options.codegen.should_codegen(is_root);
         compiler.compile_beam_bytecode = self.options.codegen.should_codegen(is_root);
         compiler.compile_modules = !(self.options.compile == Compile::DepsOnly && is_root);
+        compiler.compile_tests = !is_root || self.options.compile_tests;
+        compiler.changed_modules = if is_root {
+            self.options.changed_modules.as_ref()
+        } else {
+            None
+        };
+        compiler.features = if is_root {
+            Some(&self.options.features)
+        } else {
+            None
+        };
         compiler.subprocess_stdio = self.subprocess_stdio;
+        compiler.line_length = self.line_length();
         compiler.target_support = if is_root {
             // When compiling the root package it is context specific as to whether we need to
             // enforce that all functions have an implementation for the current target.
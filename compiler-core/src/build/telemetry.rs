@@ -3,13 +3,16 @@ use std::{
     time::{Duration, Instant},
 };
 
+use hexpm::version::Version;
+
 use crate::Warning;
 
 pub trait Telemetry: Debug {
     fn waiting_for_build_directory_lock(&self);
     fn running(&self, name: &str);
     fn resolving_package_versions(&self);
-    fn downloading_package(&self, name: &str);
+    fn downloading_packages(&self, count: usize);
+    fn downloaded_package(&self, name: &str, version: &Version);
     fn packages_downloaded(&self, start: Instant, count: usize);
     fn compiled_package(&self, duration: Duration);
     fn compiling_package(&self, name: &str);
@@ -24,7 +27,8 @@ impl Telemetry for NullTelemetry {
     fn waiting_for_build_directory_lock(&self) {}
     fn running(&self, name: &str) {}
     fn resolving_package_versions(&self) {}
-    fn downloading_package(&self, _name: &str) {}
+    fn downloading_packages(&self, _count: usize) {}
+    fn downloaded_package(&self, _name: &str, _version: &Version) {}
     fn compiled_package(&self, _duration: Duration) {}
     fn compiling_package(&self, _name: &str) {}
     fn checked_package(&self, _duration: Duration) {}
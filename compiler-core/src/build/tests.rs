@@ -1,4 +1,15 @@
-use crate::{Error, manifest::ManifestPackage};
+use crate::{
+    Error,
+    analyse::TargetSupport,
+    build::{Codegen, Compile, Mode, NullTelemetry, Options, ProjectCompiler, Target},
+    config::{ErlangConfig, PackageConfig},
+    io::{FileSystemReader, FileSystemWriter, memory::InMemoryFileSystem},
+    manifest::ManifestPackage,
+    paths::ProjectPaths,
+    warning::VectorWarningEmitterIO,
+};
+use camino::Utf8PathBuf;
+use std::rc::Rc;
 
 use super::project_compiler::{BuildTool, usable_build_tools};
 
@@ -55,3 +66,353 @@ fn usable_build_tool_mix_then_rebar3() {
         Ok(vec![BuildTool::Mix, BuildTool::Rebar3])
     )
 }
+
+fn compile_project_with_invalid_test_module(compile_tests: bool) -> crate::Result<()> {
+    let root = Utf8PathBuf::from("/");
+    let fs = InMemoryFileSystem::new();
+    fs.write(&root.join("src/my_project.gleam"), "pub fn main() { 1 }")
+        .unwrap();
+    fs.write(
+        &root.join("test/my_project_test.gleam"),
+        "pub fn main() { 1 + \"one\" }",
+    )
+    .unwrap();
+
+    let config = PackageConfig {
+        name: "my_project".into(),
+        ..Default::default()
+    };
+    let options = Options {
+        mode: Mode::Dev,
+        target: Some(Target::JavaScript),
+        compile: Compile::All,
+        codegen: Codegen::All,
+        warnings_as_errors: false,
+        root_target_support: TargetSupport::Enforced,
+        no_print_progress: true,
+        compile_tests,
+        changed_modules: None,
+        features: vec![],
+        javascript_target_version: None,
+        coverage: false,
+    };
+    let compiler = ProjectCompiler::new(
+        config,
+        options,
+        vec![],
+        &NullTelemetry,
+        Rc::new(VectorWarningEmitterIO::default()),
+        ProjectPaths::new(root),
+        fs,
+    );
+    compiler.compile().map(|_| ())
+}
+
+#[test]
+fn test_module_type_error_does_not_fail_build_without_tests() {
+    assert!(compile_project_with_invalid_test_module(false).is_ok());
+}
+
+#[test]
+fn test_module_type_error_fails_build_with_tests() {
+    assert!(matches!(
+        compile_project_with_invalid_test_module(true),
+        Err(Error::Type { .. })
+    ));
+}
+
+fn compile_project(fs: &InMemoryFileSystem, compile_tests: bool) {
+    let config = PackageConfig {
+        name: "my_project".into(),
+        ..Default::default()
+    };
+    let options = Options {
+        mode: Mode::Dev,
+        target: Some(Target::JavaScript),
+        compile: Compile::All,
+        codegen: Codegen::All,
+        warnings_as_errors: false,
+        root_target_support: TargetSupport::Enforced,
+        no_print_progress: true,
+        compile_tests,
+        changed_modules: None,
+        features: vec![],
+        javascript_target_version: None,
+        coverage: false,
+    };
+    let compiler = ProjectCompiler::new(
+        config,
+        options,
+        vec![],
+        &NullTelemetry,
+        Rc::new(VectorWarningEmitterIO::default()),
+        ProjectPaths::new(Utf8PathBuf::from("/")),
+        fs.clone(),
+    );
+    let _ = compiler.compile().expect("compile project");
+}
+
+fn project_with_test_module() -> InMemoryFileSystem {
+    let fs = InMemoryFileSystem::new();
+    fs.write(
+        &Utf8PathBuf::from("/src/my_project.gleam"),
+        "pub fn main() { 1 }",
+    )
+    .unwrap();
+    fs.write(
+        &Utf8PathBuf::from("/test/my_project_test.gleam"),
+        "import my_project
+pub fn main() { my_project.main() }",
+    )
+    .unwrap();
+    fs
+}
+
+const TEST_MODULE_CACHE: &str =
+    "/build/dev/javascript/my_project/_gleam_artefacts/my_project_test.cache_meta";
+
+#[test]
+fn building_without_tests_keeps_the_test_module_caches() {
+    let fs = project_with_test_module();
+    let cache = Utf8PathBuf::from(TEST_MODULE_CACHE);
+
+    compile_project(&fs, false);
+    compile_project(&fs, true);
+    let cached_at = fs.modification_time(&cache).unwrap();
+
+    compile_project(&fs, false);
+    assert!(fs.is_file(&cache));
+
+    // The test module is loaded from its cache rather than compiled again.
+    compile_project(&fs, true);
+    assert_eq!(fs.modification_time(&cache).unwrap(), cached_at);
+}
+
+#[test]
+fn building_without_tests_removes_test_caches_of_changed_dependencies() {
+    let fs = project_with_test_module();
+    let cache = Utf8PathBuf::from(TEST_MODULE_CACHE);
+
+    compile_project(&fs, true);
+    assert!(fs.is_file(&cache));
+
+    let source = Utf8PathBuf::from("/src/my_project.gleam");
+    fs.write(&source, "pub fn main() { 2 }").unwrap();
+    fs.set_modification_time(
+        &source,
+        std::time::SystemTime::now() + std::time::Duration::from_secs(1),
+    );
+    compile_project(&fs, false);
+    assert!(!fs.is_file(&cache));
+}
+
+fn compile_project_with_features(features: &[&str]) -> crate::Result<InMemoryFileSystem> {
+    let root = Utf8PathBuf::from("/");
+    let fs = InMemoryFileSystem::new();
+    fs.write(
+        &root.join("src/my_project.gleam"),
+        "import my_project/features
+pub fn debug() { features.debug }",
+    )
+    .unwrap();
+
+    let config = PackageConfig {
+        name: "my_project".into(),
+        features: vec!["debug".into(), "telemetry".into()],
+        ..Default::default()
+    };
+    let options = Options {
+        mode: Mode::Dev,
+        target: Some(Target::JavaScript),
+        compile: Compile::All,
+        codegen: Codegen::All,
+        warnings_as_errors: false,
+        root_target_support: TargetSupport::Enforced,
+        no_print_progress: true,
+        compile_tests: false,
+        changed_modules: None,
+        features: features.iter().map(|feature| (*feature).into()).collect(),
+        javascript_target_version: None,
+        coverage: false,
+    };
+    let compiler = ProjectCompiler::new(
+        config,
+        options,
+        vec![],
+        &NullTelemetry,
+        Rc::new(VectorWarningEmitterIO::default()),
+        ProjectPaths::new(root),
+        fs.clone(),
+    );
+    compiler.compile().map(|_| fs)
+}
+
+#[test]
+fn enabling_a_feature_changes_its_constant() {
+    let features = Utf8PathBuf::from("/build/dev/javascript/my_project/my_project/features.mjs");
+
+    let fs = compile_project_with_features(&[]).unwrap();
+    let output = fs.read(&features).unwrap();
+    assert!(output.contains("export const debug = false;"));
+    assert!(output.contains("export const telemetry = false;"));
+
+    let fs = compile_project_with_features(&["debug"]).unwrap();
+    let output = fs.read(&features).unwrap();
+    assert!(output.contains("export const debug = true;"));
+    assert!(output.contains("export const telemetry = false;"));
+}
+
+#[test]
+fn enabling_an_unknown_feature_is_an_error() {
+    assert_eq!(
+        compile_project_with_features(&["wibble"]).map(|_| ()),
+        Err(Error::UnknownFeature {
+            feature: "wibble".into(),
+            features: vec!["debug".into(), "telemetry".into()],
+        })
+    );
+}
+
+fn check_erlang_build_version(fs: &InMemoryFileSystem, module_prefix: Option<&str>) {
+    let config = PackageConfig {
+        name: "my_project".into(),
+        erlang: ErlangConfig {
+            module_prefix: module_prefix.map(Into::into),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let options = Options {
+        mode: Mode::Dev,
+        target: Some(Target::Erlang),
+        compile: Compile::All,
+        codegen: Codegen::All,
+        warnings_as_errors: false,
+        root_target_support: TargetSupport::Enforced,
+        no_print_progress: true,
+        compile_tests: false,
+        changed_modules: None,
+        features: vec![],
+        javascript_target_version: None,
+        coverage: false,
+    };
+    let compiler = ProjectCompiler::new(
+        config,
+        options,
+        vec![],
+        &NullTelemetry,
+        Rc::new(VectorWarningEmitterIO::default()),
+        ProjectPaths::new(Utf8PathBuf::from("/")),
+        fs.clone(),
+    );
+    compiler.check_gleam_version().expect("check gleam version");
+}
+
+#[test]
+fn changing_the_erlang_module_prefix_rebuilds_the_project() {
+    let fs = InMemoryFileSystem::new();
+    let beam = Utf8PathBuf::from("/build/dev/erlang/my_project/ebin/my_project.beam");
+
+    check_erlang_build_version(&fs, None);
+    fs.write(&beam, "").unwrap();
+    check_erlang_build_version(&fs, None);
+    assert!(fs.is_file(&beam));
+
+    // Modules built without the prefix are removed so they can't be used in
+    // place of the prefixed ones.
+    check_erlang_build_version(&fs, Some("wibble@"));
+    assert!(!fs.is_file(&beam));
+
+    fs.write(&beam, "").unwrap();
+    check_erlang_build_version(&fs, Some("wibble@"));
+    assert!(fs.is_file(&beam));
+}
+
+/// Builds a project depending on a Hex package for Erlang, copying the
+/// package's artefacts from and to the dependency cache, and returns the
+/// compiled module of the dependency.
+fn compile_erlang_project_with_dependency_cache(fs: &InMemoryFileSystem, runtime: &str) -> String {
+    fs.delete_directory(&Utf8PathBuf::from("/build/dev"))
+        .unwrap();
+    fs.write(
+        &Utf8PathBuf::from("/src/my_project.gleam"),
+        "import wobble
+pub fn main() { wobble.value() }",
+    )
+    .unwrap();
+    fs.write(
+        &Utf8PathBuf::from("/build/packages/wobble/gleam.toml"),
+        "name = \"wobble\"\nversion = \"1.0.0\"\n",
+    )
+    .unwrap();
+    fs.write(
+        &Utf8PathBuf::from("/build/packages/wobble/src/wobble.gleam"),
+        "pub fn value() { 1 }",
+    )
+    .unwrap();
+
+    let config = PackageConfig {
+        name: "my_project".into(),
+        ..Default::default()
+    };
+    let options = Options {
+        mode: Mode::Dev,
+        target: Some(Target::Erlang),
+        compile: Compile::All,
+        codegen: Codegen::All,
+        warnings_as_errors: false,
+        root_target_support: TargetSupport::Enforced,
+        no_print_progress: true,
+        compile_tests: false,
+        changed_modules: None,
+        features: vec![],
+        javascript_target_version: None,
+        coverage: false,
+    };
+    let wobble = ManifestPackage {
+        name: "wobble".into(),
+        ..Default::default()
+    }
+    .with_build_tools(&["gleam"]);
+    let mut compiler = ProjectCompiler::new(
+        config,
+        options,
+        vec![wobble],
+        &NullTelemetry,
+        Rc::new(VectorWarningEmitterIO::default()),
+        ProjectPaths::new(Utf8PathBuf::from("/")),
+        fs.clone(),
+    );
+    compiler.dependency_cache = Some(Utf8PathBuf::from("/cache"));
+    compiler.dependency_cache_runtime = runtime.into();
+    let _ = compiler.compile().expect("compile project");
+    fs.read(&Utf8PathBuf::from(
+        "/build/dev/erlang/wobble/_gleam_artefacts/wobble.erl",
+    ))
+    .unwrap()
+}
+
+#[test]
+fn dependency_cache_is_not_shared_between_erlang_versions() {
+    let fs = InMemoryFileSystem::new();
+    let compiled = compile_erlang_project_with_dependency_cache(&fs, "OTP 27");
+    let cached = fs
+        .files()
+        .into_iter()
+        .find(|path| path.starts_with("/cache") && path.file_name() == Some("wobble.erl"))
+        .unwrap();
+    fs.write(&cached, "% From the cache\n").unwrap();
+
+    // Built with the same version of Erlang the artefacts are taken from the
+    // cache.
+    assert_eq!(
+        compile_erlang_project_with_dependency_cache(&fs, "OTP 27"),
+        "% From the cache\n"
+    );
+
+    // With another version they are compiled again.
+    assert_eq!(
+        compile_erlang_project_with_dependency_cache(&fs, "OTP 28"),
+        compiled
+    );
+}
@@ -38,6 +38,7 @@ fn parse_and_order(
             documentation: None,
             external_erlang: None,
             external_javascript: None,
+            allow_deprecated: false,
             implementations: Implementations {
                 gleam: true,
                 uses_erlang_externals: true,
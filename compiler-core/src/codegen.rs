@@ -1,21 +1,33 @@
+#[cfg(test)]
+mod tests;
+
 use crate::{
-    Result,
+    Error, Result, Warning,
     analyse::TargetSupport,
+    ast::TypedModule,
     build::{
-        ErlangAppCodegenConfiguration, Module, module_erlang_name, package_compiler::StdlibPackage,
+        EcmaScriptVersion, ErlangAppCodegenConfiguration, Module, Origin, Outcome, Target,
+        package_compiler::StdlibPackage, prefixed_module_erlang_name,
     },
     config::PackageConfig,
     erlang,
     io::FileSystemWriter,
     javascript::{self, ModuleConfig},
     line_numbers::LineNumbers,
+    type_::{self, PRELUDE_MODULE_NAME},
+    uid::UniqueIdGenerator,
+    warning::{TypeWarningEmitter, WarningEmitter},
 };
 use ecow::EcoString;
 use erlang::escape_atom_string;
 use itertools::Itertools;
-use std::fmt::Debug;
+use std::{collections::HashMap, fmt::Debug};
+
+use camino::{Utf8Path, Utf8PathBuf};
 
-use camino::Utf8Path;
+/// The width generated code is wrapped at where possible, unless a different
+/// one is set with `generated_line_length` in `gleam.toml`.
+pub const DEFAULT_LINE_LENGTH: isize = 80;
 
 /// A code generator that creates a .erl Erlang module and record header files
 /// for each Gleam module in the package.
@@ -23,13 +35,22 @@ use camino::Utf8Path;
 pub struct Erlang<'a> {
     build_directory: &'a Utf8Path,
     include_directory: &'a Utf8Path,
+    module_prefix: Option<&'a str>,
+    line_length: isize,
 }
 
 impl<'a> Erlang<'a> {
-    pub fn new(build_directory: &'a Utf8Path, include_directory: &'a Utf8Path) -> Self {
+    pub fn new(
+        build_directory: &'a Utf8Path,
+        include_directory: &'a Utf8Path,
+        module_prefix: Option<&'a str>,
+        line_length: isize,
+    ) -> Self {
         Self {
             build_directory,
             include_directory,
+            module_prefix,
+            line_length,
         }
     }
 
@@ -40,7 +61,7 @@ impl<'a> Erlang<'a> {
         root: &Utf8Path,
     ) -> Result<()> {
         for module in modules {
-            let erl_name = module.erlang_name();
+            let erl_name = module.erlang_name(self.module_prefix);
             self.erlang_module(&writer, module, &erl_name, root)?;
             self.erlang_record_headers(&writer, module, &erl_name)?;
         }
@@ -57,7 +78,13 @@ impl<'a> Erlang<'a> {
         let name = format!("{erl_name}.erl");
         let path = self.build_directory.join(&name);
         let line_numbers = LineNumbers::new(&module.code);
-        let output = erlang::module(&module.ast, &line_numbers, root);
+        let output = erlang::module(
+            &module.ast,
+            &line_numbers,
+            root,
+            self.module_prefix,
+            self.line_length,
+        );
         tracing::debug!(name = ?name, "Generated Erlang module");
         writer.write(&path, &output?)
     }
@@ -68,7 +95,7 @@ impl<'a> Erlang<'a> {
         module: &Module,
         erl_name: &str,
     ) -> Result<()> {
-        for (name, text) in erlang::records(&module.ast) {
+        for (name, text) in erlang::records(&module.ast, self.module_prefix, self.line_length) {
             let name = format!("{erl_name}_{name}.hrl");
             tracing::debug!(name = ?name, "Generated Erlang header");
             writer.write(&self.include_directory.join(name), &text)?;
@@ -82,13 +109,19 @@ impl<'a> Erlang<'a> {
 pub struct ErlangApp<'a> {
     output_directory: &'a Utf8Path,
     config: &'a ErlangAppCodegenConfiguration,
+    module_prefix: Option<&'a str>,
 }
 
 impl<'a> ErlangApp<'a> {
-    pub fn new(output_directory: &'a Utf8Path, config: &'a ErlangAppCodegenConfiguration) -> Self {
+    pub fn new(
+        output_directory: &'a Utf8Path,
+        config: &'a ErlangAppCodegenConfiguration,
+        module_prefix: Option<&'a str>,
+    ) -> Self {
         Self {
             output_directory,
             config,
+            module_prefix,
         }
     }
 
@@ -109,12 +142,15 @@ impl<'a> ErlangApp<'a> {
             .erlang
             .application_start_module
             .as_ref()
-            .map(|module| tuple("mod", &format!("{{'{}', []}}", module_erlang_name(module))))
+            .map(|module| {
+                let module = prefixed_module_erlang_name(self.module_prefix, module);
+                tuple("mod", &format!("{{'{module}', []}}"))
+            })
             .unwrap_or_default();
 
         let modules = modules
             .iter()
-            .map(|m| m.erlang_name())
+            .map(|m| m.erlang_name(self.module_prefix))
             .chain(native_modules)
             .unique()
             .sorted()
@@ -172,6 +208,9 @@ pub struct JavaScript<'a> {
     project_root: &'a Utf8Path,
     typescript: TypeScriptDeclarations,
     target_support: TargetSupport,
+    ecmascript_version: EcmaScriptVersion,
+    line_length: isize,
+    coverage: bool,
 }
 
 impl<'a> JavaScript<'a> {
@@ -181,6 +220,8 @@ impl<'a> JavaScript<'a> {
         prelude_location: &'a Utf8Path,
         project_root: &'a Utf8Path,
         target_support: TargetSupport,
+        ecmascript_version: EcmaScriptVersion,
+        line_length: isize,
     ) -> Self {
         Self {
             prelude_location,
@@ -188,9 +229,19 @@ impl<'a> JavaScript<'a> {
             target_support,
             project_root,
             typescript,
+            ecmascript_version,
+            line_length,
+            coverage: false,
         }
     }
 
+    /// Instruments the `src` modules to record which of their lines are run,
+    /// so that a test coverage report can be written.
+    pub fn with_coverage(mut self, coverage: bool) -> Self {
+        self.coverage = coverage;
+        self
+    }
+
     pub fn render(
         &self,
         writer: &impl FileSystemWriter,
@@ -267,8 +318,226 @@ impl<'a> JavaScript<'a> {
             target_support: self.target_support,
             typescript: self.typescript,
             stdlib_package,
+            ecmascript_version: self.ecmascript_version,
+            line_length: self.line_length,
+            coverage: self.coverage && module.origin == Origin::Src,
         });
         tracing::debug!(name = ?js_name, "Generated js module");
         writer.write(&path, &output?)
     }
 }
+
+/// Generates the source code for a single type checked module.
+///
+/// The Erlang and JavaScript backends are implemented as emitters, and
+/// programs embedding the compiler can implement this trait to experiment
+/// with other backends, registering them in a [`ModuleEmitters`].
+///
+pub trait ModuleEmitter: Debug {
+    /// The target used when type checking the module, deciding which
+    /// externals are available to it.
+    fn target(&self) -> Target;
+
+    /// Generate the source code for the module.
+    fn emit(&self, module: &EmittableModule<'_>) -> Result<String>;
+}
+
+/// A type checked module along with its source code, as given to a
+/// [`ModuleEmitter`].
+#[derive(Debug)]
+pub struct EmittableModule<'a> {
+    pub ast: &'a TypedModule,
+    pub src: &'a EcoString,
+    pub path: &'a Utf8Path,
+    pub line_numbers: &'a LineNumbers,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErlangModuleEmitter;
+
+impl ModuleEmitter for ErlangModuleEmitter {
+    fn target(&self) -> Target {
+        Target::Erlang
+    }
+
+    fn emit(&self, module: &EmittableModule<'_>) -> Result<String> {
+        erlang::module(
+            module.ast,
+            module.line_numbers,
+            Utf8Path::new(""),
+            None,
+            DEFAULT_LINE_LENGTH,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JavaScriptModuleEmitter;
+
+impl ModuleEmitter for JavaScriptModuleEmitter {
+    fn target(&self) -> Target {
+        Target::JavaScript
+    }
+
+    fn emit(&self, module: &EmittableModule<'_>) -> Result<String> {
+        javascript::module(ModuleConfig {
+            module: module.ast,
+            line_numbers: module.line_numbers,
+            src: module.src,
+            target_support: TargetSupport::Enforced,
+            typescript: TypeScriptDeclarations::None,
+            stdlib_package: StdlibPackage::Missing,
+            ecmascript_version: EcmaScriptVersion::default(),
+            line_length: DEFAULT_LINE_LENGTH,
+            coverage: false,
+            path: module.path,
+            project_root: Utf8Path::new(""),
+        })
+    }
+}
+
+/// A collection of module emitters, looked up by name. The built in `erlang`
+/// and `javascript` emitters are always registered.
+#[derive(Debug)]
+pub struct ModuleEmitters {
+    emitters: HashMap<EcoString, Box<dyn ModuleEmitter>>,
+}
+
+impl Default for ModuleEmitters {
+    fn default() -> Self {
+        let mut emitters = Self {
+            emitters: HashMap::new(),
+        };
+        emitters.register("erlang", Box::new(ErlangModuleEmitter));
+        emitters.register("javascript", Box::new(JavaScriptModuleEmitter));
+        emitters
+    }
+}
+
+impl ModuleEmitters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register an emitter under the given name, replacing any emitter
+    /// previously registered with that name.
+    pub fn register(&mut self, name: impl Into<EcoString>, emitter: Box<dyn ModuleEmitter>) {
+        let _ = self.emitters.insert(name.into(), emitter);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn ModuleEmitter> {
+        self.emitters.get(name).map(|emitter| emitter.as_ref())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &EcoString> {
+        self.emitters.keys().sorted()
+    }
+
+    /// Compile a single Gleam module in memory with the emitter registered
+    /// under the given name. Returns `None` if there is no such emitter.
+    pub fn module_to_string(
+        &self,
+        emitter: &str,
+        name: &str,
+        src: &str,
+    ) -> Option<Result<InMemoryModule>> {
+        self.get(emitter)
+            .map(|emitter| emit_module_to_string(name, src, emitter))
+    }
+}
+
+/// The output of compiling a single module in memory with [`module_to_string`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InMemoryModule {
+    /// The generated source code.
+    pub code: String,
+    /// Any warnings emitted while compiling the module.
+    pub warnings: Vec<Warning>,
+}
+
+/// Compile a single Gleam module to Erlang or JavaScript source code held in
+/// a `String`, without reading from or writing to the file system.
+///
+/// The module may only import the prelude. Warnings emitted while compiling
+/// are returned alongside the generated code, while parse and type errors are
+/// returned as an error.
+///
+pub fn module_to_string(name: &str, src: &str, target: Target) -> Result<InMemoryModule> {
+    ModuleEmitters::new()
+        .module_to_string(&target.to_string(), name, src)
+        .expect("Built in emitter for target")
+}
+
+/// Compile a single Gleam module in memory like [`module_to_string`], using
+/// the given emitter to generate the code.
+///
+pub fn emit_module_to_string(
+    name: &str,
+    src: &str,
+    emitter: &dyn ModuleEmitter,
+) -> Result<InMemoryModule> {
+    let target = emitter.target();
+    let path = Utf8PathBuf::from(format!("src/{name}.gleam"));
+    let code: EcoString = src.into();
+    let (warnings, warnings_io) = WarningEmitter::vector();
+
+    let parsed =
+        crate::parse::parse_module(path.clone(), src, &warnings).map_err(|error| Error::Parse {
+            path: path.clone(),
+            src: code.clone(),
+            error,
+        })?;
+    let mut ast = parsed.module;
+    ast.name = name.into();
+
+    let ids = UniqueIdGenerator::new();
+    // DUPE: preludeinsertion
+    let mut modules = im::HashMap::new();
+    let _ = modules.insert(PRELUDE_MODULE_NAME.into(), type_::build_prelude(&ids));
+    let line_numbers = LineNumbers::new(src);
+    let config = PackageConfig::default();
+
+    let analysis = crate::analyse::ModuleAnalyzerConstructor::<()> {
+        target,
+        ids: &ids,
+        origin: Origin::Src,
+        importable_modules: &modules,
+        warnings: &TypeWarningEmitter::new(path.clone(), code.clone(), warnings),
+        direct_dependencies: &HashMap::new(),
+        target_support: TargetSupport::Enforced,
+        package_config: &config,
+    }
+    .infer_module(ast, line_numbers.clone(), path.clone());
+
+    let ast = match analysis {
+        Outcome::Ok(ast) => ast,
+        Outcome::PartialFailure(ast, errors) => {
+            return Err(Error::Type {
+                names: ast.names,
+                path,
+                src: code,
+                errors,
+            });
+        }
+        Outcome::TotalFailure(errors) => {
+            return Err(Error::Type {
+                names: Default::default(),
+                path,
+                src: code,
+                errors,
+            });
+        }
+    };
+
+    let code = emitter.emit(&EmittableModule {
+        ast: &ast,
+        src: &code,
+        path: &path,
+        line_numbers: &line_numbers,
+    })?;
+
+    Ok(InMemoryModule {
+        code,
+        warnings: warnings_io.take(),
+    })
+}
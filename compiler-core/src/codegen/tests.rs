@@ -0,0 +1,160 @@
+use crate::build::Target;
+use crate::codegen::{
+    EmittableModule, ModuleEmitter, ModuleEmitters, emit_module_to_string, module_to_string,
+};
+
+#[test]
+fn module_to_string_javascript() {
+    let output = module_to_string(
+        "wibble",
+        r#"
+pub fn add(a, b) {
+  a + b
+}
+"#,
+        Target::JavaScript,
+    )
+    .expect("compilation failed");
+
+    assert_eq!(
+        output.code,
+        r#"export function add(a, b) {
+  return a + b;
+}
+"#
+    );
+    assert!(output.warnings.is_empty());
+}
+
+#[test]
+fn module_to_string_erlang() {
+    let output = module_to_string(
+        "wibble",
+        r#"
+pub fn add(a, b) {
+  a + b
+}
+"#,
+        Target::Erlang,
+    )
+    .expect("compilation failed");
+
+    assert!(output.code.starts_with("-module(wibble).\n"));
+    assert!(output.code.contains("-export([add/2]).\n"));
+    assert!(output.warnings.is_empty());
+}
+
+#[test]
+fn module_to_string_returns_warnings() {
+    let output = module_to_string(
+        "wibble",
+        r#"
+pub fn main() {
+  let x = 1
+  Nil
+}
+"#,
+        Target::JavaScript,
+    )
+    .expect("compilation failed");
+
+    assert_eq!(output.warnings.len(), 1);
+}
+
+#[test]
+fn module_to_string_type_error() {
+    let result = module_to_string(
+        "wibble",
+        r#"
+pub fn main() {
+  1 + "2"
+}
+"#,
+        Target::JavaScript,
+    );
+
+    assert!(matches!(result, Err(crate::Error::Type { .. })));
+}
+
+#[derive(Debug)]
+struct FunctionListEmitter;
+
+impl ModuleEmitter for FunctionListEmitter {
+    fn target(&self) -> Target {
+        Target::Erlang
+    }
+
+    fn emit(&self, module: &EmittableModule<'_>) -> crate::Result<String> {
+        let functions = module
+            .ast
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                crate::ast::Definition::Function(function) => {
+                    function.name.as_ref().map(|(_, name)| name.to_string())
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        Ok(format!(
+            "module {}: {}",
+            module.ast.name,
+            functions.join(", ")
+        ))
+    }
+}
+
+#[test]
+fn custom_module_emitter() {
+    let mut emitters = ModuleEmitters::new();
+    emitters.register("functions", Box::new(FunctionListEmitter));
+
+    let output = emitters
+        .module_to_string(
+            "functions",
+            "wibble",
+            r#"
+pub fn add(a, b) {
+  a + b
+}
+
+pub fn sub(a, b) {
+  a - b
+}
+"#,
+        )
+        .expect("registered emitter")
+        .expect("compilation failed");
+
+    assert_eq!(output.code, "module wibble: add, sub");
+}
+
+#[test]
+fn unknown_module_emitter() {
+    let emitters = ModuleEmitters::new();
+    assert!(
+        emitters
+            .module_to_string("functions", "wibble", "pub fn one() { 1 }")
+            .is_none()
+    );
+}
+
+#[test]
+fn builtin_module_emitters_are_registered() {
+    let emitters = ModuleEmitters::new();
+    assert_eq!(
+        emitters
+            .names()
+            .map(|name| name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["erlang", "javascript"]
+    );
+    let emitter = emitters.get("javascript").expect("javascript emitter");
+    let output =
+        emit_module_to_string("wibble", "pub fn one() { 1 }", emitter).expect("compilation failed");
+    assert_eq!(output.code, "export function one() {\n  return 1;\n}\n");
+    assert_eq!(
+        module_to_string("wibble", "pub fn one() { 1 }", Target::JavaScript),
+        Ok(output)
+    );
+}
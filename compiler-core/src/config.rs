@@ -1,4 +1,5 @@
 mod stale_package_remover;
+use crate::dependency::Timestamp;
 use crate::error::{FileIoAction, FileKind};
 use crate::io::FileSystemReader;
 use crate::io::ordered_map;
@@ -7,10 +8,11 @@ use crate::requirement::Requirement;
 use crate::version::COMPILER_VERSION;
 use crate::{Error, Result};
 use camino::{Utf8Path, Utf8PathBuf};
-use ecow::EcoString;
+use ecow::{EcoString, eco_format};
 use globset::{Glob, GlobSetBuilder};
 use hexpm::version::{self, Version};
 use http::Uri;
+use itertools::Itertools;
 use serde::ser::SerializeSeq;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -18,9 +20,9 @@ use std::fmt::{self};
 use std::marker::PhantomData;
 
 #[cfg(test)]
-use crate::manifest::ManifestPackage;
+use crate::manifest::{ChecksumAlgorithm, ManifestPackage};
 
-use crate::build::{Mode, Runtime, Target};
+use crate::build::{EcmaScriptVersion, Mode, Runtime, Target};
 
 fn default_version() -> Version {
     Version::parse("0.1.0").expect("default version")
@@ -36,6 +38,24 @@ fn default_javascript_runtime() -> Runtime {
 
 pub type Dependencies = HashMap<EcoString, Requirement>;
 
+/// A package required by both the `dependencies` and `dev-dependencies` of a
+/// config with version ranges that no single version can satisfy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncompatibleRequirements {
+    pub package: EcoString,
+    pub dependency_range: EcoString,
+    pub dev_dependency_range: EcoString,
+}
+
+/// A requirement in the `dependencies` of a config whose version range has no
+/// upper bound, with a bounded range that could be used instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnboundedRequirement {
+    pub package: EcoString,
+    pub range: EcoString,
+    pub suggestion: EcoString,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct SpdxLicense {
     pub licence: String,
@@ -150,6 +170,49 @@ pub struct PackageConfig {
     pub javascript: JavaScriptConfig,
     #[serde(default = "erlang_target")]
     pub target: Target,
+    /// The target used by `gleam run` when none is given, if different to
+    /// the default target.
+    #[serde(default, rename = "run-target")]
+    pub run_target: Option<Target>,
+    /// Whether to write a human friendly `deps.lock` file alongside
+    /// `manifest.toml` for reviewing dependency changes.
+    #[serde(default, rename = "deps-lock")]
+    pub deps_lock: bool,
+    /// How long a chain of dependencies can be before a warning is emitted
+    /// when resolving versions, counting the direct dependency.
+    #[serde(default, rename = "max-dependency-depth")]
+    pub max_dependency_depth: Option<usize>,
+    /// Whether to warn about Hex requirements in `dependencies` with no upper
+    /// bound, which would let consumers of a library get breaking versions.
+    #[serde(default, rename = "warn-unbounded-requirements")]
+    pub warn_unbounded_requirements: bool,
+    /// How many steps version resolution can take before failing, rather
+    /// than continuing to try versions for a long time.
+    #[serde(default, rename = "max-resolution-steps")]
+    pub max_resolution_steps: Option<usize>,
+    /// When set, versions are only resolved to releases published at or
+    /// before this time, so the same versions are chosen as on that day.
+    #[serde(default, rename = "published-before")]
+    pub published_before: Option<Timestamp>,
+    /// The width lines of generated Erlang and JavaScript are wrapped at where
+    /// possible, such as to make long expressions easier to read and diff.
+    /// This is taken from the root package and used for all packages.
+    #[serde(default)]
+    pub generated_line_length: Option<usize>,
+    /// A TOML file, relative to the project root, with rules the resolved
+    /// dependencies must follow, such as which licences they may use.
+    #[serde(default, rename = "dependency-policy")]
+    pub dependency_policy: Option<Utf8PathBuf>,
+    /// Whether the package is a library or an application. Applications get
+    /// warnings that would be wrong for libraries, such as for public
+    /// functions that nothing in the package uses.
+    #[serde(default)]
+    pub kind: PackageKind,
+    /// The feature flags the package can be built with. Each one is a `Bool`
+    /// constant in the generated `<name>/features` module, set to whether the
+    /// feature was enabled with `gleam build --features`.
+    #[serde(default)]
+    pub features: Vec<EcoString>,
     #[serde(default)]
     pub internal_modules: Option<Vec<Glob>>,
 }
@@ -198,12 +261,111 @@ impl PackageConfig {
         for (name, requirement) in self.dependencies.iter().chain(&self.dev_dependencies) {
             let already_inserted = deps.insert(name.clone(), requirement.clone()).is_some();
             if already_inserted {
-                return Err(Error::DuplicateDependency(name.clone()));
+                return Err(self.duplicate_dependency_error(name, None));
             }
         }
         Ok(deps)
     }
 
+    /// Fails if a package is listed in both `dependencies` and
+    /// `dev-dependencies`, as it would be unclear which of the requirements
+    /// applies. The TOML the config was read from is used to point out the
+    /// lines the package is listed on.
+    pub fn check_duplicate_dependencies(&self, toml: &str) -> Result<()> {
+        match self
+            .dependencies
+            .keys()
+            .filter(|name| self.dev_dependencies.contains_key(*name))
+            .min()
+        {
+            Some(name) => Err(self.duplicate_dependency_error(name, Some(toml))),
+            None => Ok(()),
+        }
+    }
+
+    fn duplicate_dependency_error(&self, name: &EcoString, toml: Option<&str>) -> Error {
+        let describe = |requirement: Option<&Requirement>| match requirement {
+            Some(Requirement::Hex { version }) => eco_format!("\"{version}\""),
+            Some(Requirement::Path { path }) => eco_format!("{{ path = \"{path}\" }}"),
+            Some(Requirement::Git { git, ref_ }) => {
+                eco_format!("{{ git = \"{git}\", ref = \"{ref_}\" }}")
+            }
+            None => EcoString::new(),
+        };
+        Error::DuplicateDependency {
+            name: name.clone(),
+            requirement: describe(self.dependencies.get(name)),
+            line: toml.and_then(|toml| dependency_line(toml, "dependencies", name)),
+            dev_requirement: describe(self.dev_dependencies.get(name)),
+            dev_line: toml.and_then(|toml| dependency_line(toml, "dev-dependencies", name)),
+        }
+    }
+
+    /// Find packages that are listed in both `dependencies` and
+    /// `dev-dependencies` with version ranges that have no versions in common.
+    ///
+    /// This is a cheap check that can be performed before version resolution,
+    /// giving the programmer feedback without waiting for the solver to fail.
+    ///
+    pub fn incompatible_duplicate_requirements(&self) -> Vec<IncompatibleRequirements> {
+        self.dependencies
+            .iter()
+            .filter_map(|(name, requirement)| {
+                let Requirement::Hex { version: first } = requirement else {
+                    return None;
+                };
+                let Some(Requirement::Hex { version: second }) = self.dev_dependencies.get(name)
+                else {
+                    return None;
+                };
+                let first_range = first.to_pubgrub().ok()?;
+                let second_range = second.to_pubgrub().ok()?;
+                if first_range.intersection(&second_range) != pubgrub::range::Range::none() {
+                    return None;
+                }
+                Some(IncompatibleRequirements {
+                    package: name.clone(),
+                    dependency_range: first.to_string().into(),
+                    dev_dependency_range: second.to_string().into(),
+                })
+            })
+            .sorted_by(|a, b| a.package.cmp(&b.package))
+            .collect()
+    }
+
+    /// The Hex requirements in `dependencies` that any version above some
+    /// lower bound satisfies, such as `>= 1.0.0`. Each one comes with a range
+    /// capped below the next major version. Dev dependencies are not included
+    /// as they don't affect the packages that depend on this one.
+    pub fn unbounded_requirements(&self) -> Vec<UnboundedRequirement> {
+        let highest_version = Version::new(u32::MAX, u32::MAX, u32::MAX);
+        self.dependencies
+            .iter()
+            .filter_map(|(name, requirement)| {
+                let Requirement::Hex { version: range } = requirement else {
+                    return None;
+                };
+                let pubgrub_range = range.to_pubgrub().ok()?;
+                if !pubgrub_range.contains(&highest_version) {
+                    return None;
+                }
+                let lowest = pubgrub_range.lowest_version()?;
+                Some(UnboundedRequirement {
+                    package: name.clone(),
+                    range: range.to_string().into(),
+                    suggestion: eco_format!(
+                        ">= {}.{}.{} and < {}.0.0",
+                        lowest.major,
+                        lowest.minor,
+                        lowest.patch,
+                        lowest.major.saturating_add(1)
+                    ),
+                })
+            })
+            .sorted_by(|a, b| a.package.cmp(&b.package))
+            .collect()
+    }
+
     pub fn read<FS: FileSystemReader, P: AsRef<Utf8Path>>(
         path: P,
         fs: &FS,
@@ -322,6 +484,8 @@ fn locked_no_changes() {
     ]
     .into();
     let manifest = Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
         requirements: config.all_direct_dependencies().unwrap(),
         packages: vec![
             manifest_package("prod1", "1.1.0", &[]),
@@ -348,6 +512,8 @@ fn locked_some_removed() {
     config.dependencies = [("prod1".into(), Requirement::hex("~> 1.0"))].into();
     config.dev_dependencies = [("dev2".into(), Requirement::hex("~> 2.0"))].into();
     let manifest = Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
         requirements: config.all_direct_dependencies().unwrap(),
         packages: vec![
             manifest_package("prod1", "1.1.0", &[]),
@@ -382,6 +548,8 @@ fn locked_some_changed() {
     ]
     .into();
     let manifest = Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
         requirements: [
             ("prod1".into(), Requirement::hex("~> 1.0")),
             ("prod2".into(), Requirement::hex("~> 2.0")),
@@ -418,6 +586,8 @@ fn locked_nested_are_removed_too() {
     .into();
     config.dev_dependencies = [].into();
     let manifest = Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
         requirements: [
             ("1".into(), Requirement::hex("~> 1.0")),
             ("2".into(), Requirement::hex("~> 1.0")),
@@ -470,6 +640,8 @@ fn locked_unlock_new() {
     .into();
     config.dev_dependencies = [].into();
     let manifest = Manifest {
+        checksum_algorithm: ChecksumAlgorithm::Sha256,
+        published_before: None,
         requirements: [
             ("1".into(), Requirement::hex("~> 1.0")),
             ("2".into(), Requirement::hex("~> 1.0")),
@@ -487,6 +659,122 @@ fn locked_unlock_new() {
     )
 }
 
+#[test]
+fn incompatible_duplicate_requirements() {
+    let mut config = PackageConfig::default();
+    config.dependencies = [
+        ("wibble".into(), Requirement::hex(">= 2.0.0")),
+        ("wobble".into(), Requirement::hex("~> 1.0")),
+    ]
+    .into();
+    config.dev_dependencies = [
+        ("wibble".into(), Requirement::hex("< 1.0.0")),
+        ("wobble".into(), Requirement::hex(">= 1.2.0")),
+    ]
+    .into();
+    assert_eq!(
+        config.incompatible_duplicate_requirements(),
+        vec![IncompatibleRequirements {
+            package: "wibble".into(),
+            dependency_range: ">= 2.0.0".into(),
+            dev_dependency_range: "< 1.0.0".into(),
+        }]
+    );
+}
+
+#[test]
+fn duplicate_dependencies_are_reported_with_their_lines() {
+    let toml = r#"name = "my_project"
+version = "1.0.0"
+
+[dependencies]
+gleam_stdlib = ">= 0.40.0 and < 2.0.0"
+wibble = ">= 2.0.0"
+
+[dev-dependencies]
+gleeunit = ">= 1.0.0 and < 2.0.0"
+wibble = { path = "../wibble" }
+"#;
+    let config = toml::from_str::<PackageConfig>(toml).unwrap();
+    assert_eq!(
+        config.check_duplicate_dependencies(toml),
+        Err(Error::DuplicateDependency {
+            name: "wibble".into(),
+            requirement: "\">= 2.0.0\"".into(),
+            line: Some(6),
+            dev_requirement: "{ path = \"../wibble\" }".into(),
+            dev_line: Some(10),
+        })
+    );
+}
+
+#[test]
+fn no_duplicate_dependencies() {
+    let toml = r#"name = "my_project"
+version = "1.0.0"
+
+[dependencies]
+wibble = ">= 2.0.0"
+
+[dev-dependencies]
+wobble = ">= 1.0.0"
+"#;
+    let config = toml::from_str::<PackageConfig>(toml).unwrap();
+    assert_eq!(config.check_duplicate_dependencies(toml), Ok(()));
+}
+
+#[test]
+fn unbounded_requirements() {
+    let mut config = PackageConfig::default();
+    config.dependencies = [
+        ("wibble".into(), Requirement::hex(">= 1.2.0")),
+        ("wobble".into(), Requirement::hex(">= 1.0.0 and < 2.0.0")),
+        ("wubble".into(), Requirement::hex("~> 0.4")),
+        (
+            "zoo".into(),
+            Requirement::hex("> 0.3.1 or >= 0.1.0 and < 0.2.0"),
+        ),
+        ("local".into(), Requirement::path("../local")),
+    ]
+    .into();
+    config.dev_dependencies = [("gleeunit".into(), Requirement::hex(">= 1.0.0"))].into();
+    assert_eq!(
+        config.unbounded_requirements(),
+        vec![
+            UnboundedRequirement {
+                package: "wibble".into(),
+                range: ">= 1.2.0".into(),
+                suggestion: ">= 1.2.0 and < 2.0.0".into(),
+            },
+            UnboundedRequirement {
+                package: "zoo".into(),
+                range: "> 0.3.1 or >= 0.1.0 and < 0.2.0".into(),
+                suggestion: ">= 0.1.0 and < 1.0.0".into(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn no_unbounded_requirements() {
+    let mut config = PackageConfig::default();
+    config.dependencies = [
+        ("wibble".into(), Requirement::hex(">= 1.2.0 and < 2.0.0")),
+        ("wobble".into(), Requirement::hex("1.0.0")),
+        ("wubble".into(), Requirement::hex("< 3.0.0")),
+    ]
+    .into();
+    assert_eq!(config.unbounded_requirements(), vec![]);
+}
+
+#[test]
+fn no_incompatible_duplicate_requirements() {
+    let mut config = PackageConfig::default();
+    config.dependencies = [("wibble".into(), Requirement::hex(">= 2.0.0"))].into();
+    config.dev_dependencies = [("wobble".into(), Requirement::hex("< 1.0.0"))].into();
+    assert_eq!(config.incompatible_duplicate_requirements(), vec![]);
+}
+
 #[test]
 fn default_internal_modules() {
     // When no internal modules are specified then we default to
@@ -607,6 +895,7 @@ fn manifest_package(
         source: crate::manifest::ManifestPackageSource::Hex {
             outer_checksum: Base16Checksum(vec![]),
         },
+        retired: false,
     }
 }
 
@@ -632,16 +921,65 @@ impl Default for PackageConfig {
             links: Default::default(),
             internal_modules: Default::default(),
             target: Target::Erlang,
+            run_target: None,
+            deps_lock: false,
+            max_dependency_depth: None,
+            warn_unbounded_requirements: false,
+            max_resolution_steps: None,
+            published_before: None,
+            generated_line_length: None,
+            dependency_policy: None,
+            kind: PackageKind::Library,
+            features: vec![],
         }
     }
 }
 
+/// The line of a gleam.toml file a package is listed on in the given table of
+/// dependencies, counting from 1. Only packages listed as `name = ...` under a
+/// `[table]` header are found.
+fn dependency_line(toml: &str, table: &str, name: &str) -> Option<u32> {
+    let header = format!("[{table}]");
+    let mut in_table = false;
+    for (index, line) in toml.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_table = line == header;
+            continue;
+        }
+        if !in_table {
+            continue;
+        }
+        let Some((key, _)) = line.split_once('=') else {
+            continue;
+        };
+        if key.trim().trim_matches('"') == name {
+            return u32::try_from(index + 1).ok();
+        }
+    }
+    None
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageKind {
+    /// A package used by other packages, such as one published to Hex.
+    #[default]
+    Library,
+    /// A program that is run rather than depended upon.
+    Application,
+}
+
 #[derive(Deserialize, Serialize, Debug, PartialEq, Eq, Default, Clone)]
 pub struct ErlangConfig {
     #[serde(default)]
     pub application_start_module: Option<EcoString>,
     #[serde(default)]
     pub extra_applications: Vec<EcoString>,
+    /// A prefix for the names of the Erlang modules generated for the
+    /// project and all its Gleam dependencies.
+    #[serde(default)]
+    pub module_prefix: Option<EcoString>,
 }
 
 #[derive(Deserialize, Serialize, Debug, PartialEq, Default, Clone)]
@@ -652,6 +990,9 @@ pub struct JavaScriptConfig {
     pub runtime: Runtime,
     #[serde(default, rename = "deno")]
     pub deno: DenoConfig,
+    /// The version of ECMAScript the generated JavaScript is written for.
+    #[serde(default)]
+    pub target_version: EcmaScriptVersion,
 }
 
 #[derive(Deserialize, Debug, PartialEq, Eq, Clone)]
@@ -992,6 +1333,27 @@ name = "1"
     )
 }
 
+#[test]
+fn run_target() {
+    let input = r#"
+name = "wibble"
+target = "erlang"
+run-target = "javascript"
+"#;
+    let config = toml::from_str::<PackageConfig>(input).unwrap();
+    assert_eq!(config.target, Target::Erlang);
+    assert_eq!(config.run_target, Some(Target::JavaScript));
+}
+
+#[test]
+fn invalid_run_target() {
+    let input = r#"
+name = "wibble"
+run-target = "wasm"
+"#;
+    assert!(toml::from_str::<PackageConfig>(input).is_err());
+}
+
 #[test]
 fn package_config_to_json() {
     let input = r#"
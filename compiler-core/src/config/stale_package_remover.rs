@@ -84,7 +84,9 @@ impl<'a> StalePackageRemover<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::manifest::{Base16Checksum, Manifest, ManifestPackage, ManifestPackageSource};
+    use crate::manifest::{
+        Base16Checksum, ChecksumAlgorithm, Manifest, ManifestPackage, ManifestPackageSource,
+    };
     use crate::requirement::Requirement;
     use hexpm::version::{Range, Version};
     use std::collections::HashMap;
@@ -99,6 +101,8 @@ mod tests {
             },
         )]);
         let manifest = Manifest {
+            checksum_algorithm: ChecksumAlgorithm::Sha256,
+            published_before: None,
             requirements: requirements.clone(),
             packages: vec![ManifestPackage {
                 name: "required_package".into(),
@@ -113,6 +117,7 @@ mod tests {
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![]),
                 },
+                retired: false,
             }],
         };
 
@@ -2,9 +2,6 @@ use ecow::EcoString;
 use petgraph::{Direction, algo::Cycle, graph::NodeIndex};
 use std::collections::{HashMap, HashSet};
 
-#[cfg(test)]
-use pretty_assertions::assert_eq;
-
 /// Take a sequence of values and their deps, and return the values in
 /// order so that deps come before the dependants.
 ///
@@ -83,6 +80,92 @@ fn find_cycle(
     false
 }
 
+/// The set of values reachable from each value in a dependency graph, such
+/// as the modules a module imports directly or indirectly.
+///
+/// The closures for the whole graph are computed once, when this is
+/// constructed, so that the build and any features needing to know what
+/// depends on what can share them rather than each walking the graph again.
+///
+/// As with `toposort_deps`, any deps that are not nodes are ignored. A value
+/// is only included in its own closure if it is part of a cycle.
+///
+#[derive(Debug, Clone, Default)]
+pub struct Reachability {
+    closures: HashMap<EcoString, HashSet<EcoString>>,
+}
+
+impl Reachability {
+    pub fn new(inputs: Vec<(EcoString, Vec<EcoString>)>) -> Self {
+        let mut graph = petgraph::Graph::<EcoString, ()>::with_capacity(inputs.len(), 0);
+        let mut indexes = HashMap::with_capacity(inputs.len());
+
+        for (value, _deps) in &inputs {
+            let _ = indexes
+                .entry(value.clone())
+                .or_insert_with(|| graph.add_node(value.clone()));
+        }
+
+        for (value, deps) in &inputs {
+            let &from_index = indexes.get(value).expect("Finding index for value");
+            for &to_index in deps.iter().filter_map(|dep| indexes.get(dep)) {
+                let _ = graph.update_edge(from_index, to_index, ());
+            }
+        }
+
+        // Strongly connected components are returned with the deps of a
+        // component always coming before it, so by the time we get to a
+        // component the closures of everything it depends on are known.
+        let mut closures: HashMap<NodeIndex, HashSet<EcoString>> = HashMap::new();
+        for component in petgraph::algo::tarjan_scc(&graph) {
+            let is_cycle = component.len() > 1
+                || component
+                    .first()
+                    .is_some_and(|&index| graph.contains_edge(index, index));
+
+            let mut closure = HashSet::new();
+            if is_cycle {
+                closure.extend(component.iter().map(|&index| graph[index].clone()));
+            }
+            for &index in &component {
+                for dep in graph.neighbors_directed(index, Direction::Outgoing) {
+                    if component.contains(&dep) {
+                        continue;
+                    }
+                    let _ = closure.insert(graph[dep].clone());
+                    if let Some(dep_closure) = closures.get(&dep) {
+                        closure.extend(dep_closure.iter().cloned());
+                    }
+                }
+            }
+            for &index in &component {
+                let _ = closures.insert(index, closure.clone());
+            }
+        }
+
+        Self {
+            closures: closures
+                .into_iter()
+                .map(|(index, closure)| (graph[index].clone(), closure))
+                .collect(),
+        }
+    }
+
+    /// Everything the given value depends on, directly or indirectly.
+    /// Returns `None` if the value is not in the graph.
+    pub fn dependencies_of(&self, value: &str) -> Option<&HashSet<EcoString>> {
+        self.closures.get(value)
+    }
+
+    /// Every value that depends on the given value, directly or indirectly.
+    pub fn dependents_of<'a>(&'a self, value: &'a str) -> impl Iterator<Item = &'a EcoString> {
+        self.closures
+            .iter()
+            .filter(move |(_, closure)| closure.contains(value))
+            .map(|(dependent, _)| dependent)
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum Error {
     Cycle(Vec<EcoString>),
@@ -91,6 +174,7 @@ pub enum Error {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use pretty_assertions::assert_eq;
 
     #[test]
     fn toposort_deps_test() {
@@ -168,4 +252,66 @@ mod tests {
             ]))
         );
     }
+
+    fn set(values: &[&str]) -> HashSet<EcoString> {
+        values.iter().map(|value| EcoString::from(*value)).collect()
+    }
+
+    #[test]
+    fn reachability_diamond() {
+        // a -> b -> d
+        // |         ^
+        // v         |
+        // c --------+
+        let reachability = Reachability::new(vec![
+            ("a".into(), vec!["b".into(), "c".into()]),
+            ("b".into(), vec!["d".into()]),
+            ("c".into(), vec!["d".into()]),
+            ("d".into(), vec!["external".into()]),
+        ]);
+
+        assert_eq!(
+            reachability.dependencies_of("a"),
+            Some(&set(&["b", "c", "d"]))
+        );
+        assert_eq!(reachability.dependencies_of("b"), Some(&set(&["d"])));
+        assert_eq!(reachability.dependencies_of("d"), Some(&set(&[])));
+        assert_eq!(reachability.dependencies_of("external"), None);
+        assert_eq!(
+            reachability
+                .dependents_of("d")
+                .cloned()
+                .collect::<HashSet<_>>(),
+            set(&["a", "b", "c"])
+        );
+    }
+
+    #[test]
+    fn reachability_cycle() {
+        // a -> b -> c -> d
+        //      ^    |
+        //      |    v
+        //      +--- e     f -> f
+        let reachability = Reachability::new(vec![
+            ("a".into(), vec!["b".into()]),
+            ("b".into(), vec!["c".into()]),
+            ("c".into(), vec!["d".into(), "e".into()]),
+            ("d".into(), vec![]),
+            ("e".into(), vec!["b".into()]),
+            ("f".into(), vec!["f".into()]),
+        ]);
+
+        assert_eq!(
+            reachability.dependencies_of("a"),
+            Some(&set(&["b", "c", "d", "e"]))
+        );
+        for value in ["b", "c", "e"] {
+            assert_eq!(
+                reachability.dependencies_of(value),
+                Some(&set(&["b", "c", "d", "e"]))
+            );
+        }
+        assert_eq!(reachability.dependencies_of("d"), Some(&set(&[])));
+        assert_eq!(reachability.dependencies_of("f"), Some(&set(&["f"])));
+    }
 }
@@ -1,13 +1,21 @@
-use std::{borrow::Borrow, cell::RefCell, collections::HashMap, error::Error as StdError};
+use std::{
+    borrow::Borrow,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    error::Error as StdError,
+    rc::Rc,
+    str::FromStr,
+};
 
 use crate::{Error, Result};
 
 use ecow::EcoString;
 use hexpm::{
-    Dependency, Release,
+    Dependency, Release, ReleaseMeta,
     version::{Range, ResolutionError, Version},
 };
 use pubgrub::{
+    error::PubGrubError,
     solver::{Dependencies, choose_package_with_fewest_versions},
     type_aliases::Map,
 };
@@ -16,6 +24,11 @@ pub type PackageVersions = HashMap<String, Version>;
 
 type PubgrubRange = pubgrub::range::Range<Version>;
 
+/// How many steps version resolution can take before giving up, unless
+/// `max-resolution-steps` is set in gleam.toml. Resolving the dependencies of
+/// typical projects takes far fewer steps than this.
+pub const DEFAULT_MAX_RESOLUTION_STEPS: usize = 100_000;
+
 pub fn resolve_versions<Requirements>(
     package_fetcher: Box<dyn PackageFetcher>,
     provided_packages: HashMap<EcoString, hexpm::Package>,
@@ -23,6 +36,29 @@ pub fn resolve_versions<Requirements>(
     dependencies: Requirements,
     locked: &HashMap<EcoString, Version>,
 ) -> Result<PackageVersions>
+where
+    Requirements: Iterator<Item = (EcoString, Range)>,
+{
+    resolve_versions_with_step_limit(
+        package_fetcher,
+        provided_packages,
+        root_name,
+        dependencies,
+        locked,
+        DEFAULT_MAX_RESOLUTION_STEPS,
+    )
+}
+
+/// Resolve versions, failing with `Error::DependencyResolutionTooComplex`
+/// rather than continuing to backtrack once `max_steps` steps have been taken.
+pub fn resolve_versions_with_step_limit<Requirements>(
+    package_fetcher: Box<dyn PackageFetcher>,
+    provided_packages: HashMap<EcoString, hexpm::Package>,
+    root_name: EcoString,
+    dependencies: Requirements,
+    locked: &HashMap<EcoString, Version>,
+    max_steps: usize,
+) -> Result<PackageVersions>
 where
     Requirements: Iterator<Item = (EcoString, Range)>,
 {
@@ -50,15 +86,23 @@ where
         }],
     };
 
-    let packages = pubgrub::solver::resolve(
-        &DependencyProvider::new(package_fetcher, provided_packages, root, locked, exact_deps),
-        root_name.as_str().into(),
-        root_version,
-    )
-    .map_err(Error::dependency_resolution_failed)?
-    .into_iter()
-    .filter(|(name, _)| name.as_str() != root_name.as_str())
-    .collect();
+    let provider = DependencyProvider::new(
+        package_fetcher,
+        provided_packages,
+        root,
+        exact_deps,
+        max_steps,
+    );
+    let packages = pubgrub::solver::resolve(&provider, root_name.as_str().into(), root_version)
+        .map_err(|error| match error {
+            PubGrubError::ErrorInShouldCancel(_) if provider.steps.get() > max_steps => {
+                Error::DependencyResolutionTooComplex { max_steps }
+            }
+            error => Error::dependency_resolution_failed(error),
+        })?
+        .into_iter()
+        .filter(|(name, _)| name.as_str() != root_name.as_str())
+        .collect();
 
     Ok(packages)
 }
@@ -143,17 +187,286 @@ but it is locked to {locked_version}, which is incompatible.",
 
 pub trait PackageFetcher {
     fn get_dependencies(&self, package: &str) -> Result<hexpm::Package, Box<dyn StdError>>;
+
+    /// When each release of a package was published. This is only needed to
+    /// resolve versions published before a given time, so a fetcher that
+    /// can't tell fails by default.
+    fn get_publication_times(
+        &self,
+        package: &str,
+    ) -> Result<HashMap<Version, Timestamp>, Box<dyn StdError>> {
+        Err(format!("I couldn't find when the releases of `{package}` were published").into())
+    }
+}
+
+impl<Fetcher: PackageFetcher + ?Sized> PackageFetcher for Rc<Fetcher> {
+    fn get_dependencies(&self, package: &str) -> Result<hexpm::Package, Box<dyn StdError>> {
+        self.as_ref().get_dependencies(package)
+    }
+
+    fn get_publication_times(
+        &self,
+        package: &str,
+    ) -> Result<HashMap<Version, Timestamp>, Box<dyn StdError>> {
+        self.as_ref().get_publication_times(package)
+    }
+}
+
+/// Fetches packages with another fetcher, leaving out every release that was
+/// published after the cutoff so versions are resolved as they would have
+/// been at that time.
+pub struct PublishedBefore {
+    fetcher: Box<dyn PackageFetcher>,
+    cutoff: Timestamp,
+}
+
+impl std::fmt::Debug for PublishedBefore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PublishedBefore")
+            .field("cutoff", &self.cutoff)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PublishedBefore {
+    pub fn new(fetcher: Box<dyn PackageFetcher>, cutoff: Timestamp) -> Self {
+        Self { fetcher, cutoff }
+    }
+}
+
+impl PackageFetcher for PublishedBefore {
+    fn get_dependencies(&self, package: &str) -> Result<hexpm::Package, Box<dyn StdError>> {
+        let mut package_info = self.fetcher.get_dependencies(package)?;
+        let times = self.fetcher.get_publication_times(package)?;
+        package_info.releases.retain(|release| {
+            times
+                .get(&release.version)
+                .is_some_and(|published| published <= &self.cutoff)
+        });
+        Ok(package_info)
+    }
+
+    fn get_publication_times(
+        &self,
+        package: &str,
+    ) -> Result<HashMap<Version, Timestamp>, Box<dyn StdError>> {
+        self.fetcher.get_publication_times(package)
+    }
+}
+
+/// A moment in UTC, written the way Hex writes when a release was published:
+/// `2024-06-01T12:30:00Z`, optionally with a fraction of a second. A date on
+/// its own, such as `2024-06-01`, is the start of that day.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(try_from = "String", into = "String")]
+pub struct Timestamp {
+    // The fields are in order of significance so the derived ordering is
+    // chronological.
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+    nanosecond: u32,
+}
+
+impl FromStr for Timestamp {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("`{text}` is not a timestamp such as `2024-06-01T12:30:00Z`");
+        let number = |part: Option<&str>, digits: usize| -> Option<u32> {
+            let part = part?;
+            if part.len() != digits || !part.bytes().all(|byte| byte.is_ascii_digit()) {
+                return None;
+            }
+            part.parse().ok()
+        };
+
+        let (date, time) = match text.split_once('T') {
+            Some((date, time)) => (date, Some(time)),
+            None => (text, None),
+        };
+
+        let mut date = date.split('-');
+        let year = number(date.next(), 4).ok_or_else(invalid)?;
+        let month = number(date.next(), 2).ok_or_else(invalid)?;
+        let day = number(date.next(), 2).ok_or_else(invalid)?;
+        if date.next().is_some() {
+            return Err(invalid());
+        }
+
+        let (hour, minute, second, nanosecond) = match time {
+            None => (0, 0, 0, 0),
+            Some(time) => {
+                let time = time.strip_suffix('Z').ok_or_else(invalid)?;
+                let (time, fraction) = match time.split_once('.') {
+                    Some((time, fraction)) => (time, Some(fraction)),
+                    None => (time, None),
+                };
+                let mut time = time.split(':');
+                let hour = number(time.next(), 2).ok_or_else(invalid)?;
+                let minute = number(time.next(), 2).ok_or_else(invalid)?;
+                let second = number(time.next(), 2).ok_or_else(invalid)?;
+                if time.next().is_some() {
+                    return Err(invalid());
+                }
+                let nanosecond = match fraction {
+                    None => 0,
+                    Some(fraction) if (1..=9).contains(&fraction.len()) => {
+                        number(Some(fraction), fraction.len()).ok_or_else(invalid)?
+                            * 10u32.pow(9 - fraction.len() as u32)
+                    }
+                    Some(_) => return Err(invalid()),
+                };
+                (hour, minute, second, nanosecond)
+            }
+        };
+
+        if !(1..=12).contains(&month)
+            || !(1..=days_in_month(year, month)).contains(&day)
+            || hour > 23
+            || minute > 59
+            || second > 60
+        {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+            hour: hour as u8,
+            minute: minute as u8,
+            second: second as u8,
+            nanosecond,
+        })
+    }
+}
+
+/// The number of days in a month of the Gregorian calendar.
+fn days_in_month(year: u32, month: u32) -> u32 {
+    let leap_year =
+        year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+    match month {
+        2 if leap_year => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl TryFrom<String> for Timestamp {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        text.parse()
+    }
+}
+
+impl From<Timestamp> for String {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.to_string()
+    }
+}
+
+impl std::fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )?;
+        if self.nanosecond != 0 {
+            let fraction = format!("{:09}", self.nanosecond);
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+        write!(f, "Z")
+    }
+}
+
+/// The metadata of the packages in a registry, read from a file rather than
+/// fetched from Hex so that versions can be resolved offline and the same way
+/// every time.
+///
+/// It is JSON, with the releases of each package listed by name:
+///
+/// ```json
+/// {
+///   "packages": {
+///     "gleam_stdlib": [
+///       {
+///         "version": "0.60.0",
+///         "requirements": {},
+///         "outer_checksum": "F3D1...",
+///         "meta": { "app": "gleam_stdlib", "build_tools": ["gleam"] }
+///       }
+///     ]
+///   }
+/// }
+/// ```
+///
+/// Requirements are objects with `requirement` and `optional` fields, and a
+/// retired release has a `retirement_status` with a `reason` and `message`.
+#[derive(Debug, serde::Deserialize)]
+pub struct RegistrySnapshot {
+    packages: HashMap<String, Vec<Release<ReleaseMeta>>>,
+}
+
+impl RegistrySnapshot {
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    pub fn release(&self, package: &str, version: &Version) -> Option<&Release<ReleaseMeta>> {
+        self.packages
+            .get(package)?
+            .iter()
+            .find(|release| &release.version == version)
+    }
+}
+
+impl PackageFetcher for RegistrySnapshot {
+    fn get_dependencies(&self, package: &str) -> Result<hexpm::Package, Box<dyn StdError>> {
+        let Some(releases) = self.packages.get(package) else {
+            return Err(format!(
+                "I couldn't find a package called `{package}` in the registry snapshot"
+            )
+            .into());
+        };
+        let releases = releases
+            .iter()
+            .map(|release| Release {
+                version: release.version.clone(),
+                requirements: release.requirements.clone(),
+                retirement_status: release.retirement_status.clone(),
+                outer_checksum: release.outer_checksum.clone(),
+                meta: (),
+            })
+            .collect();
+        Ok(hexpm::Package {
+            name: package.into(),
+            repository: "hexpm".into(),
+            releases,
+        })
+    }
 }
 
 struct DependencyProvider<'a> {
     packages: RefCell<HashMap<EcoString, hexpm::Package>>,
     remote: Box<dyn PackageFetcher>,
-    locked: &'a HashMap<EcoString, Version>,
     // Map of packages where an exact version was requested
     // We need this because by default pubgrub checks exact version by checking if a version is between the exact
     // and the version 1 bump ahead. That default breaks on prerelease builds since a bump includes the whole patch
     exact_only: &'a HashMap<String, Version>,
     optional_dependencies: RefCell<HashMap<EcoString, pubgrub::range::Range<Version>>>,
+    /// How many steps the solver has taken, so that it can be stopped once
+    /// it has taken `max_steps`.
+    steps: Cell<usize>,
+    max_steps: usize,
 }
 
 impl<'a> DependencyProvider<'a> {
@@ -161,16 +474,17 @@ impl<'a> DependencyProvider<'a> {
         remote: Box<dyn PackageFetcher>,
         mut packages: HashMap<EcoString, hexpm::Package>,
         root: hexpm::Package,
-        locked: &'a HashMap<EcoString, Version>,
         exact_only: &'a HashMap<String, Version>,
+        max_steps: usize,
     ) -> Self {
         let _ = packages.insert(root.name.as_str().into(), root);
         Self {
             packages: RefCell::new(packages),
-            locked,
             remote,
             exact_only,
             optional_dependencies: RefCell::new(Default::default()),
+            steps: Cell::new(0),
+            max_steps,
         }
     }
 
@@ -178,8 +492,8 @@ impl<'a> DependencyProvider<'a> {
     /// store. Does nothing if the packages are already known.
     ///
     /// Package versions are sorted from newest to oldest, with all pre-releases
-    /// at the end to ensure that a non-prerelease version will be picked first
-    /// if there is one.
+    /// and then all retired releases at the end to ensure that a non-prerelease
+    /// version that hasn't been retired will be picked first if there is one.
     //
     fn ensure_package_fetched(
         // We would like to use `&mut self` but the pubgrub library enforces
@@ -193,11 +507,12 @@ impl<'a> DependencyProvider<'a> {
             // Sort the packages from newest to oldest, pres after all others
             package.releases.sort_by(|a, b| a.version.cmp(&b.version));
             package.releases.reverse();
-            let (pre, mut norm): (_, Vec<_>) = package
-                .releases
-                .into_iter()
-                .partition(|r| r.version.is_pre());
+            let (retired, releases): (Vec<_>, Vec<_>) =
+                package.releases.into_iter().partition(|r| r.is_retired());
+            let (pre, mut norm): (_, Vec<_>) =
+                releases.into_iter().partition(|r| r.version.is_pre());
             norm.extend(pre);
+            norm.extend(retired);
             package.releases = norm;
             let _ = packages.insert(name.into(), package);
         }
@@ -260,11 +575,6 @@ impl pubgrub::solver::DependencyProvider<PackageName, Version> for DependencyPro
             None => return Ok(Dependencies::Unknown),
         };
 
-        // Only use retired versions if they have been locked
-        if release.is_retired() && self.locked.get(name.as_str()) != Some(version) {
-            return Ok(Dependencies::Unknown);
-        }
-
         let mut deps: Map<PackageName, PubgrubRange> = Default::default();
         for (name, d) in &release.requirements {
             let mut range = d.requirement.to_pubgrub()?;
@@ -289,6 +599,16 @@ impl pubgrub::solver::DependencyProvider<PackageName, Version> for DependencyPro
         }
         Ok(Dependencies::Known(deps))
     }
+
+    // Called by the solver before each step it takes.
+    fn should_cancel(&self) -> Result<(), Box<dyn StdError>> {
+        self.steps.set(self.steps.get() + 1);
+        if self.steps.get() > self.max_steps {
+            Err(format!("took more than {} steps", self.max_steps).into())
+        } else {
+            Ok(())
+        }
+    }
 }
 
 #[cfg(test)]
@@ -306,6 +626,27 @@ mod tests {
                 .cloned()
                 .ok_or(Box::new(hexpm::ApiError::NotFound))
         }
+
+        fn get_publication_times(
+            &self,
+            package: &str,
+        ) -> Result<HashMap<Version, Timestamp>, Box<dyn StdError>> {
+            let times: &[(&str, &str)] = match package {
+                "gleam_stdlib" => &[
+                    ("0.1.0", "2020-01-01T00:00:00Z"),
+                    ("0.2.0", "2021-01-01T00:00:00Z"),
+                    ("0.2.2", "2022-06-01T12:30:00.25Z"),
+                    ("0.3.0", "2023-01-01T00:00:00Z"),
+                ],
+                _ => return Err(Box::new(hexpm::ApiError::NotFound)),
+            };
+            Ok(times
+                .iter()
+                .map(|(version, time)| {
+                    (Version::try_from(*version).unwrap(), time.parse().unwrap())
+                })
+                .collect())
+        }
     }
 
     fn make_remote() -> Box<Remote> {
@@ -447,6 +788,40 @@ mod tests {
             },
         );
 
+        let _ = deps.insert(
+            "package_with_retired_and_fixed".into(),
+            hexpm::Package {
+                name: "package_with_retired_and_fixed".into(),
+                repository: "hexpm".into(),
+                releases: vec![
+                    Release {
+                        version: Version::try_from("0.1.0").unwrap(),
+                        requirements: [].into(),
+                        retirement_status: None,
+                        outer_checksum: vec![1, 2, 3],
+                        meta: (),
+                    },
+                    Release {
+                        version: Version::try_from("0.2.0").unwrap(),
+                        requirements: [].into(),
+                        retirement_status: Some(hexpm::RetirementStatus {
+                            reason: hexpm::RetirementReason::Invalid,
+                            message: "Broken".into(),
+                        }),
+                        outer_checksum: vec![1, 2, 3],
+                        meta: (),
+                    },
+                    Release {
+                        version: Version::try_from("0.2.1").unwrap(),
+                        requirements: [].into(),
+                        retirement_status: None,
+                        outer_checksum: vec![1, 2, 3],
+                        meta: (),
+                    },
+                ],
+            },
+        );
+
         let _ = deps.insert(
             "package_with_optional".into(),
             hexpm::Package {
@@ -474,6 +849,103 @@ mod tests {
         Box::new(Remote { deps })
     }
 
+    /// Only the oldest of the many versions of `wibble` is compatible with the
+    /// version of `wobble` required, so every other version has to be tried
+    /// and rejected first.
+    fn make_backtracking_remote() -> Box<Remote> {
+        let release = |version: String, wobble_requirement: &str| Release {
+            version: Version::parse(&version).unwrap(),
+            requirements: [(
+                "wobble".into(),
+                Dependency {
+                    app: None,
+                    optional: false,
+                    repository: None,
+                    requirement: Range::new(wobble_requirement.into()),
+                },
+            )]
+            .into(),
+            retirement_status: None,
+            outer_checksum: vec![1, 2, 3],
+            meta: (),
+        };
+        let mut wibble_releases = vec![release("1.0.0".into(), "== 2.0.0")];
+        wibble_releases.extend((1..50).map(|minor| release(format!("1.{minor}.0"), "== 1.0.0")));
+
+        let mut deps = HashMap::new();
+        let _ = deps.insert(
+            "wibble".into(),
+            hexpm::Package {
+                name: "wibble".into(),
+                repository: "hexpm".into(),
+                releases: wibble_releases,
+            },
+        );
+        let _ = deps.insert(
+            "wobble".into(),
+            hexpm::Package {
+                name: "wobble".into(),
+                repository: "hexpm".into(),
+                releases: ["1.0.0", "2.0.0"]
+                    .into_iter()
+                    .map(|version| Release {
+                        version: Version::parse(version).unwrap(),
+                        requirements: [].into(),
+                        retirement_status: None,
+                        outer_checksum: vec![1, 2, 3],
+                        meta: (),
+                    })
+                    .collect(),
+            },
+        );
+        Box::new(Remote { deps })
+    }
+
+    fn backtracking_requirements() -> impl Iterator<Item = (EcoString, Range)> {
+        vec![
+            ("wibble".into(), Range::new(">= 1.0.0".into())),
+            ("wobble".into(), Range::new(">= 2.0.0".into())),
+        ]
+        .into_iter()
+    }
+
+    #[test]
+    fn resolution_with_backtracking() {
+        let result = resolve_versions(
+            make_backtracking_remote(),
+            HashMap::new(),
+            "app".into(),
+            backtracking_requirements(),
+            &vec![].into_iter().collect(),
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            vec![
+                ("wibble".into(), Version::parse("1.0.0").unwrap()),
+                ("wobble".into(), Version::parse("2.0.0").unwrap()),
+            ]
+            .into_iter()
+            .collect()
+        );
+    }
+
+    #[test]
+    fn resolution_stops_after_max_steps() {
+        let result = resolve_versions_with_step_limit(
+            make_backtracking_remote(),
+            HashMap::new(),
+            "app".into(),
+            backtracking_requirements(),
+            &vec![].into_iter().collect(),
+            10,
+        );
+        assert_eq!(
+            result,
+            Err(Error::DependencyResolutionTooComplex { max_steps: 10 })
+        );
+    }
+
     #[test]
     fn resolution_with_locked() {
         let locked_stdlib = ("gleam_stdlib".into(), Version::parse("0.1.0").unwrap());
@@ -709,6 +1181,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn resolution_retired_version_avoided_for_newer_version() {
+        let result = resolve_versions(
+            make_remote(),
+            HashMap::new(),
+            "app".into(),
+            vec![(
+                "package_with_retired_and_fixed".into(),
+                Range::new(">= 0.2.0".into()),
+            )]
+            .into_iter(),
+            &vec![].into_iter().collect(),
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            vec![(
+                "package_with_retired_and_fixed".into(),
+                Version::new(0, 2, 1)
+            ),]
+            .into_iter()
+            .collect()
+        );
+    }
+
+    #[test]
+    fn resolution_retired_versions_used_if_nothing_else_satisfies() {
+        let result = resolve_versions(
+            make_remote(),
+            HashMap::new(),
+            "app".into(),
+            vec![("package_with_retired".into(), Range::new("> 0.1.0".into()))].into_iter(),
+            &vec![].into_iter().collect(),
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            vec![("package_with_retired".into(), Version::new(0, 2, 0)),]
+                .into_iter()
+                .collect()
+        );
+    }
+
     #[test]
     fn resolution_prerelease_can_be_selected() {
         let result = resolve_versions(
@@ -832,4 +1347,193 @@ mod tests {
         assert_eq!(parse_exact_version("~> 1.0.0"), None);
         assert_eq!(parse_exact_version(">= 1.0.0"), None);
     }
+
+    const SNAPSHOT: &str = r#"{
+  "packages": {
+    "gleam_stdlib": [
+      {
+        "version": "0.1.0",
+        "requirements": {},
+        "outer_checksum": "0102",
+        "meta": { "app": "gleam_stdlib", "build_tools": ["gleam"] }
+      },
+      {
+        "version": "0.2.0",
+        "requirements": {},
+        "outer_checksum": "0304",
+        "meta": { "app": "gleam_stdlib", "build_tools": ["gleam"] }
+      },
+      {
+        "version": "0.3.0",
+        "requirements": {},
+        "retirement_status": { "reason": "security", "message": "Don't use" },
+        "outer_checksum": "0506",
+        "meta": { "app": "gleam_stdlib", "build_tools": ["gleam"] }
+      }
+    ],
+    "gleam_otp": [
+      {
+        "version": "0.1.0",
+        "requirements": {
+          "gleam_stdlib": { "requirement": ">= 0.1.0", "optional": false }
+        },
+        "outer_checksum": "0708",
+        "meta": { "app": "gleam_otp", "build_tools": ["gleam"] }
+      }
+    ]
+  }
+}"#;
+
+    #[test]
+    fn resolution_from_registry_snapshot() {
+        let snapshot = RegistrySnapshot::from_json(SNAPSHOT).unwrap();
+        let result = resolve_versions(
+            Box::new(snapshot),
+            HashMap::new(),
+            "app".into(),
+            vec![("gleam_otp".into(), Range::new("~> 0.1".into()))].into_iter(),
+            &vec![].into_iter().collect(),
+        )
+        .unwrap();
+        // The newest release of gleam_stdlib is retired, so it isn't used.
+        assert_eq!(
+            result,
+            vec![
+                ("gleam_otp".into(), Version::try_from("0.1.0").unwrap()),
+                ("gleam_stdlib".into(), Version::try_from("0.2.0").unwrap()),
+            ]
+            .into_iter()
+            .collect()
+        );
+    }
+
+    #[test]
+    fn resolution_from_registry_snapshot_without_package() {
+        let snapshot = RegistrySnapshot::from_json(SNAPSHOT).unwrap();
+        let result = resolve_versions(
+            Box::new(snapshot),
+            HashMap::new(),
+            "app".into(),
+            vec![("gleam_erlang".into(), Range::new("~> 0.1".into()))].into_iter(),
+            &vec![].into_iter().collect(),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn registry_snapshot_release() {
+        let snapshot = RegistrySnapshot::from_json(SNAPSHOT).unwrap();
+        let release = snapshot
+            .release("gleam_stdlib", &Version::new(0, 2, 0))
+            .unwrap();
+        assert_eq!(release.outer_checksum, vec![3, 4]);
+        assert_eq!(release.meta.app, "gleam_stdlib");
+        assert!(
+            snapshot
+                .release("gleam_stdlib", &Version::new(0, 4, 0))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn resolution_excludes_versions_published_after_the_cutoff() {
+        let result = resolve_versions(
+            Box::new(PublishedBefore::new(
+                make_remote(),
+                "2022-12-31T23:59:59Z".parse().unwrap(),
+            )),
+            HashMap::new(),
+            "app".into(),
+            vec![("gleam_stdlib".into(), Range::new("~> 0.1".into()))].into_iter(),
+            &vec![].into_iter().collect(),
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            vec![("gleam_stdlib".into(), Version::try_from("0.2.2").unwrap())]
+                .into_iter()
+                .collect()
+        );
+    }
+
+    #[test]
+    fn resolution_includes_versions_published_at_the_cutoff() {
+        let result = resolve_versions(
+            Box::new(PublishedBefore::new(
+                make_remote(),
+                "2023-01-01".parse().unwrap(),
+            )),
+            HashMap::new(),
+            "app".into(),
+            vec![("gleam_stdlib".into(), Range::new("~> 0.1".into()))].into_iter(),
+            &vec![].into_iter().collect(),
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            vec![("gleam_stdlib".into(), Version::try_from("0.3.0").unwrap())]
+                .into_iter()
+                .collect()
+        );
+    }
+
+    #[test]
+    fn timestamp_round_trip() {
+        for text in [
+            "2024-06-01T12:30:00Z",
+            "2020-05-02T17:18:23.336328Z",
+            "1999-12-31T23:59:60.5Z",
+        ] {
+            let timestamp: Timestamp = text.parse().unwrap();
+            assert_eq!(timestamp.to_string(), text);
+        }
+        assert_eq!(
+            "2024-06-01".parse::<Timestamp>().unwrap().to_string(),
+            "2024-06-01T00:00:00Z"
+        );
+    }
+
+    #[test]
+    fn timestamp_ordering() {
+        let parse = |text: &str| text.parse::<Timestamp>().unwrap();
+        assert!(parse("2024-06-01T12:30:00Z") < parse("2024-06-01T12:30:00.1Z"));
+        assert!(parse("2023-12-31T23:59:59Z") < parse("2024-01-01"));
+        assert!(parse("2024-02-01") > parse("2024-01-31T23:00:00Z"));
+    }
+
+    #[test]
+    fn invalid_timestamps() {
+        for text in [
+            "",
+            "2024",
+            "2024-6-01",
+            "2024-13-01",
+            "2024-06-00",
+            "2024-06-01T12:30:00",
+            "2024-06-01T24:00:00Z",
+            "2024-06-01T12:30Z",
+            "2024-06-01T12:30:00.Z",
+            "2024-06-01T12:30:00.1234567890Z",
+            "2024-06-01T12:30:00+01:00",
+            "2024-06-01-01",
+        ] {
+            assert!(text.parse::<Timestamp>().is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn impossible_dates_are_not_timestamps() {
+        for text in [
+            "2024-02-30",
+            "2024-02-31",
+            "2023-02-29",
+            "1900-02-29",
+            "2024-04-31",
+        ] {
+            assert!(text.parse::<Timestamp>().is_err(), "{text}");
+        }
+        for text in ["2024-02-29", "2000-02-29", "2024-01-31", "2024-04-30"] {
+            assert!(text.parse::<Timestamp>().is_ok(), "{text}");
+        }
+    }
 }
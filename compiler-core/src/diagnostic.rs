@@ -7,9 +7,10 @@ use codespan_reporting::{diagnostic::Label as CodespanLabel, files::SimpleFiles}
 use ecow::EcoString;
 use termcolor::Buffer;
 
-use crate::ast::SrcSpan;
+use crate::{ast::SrcSpan, line_numbers::LineNumbers};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Level {
     Error,
     Warning,
@@ -60,6 +61,34 @@ pub struct Diagnostic {
 }
 
 impl Diagnostic {
+    /// A representation of this diagnostic suitable for serialising as JSON,
+    /// for use by editors and other tools.
+    pub fn to_json(&self) -> JsonDiagnostic {
+        JsonDiagnostic {
+            level: self.level,
+            title: self.title.clone(),
+            text: self.text.clone(),
+            hint: self.hint.clone(),
+            location: self.location.as_ref().map(|location| {
+                let line_numbers = LineNumbers::new(&location.src);
+                let position = |byte_index| {
+                    let line_column = line_numbers.line_and_column_number(byte_index);
+                    JsonPosition {
+                        line: line_column.line,
+                        column: line_column.column,
+                        byte: byte_index,
+                    }
+                };
+                JsonLocation {
+                    path: location.path.clone(),
+                    label: location.label.text.clone(),
+                    start: position(location.label.span.start),
+                    end: position(location.label.span.end),
+                }
+            }),
+        }
+    }
+
     pub fn write(&self, buffer: &mut Buffer) {
         use std::io::Write;
         match &self.location {
@@ -139,3 +168,29 @@ impl Diagnostic {
             .expect("write_title_reset");
     }
 }
+
+/// A diagnostic as it is serialised to JSON. See [`Diagnostic::to_json`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct JsonDiagnostic {
+    pub level: Level,
+    pub title: String,
+    pub text: String,
+    pub hint: Option<String>,
+    pub location: Option<JsonLocation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct JsonLocation {
+    pub path: Utf8PathBuf,
+    pub label: Option<String>,
+    pub start: JsonPosition,
+    pub end: JsonPosition,
+}
+
+/// A position in a source file. Lines and columns start at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct JsonPosition {
+    pub line: u32,
+    pub column: u32,
+    pub byte: u32,
+}
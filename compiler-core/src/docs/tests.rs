@@ -49,7 +49,10 @@ fn compile_with_markdown_pages(
     let mut type_manifests = im::HashMap::new();
     let mut defined_modules = im::HashMap::new();
     let warnings = WarningEmitter::null();
-    let target = TargetCodegenConfiguration::Erlang { app_file: None };
+    let target = TargetCodegenConfiguration::Erlang {
+        app_file: None,
+        module_prefix: None,
+    };
 
     let root = Utf8PathBuf::from("/");
     let build = root.join("build");
@@ -562,3 +565,33 @@ fn output_of_search_data_json() {
     let json = serde_to_string(&data).unwrap();
     insta::assert_snapshot!(json);
 }
+
+#[test]
+fn re_exported_function_is_documented_in_the_re_exporting_module() {
+    let mut config = PackageConfig::default();
+    config.name = EcoString::from("test_project_name");
+    config.repository = Repository::GitHub {
+        user: "wibble".to_string(),
+        repo: "wobble".to_string(),
+        path: None,
+    };
+    let modules = vec![
+        (
+            "app/internal.gleam",
+            "/// Internal docs.\npub fn greet(name: String) -> String { name }",
+        ),
+        (
+            "app.gleam",
+            "import app/internal.{greet}\n\n/// Greets someone.\npub const greet = greet\n",
+        ),
+    ];
+    let output = compile(config, modules);
+    let app = output
+        .split("//// ")
+        .find(|file| file.starts_with("app.html"))
+        .expect("app.html");
+    assert!(app.contains("pub const greet: fn(String) -&gt; String"));
+    assert!(app.contains("Greets someone."));
+    assert!(!app.contains("Internal docs."));
+    assert!(app.contains("https://github.com/wibble/wobble/blob/v0.1.0/src/app.gleam#L4-L4"));
+}
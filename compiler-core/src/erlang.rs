@@ -6,7 +6,7 @@ mod pattern;
 #[cfg(test)]
 mod tests;
 
-use crate::build::{Target, module_erlang_name};
+use crate::build::{Target, prefixed_module_erlang_name};
 use crate::strings::convert_string_escape_chars;
 use crate::type_::is_prelude_module;
 use crate::{
@@ -32,19 +32,15 @@ use std::{collections::HashMap, ops::Deref, str::FromStr, sync::Arc};
 use vec1::Vec1;
 
 const INDENT: isize = 4;
-const MAX_COLUMNS: isize = 80;
 
-fn module_name_to_erlang(module: &str) -> Document<'_> {
-    EcoString::from(module.replace('/', "@")).to_doc()
-}
-
-fn module_name_atom(module: &str) -> Document<'static> {
-    atom_string(module.replace('/', "@"))
+fn module_name_atom(module: &str, module_prefix: Option<&str>) -> Document<'static> {
+    atom_string(prefixed_module_erlang_name(module_prefix, module).into())
 }
 
 #[derive(Debug, Clone)]
 struct Env<'a> {
     module: &'a str,
+    module_prefix: Option<&'a str>,
     function: &'a str,
     src_path: &'a Utf8Path,
     project_root: &'a Utf8Path,
@@ -58,6 +54,7 @@ struct Env<'a> {
 impl<'env> Env<'env> {
     pub fn new(
         module: &'env str,
+        module_prefix: Option<&'env str>,
         src_path: &'env Utf8Path,
         project_root: &'env Utf8Path,
         function: &'env str,
@@ -74,6 +71,27 @@ impl<'env> Env<'env> {
             line_numbers,
             function,
             module,
+            module_prefix,
+        }
+    }
+
+    /// The name of the Erlang module generated for the given Gleam module.
+    fn erlang_module_name(&self, module: &str) -> EcoString {
+        prefixed_module_erlang_name(self.module_prefix, module)
+    }
+
+    /// The Erlang module and function to call for a module function. If it
+    /// has an Erlang external implementation then that is called directly,
+    /// skipping the generated wrapper function.
+    fn module_fn_target<'a>(
+        &self,
+        module: &str,
+        name: &'a str,
+        external_erlang: &'a Option<(EcoString, EcoString)>,
+    ) -> (EcoString, &'a str) {
+        match external_erlang {
+            Some((module, name)) => (module.clone(), name),
+            None => (self.erlang_module_name(module), name),
         }
     }
 
@@ -102,7 +120,11 @@ impl<'env> Env<'env> {
     }
 }
 
-pub fn records(module: &TypedModule) -> Vec<(&str, String)> {
+pub fn records<'a>(
+    module: &'a TypedModule,
+    module_prefix: Option<&str>,
+    line_length: isize,
+) -> Vec<(&'a str, String)> {
     module
         .definitions
         .iter()
@@ -136,13 +158,25 @@ pub fn records(module: &TypedModule) -> Vec<(&str, String)> {
                 .collect::<Option<Vec<_>>>()
                 .map(|fields| (constructor.name.as_str(), fields))
         })
-        .map(|(name, fields)| (name, record_definition(name, &fields)))
+        .map(|(name, fields)| {
+            (
+                name,
+                record_definition(name, &fields, module_prefix, line_length),
+            )
+        })
         .collect()
 }
 
-pub fn record_definition(name: &str, fields: &[(&str, Arc<Type>)]) -> String {
+pub fn record_definition(
+    name: &str,
+    fields: &[(&str, Arc<Type>)],
+    module_prefix: Option<&str>,
+    line_length: isize,
+) -> String {
     let name = &name.to_snake_case();
-    let type_printer = TypePrinter::new("").var_as_any();
+    let type_printer = TypePrinter::new("")
+        .with_module_prefix(module_prefix)
+        .var_as_any();
     let fields = fields.iter().map(move |(name, type_)| {
         let type_ = type_printer.print(type_);
         docvec![atom_string((*name).to_string()), " :: ", type_.group()]
@@ -160,21 +194,24 @@ pub fn record_definition(name: &str, fields: &[(&str, Arc<Type>)]) -> String {
         "}).",
         line()
     ]
-    .to_pretty_string(MAX_COLUMNS)
+    .to_pretty_string(line_length)
 }
 
 pub fn module<'a>(
     module: &'a TypedModule,
     line_numbers: &'a LineNumbers,
     root: &'a Utf8Path,
+    module_prefix: Option<&'a str>,
+    line_length: isize,
 ) -> Result<String> {
-    Ok(module_document(module, line_numbers, root)?.to_pretty_string(MAX_COLUMNS))
+    Ok(module_document(module, line_numbers, root, module_prefix)?.to_pretty_string(line_length))
 }
 
 fn module_document<'a>(
     module: &'a TypedModule,
     line_numbers: &'a LineNumbers,
     root: &'a Utf8Path,
+    module_prefix: Option<&'a str>,
 ) -> Result<Document<'a>> {
     let mut exports = vec![];
     let mut type_defs = vec![];
@@ -182,7 +219,7 @@ fn module_document<'a>(
 
     let header = "-module("
         .to_doc()
-        .append(module.erlang_name())
+        .append(module_name_atom(&module.name, module_prefix))
         .append(").")
         .append(line());
 
@@ -199,6 +236,7 @@ fn module_document<'a>(
             &mut type_exports,
             &mut type_defs,
             &module.name,
+            module_prefix,
             &overridden_publicity,
         );
     }
@@ -245,6 +283,7 @@ fn module_document<'a>(
         if let Some((statement_document, env)) = module_statement(
             definition,
             &module.name,
+            module_prefix,
             module.type_info.is_internal,
             line_numbers,
             src_path_relative,
@@ -310,6 +349,7 @@ fn register_imports(
     type_exports: &mut Vec<Document<'_>>,
     type_defs: &mut Vec<Document<'_>>,
     module_name: &str,
+    module_prefix: Option<&str>,
     overridden_publicity: &HashSet<EcoString>,
 ) {
     match s {
@@ -358,7 +398,7 @@ fn register_imports(
                 })
                 .collect();
             let phantom_vars_constructor = if !phantom_vars.is_empty() {
-                let type_printer = TypePrinter::new(module_name);
+                let type_printer = TypePrinter::new(module_name).with_module_prefix(module_prefix);
                 Some(tuple(
                     std::iter::once("gleam_phantom".to_doc())
                         .chain(phantom_vars.iter().map(|pv| type_printer.print(pv))),
@@ -386,7 +426,8 @@ fn register_imports(
                         if c.arguments.is_empty() {
                             name
                         } else {
-                            let type_printer = TypePrinter::new(module_name);
+                            let type_printer =
+                                TypePrinter::new(module_name).with_module_prefix(module_prefix);
                             let args = c.arguments.iter().map(|a| type_printer.print(&a.type_));
                             tuple(std::iter::once(name).chain(args))
                         }
@@ -395,7 +436,7 @@ fn register_imports(
                 join(constructors, break_(" |", " | "))
             }
             .nest(INDENT);
-            let type_printer = TypePrinter::new(module_name);
+            let type_printer = TypePrinter::new(module_name).with_module_prefix(module_prefix);
             let params = join(
                 typed_parameters.iter().map(|a| type_printer.print(a)),
                 ", ".to_doc(),
@@ -422,6 +463,7 @@ fn register_imports(
 fn module_statement<'a>(
     statement: &'a TypedDefinition,
     module: &'a str,
+    module_prefix: Option<&'a str>,
     is_internal_module: bool,
     line_numbers: &'a LineNumbers,
     src_path: &'a Utf8Path,
@@ -436,6 +478,7 @@ fn module_statement<'a>(
         Definition::Function(function) => module_function(
             function,
             module,
+            module_prefix,
             is_internal_module,
             line_numbers,
             src_path,
@@ -447,6 +490,7 @@ fn module_statement<'a>(
 fn module_function<'a>(
     function: &'a TypedFunction,
     module: &'a str,
+    module_prefix: Option<&'a str>,
     is_internal_module: bool,
     line_numbers: &'a LineNumbers,
     src_path: &'a Utf8Path,
@@ -471,12 +515,21 @@ fn module_function<'a>(
     let function_name = escape_erlang_existing_name(function_name);
     let file_attribute = file_attribute(src_path, function, line_numbers);
 
-    let mut env = Env::new(module, src_path, project_root, function_name, line_numbers);
+    let mut env = Env::new(
+        module,
+        module_prefix,
+        src_path,
+        project_root,
+        function_name,
+        line_numbers,
+    );
     let var_usages = collect_type_var_usages(
         HashMap::new(),
         std::iter::once(&function.return_type).chain(function.arguments.iter().map(|a| &a.type_)),
     );
-    let type_printer = TypePrinter::new(module).with_var_usages(&var_usages);
+    let type_printer = TypePrinter::new(module)
+        .with_module_prefix(module_prefix)
+        .with_var_usages(&var_usages);
     let args_spec = function
         .arguments
         .iter()
@@ -1300,27 +1353,31 @@ fn var<'a>(name: &'a str, constructor: &'a ValueConstructor, env: &mut Env<'a>)
 
         ValueConstructorVariant::ModuleFn {
             arity,
-            external_erlang: Some((module, name)),
+            module,
+            external_erlang: None,
             ..
-        } => function_reference(Some(module), name, *arity),
-
-        ValueConstructorVariant::ModuleFn { arity, module, .. } if module == env.module => {
-            function_reference(None, name, *arity)
-        }
+        } if module == env.module => function_reference(None, name, *arity),
 
         ValueConstructorVariant::ModuleFn {
             arity,
             module,
             name,
+            external_erlang,
             ..
-        } => function_reference(Some(module), name, *arity),
+        } => {
+            let (module, name) = env.module_fn_target(module, name, external_erlang);
+            function_reference(Some(module), name, *arity)
+        }
     }
 }
 
-fn function_reference<'a>(module: Option<&'a str>, name: &'a str, arity: usize) -> Document<'a> {
+fn function_reference(module: Option<EcoString>, name: &str, arity: usize) -> Document<'_> {
     match module {
         None => "fun ".to_doc(),
-        Some(module) => "fun ".to_doc().append(module_name_atom(module)).append(":"),
+        Some(module) => "fun "
+            .to_doc()
+            .append(atom_string(module.into()))
+            .append(":"),
     }
     .append(atom(escape_erlang_existing_name(name)))
     .append("/")
@@ -1415,6 +1472,26 @@ fn clause<'a>(clause: &'a TypedClause, env: &mut Env<'a>) -> Document<'a> {
         ..
     } = clause;
 
+    if let Some((low, high)) = integer_range_patterns(clause) {
+        let name = env.next_local_var_name("gleam@range");
+        let range_guard = docvec![
+            name.clone(),
+            " >= ",
+            int(low),
+            " andalso ",
+            name.clone(),
+            " =< ",
+            int(high)
+        ];
+        let guard = optional_clause_guard(guard.as_ref(), vec![range_guard], env);
+        return name.append(guard).append(" ->").append(
+            line()
+                .append(clause_consequence(then, env))
+                .nest(INDENT)
+                .group(),
+        );
+    }
+
     // These are required to get the alternative patterns working properly.
     // Simply rendering the duplicate erlang clauses breaks the variable
     // rewriting because each pattern would define different (rewritten)
@@ -1460,6 +1537,48 @@ fn clause<'a>(clause: &'a TypedClause, env: &mut Env<'a>) -> Document<'a> {
     doc
 }
 
+/// If a clause matches a single subject against alternative integer literal
+/// patterns that together form a contiguous range, such as `1 | 2 | 3`, this
+/// returns the literals for the lowest and highest values of that range.
+///
+/// These clauses can be generated as a single clause with a range guard
+/// rather than one clause per pattern.
+fn integer_range_patterns(clause: &TypedClause) -> Option<(&str, &str)> {
+    // Short alternatives are left as they are, as a range guard is no smaller.
+    const MINIMUM_RANGE_PATTERNS: usize = 3;
+
+    if clause.alternative_patterns.len() + 1 < MINIMUM_RANGE_PATTERNS {
+        return None;
+    }
+
+    let mut values = std::iter::once(&clause.pattern)
+        .chain(&clause.alternative_patterns)
+        .map(|patterns| match patterns.as_slice() {
+            [
+                Pattern::Int {
+                    value, int_value, ..
+                },
+            ] => Some((int_value, value.as_str())),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    values.sort_by_key(|(int_value, _)| *int_value);
+    values.dedup_by(|(one, _), (other, _)| one == other);
+
+    let contiguous = values
+        .iter()
+        .tuple_windows()
+        .all(|((one, _), (next, _))| *next - *one == 1.into());
+    if !contiguous || values.len() < MINIMUM_RANGE_PATTERNS {
+        return None;
+    }
+
+    let (_, low) = values.first()?;
+    let (_, high) = values.last()?;
+    Some((low, high))
+}
+
 fn clause_consequence<'a>(consequence: &'a TypedExpr, env: &mut Env<'a>) -> Document<'a> {
     match consequence {
         TypedExpr::Block { statements, .. } => statement_sequence(statements, env),
@@ -1690,17 +1809,23 @@ fn call<'a>(fun: &'a TypedExpr, args: &'a [TypedCallArg], env: &mut Env<'a>) ->
 }
 
 fn module_fn_with_args<'a>(
-    module: &'a str,
+    module: &str,
     name: &'a str,
+    external_erlang: &'a Option<(EcoString, EcoString)>,
     args: Vec<Document<'a>>,
     env: &Env<'a>,
 ) -> Document<'a> {
+    let is_local = match external_erlang {
+        Some((external_module, _)) => external_module == env.module,
+        None => module == env.module,
+    };
+    let (module, name) = env.module_fn_target(module, name, external_erlang);
     let name = escape_erlang_existing_name(name);
     let args = wrap_args(args);
-    if module == env.module {
+    if is_local {
         atom(name).append(args)
     } else {
-        atom_string(module.replace('/', "@"))
+        atom_string(module.into())
             .append(":")
             .append(atom(name))
             .append(args)
@@ -1731,16 +1856,19 @@ fn docs_args_call<'a>(
                 ValueConstructor {
                     variant:
                         ValueConstructorVariant::ModuleFn {
-                            external_erlang: Some((module, name)),
+                            module,
+                            name,
+                            external_erlang,
                             ..
-                        }
-                        | ValueConstructorVariant::ModuleFn { module, name, .. },
+                        },
                     ..
                 },
             ..
-        } => module_fn_with_args(module, name, args, env),
+        } => module_fn_with_args(module, name, external_erlang, args, env),
 
-        // Match against a Constant::Var that contains a function.
+        // Match against a Constant::Var that contains a function, either
+        // defined in this module or accessed through another one, such as a
+        // function re-exported by a constant.
         // We want this to be emitted like a normal function call, not a function variable
         // substitution.
         TypedExpr::Var {
@@ -1758,14 +1886,25 @@ fn docs_args_call<'a>(
                     ..
                 },
             ..
+        }
+        | TypedExpr::ModuleSelect {
+            constructor:
+                ModuleValueConstructor::Constant {
+                    literal:
+                        Constant::Var {
+                            constructor: Some(constructor),
+                            ..
+                        },
+                    ..
+                },
+            ..
         } if constructor.variant.is_module_fn() => match &constructor.variant {
             ValueConstructorVariant::ModuleFn {
-                external_erlang: Some((module, name)),
+                module,
+                name,
+                external_erlang,
                 ..
-            }
-            | ValueConstructorVariant::ModuleFn { module, name, .. } => {
-                module_fn_with_args(module, name, args, env)
-            }
+            } => module_fn_with_args(module, name, external_erlang, args, env),
             _ => {
                 unreachable!("The above clause guard ensures that this is a module fn")
             }
@@ -1774,13 +1913,15 @@ fn docs_args_call<'a>(
         TypedExpr::ModuleSelect {
             constructor:
                 ModuleValueConstructor::Fn {
-                    external_erlang: Some((module, name)),
+                    module,
+                    name,
+                    external_erlang,
                     ..
-                }
-                | ModuleValueConstructor::Fn { module, name, .. },
+                },
             ..
         } => {
             let args = wrap_args(args);
+            let (module, name) = env.module_fn_target(module, name, external_erlang);
             let name = escape_erlang_existing_name(name);
             // We use the constructor Fn variant's `module` and function `name`.
             // It would also be valid to use the module and label as in the
@@ -1790,7 +1931,7 @@ fn docs_args_call<'a>(
             // This also enables an optimisation in the Erlang compiler in which
             // some Erlang BIFs can be replaced with literals if their arguments
             // are literals, such as `binary_to_atom`.
-            atom_string(module_erlang_name(module).to_string())
+            atom_string(module.into())
                 .append(":")
                 .append(atom_string(name.to_string()))
                 .append(args)
@@ -2050,12 +2191,16 @@ fn expr<'a>(expression: &'a TypedExpr, env: &mut Env<'a>) -> Document<'a> {
             type_,
             constructor:
                 ModuleValueConstructor::Fn {
-                    external_erlang: Some((module, name)),
+                    module,
+                    name,
+                    external_erlang,
                     ..
-                }
-                | ModuleValueConstructor::Fn { module, name, .. },
+                },
             ..
-        } => module_select_fn(type_.clone(), module, name),
+        } => {
+            let (module, name) = env.module_fn_target(module, name, external_erlang);
+            module_select_fn(type_.clone(), module, name)
+        }
 
         TypedExpr::RecordAccess { record, index, .. } => tuple_index(record, index + 1, env),
 
@@ -2169,17 +2314,18 @@ fn tuple_index<'a>(tuple: &'a TypedExpr, index: u64, env: &mut Env<'a>) -> Docum
         .append(wrap_args([index_doc, tuple_doc]))
 }
 
-fn module_select_fn<'a>(type_: Arc<Type>, module_name: &'a str, label: &'a str) -> Document<'a> {
+fn module_select_fn(type_: Arc<Type>, module_name: EcoString, label: &str) -> Document<'_> {
     match crate::type_::collapse_links(type_).as_ref() {
         Type::Fn { args, .. } => "fun "
             .to_doc()
-            .append(module_name_to_erlang(module_name))
+            .append(module_name.to_doc())
             .append(":")
             .append(atom(label))
             .append("/")
             .append(args.len()),
 
-        _ => module_name_to_erlang(module_name)
+        _ => module_name
+            .to_doc()
             .append(":")
             .append(atom(label))
             .append("()"),
@@ -2488,6 +2634,7 @@ fn erl_safe_type_name(mut name: String) -> EcoString {
 struct TypePrinter<'a> {
     var_as_any: bool,
     current_module: &'a str,
+    module_prefix: Option<&'a str>,
     var_usages: Option<&'a HashMap<u64, u64>>,
 }
 
@@ -2495,11 +2642,17 @@ impl<'a> TypePrinter<'a> {
     fn new(current_module: &'a str) -> Self {
         Self {
             current_module,
+            module_prefix: None,
             var_usages: None,
             var_as_any: false,
         }
     }
 
+    pub fn with_module_prefix(mut self, module_prefix: Option<&'a str>) -> Self {
+        self.module_prefix = module_prefix;
+        self
+    }
+
     pub fn with_var_usages(mut self, var_usages: &'a HashMap<u64, u64>) -> Self {
         self.var_usages = Some(var_usages);
         self
@@ -2572,7 +2725,8 @@ impl<'a> TypePrinter<'a> {
         if self.current_module == module {
             docvec![name, "(", args, ")"]
         } else {
-            docvec![module_name_atom(module), ":", name, "(", args, ")"]
+            let module = module_name_atom(module, self.module_prefix);
+            docvec![module, ":", name, "(", args, ")"]
         }
     }
 
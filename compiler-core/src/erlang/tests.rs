@@ -9,6 +9,7 @@ use crate::type_::PRELUDE_MODULE_NAME;
 use crate::warning::WarningEmitter;
 use crate::{
     build::{Origin, Target},
+    codegen::DEFAULT_LINE_LENGTH,
     erlang::module,
     line_numbers::LineNumbers,
     uid::UniqueIdGenerator,
@@ -40,6 +41,15 @@ mod use_;
 mod variables;
 
 pub fn compile_test_project(src: &str, src_path: &str, dep: Option<(&str, &str, &str)>) -> String {
+    compile_test_project_with_line_length(src, src_path, dep, DEFAULT_LINE_LENGTH)
+}
+
+pub fn compile_test_project_with_line_length(
+    src: &str,
+    src_path: &str,
+    dep: Option<(&str, &str, &str)>,
+    line_length: isize,
+) -> String {
     let mut modules = im::HashMap::new();
     let ids = UniqueIdGenerator::new();
     // DUPE: preludeinsertion
@@ -118,7 +128,7 @@ pub fn compile_test_project(src: &str, src_path: &str, dep: Option<(&str, &str,
     built_module.attach_doc_and_module_comments();
 
     let line_numbers = LineNumbers::new(src);
-    module(&built_module.ast, &line_numbers, root)
+    module(&built_module.ast, &line_numbers, root, None, line_length)
         .unwrap()
         .replace(
             std::include_str!("../../templates/echo.erl"),
@@ -996,3 +1006,19 @@ pub fn main() {
 }        "
     );
 }
+
+#[test]
+fn long_lines_wrap_at_configured_length() {
+    let src = r#"
+pub fn main() {
+  [1_000, 2_000, 3_000, 4_000, 5_000]
+}
+"#;
+    let compiled =
+        compile_test_project_with_line_length(src, "/root/project/test/my/mod.gleam", None, 30);
+    insta::assert_snapshot!(
+        insta::internals::AutoName,
+        format!("----- SOURCE CODE\n{src}\n\n----- COMPILED ERLANG\n{compiled}"),
+        src
+    );
+}
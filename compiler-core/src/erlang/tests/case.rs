@@ -101,3 +101,116 @@ pub fn main() {
 "#,
     );
 }
+
+#[test]
+fn contiguous_integer_alternatives_use_a_range_guard() {
+    assert_erl!(
+        r#"
+pub fn main(x) {
+  case x {
+    0 | 1 | 2 | 3 | 4 | 5 | 6 | 7 | 8 | 9 -> "digit"
+    _ -> "other"
+  }
+}
+"#,
+    );
+}
+
+#[test]
+fn unordered_integer_alternatives_with_guard_use_a_range_guard() {
+    assert_erl!(
+        r#"
+pub fn main(x, y) {
+  case x {
+    3 | 1 | 2 if y -> "small"
+    _ -> "other"
+  }
+}
+"#,
+    );
+}
+
+#[test]
+fn non_contiguous_integer_alternatives_are_not_a_range() {
+    assert_erl!(
+        r#"
+pub fn main(x) {
+  case x {
+    1 | 2 | 4 -> "some"
+    _ -> "other"
+  }
+}
+"#,
+    );
+}
+
+#[test]
+fn case_on_bool() {
+    assert_erl!(
+        r#"
+pub fn main(x) {
+  case x {
+    True -> "yes"
+    False -> "no"
+  }
+}
+"#,
+    );
+}
+
+#[test]
+fn case_on_bool_with_false_first() {
+    assert_erl!(
+        r#"
+pub fn main(x) {
+  case x {
+    False -> "no"
+    True -> "yes"
+  }
+}
+"#,
+    );
+}
+
+#[test]
+fn many_literal_clauses_are_a_single_case() {
+    assert_erl!(
+        r#"
+pub fn name(n) {
+  case n {
+    1 -> "one"
+    2 -> "two"
+    3 -> "three"
+    4 -> "four"
+    5 -> "five"
+    6 -> "six"
+    7 -> "seven"
+    8 -> "eight"
+    9 -> "nine"
+    10 -> "ten"
+    11 -> "eleven"
+    12 -> "twelve"
+    13 -> "thirteen"
+    14 -> "fourteen"
+    15 -> "fifteen"
+    16 -> "sixteen"
+    17 -> "seventeen"
+    18 -> "eighteen"
+    19 -> "nineteen"
+    20 -> "twenty"
+    21 -> "twenty one"
+    22 -> "twenty two"
+    23 -> "twenty three"
+    24 -> "twenty four"
+    25 -> "twenty five"
+    26 -> "twenty six"
+    27 -> "twenty seven"
+    28 -> "twenty eight"
+    29 -> "twenty nine"
+    30 -> "thirty"
+    _ -> "many"
+  }
+}
+"#
+    );
+}
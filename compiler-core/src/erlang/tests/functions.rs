@@ -137,3 +137,50 @@ pub fn main() {
 "
     );
 }
+
+#[test]
+fn function_with_concrete_signature_spec() {
+    assert_erl!(
+        "
+pub fn wibble(
+  a: Int,
+  b: Float,
+  c: String,
+  d: BitArray,
+  e: List(Bool),
+  f: #(Int, Nil),
+) -> Result(Int, String) {
+  Ok(a)
+}
+"
+    );
+}
+
+#[test]
+fn generic_function_spec() {
+    assert_erl!(
+        "
+pub fn wibble(a: a, f: fn(a) -> b) -> List(b) {
+  [f(a)]
+}
+"
+    );
+}
+
+#[test]
+fn re_exported_function_called() {
+    assert_erl!(
+        (
+            "package",
+            "some/facade",
+            "fn internal_wibble(x) { x }\npub const wibble = internal_wibble"
+        ),
+        r#"
+import some/facade
+
+pub fn main() {
+  facade.wibble(1)
+}
+"#
+    );
+}
@@ -69,6 +69,20 @@ pub fn expect(value, message) {
     );
 }
 
+#[test]
+fn result_argument() {
+    // The value is only bound if it matches, otherwise an error with the
+    // value and location of the assertion is raised.
+    assert_erl!(
+        r#"
+pub fn unwrap(result: Result(Int, Nil)) -> Int {
+  let assert Ok(x) = result
+  x
+}
+"#
+    );
+}
+
 // TODO: patterns that are just vars don't render a case expression
 // #[test]
 // fn just_pattern() {
@@ -1,4 +1,5 @@
 use crate::assert_erl;
+use crate::codegen::DEFAULT_LINE_LENGTH;
 use crate::erlang::*;
 use crate::type_;
 
@@ -9,7 +10,9 @@ fn basic() {
         &[
             ("name", type_::tuple(vec![])),
             ("is_cute", type_::tuple(vec![]))
-        ]
+        ],
+        None,
+        DEFAULT_LINE_LENGTH
     ));
 }
 
@@ -22,7 +25,9 @@ fn reserve_words() {
             ("receive", type_::int()),
             ("catch", type_::tuple(vec![])),
             ("unreserved", type_::tuple(vec![]))
-        ]
+        ],
+        None,
+        DEFAULT_LINE_LENGTH
     ));
 }
 
@@ -35,7 +40,9 @@ fn type_vars() {
             ("name", type_::generic_var(1)),
             ("is_cute", type_::unbound_var(1)),
             ("linked", type_::link(type_::int()))
-        ]
+        ],
+        None,
+        DEFAULT_LINE_LENGTH
     ));
 }
 
@@ -55,7 +62,9 @@ fn module_types() {
                 args: vec![],
                 inferred_variant: None,
             })
-        )]
+        )],
+        None,
+        DEFAULT_LINE_LENGTH
     ));
 }
 
@@ -79,7 +88,22 @@ fn long_definition_formatting() {
                     type_::list(type_::tuple(vec![type_::nil(), type_::nil(), type_::nil()])),
                 ]))
             ),
-        ]
+        ],
+        None,
+        DEFAULT_LINE_LENGTH
+    ));
+}
+
+#[test]
+fn definition_wraps_at_configured_length() {
+    insta::assert_snapshot!(record_definition(
+        "PetCat",
+        &[
+            ("name", type_::tuple(vec![])),
+            ("is_cute", type_::tuple(vec![]))
+        ],
+        None,
+        30
     ));
 }
 
@@ -125,6 +125,15 @@ pub enum Error {
     #[error("Non Utf-8 Path: {path}")]
     NonUtf8Path { path: PathBuf },
 
+    #[error("invalid UTF-8 in {path} at byte {byte_offset}")]
+    NonUtf8File {
+        path: Utf8PathBuf,
+        /// The contents of the file with any invalid sequences replaced, so
+        /// the error can point at the offending byte.
+        src: EcoString,
+        byte_offset: u32,
+    },
+
     #[error("{error}")]
     GitInitialization { error: String },
 
@@ -188,6 +197,39 @@ pub enum Error {
     #[error("{input} is not a valid version. {error}")]
     InvalidVersionFormat { input: String, error: String },
 
+    #[error("the {name} environment variable is not a valid URL")]
+    InvalidUrlEnvironmentVariable { name: String, value: String },
+
+    #[error("the {name} environment variable is not a valid number")]
+    InvalidNumberEnvironmentVariable { name: String, value: String },
+
+    #[error("the host {host} is not in the network allowlist")]
+    HostNotAllowed { host: String },
+
+    #[error("dependency resolution took more than {max_steps} steps")]
+    DependencyResolutionTooComplex { max_steps: usize },
+
+    #[error("the package repository URL {url} does not use HTTPS")]
+    InsecureRepositoryUrl { url: String },
+
+    #[error("the download URL {url} does not use HTTPS")]
+    InsecureDownloadUrl { url: String },
+
+    #[error("retired package versions were resolved: {}", packages.iter().join(", "))]
+    RetiredPackages { packages: Vec<EcoString> },
+
+    #[error("packages have no recorded checksum: {}", packages.iter().join(", "))]
+    MissingChecksums { packages: Vec<EcoString> },
+
+    #[error("the dependencies break the policy in {path}")]
+    DependencyPolicyViolations {
+        path: Utf8PathBuf,
+        violations: Vec<EcoString>,
+    },
+
+    #[error("package {package} is larger than {limit} bytes once unpacked")]
+    PackageTooLarge { package: EcoString, limit: u64 },
+
     #[error("project root already exists")]
     ProjectRootAlreadyExist { path: String },
 
@@ -198,6 +240,9 @@ file_names.iter().map(|x| x.as_str()).join(", "))]
     #[error("Packages not exist: {}", packages.iter().join(", "))]
     RemovedPackagesNotExist { packages: Vec<String> },
 
+    #[error("Package not in manifest: {package}")]
+    PackageNotInManifest { package: String },
+
     #[error("unable to find project root")]
     UnableToFindProjectRoot { path: String },
 
@@ -217,12 +262,28 @@ file_names.iter().map(|x| x.as_str()).join(", "))]
         error: javascript::Error,
     },
 
+    #[error("javascript bundling failed")]
+    JavaScriptBundle {
+        path: Utf8PathBuf,
+        error: javascript::bundle::BundleError,
+    },
+
     #[error("Invalid runtime for {target} target: {invalid_runtime}")]
     InvalidRuntime {
         target: Target,
         invalid_runtime: Runtime,
     },
 
+    #[error("Reloading is not supported for the {target} target")]
+    ReloadUnsupportedTarget { target: Target },
+
+    #[error("Test coverage is not supported for the {target} target")]
+    CoverageUnsupported {
+        target: Target,
+        /// The JavaScript runtime the tests would be run with.
+        runtime: Option<Runtime>,
+    },
+
     #[error("package downloading failed: {error}")]
     DownloadPackageError {
         package_name: String,
@@ -239,8 +300,17 @@ file_names.iter().map(|x| x.as_str()).join(", "))]
     #[error("Dependency tree resolution failed: {0}")]
     DependencyResolutionFailed(String),
 
-    #[error("The package {0} is listed in dependencies and dev-dependencies")]
-    DuplicateDependency(EcoString),
+    #[error("The package {name} is listed in dependencies and dev-dependencies")]
+    DuplicateDependency {
+        name: EcoString,
+        /// The requirement in `dependencies`, as it would be written in
+        /// gleam.toml, and the line of gleam.toml it is on if known.
+        requirement: EcoString,
+        line: Option<u32>,
+        /// The requirement in `dev-dependencies` and the line it is on.
+        dev_requirement: EcoString,
+        dev_line: Option<u32>,
+    },
 
     #[error("Expected package {expected} at path {path} but found {found} instead")]
     WrongDependencyProvided {
@@ -271,6 +341,24 @@ file_names.iter().map(|x| x.as_str()).join(", "))]
         build_tools: Vec<EcoString>,
     },
 
+    #[error("Version {version} of {package} has not been published to Hex")]
+    DocsVersionNotPublished { package: String, version: String },
+
+    #[error("The feature {feature} is not one of the package's features")]
+    UnknownFeature {
+        feature: EcoString,
+        features: Vec<EcoString>,
+    },
+
+    #[error("The feature name {name} is not valid")]
+    InvalidFeatureName { name: EcoString },
+
+    #[error("Building the project twice produced different output")]
+    NonDeterministicBuild { files: Vec<Utf8PathBuf> },
+
+    #[error("The project contains unfinished code")]
+    UnfinishedCode { locations: Vec<EcoString> },
+
     #[error("Opening docs at {path} failed: {error}")]
     FailedToOpenDocs { path: Utf8PathBuf, error: String },
 
@@ -393,6 +481,16 @@ pub enum ShellCommandFailureReason {
 }
 
 impl Error {
+    pub fn non_utf8_file(path: Utf8PathBuf, error: std::string::FromUtf8Error) -> Error {
+        let byte_offset = error.utf8_error().valid_up_to() as u32;
+        let src = String::from_utf8_lossy(error.as_bytes()).into();
+        Self::NonUtf8File {
+            path,
+            src,
+            byte_offset,
+        }
+    }
+
     pub fn http<E>(error: E) -> Error
     where
         E: std::error::Error,
@@ -618,7 +716,7 @@ pub enum FileIoAction {
     Read,
     Parse,
     Delete,
-    // Rename,
+    Rename,
     Create,
     WriteTo,
     Canonicalise,
@@ -636,7 +734,7 @@ impl FileIoAction {
             FileIoAction::Read => "read",
             FileIoAction::Parse => "parse",
             FileIoAction::Delete => "delete",
-            // FileIoAction::Rename => "rename",
+            FileIoAction::Rename => "rename",
             FileIoAction::Create => "create",
             FileIoAction::WriteTo => "write to",
             FileIoAction::FindParent => "find the parent of",
@@ -989,6 +1087,17 @@ If you want to overwrite these files, delete them and run the command again.
                 location: None,
             }],
 
+            Error::PackageNotInManifest { package } => vec![Diagnostic {
+                title: "Package not found".into(),
+                text: format!(
+                    "The package `{package}` is not a dependency of your package so it
+has no resolved version."
+                ),
+                level: Level::Error,
+                hint: Some("Run `gleam deps list` to see the resolved dependencies.".into()),
+                location: None,
+            }],
+
             Error::RemovedPackagesNotExist { packages } => vec![
                 Diagnostic {
                     title: "Package not found".into(),
@@ -1501,6 +1610,35 @@ The error from the encryption library was:
                 }]
             }
 
+            Error::NonUtf8File {
+                path,
+                src,
+                byte_offset,
+            } => {
+                let text = format!(
+                    "The file is not valid UTF-8. The first invalid byte is at \
+offset {byte_offset}. Gleam only supports UTF-8 encoded source files."
+                );
+                vec![Diagnostic {
+                    title: "Invalid UTF-8".into(),
+                    text: wrap(&text),
+                    level: Level::Error,
+                    location: Some(Location {
+                        label: Label {
+                            text: Some("Invalid UTF-8 here".into()),
+                            span: crate::ast::SrcSpan::new(
+                                *byte_offset,
+                                *byte_offset + char::REPLACEMENT_CHARACTER.len_utf8() as u32,
+                            ),
+                        },
+                        path: path.clone(),
+                        src: src.clone(),
+                        extra_labels: vec![],
+                    }),
+                    hint: None,
+                }]
+            }
+
             Error::GitInitialization { error } => {
                 let text = format!(
                     "An error occurred while trying make a git repository for this project:
@@ -3732,6 +3870,36 @@ Fix the warnings and try again."
                 }],
             },
 
+            Error::JavaScriptBundle { path, error } => match error {
+                javascript::bundle::BundleError::ExternalImport { specifier } => {
+                    vec![Diagnostic {
+                        title: "JavaScript module can not be bundled".into(),
+                        text: wrap_format!(
+                            "The module `{path}` imports `{specifier}`, which is not one \
+of the modules in the build directory. Only modules in the build directory can be bundled."
+                        ),
+                        hint: None,
+                        level: Level::Error,
+                        location: None,
+                    }]
+                }
+                javascript::bundle::BundleError::UnsupportedStatement { statement } => {
+                    vec![Diagnostic {
+                        title: "JavaScript module can not be bundled".into(),
+                        text: format!(
+                            "The module `{path}` contains a statement that can not be bundled:
+
+{statement}
+
+Only named imports and exports, namespace imports, and re-exports are supported."
+                        ),
+                        hint: None,
+                        level: Level::Error,
+                        location: None,
+                    }]
+                }
+            },
+
             Error::DownloadPackageError {
                 package_name,
                 package_version,
@@ -3784,6 +3952,185 @@ The error from the parser was:
                 }]
             }
 
+            Error::InvalidUrlEnvironmentVariable { name, value } => {
+                let text = format!(
+                    "The {name} environment variable is set to \"{value}\",
+which is not a valid URL."
+                );
+                vec![Diagnostic {
+                    title: "Invalid URL".into(),
+                    text,
+                    hint: Some(format!("Set {name} to a URL such as https://repo.hex.pm/")),
+                    location: None,
+                    level: Level::Error,
+                }]
+            }
+
+            Error::InvalidNumberEnvironmentVariable { name, value } => {
+                let text = format!(
+                    "The {name} environment variable is set to \"{value}\",
+which is not a valid number."
+                );
+                vec![Diagnostic {
+                    title: "Invalid number".into(),
+                    text,
+                    hint: Some(format!("Set {name} to a whole number such as 8")),
+                    location: None,
+                    level: Level::Error,
+                }]
+            }
+
+            Error::HostNotAllowed { host } => {
+                let text = format!(
+                    "A request was made to {host}, which is not one of the hosts
+network access is allowed to."
+                );
+                vec![Diagnostic {
+                    title: "Host not allowed".into(),
+                    text,
+                    hint: Some(format!(
+                        "Add {host} to --allow-network if it should be contacted."
+                    )),
+                    location: None,
+                    level: Level::Error,
+                }]
+            }
+
+            Error::DependencyResolutionTooComplex { max_steps } => {
+                let text = wrap_format!(
+                    "Dependency resolution was stopped after {max_steps} steps without \
+finding a set of versions that satisfies the version constraints. This can \
+happen when many versions of the dependencies have to be tried one after \
+another before finding ones that are compatible with each other."
+                );
+                vec![Diagnostic {
+                    title: "Dependency resolution took too long".into(),
+                    text,
+                    hint: Some(
+                        "Narrow the version ranges of your dependencies in gleam.toml, or set
+`max-resolution-steps` in gleam.toml to allow more steps."
+                            .into(),
+                    ),
+                    location: None,
+                    level: Level::Error,
+                }]
+            }
+
+            Error::InsecureRepositoryUrl { url } => {
+                let text = format!(
+                    "The package repository URL {url} uses plain HTTP rather than
+HTTPS. Packages downloaded over HTTP could be altered by anyone between you
+and the repository, so they are not fetched by default."
+                );
+                vec![Diagnostic {
+                    title: "Insecure package repository".into(),
+                    text,
+                    hint: Some(
+                        "Use an HTTPS URL, or pass --allow-insecure-http if you trust every
+network between you and the repository."
+                            .into(),
+                    ),
+                    location: None,
+                    level: Level::Error,
+                }]
+            }
+
+            Error::InsecureDownloadUrl { url } => {
+                let text = wrap_format!(
+                    "HTTPS is required for the package repository, but packages were to \
+be downloaded from {url}, which does not use HTTPS. Accepting it would let \
+anyone between you and that server alter the packages downloaded."
+                );
+                vec![Diagnostic {
+                    title: "Insecure download URL".into(),
+                    text,
+                    hint: Some(
+                        "Check the repository is configured with an HTTPS URL and that it
+doesn't redirect downloads to plain HTTP."
+                            .into(),
+                    ),
+                    location: None,
+                    level: Level::Error,
+                }]
+            }
+
+            Error::RetiredPackages { packages } => {
+                let text = wrap(
+                    "Retired package versions have been disallowed, but these \
+packages are locked to versions that have been retired on Hex:",
+                ) + "\n\n    "
+                    + &packages.join("\n    ");
+                vec![Diagnostic {
+                    title: "Retired package versions".into(),
+                    text,
+                    hint: Some(
+                        "Update them with `gleam update`, or change your requirements so
+that a version that hasn't been retired can be used."
+                            .into(),
+                    ),
+                    location: None,
+                    level: Level::Error,
+                }]
+            }
+
+            Error::MissingChecksums { packages } => {
+                let text = wrap(
+                    "Strict checksums are enabled, but these packages have no \
+checksum recorded in the manifest so they can't be verified:",
+                ) + "\n\n    "
+                    + &packages.join("\n    ");
+                vec![Diagnostic {
+                    title: "Missing package checksums".into(),
+                    text,
+                    hint: Some(
+                        "Check that the package repository provides checksums for \
+these packages."
+                            .into(),
+                    ),
+                    location: None,
+                    level: Level::Error,
+                }]
+            }
+
+            Error::DependencyPolicyViolations { path, violations } => {
+                let text = wrap_format!(
+                    "The resolved dependencies break the dependency policy in {path}:"
+                ) + "\n\n"
+                    + &violations
+                        .iter()
+                        .map(|violation| format!("  - {violation}"))
+                        .join("\n");
+                vec![Diagnostic {
+                    title: "Dependency policy violated".into(),
+                    text,
+                    hint: Some(
+                        "Change your requirements so that other packages or versions are
+used, or update the policy if these should be allowed."
+                            .into(),
+                    ),
+                    location: None,
+                    level: Level::Error,
+                }]
+            }
+
+            Error::PackageTooLarge { package, limit } => {
+                let text = format!(
+                    "The package `{package}` is larger than the limit of {limit} bytes once
+unpacked, so it has not been extracted."
+                );
+                vec![Diagnostic {
+                    title: "Package too large".into(),
+                    text,
+                    hint: Some(
+                        "If you trust this package the limit can be raised with
+--max-package-size."
+                            .into(),
+                    ),
+                    location: None,
+                    level: Level::Error,
+                }]
+            }
+
             Error::DependencyCanonicalizationFailed(package) => {
                 let text = format!("Local package `{package}` has no canonical path");
 
@@ -3850,14 +4197,134 @@ The error from the version resolver library was:
                 }]
             }
 
-            Error::DuplicateDependency(name) => {
+            Error::DuplicateDependency {
+                name,
+                requirement,
+                line,
+                dev_requirement,
+                dev_line,
+            } => {
+                let location = |table: &str, line: &Option<u32>| match line {
+                    Some(line) => format!("[{table}] line {line}"),
+                    None => format!("[{table}]"),
+                };
                 let text = format!(
                     "The package `{name}` is specified in both the dependencies and
-dev-dependencies sections of the gleam.toml file."
+dev-dependencies sections of the gleam.toml file.
+
+    {}: {name} = {requirement}
+    {}: {name} = {dev_requirement}",
+                    location("dependencies", line),
+                    location("dev-dependencies", dev_line),
                 );
                 vec![Diagnostic {
                     title: "Dependency duplicated".into(),
                     text,
+                    hint: Some(
+                        "Remove it from one of the sections. Packages in dependencies \
+can also be used by the tests."
+                            .into(),
+                    ),
+                    location: None,
+                    level: Level::Error,
+                }]
+            }
+
+            Error::DocsVersionNotPublished { package, version } => {
+                let text = format!(
+                    "Documentation can only be published for versions of a package that
+have been published to Hex, and version {version} of {package} has not been."
+                );
+                vec![Diagnostic {
+                    title: "Version not published".into(),
+                    text,
+                    hint: Some(
+                        "Publish the package with `gleam publish`, which also publishes
+its documentation, or check the version in gleam.toml."
+                            .into(),
+                    ),
+                    location: None,
+                    level: Level::Error,
+                }]
+            }
+
+            Error::UnknownFeature { feature, features } => {
+                let text = if features.is_empty() {
+                    format!(
+                        "The feature `{feature}` was enabled but this package has no features.
+Features are listed in gleam.toml like so:
+
+    features = [\"{feature}\"]"
+                    )
+                } else {
+                    let features = features.iter().map(|f| format!("  - {f}")).join("\n");
+                    format!(
+                        "The feature `{feature}` was enabled but it is not one of the features
+listed in gleam.toml. This package's features are:
+
+{features}"
+                    )
+                };
+                vec![Diagnostic {
+                    title: "Unknown feature".into(),
+                    text,
+                    hint: None,
+                    location: None,
+                    level: Level::Error,
+                }]
+            }
+
+            Error::InvalidFeatureName { name } => {
+                let text = format!(
+                    "`{name}` cannot be used as the name of a feature. Feature names
+must start with a lowercase letter, contain only lowercase letters, numbers and
+underscores, and not be a Gleam keyword."
+                );
+                vec![Diagnostic {
+                    title: "Invalid feature name".into(),
+                    text,
+                    hint: None,
+                    location: None,
+                    level: Level::Error,
+                }]
+            }
+
+            Error::NonDeterministicBuild { files } => {
+                let files = files.iter().map(|file| format!("  - {file}")).join("\n");
+                let text = format!(
+                    "Building the project a second time from scratch produced different
+output from the first build. These files differ:
+
+{files}
+
+This is a bug in the Gleam compiler, please let us know by opening an issue
+in our tracker: https://github.com/gleam-lang/gleam/issues"
+                );
+                vec![Diagnostic {
+                    title: "Non-deterministic build".into(),
+                    text,
+                    hint: None,
+                    location: None,
+                    level: Level::Error,
+                }]
+            }
+
+            Error::UnfinishedCode { locations } => {
+                let locations = locations
+                    .iter()
+                    .map(|location| format!("  - {location}"))
+                    .join("\n");
+                let text = format!(
+                    "The build was asked to fail if any unfinished code remains, and
+these expressions were found:
+
+{locations}
+
+Please replace them with finished code and try again."
+                );
+                vec![Diagnostic {
+                    title: "Unfinished code".into(),
+                    text,
                     hint: None,
                     location: None,
                     level: Level::Error,
@@ -3989,6 +4456,47 @@ satisfying {required_version} but you are using v{gleam_version}.",
                 }]
             }
 
+            Error::ReloadUnsupportedTarget { target } => vec![Diagnostic {
+                title: "Reloading not supported".into(),
+                text: format!(
+                    "Modules can only be reloaded into a running program on the Erlang target,
+but this program would be run on the {} target.",
+                    match target {
+                        Target::Erlang => "Erlang",
+                        Target::JavaScript => "JavaScript",
+                    }
+                ),
+                hint: Some("Run the program on the Erlang target to reload it.".into()),
+                location: None,
+                level: Level::Error,
+            }],
+
+            Error::CoverageUnsupported { target, runtime } => {
+                let (text, hint) = match target {
+                    Target::Erlang => (
+                        "Test coverage can only be recorded on the JavaScript target,
+but these tests would be run on the Erlang target."
+                            .into(),
+                        "Run the tests with `--target javascript` to record their coverage.",
+                    ),
+                    Target::JavaScript => (
+                        format!(
+                            "Test coverage can only be recorded when running on Node.js,
+but these tests would be run with {}.",
+                            runtime.map_or("another runtime".into(), |runtime| runtime.to_string())
+                        ),
+                        "Run the tests with `--runtime nodejs` to record their coverage.",
+                    ),
+                };
+                vec![Diagnostic {
+                    title: "Test coverage not supported".into(),
+                    text,
+                    hint: Some(hint.into()),
+                    location: None,
+                    level: Level::Error,
+                }]
+            }
+
             Error::JavaScriptPreludeRequired => vec![Diagnostic {
                 title: "JavaScript prelude required".into(),
                 text: "The --javascript-prelude flag must be given when compiling to JavaScript."
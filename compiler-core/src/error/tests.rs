@@ -35,3 +35,11 @@ fn test_shell_program_not_found_error() {
         }
     }
 }
+
+#[test]
+fn non_utf8_file() {
+    let bytes = b"pub fn main() {\n  \"caf\xe9\"\n}\n".to_vec();
+    let error = String::from_utf8(bytes).unwrap_err();
+    let error = Error::non_utf8_file("src/wibble.gleam".into(), error);
+    assert_snapshot!(error.pretty_string());
+}
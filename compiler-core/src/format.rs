@@ -798,6 +798,7 @@ impl<'comments> Formatter<'comments> {
             .set_internal(function.publicity)
             .set_external_erlang(&function.external_erlang)
             .set_external_javascript(&function.external_javascript)
+            .set_allow_deprecated(function.allow_deprecated)
             .to_doc();
 
         // Fn name and args
@@ -3092,6 +3093,7 @@ struct AttributesPrinter<'a> {
     external_javascript: &'a Option<(EcoString, EcoString, SrcSpan)>,
     deprecation: &'a Deprecation,
     internal: bool,
+    allow_deprecated: bool,
 }
 
 impl<'a> AttributesPrinter<'a> {
@@ -3101,6 +3103,7 @@ impl<'a> AttributesPrinter<'a> {
             external_javascript: &None,
             deprecation: &Deprecation::NotDeprecated,
             internal: false,
+            allow_deprecated: false,
         }
     }
 
@@ -3129,6 +3132,11 @@ impl<'a> AttributesPrinter<'a> {
         self.deprecation = deprecation;
         self
     }
+
+    pub fn set_allow_deprecated(mut self, allow_deprecated: bool) -> Self {
+        self.allow_deprecated = allow_deprecated;
+        self
+    }
 }
 
 impl<'a> Documentable<'a> for AttributesPrinter<'a> {
@@ -3154,6 +3162,11 @@ impl<'a> Documentable<'a> for AttributesPrinter<'a> {
             attributes.push("@internal".to_doc());
         };
 
+        // @allow attribute
+        if self.allow_deprecated {
+            attributes.push("@allow(deprecated)".to_doc());
+        };
+
         if attributes.is_empty() {
             nil()
         } else {
@@ -35,6 +35,18 @@ pub fn main() -> Nil
     );
 }
 
+#[test]
+fn allow_deprecated() {
+    assert_format!(
+        r#"@deprecated("use something else instead")
+@allow(deprecated)
+pub fn main() -> Nil {
+  old()
+}
+"#
+    );
+}
+
 #[test]
 fn anonymous_function_as_final_function_argument() {
     assert_format!(
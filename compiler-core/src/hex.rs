@@ -1,14 +1,18 @@
+use std::{num::NonZeroUsize, time::Duration};
+
 use camino::Utf8Path;
 use debug_ignore::DebugIgnore;
+use ecow::EcoString;
 use flate2::read::GzDecoder;
-use futures::future;
+use futures::{StreamExt, stream};
 use hexpm::{ApiError, version::Version};
 use tar::Archive;
 
 use crate::{
     Error, Result,
+    build::Telemetry,
     io::{FileSystemReader, FileSystemWriter, HttpClient, TarUnpacker},
-    manifest::{ManifestPackage, ManifestPackageSource},
+    manifest::{Base16Checksum, Manifest, ManifestPackage, ManifestPackageSource},
     paths::{self, ProjectPaths},
 };
 
@@ -126,6 +130,124 @@ pub async fn remove_api_key<Http: HttpClient>(
     hexpm::remove_api_key_response(response).map_err(Error::hex)
 }
 
+/// What was done to get a Hex package when downloading dependencies.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct PackageDownload {
+    pub name: EcoString,
+    pub version: String,
+    pub outer_checksum: Base16Checksum,
+    /// The size of the tarball fetched from Hex, or `None` if the package was
+    /// already available locally.
+    pub downloaded_bytes: Option<u64>,
+}
+
+impl PackageDownload {
+    pub fn already_present(package: &ManifestPackage) -> Option<Self> {
+        match &package.source {
+            ManifestPackageSource::Hex { outer_checksum } => Some(Self {
+                name: package.name.clone(),
+                version: package.version.to_string(),
+                outer_checksum: outer_checksum.clone(),
+                downloaded_bytes: None,
+            }),
+            ManifestPackageSource::Git { .. } | ManifestPackageSource::Local { .. } => None,
+        }
+    }
+}
+
+/// A machine readable summary of downloading the Hex packages of a project.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct DownloadSummary {
+    pub fetched: usize,
+    pub cached: usize,
+    pub total_bytes: u64,
+    pub elapsed_ms: u64,
+    pub packages: Vec<PackageDownload>,
+}
+
+impl DownloadSummary {
+    pub fn new(mut packages: Vec<PackageDownload>, elapsed: Duration) -> Self {
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+        let fetched = packages
+            .iter()
+            .filter(|package| package.downloaded_bytes.is_some())
+            .count();
+        Self {
+            fetched,
+            cached: packages.len() - fetched,
+            total_bytes: packages.iter().filter_map(|p| p.downloaded_bytes).sum(),
+            elapsed_ms: elapsed.as_millis().try_into().unwrap_or(u64::MAX),
+            packages,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("download summary JSON serialisation")
+    }
+}
+
+/// The exact Hex packages of a project, where they are fetched from, and their
+/// checksums, so that they can be mirrored and later downloaded without
+/// resolving versions, such as on a machine with no access to the internet.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct DownloadPlan {
+    pub packages: Vec<PlannedPackage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PlannedPackage {
+    pub name: EcoString,
+    pub version: Version,
+    pub url: String,
+    pub outer_checksum: Base16Checksum,
+}
+
+impl DownloadPlan {
+    /// The plan for the Hex packages of the manifest. Git and local packages
+    /// are not included as they don't come from the package repository.
+    pub fn new(manifest: &Manifest, project_name: &str, repository_base: &http::Uri) -> Self {
+        let mut packages: Vec<_> = manifest
+            .packages
+            .iter()
+            .filter(|package| package.name != project_name)
+            .filter_map(|package| match &package.source {
+                ManifestPackageSource::Hex { outer_checksum } => Some(PlannedPackage {
+                    name: package.name.clone(),
+                    version: package.version.clone(),
+                    url: format!(
+                        "{repository_base}tarballs/{}-{}.tar",
+                        package.name, package.version
+                    ),
+                    outer_checksum: outer_checksum.clone(),
+                }),
+                ManifestPackageSource::Git { .. } | ManifestPackageSource::Local { .. } => None,
+            })
+            .collect();
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+        Self { packages }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("download plan JSON serialisation")
+    }
+}
+
+impl PlannedPackage {
+    fn to_manifest_package(&self) -> ManifestPackage {
+        ManifestPackage {
+            name: self.name.clone(),
+            version: self.version.clone(),
+            build_tools: vec![],
+            otp_app: None,
+            requirements: vec![],
+            source: ManifestPackageSource::Hex {
+                outer_checksum: self.outer_checksum.clone(),
+            },
+            retired: false,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Downloader {
     fs_reader: DebugIgnore<Box<dyn FileSystemReader>>,
@@ -134,14 +256,20 @@ pub struct Downloader {
     untar: DebugIgnore<Box<dyn TarUnpacker>>,
     hex_config: hexpm::Config,
     paths: ProjectPaths,
+    concurrency_per_host: NonZeroUsize,
 }
 
+/// How many packages are downloaded from the same host at once unless told
+/// otherwise, so as to not overwhelm a shared repository or mirror.
+pub const DEFAULT_CONCURRENCY_PER_HOST: NonZeroUsize = NonZeroUsize::new(4).expect("non zero");
+
 impl Downloader {
     pub fn new(
         fs_reader: Box<dyn FileSystemReader>,
         fs_writer: Box<dyn FileSystemWriter>,
         http: Box<dyn HttpClient>,
         untar: Box<dyn TarUnpacker>,
+        hex_config: hexpm::Config,
         paths: ProjectPaths,
     ) -> Self {
         Self {
@@ -149,15 +277,33 @@ impl Downloader {
             fs_writer: DebugIgnore(fs_writer),
             http: DebugIgnore(http),
             untar: DebugIgnore(untar),
-            hex_config: hexpm::Config::new(),
+            hex_config,
             paths,
+            concurrency_per_host: DEFAULT_CONCURRENCY_PER_HOST,
         }
     }
 
+    /// Limit how many packages are downloaded from the same host at once.
+    /// Every package is fetched from the configured repository, so this
+    /// limits all the downloads made by the downloader.
+    pub fn with_concurrency_per_host(mut self, concurrency_per_host: NonZeroUsize) -> Self {
+        self.concurrency_per_host = concurrency_per_host;
+        self
+    }
+
     pub async fn ensure_package_downloaded(
         &self,
         package: &ManifestPackage,
     ) -> Result<bool, Error> {
+        let download = self.download_package_to_cache(package).await?;
+        Ok(download.downloaded_bytes.is_some())
+    }
+
+    /// Download a package to the global cache if it is not already there.
+    pub async fn download_package_to_cache(
+        &self,
+        package: &ManifestPackage,
+    ) -> Result<PackageDownload, Error> {
         let outer_checksum = match &package.source {
             ManifestPackageSource::Hex { outer_checksum } => outer_checksum,
             _ => {
@@ -169,13 +315,19 @@ impl Downloader {
             &package.name,
             &package.version.to_string(),
         );
+        let mut download = PackageDownload {
+            name: package.name.clone(),
+            version: package.version.to_string(),
+            outer_checksum: outer_checksum.clone(),
+            downloaded_bytes: None,
+        };
         if self.fs_reader.is_file(&tarball_path) {
             tracing::info!(
                 package = package.name.as_str(),
                 version = %package.version,
                 "package_in_cache"
             );
-            return Ok(false);
+            return Ok(download);
         }
         tracing::info!(
             package = &package.name.as_str(),
@@ -200,15 +352,17 @@ impl Downloader {
                 }
             })?;
         self.fs_writer.write_bytes(&tarball_path, &tarball)?;
-        Ok(true)
+        download.downloaded_bytes = Some(tarball.len() as u64);
+        Ok(download)
     }
 
     pub async fn ensure_package_in_build_directory(
         &self,
         package: &ManifestPackage,
-    ) -> Result<bool> {
-        let _ = self.ensure_package_downloaded(package).await?;
-        self.extract_package_from_cache(&package.name, &package.version)
+    ) -> Result<PackageDownload> {
+        let download = self.download_package_to_cache(package).await?;
+        let _ = self.extract_package_from_cache(&package.name, &package.version)?;
+        Ok(download)
     }
 
     // It would be really nice if this was async but the library is sync
@@ -235,7 +389,7 @@ impl Downloader {
             if path.as_ref() == contents_path {
                 // Expand this inner source code and write to the file system
                 let archive = Archive::new(GzDecoder::new(file));
-                let result = self.untar.unpack(&destination, archive);
+                let result = self.untar.unpack(name, &destination, archive);
 
                 // If we failed to expand the tarball remove any source code
                 // that was partially written so that we don't mistakenly think
@@ -255,23 +409,51 @@ impl Downloader {
         })
     }
 
+    /// Download the packages of a plan to the global cache from the configured
+    /// repository, failing if any of them don't have the planned checksum.
+    pub async fn download_plan_to_cache(
+        &self,
+        plan: &DownloadPlan,
+        telemetry: &impl Telemetry,
+    ) -> Result<Vec<PackageDownload>> {
+        let futures = plan.packages.iter().map(|planned| async move {
+            let package = planned.to_manifest_package();
+            let download = self.download_package_to_cache(&package).await?;
+            telemetry.downloaded_package(&package.name, &package.version);
+            Ok(download)
+        });
+        stream::iter(futures)
+            .buffered(self.concurrency_per_host.get())
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect()
+    }
+
     pub async fn download_hex_packages<'a, Packages: Iterator<Item = &'a ManifestPackage>>(
         &self,
         packages: Packages,
         project_name: &str,
-    ) -> Result<()> {
-        let futures = packages
-            .filter(|package| project_name != package.name)
-            .map(|package| self.ensure_package_in_build_directory(package));
+        telemetry: &impl Telemetry,
+    ) -> Result<Vec<PackageDownload>> {
+        let futures =
+            packages
+                .filter(|package| project_name != package.name)
+                .map(|package| async move {
+                    let download = self.ensure_package_in_build_directory(package).await?;
+                    telemetry.downloaded_package(&package.name, &package.version);
+                    Ok(download)
+                });
 
-        // Run the futures to download the packages concurrently
-        let results = future::join_all(futures).await;
+        // Run the futures to download the packages concurrently, up to the
+        // limit for the repository's host
+        let results: Vec<_> = stream::iter(futures)
+            .buffered(self.concurrency_per_host.get())
+            .collect()
+            .await;
 
-        // Count the number of packages downloaded while checking for errors
-        for result in results {
-            let _ = result?;
-        }
-        Ok(())
+        // Collect what was done for each package while checking for errors
+        results.into_iter().collect()
     }
 }
 
@@ -306,3 +488,268 @@ pub async fn get_package_release<Http: HttpClient>(
     let response = http.send(request).await?;
     hexpm::get_package_release_response(response).map_err(Error::hex)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{WrappedReader, memory::InMemoryFileSystem};
+    use async_trait::async_trait;
+    use futures::future;
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    const TARBALL: &[u8] = b"fake tarball contents";
+
+    // The SHA-256 of `TARBALL`
+    const TARBALL_CHECKSUM: &str =
+        "8761DED538856288531A4EEE97AE0B847C095D902B60D3A2C723FC3D1D687416";
+
+    #[derive(Debug)]
+    struct MockHttpClient;
+
+    #[async_trait]
+    impl HttpClient for MockHttpClient {
+        async fn send(
+            &self,
+            _request: http::Request<Vec<u8>>,
+        ) -> Result<http::Response<Vec<u8>>, Error> {
+            Ok(http::Response::builder()
+                .status(200)
+                .body(TARBALL.to_vec())
+                .expect("mock response"))
+        }
+    }
+
+    struct NoUntar;
+
+    impl TarUnpacker for NoUntar {
+        fn io_result_entries<'a>(
+            &self,
+            _archive: &'a mut Archive<WrappedReader>,
+        ) -> std::io::Result<tar::Entries<'a, WrappedReader>> {
+            panic!("tarballs are not unpacked in these tests")
+        }
+
+        fn io_result_unpack(
+            &self,
+            _path: &Utf8Path,
+            _archive: Archive<GzDecoder<tar::Entry<'_, WrappedReader>>>,
+        ) -> std::io::Result<()> {
+            panic!("tarballs are not unpacked in these tests")
+        }
+    }
+
+    fn hex_package(name: &str) -> ManifestPackage {
+        ManifestPackage {
+            name: name.into(),
+            version: Version::new(1, 0, 0),
+            build_tools: vec!["gleam".into()],
+            otp_app: None,
+            requirements: vec![],
+            source: ManifestPackageSource::Hex {
+                outer_checksum: Base16Checksum(base16::decode(TARBALL_CHECKSUM).expect("checksum")),
+            },
+            retired: false,
+        }
+    }
+
+    fn downloader(fs: &InMemoryFileSystem) -> Downloader {
+        Downloader::new(
+            Box::new(fs.clone()),
+            Box::new(fs.clone()),
+            Box::new(MockHttpClient),
+            Box::new(NoUntar),
+            hexpm::Config::new(),
+            ProjectPaths::new("/project".into()),
+        )
+    }
+
+    #[test]
+    fn download_summary_for_mocked_download() {
+        let fs = InMemoryFileSystem::new();
+        let downloader = downloader(&fs);
+        let wibble = hex_package("summary_wibble");
+        let wobble = hex_package("summary_wobble");
+
+        // `wobble` is already in the cache, so only `wibble` is fetched.
+        let wobble_path = paths::global_package_cache_package_tarball("summary_wobble", "1.0.0");
+        fs.write_bytes(&wobble_path, TARBALL).expect("write cached");
+
+        let downloads = vec![
+            futures::executor::block_on(downloader.download_package_to_cache(&wibble))
+                .expect("wibble download"),
+            futures::executor::block_on(downloader.download_package_to_cache(&wobble))
+                .expect("wobble download"),
+        ];
+        let summary = DownloadSummary::new(downloads, Duration::from_millis(42));
+
+        assert_eq!(summary.fetched, 1);
+        assert_eq!(summary.cached, 1);
+        assert_eq!(summary.total_bytes, TARBALL.len() as u64);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&summary.to_json()).expect("summary JSON");
+        assert_eq!(json["fetched"], 1);
+        assert_eq!(json["cached"], 1);
+        assert_eq!(json["total_bytes"], TARBALL.len());
+        assert_eq!(json["elapsed_ms"], 42);
+        assert_eq!(json["packages"][0]["name"], "summary_wibble");
+        assert_eq!(json["packages"][0]["downloaded_bytes"], TARBALL.len());
+        assert_eq!(json["packages"][0]["outer_checksum"], TARBALL_CHECKSUM);
+        assert_eq!(json["packages"][1]["name"], "summary_wobble");
+        assert_eq!(
+            json["packages"][1]["downloaded_bytes"],
+            serde_json::Value::Null
+        );
+        assert!(fs.is_file(&paths::global_package_cache_package_tarball(
+            "summary_wibble",
+            "1.0.0"
+        )));
+    }
+
+    #[test]
+    fn download_plan_round_trip() {
+        let mut git_package = hex_package("plan_git");
+        git_package.source = ManifestPackageSource::Git {
+            repo: "https://example.com/plan_git.git".into(),
+            commit: "abc123".into(),
+        };
+        let manifest = Manifest {
+            requirements: Default::default(),
+            packages: vec![
+                hex_package("plan_wobble"),
+                hex_package("my_project"),
+                git_package,
+                hex_package("plan_wibble"),
+            ],
+            checksum_algorithm: Default::default(),
+            published_before: None,
+        };
+
+        // Resolve on one machine...
+        let repository = "https://repo.hex.pm/".parse().expect("repository URL");
+        let plan = DownloadPlan::new(&manifest, "my_project", &repository);
+        let json = plan.to_json();
+
+        // ...and fetch on another.
+        let plan: DownloadPlan = serde_json::from_str(&json).expect("plan JSON");
+        assert_eq!(
+            plan.packages
+                .iter()
+                .map(|package| package.url.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "https://repo.hex.pm/tarballs/plan_wibble-1.0.0.tar",
+                "https://repo.hex.pm/tarballs/plan_wobble-1.0.0.tar",
+            ]
+        );
+
+        let fs = InMemoryFileSystem::new();
+        let downloads = futures::executor::block_on(
+            downloader(&fs).download_plan_to_cache(&plan, &crate::build::NullTelemetry),
+        )
+        .expect("plan download");
+        assert_eq!(downloads.len(), 2);
+        for name in ["plan_wibble", "plan_wobble"] {
+            let path = paths::global_package_cache_package_tarball(name, "1.0.0");
+            assert_eq!(fs.read_bytes(&path).expect("cached tarball"), TARBALL);
+        }
+        let git_path = paths::global_package_cache_package_tarball("plan_git", "1.0.0");
+        assert!(!fs.is_file(&git_path));
+    }
+
+    #[test]
+    fn download_plan_with_different_checksum_fails() {
+        let mut package = hex_package("plan_tampered");
+        package.source = ManifestPackageSource::Hex {
+            outer_checksum: Base16Checksum(vec![0; 32]),
+        };
+        let manifest = Manifest {
+            requirements: Default::default(),
+            packages: vec![package],
+            checksum_algorithm: Default::default(),
+            published_before: None,
+        };
+        let repository = "https://repo.hex.pm/".parse().expect("repository URL");
+        let plan = DownloadPlan::new(&manifest, "my_project", &repository);
+
+        let fs = InMemoryFileSystem::new();
+        let result = futures::executor::block_on(
+            downloader(&fs).download_plan_to_cache(&plan, &crate::build::NullTelemetry),
+        );
+        assert!(matches!(result, Err(Error::DownloadPackageError { .. })));
+        let path = paths::global_package_cache_package_tarball("plan_tampered", "1.0.0");
+        assert!(!fs.is_file(&path));
+    }
+
+    /// Counts the requests being handled at once, pausing each one so that
+    /// any others that can run do so before it finishes.
+    #[derive(Debug, Default, Clone)]
+    struct ConcurrencyCountingHttpClient {
+        in_flight: Arc<AtomicUsize>,
+        most_in_flight: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl HttpClient for ConcurrencyCountingHttpClient {
+        async fn send(
+            &self,
+            request: http::Request<Vec<u8>>,
+        ) -> Result<http::Response<Vec<u8>>, Error> {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            let _ = self.most_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            let mut paused = false;
+            future::poll_fn(|context| {
+                if paused {
+                    std::task::Poll::Ready(())
+                } else {
+                    paused = true;
+                    context.waker().wake_by_ref();
+                    std::task::Poll::Pending
+                }
+            })
+            .await;
+            let _ = self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            MockHttpClient.send(request).await
+        }
+    }
+
+    fn most_concurrent_downloads(concurrency_per_host: usize) -> usize {
+        let http = ConcurrencyCountingHttpClient::default();
+        let manifest = Manifest {
+            requirements: Default::default(),
+            packages: (0..6)
+                .map(|i| hex_package(&format!("concurrent_{i}")))
+                .collect(),
+            checksum_algorithm: Default::default(),
+            published_before: None,
+        };
+        let repository = "https://repo.hex.pm/".parse().expect("repository URL");
+        let plan = DownloadPlan::new(&manifest, "my_project", &repository);
+        let fs = InMemoryFileSystem::new();
+        let downloader = Downloader::new(
+            Box::new(fs.clone()),
+            Box::new(fs.clone()),
+            Box::new(http.clone()),
+            Box::new(NoUntar),
+            hexpm::Config::new(),
+            ProjectPaths::new("/project".into()),
+        )
+        .with_concurrency_per_host(NonZeroUsize::new(concurrency_per_host).expect("non zero"));
+
+        let downloads = futures::executor::block_on(
+            downloader.download_plan_to_cache(&plan, &crate::build::NullTelemetry),
+        )
+        .expect("plan download");
+        assert_eq!(downloads.len(), 6);
+        http.most_in_flight.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn downloads_from_one_host_are_limited_to_the_concurrency_per_host() {
+        assert_eq!(most_concurrent_downloads(2), 2);
+        assert_eq!(most_concurrent_downloads(6), 6);
+    }
+}
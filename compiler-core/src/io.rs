@@ -338,6 +338,7 @@ pub trait FileSystemWriter {
     fn delete_directory(&self, path: &Utf8Path) -> Result<(), Error>;
     fn copy(&self, from: &Utf8Path, to: &Utf8Path) -> Result<(), Error>;
     fn copy_dir(&self, from: &Utf8Path, to: &Utf8Path) -> Result<(), Error>;
+    fn rename_directory(&self, from: &Utf8Path, to: &Utf8Path) -> Result<(), Error>;
     fn hardlink(&self, from: &Utf8Path, to: &Utf8Path) -> Result<(), Error>;
     fn symlink_dir(&self, from: &Utf8Path, to: &Utf8Path) -> Result<(), Error>;
     fn delete_file(&self, path: &Utf8Path) -> Result<(), Error>;
@@ -387,6 +388,39 @@ pub trait HttpClient {
     -> Result<http::Response<Vec<u8>>, Error>;
 }
 
+/// The top level directories of a package's source code that are never used
+/// when building it, such as documentation and test fixtures.
+const DIRECTORIES_NOT_NEEDED_TO_BUILD: &[&str] = &["doc", "docs", "test", "tests"];
+
+/// Whether a file or directory of a package's source code, with a path
+/// relative to the root of the package, could be needed to build it.
+///
+/// This is conservative: only directories that are never used by any of the
+/// build tools are considered not needed.
+pub fn is_needed_to_build_package(path: &std::path::Path) -> bool {
+    match path.components().next() {
+        Some(std::path::Component::Normal(name)) => !DIRECTORIES_NOT_NEEDED_TO_BUILD
+            .iter()
+            .any(|directory| name == *directory),
+        _ => true,
+    }
+}
+
+/// The error a [`TarUnpacker`] fails with when the files of a package would
+/// take up more than the maximum allowed size once unpacked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PackageSizeLimitExceeded {
+    pub limit: u64,
+}
+
+impl std::fmt::Display for PackageSizeLimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unpacked package is larger than {} bytes", self.limit)
+    }
+}
+
+impl std::error::Error for PackageSizeLimitExceeded {}
+
 pub trait TarUnpacker {
     // FIXME: The reader types are restrictive here. We should be more generic
     // than this.
@@ -414,17 +448,28 @@ pub trait TarUnpacker {
 
     fn unpack(
         &self,
+        package: &str,
         path: &Utf8Path,
         archive: Archive<GzDecoder<Entry<'_, WrappedReader>>>,
     ) -> Result<()> {
         tracing::debug!(path = ?path, "unpacking tar archive");
-        self.io_result_unpack(path, archive)
-            .map_err(|e| Error::FileIo {
-                action: FileIoAction::WriteTo,
-                kind: FileKind::Directory,
-                path: path.to_path_buf(),
-                err: Some(e.to_string()),
-            })
+        self.io_result_unpack(path, archive).map_err(|e| {
+            match e
+                .get_ref()
+                .and_then(|error| error.downcast_ref::<PackageSizeLimitExceeded>())
+            {
+                Some(PackageSizeLimitExceeded { limit }) => Error::PackageTooLarge {
+                    package: package.into(),
+                    limit: *limit,
+                },
+                None => Error::FileIo {
+                    action: FileIoAction::WriteTo,
+                    kind: FileKind::Directory,
+                    path: path.to_path_buf(),
+                    err: Some(e.to_string()),
+                },
+            }
+        })
     }
 }
 
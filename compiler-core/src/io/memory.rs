@@ -145,8 +145,30 @@ impl FileSystemWriter for InMemoryFileSystem {
         self.write_bytes(to, &self.read_bytes(from)?)
     }
 
-    fn copy_dir(&self, _: &Utf8Path, _: &Utf8Path) -> Result<(), Error> {
-        panic!("unimplemented") // TODO
+    fn copy_dir(&self, from: &Utf8Path, to: &Utf8Path) -> Result<(), Error> {
+        let mut files = self.files.deref().borrow_mut();
+        let copies: Vec<_> = files
+            .iter()
+            .filter_map(|(path, file)| {
+                let relative = path.strip_prefix(from).ok()?;
+                Some((to.join(relative), file.clone()))
+            })
+            .collect();
+        files.extend(copies);
+        Ok(())
+    }
+
+    fn rename_directory(&self, from: &Utf8Path, to: &Utf8Path) -> Result<(), Error> {
+        if self.exists(to) {
+            return Err(Error::FileIo {
+                kind: FileKind::Directory,
+                action: FileIoAction::Rename,
+                path: from.to_path_buf(),
+                err: Some(format!("{to} already exists")),
+            });
+        }
+        self.copy_dir(from, to)?;
+        self.delete_directory(from)
     }
 
     fn mkdir(&self, path: &Utf8Path) -> Result<(), Error> {
@@ -281,9 +303,9 @@ impl FileSystemReader for InMemoryFileSystem {
             .is_some_and(|file| file.is_directory())
     }
 
-    fn reader(&self, _path: &Utf8Path) -> Result<WrappedReader, Error> {
-        // TODO
-        unreachable!("Memory reader unimplemented")
+    fn reader(&self, path: &Utf8Path) -> Result<WrappedReader, Error> {
+        let bytes = self.read_bytes(path)?;
+        Ok(WrappedReader::new(path, Box::new(io::Cursor::new(bytes))))
     }
 
     fn read_dir(&self, path: &Utf8Path) -> Result<ReadDir> {
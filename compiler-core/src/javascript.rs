@@ -1,5 +1,7 @@
+pub mod bundle;
 mod expression;
 mod import;
+mod mutual_recursion;
 mod pattern;
 #[cfg(test)]
 mod tests;
@@ -9,8 +11,8 @@ use num_bigint::BigInt;
 use num_traits::ToPrimitive;
 
 use crate::analyse::TargetSupport;
-use crate::build::Target;
 use crate::build::package_compiler::StdlibPackage;
+use crate::build::{EcmaScriptVersion, Target};
 use crate::codegen::TypeScriptDeclarations;
 use crate::type_::PRELUDE_MODULE_NAME;
 use crate::{
@@ -23,8 +25,10 @@ use camino::Utf8Path;
 use ecow::{EcoString, eco_format};
 use expression::Context;
 use itertools::Itertools;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 use self::import::{Imports, Member};
+use self::mutual_recursion::MutualRecursion;
 
 const INDENT: isize = 2;
 
@@ -33,6 +37,24 @@ pub const PRELUDE_TS_DEF: &str = include_str!("../templates/prelude.d.mts");
 
 pub type Output<'a> = Result<Document<'a>, Error>;
 
+/// A value imported unqualified with the same name as a function or constant
+/// defined in the module, such as in a module that re-exports it:
+///
+/// ```gleam
+/// import wibble.{wobble}
+/// pub const wobble = wobble
+/// ```
+///
+/// JavaScript doesn't allow both names to be declared in the module, so the
+/// value is referenced through the imported module instead.
+#[derive(Debug, Clone)]
+pub(crate) struct ShadowedImport {
+    /// The module the value is imported from.
+    module: EcoString,
+    /// The reference to the value through the imported module.
+    reference: EcoString,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum JavaScriptCodegenTarget {
     JavaScript,
@@ -46,10 +68,17 @@ pub struct Generator<'a> {
     project_root: &'a Utf8Path,
     tracker: UsageTracker,
     module_scope: im::HashMap<EcoString, usize>,
+    shadowed_imports: HashMap<EcoString, ShadowedImport>,
     current_module_name_segments_count: usize,
     target_support: TargetSupport,
     typescript: TypeScriptDeclarations,
     stdlib_package: StdlibPackage,
+    ecmascript_version: EcmaScriptVersion,
+    /// The groups of mutually recursive functions in the module.
+    mutual_recursion: Vec<MutualRecursion<'a>>,
+    /// The indexes of the groups that are generated inside a single loop,
+    /// rather than as separate functions.
+    looping_mutual_recursion: HashSet<usize>,
 }
 
 impl<'a> Generator<'a> {
@@ -58,6 +87,9 @@ impl<'a> Generator<'a> {
             target_support,
             typescript,
             stdlib_package,
+            ecmascript_version,
+            line_length: _,
+            coverage,
             module,
             line_numbers,
             src: _,
@@ -71,11 +103,18 @@ impl<'a> Generator<'a> {
             line_numbers,
             project_root,
             module,
-            tracker: UsageTracker::default(),
+            tracker: UsageTracker {
+                coverage_lines: coverage.then(BTreeSet::new),
+                ..Default::default()
+            },
             module_scope: Default::default(),
+            shadowed_imports: HashMap::new(),
             target_support,
             typescript,
             stdlib_package,
+            ecmascript_version,
+            mutual_recursion: mutual_recursion::groups(module),
+            looping_mutual_recursion: HashSet::new(),
         }
     }
 
@@ -100,6 +139,7 @@ impl<'a> Generator<'a> {
         let type_reference = self.type_reference();
 
         // Determine what JavaScript imports we need to generate
+        self.shadowed_imports = self.collect_shadowed_imports();
         let mut imports = self.collect_imports();
 
         // Determine what names are defined in the module scope so we know to
@@ -214,6 +254,12 @@ impl<'a> Generator<'a> {
             nil()
         };
 
+        if let Some(lines) = &self.tracker.coverage_lines
+            && !lines.is_empty()
+        {
+            statements.insert(0, self.coverage_registration(lines));
+        }
+
         // Put it all together
 
         if imports.is_empty() && statements.is_empty() {
@@ -239,6 +285,38 @@ impl<'a> Generator<'a> {
         }
     }
 
+    /// Registers the lines of the module that record when they are run, so
+    /// that lines which are never run are included in the coverage report.
+    fn coverage_registration(&self, covered_lines: &BTreeSet<u32>) -> Document<'a> {
+        let src_path = &self.module.type_info.src_path;
+        let path = src_path
+            .strip_prefix(self.project_root)
+            .unwrap_or(src_path)
+            .as_str()
+            .replace('\\', "/")
+            .replace('"', "\\\"");
+        let counters = join(
+            covered_lines
+                .iter()
+                .map(|line| eco_format!("{line}: 0").to_doc()),
+            break_(",", ", "),
+        );
+        docvec![
+            "globalThis.__gleam_coverage = globalThis.__gleam_coverage || {};",
+            line(),
+            docvec![
+                "const $Coverage = {",
+                docvec![break_("", " "), counters].nest(INDENT),
+                break_(",", " "),
+                "};"
+            ]
+            .group(),
+            line(),
+            eco_format!("globalThis.__gleam_coverage[\"{path}\"] = $Coverage;"),
+            lines(2),
+        ]
+    }
+
     fn register_prelude_usage(
         &self,
         imports: &mut Imports<'a>,
@@ -283,7 +361,10 @@ impl<'a> Generator<'a> {
                     return None;
                 }
 
-                self.module_function(function)
+                match self.mutual_recursion_of(function) {
+                    Some((group, index)) => self.mutually_recursive_function(group, index),
+                    None => self.module_function(function),
+                }
             }
         }
     }
@@ -383,6 +464,54 @@ impl<'a> Generator<'a> {
             .collect()
     }
 
+    fn collect_shadowed_imports(&self) -> HashMap<EcoString, ShadowedImport> {
+        let defined: HashSet<&EcoString> = self
+            .module
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                Definition::ModuleConstant(ModuleConstant { name, .. })
+                | Definition::Function(Function {
+                    name: Some((_, name)),
+                    ..
+                }) => Some(name),
+                Definition::Function(Function { .. })
+                | Definition::Import(Import { .. })
+                | Definition::TypeAlias(TypeAlias { .. })
+                | Definition::CustomType(CustomType { .. }) => None,
+            })
+            .collect();
+
+        let mut shadowed = HashMap::new();
+        for definition in &self.module.definitions {
+            let Definition::Import(Import {
+                module,
+                as_name,
+                unqualified_values,
+                ..
+            }) = definition
+            else {
+                continue;
+            };
+            for value in unqualified_values {
+                if !defined.contains(value.used_name()) {
+                    continue;
+                }
+                let reference = eco_format!(
+                    "{}.{}",
+                    module_alias(module, as_name),
+                    maybe_escape_identifier(&value.name)
+                );
+                let import = ShadowedImport {
+                    module: module.clone(),
+                    reference,
+                };
+                let _ = shadowed.insert(value.used_name().clone(), import);
+            }
+        }
+        shadowed
+    }
+
     fn collect_imports(&mut self) -> Imports<'a> {
         let mut imports = Imports::new();
 
@@ -450,22 +579,16 @@ impl<'a> Generator<'a> {
         as_name: &Option<(AssignName, SrcSpan)>,
         unqualified: &[UnqualifiedImport],
     ) {
-        let get_name = |module: &'a str| {
-            module
-                .split('/')
-                .last()
-                .expect("JavaScript generator could not identify imported module name.")
-        };
-
-        let (discarded, module_name) = match as_name {
-            None => (false, get_name(module)),
-            Some((AssignName::Discard(_), _)) => (true, get_name(module)),
-            Some((AssignName::Variable(name), _)) => (false, name.as_str()),
-        };
-
-        let module_name = eco_format!("${module_name}");
+        let discarded = matches!(as_name, Some((AssignName::Discard(_), _)));
+        let module_name = module_alias(module, as_name);
         let path = self.import_path(package, module);
-        let unqualified_imports = unqualified.iter().map(|i| {
+
+        // Values shadowed by a definition in this module are referenced
+        // through the module instead, so it is imported even if discarded.
+        let (shadowed, unqualified): (Vec<_>, Vec<_>) = unqualified
+            .iter()
+            .partition(|i| self.shadowed_imports.contains_key(i.used_name()));
+        let unqualified_imports = unqualified.into_iter().map(|i| {
             let alias = i.as_name.as_ref().map(|n| {
                 self.register_in_scope(n);
                 maybe_escape_identifier(n).to_doc()
@@ -474,7 +597,11 @@ impl<'a> Generator<'a> {
             Member { name, alias }
         });
 
-        let aliases = if discarded { vec![] } else { vec![module_name] };
+        let aliases = if discarded && shadowed.is_empty() {
+            vec![]
+        } else {
+            vec![module_name]
+        };
         imports.register_module(path, aliases, unqualified_imports);
     }
 
@@ -515,8 +642,15 @@ impl<'a> Generator<'a> {
             "export const "
         };
 
-        let document =
-            expression::constant_expression(Context::Constant, &mut self.tracker, value)?;
+        let context = Context::Constant {
+            shadowed_imports: &self.shadowed_imports,
+        };
+        let document = expression::constant_expression(
+            context,
+            &mut self.tracker,
+            self.ecmascript_version,
+            value,
+        )?;
 
         Ok(docvec![
             head,
@@ -546,6 +680,7 @@ impl<'a> Generator<'a> {
             &self.module.type_info.src_path,
             self.project_root,
             self.line_numbers,
+            self.ecmascript_version,
             name.clone(),
             argument_names,
             &mut self.tracker,
@@ -584,6 +719,151 @@ impl<'a> Generator<'a> {
         Some(Ok(document))
     }
 
+    /// The index of the group of mutually recursive functions the function is
+    /// part of, and its index within that group.
+    fn mutual_recursion_of(&self, function: &TypedFunction) -> Option<(usize, usize)> {
+        let (_, name) = function.name.as_ref()?;
+        self.mutual_recursion
+            .iter()
+            .enumerate()
+            .find_map(|(group, functions)| Some((group, functions.index_of(name)?)))
+    }
+
+    fn mutually_recursive_function(&mut self, group: usize, index: usize) -> Option<Output<'a>> {
+        let functions = self
+            .mutual_recursion
+            .get(group)
+            .expect("Index of a mutually recursive group")
+            .clone();
+        let function = functions.function(index);
+
+        // The loop is generated along with the first function of the group, so
+        // by the time any of the others are generated it is known whether they
+        // are wrappers for it.
+        if index == 0 {
+            match self.mutual_recursion_loop(&functions) {
+                Ok(Some(loop_)) => {
+                    let _ = self.looping_mutual_recursion.insert(group);
+                    let wrapper = mutually_recursive_function_wrapper(&functions, index);
+                    Some(Ok(docvec![wrapper, lines(2), loop_]))
+                }
+                Ok(None) => self.module_function(function),
+                Err(error) => Some(Err(error)),
+            }
+        } else if self.looping_mutual_recursion.contains(&group) {
+            Some(Ok(mutually_recursive_function_wrapper(&functions, index)))
+        } else {
+            self.module_function(function)
+        }
+    }
+
+    /// Generates the function that runs the bodies of a group of mutually
+    /// recursive functions in a loop. Returns nothing if none of the functions
+    /// call another in tail position, or if any of them can't be generated, in
+    /// which case they are generated as separate functions instead.
+    fn mutual_recursion_loop(
+        &mut self,
+        functions: &MutualRecursion<'a>,
+    ) -> Result<Option<Document<'a>>, Error> {
+        let mut mutual_tail_call_used = false;
+        let mut branches = Vec::with_capacity(functions.functions.len());
+        for (index, function) in functions.functions.iter().enumerate() {
+            let argument_names = function
+                .arguments
+                .iter()
+                .map(|arg| arg.names.get_variable_name())
+                .collect();
+            let mut generator = expression::Generator::new(
+                self.module.name.clone(),
+                &self.module.type_info.src_path,
+                self.project_root,
+                self.line_numbers,
+                self.ecmascript_version,
+                functions.name_of(index).clone(),
+                argument_names,
+                &mut self.tracker,
+                self.module_scope.clone(),
+            );
+            generator.set_mutual_recursion(functions, index);
+
+            let body = match generator.function_body(&function.body, &function.arguments) {
+                Ok(body) => body,
+                Err(error) if error.is_unsupported() && !self.target_support.is_enforced() => {
+                    return Ok(None);
+                }
+                Err(error) => return Err(error),
+            };
+            mutual_tail_call_used |= generator.mutual_tail_call_used;
+
+            let assignments = concat(
+                function
+                    .arguments
+                    .iter()
+                    .flat_map(Arg::get_variable_name)
+                    .map(|name| {
+                        let variable = mutual_recursion::loop_variable(functions, index, name);
+                        docvec![
+                            "let ",
+                            maybe_escape_identifier(name),
+                            " = ",
+                            variable,
+                            ";",
+                            line()
+                        ]
+                    }),
+            );
+            let condition = if index == 0 {
+                docvec!["if (loop$function === ", index, ") {"]
+            } else if index + 1 < functions.functions.len() {
+                docvec![" else if (loop$function === ", index, ") {"]
+            } else {
+                " else {".to_doc()
+            };
+            branches.push(docvec![
+                condition,
+                docvec![line(), assignments, body].nest(INDENT),
+                line(),
+                "}"
+            ]);
+        }
+
+        if !mutual_tail_call_used {
+            return Ok(None);
+        }
+
+        let arguments = std::iter::once("loop$function".to_doc()).chain(
+            functions
+                .functions
+                .iter()
+                .enumerate()
+                .flat_map(|(index, function)| {
+                    function
+                        .arguments
+                        .iter()
+                        .flat_map(Arg::get_variable_name)
+                        .map(move |name| {
+                            mutual_recursion::loop_variable(functions, index, name).to_doc()
+                        })
+                }),
+        );
+        Ok(Some(docvec![
+            "function ",
+            functions.loop_name(),
+            wrap_args(arguments),
+            " {",
+            docvec![
+                line(),
+                "while (true) {",
+                docvec![line(), branches].nest(INDENT),
+                line(),
+                "}"
+            ]
+            .nest(INDENT),
+            line(),
+            "}",
+        ]))
+    }
+
     fn register_module_definitions_in_scope(&mut self) {
         for statement in self.module.definitions.iter() {
             match statement {
@@ -619,6 +899,13 @@ pub struct ModuleConfig<'a> {
     pub target_support: TargetSupport,
     pub typescript: TypeScriptDeclarations,
     pub stdlib_package: StdlibPackage,
+    /// The version of ECMAScript the module is generated for.
+    pub ecmascript_version: EcmaScriptVersion,
+    /// The width lines are wrapped at where possible.
+    pub line_length: isize,
+    /// Whether the module records which of its lines are run, so that a test
+    /// coverage report can be written.
+    pub coverage: bool,
     pub path: &'a Utf8Path,
     pub project_root: &'a Utf8Path,
 }
@@ -626,10 +913,11 @@ pub struct ModuleConfig<'a> {
 pub fn module(config: ModuleConfig<'_>) -> Result<String, crate::Error> {
     let path = config.path.to_path_buf();
     let src = config.src.clone();
+    let line_length = config.line_length;
     let document = Generator::new(config)
         .compile()
         .map_err(|error| crate::Error::JavaScript { path, src, error })?;
-    Ok(document.to_pretty_string(80))
+    Ok(document.to_pretty_string(line_length))
 }
 
 pub fn ts_declaration(
@@ -679,6 +967,52 @@ fn fun_args(args: &'_ [TypedArg], tail_recursion_used: bool) -> Document<'_> {
     }))
 }
 
+/// A function of a group of mutually recursive functions, which starts the
+/// loop the group is generated in at its own body.
+fn mutually_recursive_function_wrapper<'a>(
+    functions: &MutualRecursion<'a>,
+    index: usize,
+) -> Document<'a> {
+    let function = functions.function(index);
+    let head = if function.publicity.is_private() {
+        "function "
+    } else {
+        "export function "
+    };
+
+    // Every argument of the loop function that comes before the ones of this
+    // function is given `undefined`, the ones after it are left out.
+    let preceding = functions
+        .functions
+        .iter()
+        .take(index)
+        .flat_map(|function| function.arguments.iter().flat_map(Arg::get_variable_name))
+        .map(|_| "undefined".to_doc());
+    let own = function
+        .arguments
+        .iter()
+        .flat_map(Arg::get_variable_name)
+        .map(|name| maybe_escape_identifier(name).to_doc());
+    let arguments = std::iter::once(index.to_doc()).chain(preceding).chain(own);
+
+    docvec![
+        head,
+        maybe_escape_identifier(functions.name_of(index)),
+        fun_args(function.arguments.as_slice(), false),
+        " {",
+        docvec![
+            line(),
+            "return ",
+            functions.loop_name(),
+            wrap_args(arguments),
+            ";"
+        ]
+        .nest(INDENT),
+        line(),
+        "}",
+    ]
+}
+
 fn wrap_args<'a, I>(args: I) -> Document<'a>
 where
     I: IntoIterator<Item = Document<'a>>,
@@ -844,6 +1178,22 @@ pub(crate) struct UsageTracker {
     pub codepoint_bit_array_segment_used: bool,
     pub float_bit_array_segment_used: bool,
     pub echo_used: bool,
+    /// When the module is instrumented for test coverage, the lines of the
+    /// statements that record when they are run.
+    pub coverage_lines: Option<BTreeSet<u32>>,
+}
+
+/// The name the namespace of an imported module is bound to, such as
+/// `$wobble` for `import wibble/wobble`.
+fn module_alias(module: &str, as_name: &Option<(AssignName, SrcSpan)>) -> EcoString {
+    let name = match as_name {
+        Some((AssignName::Variable(name), _)) => name.as_str(),
+        None | Some((AssignName::Discard(_), _)) => module
+            .split('/')
+            .last()
+            .expect("JavaScript generator could not identify imported module name."),
+    };
+    eco_format!("${name}")
 }
 
 fn bool(bool: bool) -> Document<'static> {
@@ -0,0 +1,440 @@
+//! Combining the JavaScript modules of a project and its dependencies into a
+//! single script that can be loaded in a browser without a module loader.
+//!
+//! Each module is wrapped in a function and its `import` and `export`
+//! statements are replaced with bindings to objects standing in for the
+//! modules' namespaces. Only the modules reachable from the entrypoint module
+//! are included.
+//!
+//! The modules are not parsed, rather their top level `import` and `export`
+//! statements are found line by line. This works for the modules generated by
+//! the compiler and for FFI modules written in the same style. Statements that
+//! can not be bundled, such as default exports or imports of modules outside
+//! of the build directory, result in an error.
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+use ecow::EcoString;
+use regex::Regex;
+
+use crate::{Error, Result, io::FileSystemReader};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleError {
+    /// A module imports a module that is not in the build directory, such as
+    /// an npm package or a runtime module.
+    ExternalImport { specifier: EcoString },
+    /// A module contains an `import` or `export` statement that can not be
+    /// bundled.
+    UnsupportedStatement { statement: EcoString },
+}
+
+/// Bundle the module at `entry`, a path relative to the `root` build directory,
+/// together with every module it imports. The resulting script calls the `main`
+/// function of the entry module.
+///
+pub fn bundle(io: &impl FileSystemReader, root: &Utf8Path, entry: &Utf8Path) -> Result<String> {
+    let entry = normalise(entry).expect("Bundle entry module path");
+
+    // Read every module reachable from the entrypoint.
+    let mut modules = HashMap::new();
+    let mut to_read = vec![entry.clone()];
+    while let Some(path) = to_read.pop() {
+        if modules.contains_key(&path) {
+            continue;
+        }
+        let src = io.read(&root.join(&path))?;
+        let module = Module::parse(&path, &src)?;
+        to_read.extend(module.dependencies().cloned());
+        let _ = modules.insert(path, module);
+    }
+
+    // Modules are evaluated after the modules they import. Imports that form
+    // a cycle are bound once every module has been evaluated.
+    let mut order = Vec::with_capacity(modules.len());
+    let mut visited = HashSet::new();
+    evaluation_order(&entry, &modules, &mut visited, &mut order);
+    let index: HashMap<&Utf8PathBuf, usize> = order
+        .iter()
+        .enumerate()
+        .map(|(index, path)| (*path, index))
+        .collect();
+
+    let mut buffer = String::new();
+    buffer.push_str(&format!(
+        "// This file was generated by Gleam
+// It bundles {entry} and the modules it imports.
+
+(() => {{
+\"use strict\";
+
+const $modules = Array.from({{ length: {length} }}, () => ({{}}));
+const $links = [];
+const $export = (namespace, getters) => {{
+  for (const name in getters) {{
+    Object.defineProperty(namespace, name, {{ get: getters[name], enumerable: true }});
+  }}
+}};
+const $exportAll = (namespace, from) => {{
+  for (const name of Object.keys(from)) {{
+    if (name !== \"default\" && !(name in namespace)) {{
+      Object.defineProperty(namespace, name, {{ get: () => from[name], enumerable: true }});
+    }}
+  }}
+}};
+",
+        length = order.len(),
+    ));
+
+    for path in &order {
+        let module = modules.get(*path).expect("Bundled module");
+        module.render(path, &index, &mut buffer);
+    }
+
+    buffer.push_str(&format!(
+        "
+for (const link of $links) link();
+$modules[{entry}].main();
+}})();
+",
+        entry = index.get(&entry).expect("Bundle entry module"),
+    ));
+
+    Ok(buffer)
+}
+
+fn evaluation_order<'a>(
+    path: &'a Utf8PathBuf,
+    modules: &'a HashMap<Utf8PathBuf, Module>,
+    visited: &mut HashSet<&'a Utf8PathBuf>,
+    order: &mut Vec<&'a Utf8PathBuf>,
+) {
+    if !visited.insert(path) {
+        return;
+    }
+    let (path, module) = modules.get_key_value(path).expect("Bundled module");
+    for dependency in module.dependencies() {
+        evaluation_order(dependency, modules, visited, order);
+    }
+    order.push(path);
+}
+
+/// A JavaScript module with its top level `import` and `export` statements
+/// taken out of its source.
+#[derive(Debug, Default)]
+struct Module {
+    /// The source of the module without its `import` and `export` statements.
+    body: String,
+    /// `import * as alias from "path"`
+    namespace_imports: Vec<(EcoString, Utf8PathBuf)>,
+    /// `import { name as alias } from "path"`, and modules imported only for
+    /// their side effects, which have no names.
+    named_imports: Vec<(Utf8PathBuf, Vec<(EcoString, EcoString)>)>,
+    /// Exported names and the local names they refer to.
+    exports: Vec<(EcoString, EcoString)>,
+    /// `export { name as alias } from "path"`
+    reexports: Vec<(Utf8PathBuf, Vec<(EcoString, EcoString)>)>,
+    /// `export * from "path"`
+    reexports_all: Vec<Utf8PathBuf>,
+}
+
+impl Module {
+    fn parse(path: &Utf8Path, src: &str) -> Result<Self> {
+        let mut module = Module::default();
+        let mut lines = src.lines();
+
+        while let Some(line) = lines.next() {
+            let Some(keyword) = statement_keyword(line) else {
+                module.body.push_str(line);
+                module.body.push('\n');
+                continue;
+            };
+
+            // Declarations are kept in the body, with their names exported.
+            let declaration = match keyword {
+                "export" => exported_declaration_regex().captures(line),
+                _ => None,
+            };
+            if let Some(captures) = declaration {
+                let prefix = captures.get(1).expect("Export prefix");
+                let name: EcoString = captures[2].into();
+                module.exports.push((name.clone(), name));
+                module.body.push_str(&line[prefix.end()..]);
+                module.body.push('\n');
+                continue;
+            }
+
+            // Other statements may span several lines.
+            let mut statement = line.to_string();
+            while !is_complete_statement(&statement) {
+                let Some(line) = lines.next() else {
+                    return Err(unsupported(path, &statement));
+                };
+                statement.push('\n');
+                statement.push_str(line);
+            }
+            module.add_statement(path, &statement)?;
+        }
+
+        Ok(module)
+    }
+
+    fn add_statement(&mut self, path: &Utf8Path, statement: &str) -> Result<()> {
+        let resolve = |specifier: &str| resolve(path, specifier);
+
+        if let Some(captures) = namespace_import_regex().captures(statement) {
+            let module = resolve(&captures[2])?;
+            self.namespace_imports.push((captures[1].into(), module));
+        } else if let Some(captures) = named_import_regex().captures(statement) {
+            let module = resolve(&captures[2])?;
+            let names = members(path, statement, &captures[1])?;
+            self.named_imports.push((module, names));
+        } else if let Some(captures) = side_effect_import_regex().captures(statement) {
+            let module = resolve(&captures[1])?;
+            self.named_imports.push((module, vec![]));
+        } else if let Some(captures) = reexport_regex().captures(statement) {
+            let module = resolve(&captures[2])?;
+            let names = members(path, statement, &captures[1])?;
+            self.reexports.push((module, names));
+        } else if let Some(captures) = reexport_all_regex().captures(statement) {
+            self.reexports_all.push(resolve(&captures[1])?);
+        } else if let Some(captures) = export_list_regex().captures(statement) {
+            let names = members(path, statement, &captures[1])?;
+            self.exports
+                .extend(names.into_iter().map(|(local, name)| (name, local)));
+        } else {
+            return Err(unsupported(path, statement));
+        }
+        Ok(())
+    }
+
+    fn dependencies(&self) -> impl Iterator<Item = &Utf8PathBuf> {
+        self.namespace_imports
+            .iter()
+            .map(|(_, path)| path)
+            .chain(self.named_imports.iter().map(|(path, _)| path))
+            .chain(self.reexports.iter().map(|(path, _)| path))
+            .chain(self.reexports_all.iter())
+    }
+
+    fn render(&self, path: &Utf8Path, index: &HashMap<&Utf8PathBuf, usize>, buffer: &mut String) {
+        let namespace =
+            |path: &Utf8PathBuf| format!("$modules[{}]", index.get(path).expect("Bundled module"));
+
+        buffer.push_str(&format!("\n// {path}\n(() => {{\n"));
+
+        for (alias, module) in &self.namespace_imports {
+            buffer.push_str(&format!("const {alias} = {};\n", namespace(module)));
+        }
+
+        // Named imports are bound again once all modules have been evaluated
+        // so that modules which import each other can see each other's names.
+        let named_imports = self
+            .named_imports
+            .iter()
+            .filter(|(_, names)| !names.is_empty())
+            .collect::<Vec<_>>();
+        if !named_imports.is_empty() {
+            let aliases = named_imports
+                .iter()
+                .flat_map(|(_, names)| names.iter().map(|(_, alias)| alias.as_str()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            buffer.push_str(&format!("let {aliases};\nconst $link = () => {{\n"));
+            for (module, names) in named_imports {
+                let names = names
+                    .iter()
+                    .map(|(name, alias)| {
+                        if name == alias {
+                            name.to_string()
+                        } else {
+                            format!("{name}: {alias}")
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                buffer.push_str(&format!("  ({{ {names} }} = {});\n", namespace(module)));
+            }
+            buffer.push_str("};\n$link();\n$links.push($link);\n");
+        }
+
+        buffer.push_str(&self.body);
+
+        let getters = self
+            .exports
+            .iter()
+            .map(|(name, local)| format!("  {name}: () => {local},\n"))
+            .chain(self.reexports.iter().flat_map(|(module, names)| {
+                let module = namespace(module);
+                names
+                    .iter()
+                    .map(move |(name, alias)| format!("  {alias}: () => {module}.{name},\n"))
+            }))
+            .collect::<String>();
+        let this = namespace(&path.to_path_buf());
+        if !getters.is_empty() {
+            buffer.push_str(&format!("$export({this}, {{\n{getters}}});\n"));
+        }
+        for module in &self.reexports_all {
+            buffer.push_str(&format!("$exportAll({this}, {});\n", namespace(module)));
+        }
+
+        buffer.push_str("})();\n");
+    }
+}
+
+/// The keyword of a top level `import` or `export` statement starting on this
+/// line, if there is one. Dynamic imports and `import.meta` are expressions
+/// rather than statements and are left alone.
+fn statement_keyword(line: &str) -> Option<&'static str> {
+    ["import", "export"].into_iter().find(|keyword| {
+        line.strip_prefix(keyword).is_some_and(|rest| {
+            rest.starts_with(|c: char| c.is_whitespace() || c == '{' || c == '*' || c == '"')
+                || rest.starts_with('\'')
+        })
+    })
+}
+
+fn is_complete_statement(statement: &str) -> bool {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r#"(?s)(["'][^"']*["']|\})\s*;?\s*$"#).expect("Complete statement regex")
+    })
+    .is_match(statement)
+}
+
+fn members(path: &Utf8Path, statement: &str, members: &str) -> Result<Vec<(EcoString, EcoString)>> {
+    members
+        .split(',')
+        .map(str::trim)
+        .filter(|member| !member.is_empty())
+        .map(|member| match member_regex().captures(member) {
+            Some(captures) => {
+                let name: EcoString = captures[1].into();
+                let alias = captures
+                    .get(2)
+                    .map_or(name.clone(), |alias| alias.as_str().into());
+                Ok((name, alias))
+            }
+            None => Err(unsupported(path, statement)),
+        })
+        .collect()
+}
+
+fn unsupported(path: &Utf8Path, statement: &str) -> Error {
+    Error::JavaScriptBundle {
+        path: path.to_path_buf(),
+        error: BundleError::UnsupportedStatement {
+            statement: statement.into(),
+        },
+    }
+}
+
+/// Resolve an import specifier relative to the module importing it.
+fn resolve(path: &Utf8Path, specifier: &str) -> Result<Utf8PathBuf> {
+    let external = || Error::JavaScriptBundle {
+        path: path.to_path_buf(),
+        error: BundleError::ExternalImport {
+            specifier: specifier.into(),
+        },
+    };
+    if !specifier.starts_with("./") && !specifier.starts_with("../") {
+        return Err(external());
+    }
+    let directory = path.parent().unwrap_or(Utf8Path::new(""));
+    normalise(&directory.join(specifier)).ok_or_else(external)
+}
+
+/// Remove `.` and `..` components from a relative path, returning `None` if the
+/// path leads outside of the directory it is relative to.
+fn normalise(path: &Utf8Path) -> Option<Utf8PathBuf> {
+    let mut normalised = Utf8PathBuf::new();
+    for component in path.components() {
+        match component {
+            Utf8Component::CurDir => (),
+            Utf8Component::ParentDir => {
+                if !normalised.pop() {
+                    return None;
+                }
+            }
+            Utf8Component::Normal(name) => normalised.push(name),
+            Utf8Component::RootDir | Utf8Component::Prefix(_) => return None,
+        }
+    }
+    Some(normalised)
+}
+
+const IDENTIFIER: &str = r"[A-Za-z_$][\w$]*";
+const SPECIFIER: &str = r#"["']([^"']+)["']"#;
+
+fn exported_declaration_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(&format!(
+            r"^(export\s+)(?:async\s+)?(?:function\s*\*?|class|const|let|var)\s*({IDENTIFIER})"
+        ))
+        .expect("Exported declaration regex")
+    })
+}
+
+fn namespace_import_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(&format!(
+            r"^import\s*\*\s*as\s+({IDENTIFIER})\s+from\s*{SPECIFIER}\s*;?\s*$"
+        ))
+        .expect("Namespace import regex")
+    })
+}
+
+fn named_import_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(&format!(
+            r"^import\s*\{{([^}}]*)\}}\s*from\s*{SPECIFIER}\s*;?\s*$"
+        ))
+        .expect("Named import regex")
+    })
+}
+
+fn side_effect_import_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(&format!(r"^import\s*{SPECIFIER}\s*;?\s*$")).expect("Side effect import regex")
+    })
+}
+
+fn reexport_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(&format!(
+            r"^export\s*\{{([^}}]*)\}}\s*from\s*{SPECIFIER}\s*;?\s*$"
+        ))
+        .expect("Re-export regex")
+    })
+}
+
+fn reexport_all_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(&format!(r"^export\s*\*\s*from\s*{SPECIFIER}\s*;?\s*$"))
+            .expect("Re-export all regex")
+    })
+}
+
+fn export_list_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^export\s*\{([^}]*)\}\s*;?\s*$").expect("Export list regex"))
+}
+
+fn member_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(&format!(r"^({IDENTIFIER})(?:\s+as\s+({IDENTIFIER}))?$")).expect("Member regex")
+    })
+}
@@ -0,0 +1,151 @@
+use camino::Utf8Path;
+
+use crate::Error;
+use crate::io::{FileSystemWriter, memory::InMemoryFileSystem};
+
+use super::{BundleError, bundle};
+
+fn bundle_files(files: &[(&str, &str)]) -> Result<String, Error> {
+    let io = InMemoryFileSystem::new();
+    for (path, src) in files {
+        io.write(&Utf8Path::new("/build").join(path), src)
+            .expect("Write module");
+    }
+    bundle(&io, Utf8Path::new("/build"), Utf8Path::new("app/app.mjs"))
+}
+
+#[test]
+fn multiple_modules() {
+    let output = bundle_files(&[
+        (
+            "prelude.mjs",
+            "export class CustomType {}
+export function toList(elements) {
+  return elements;
+}
+",
+        ),
+        ("app/gleam.mjs", "export * from \"../prelude.mjs\";\n"),
+        (
+            "app/app.mjs",
+            "import * as $other from \"./app/other.mjs\";
+import { Wibble } from \"./app/other.mjs\";
+import { toList } from \"./gleam.mjs\";
+
+export function main() {
+  return $other.wobble(new Wibble(toList([1])));
+}
+",
+        ),
+        (
+            "app/app/other.mjs",
+            "import { CustomType as $CustomType } from \"../gleam.mjs\";
+import { wobble as do_wobble } from \"./other_ffi.mjs\";
+
+export class Wibble extends $CustomType {
+  constructor(x0) {
+    super();
+    this[0] = x0;
+  }
+}
+
+export {
+  do_wobble as wobble,
+};
+",
+        ),
+        (
+            "app/app/other_ffi.mjs",
+            "import { Wibble } from \"./other.mjs\";
+
+export function wobble(wibble) {
+  return wibble instanceof Wibble;
+}
+",
+        ),
+        ("app/unused.mjs", "export function unused() {}\n"),
+    ])
+    .expect("Bundle");
+
+    assert!(!output.contains("unused"));
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn external_import() {
+    let error = bundle_files(&[(
+        "app/app.mjs",
+        "import * as fs from \"node:fs\";
+export function main() {}
+",
+    )])
+    .expect_err("Bundle");
+
+    assert_eq!(
+        error,
+        Error::JavaScriptBundle {
+            path: "app/app.mjs".into(),
+            error: BundleError::ExternalImport {
+                specifier: "node:fs".into()
+            }
+        }
+    );
+}
+
+#[test]
+fn import_outside_of_build_directory() {
+    let error = bundle_files(&[(
+        "app/app.mjs",
+        "import { wibble } from \"../../wibble.mjs\";
+export function main() {}
+",
+    )])
+    .expect_err("Bundle");
+
+    assert_eq!(
+        error,
+        Error::JavaScriptBundle {
+            path: "app/app.mjs".into(),
+            error: BundleError::ExternalImport {
+                specifier: "../../wibble.mjs".into()
+            }
+        }
+    );
+}
+
+#[test]
+fn default_export() {
+    let error = bundle_files(&[(
+        "app/app.mjs",
+        "export default function main() {
+  return 1;
+}
+",
+    )])
+    .expect_err("Bundle");
+
+    assert_eq!(
+        error,
+        Error::JavaScriptBundle {
+            path: "app/app.mjs".into(),
+            error: BundleError::UnsupportedStatement {
+                statement: "export default function main() {\n  return 1;\n}".into()
+            }
+        }
+    );
+}
+
+#[test]
+fn dynamic_imports_are_left_alone() {
+    let output = bundle_files(&[(
+        "app/app.mjs",
+        "export function main() {
+  return import.meta.url;
+}
+import(\"./wibble.mjs\");
+",
+    )])
+    .expect("Bundle");
+
+    assert!(output.contains("\nimport(\"./wibble.mjs\");\n"));
+}
@@ -2,6 +2,7 @@ use num_bigint::BigInt;
 use vec1::Vec1;
 
 use super::{
+    mutual_recursion::{MutualRecursion, loop_variable},
     pattern::{Assignment, CompiledPattern},
     *,
 };
@@ -85,6 +86,7 @@ pub(crate) struct Generator<'module, 'ast> {
     src_path: &'module Utf8Path,
     project_root: &'module Utf8Path,
     line_numbers: &'module LineNumbers,
+    pub ecmascript_version: EcmaScriptVersion,
     function_name: Option<EcoString>,
     function_arguments: Vec<Option<&'module EcoString>>,
     current_scope_vars: im::HashMap<EcoString, usize>,
@@ -97,6 +99,17 @@ pub(crate) struct Generator<'module, 'ast> {
     // at the top level of the function to use in place of pushing new stack
     // frames.
     pub tail_recursion_used: bool,
+    /// When the function is one of a group of mutually recursive functions,
+    /// the group and the index of the function within it. Tail calls to the
+    /// functions of the group continue the loop the group is generated in.
+    mutual_recursion: Option<(&'module MutualRecursion<'ast>, usize)>,
+    // We track whether a tail call to another function of the group is made so
+    // that the module generator knows whether the loop is worth generating.
+    pub mutual_tail_call_used: bool,
+    // We track whether the expression being generated makes a tail call of
+    // either kind, which continues a loop rather than returning, so that a
+    // `switch` arm making one can `break` out of the switch.
+    tail_call_made: bool,
     /// Statements to be compiled when lifting blocks into statement scope.
     /// For example, when compiling the following code:
     /// ```gleam
@@ -129,6 +142,7 @@ impl<'module, 'a> Generator<'module, 'a> {
         src_path: &'module Utf8Path,
         project_root: &'module Utf8Path,
         line_numbers: &'module LineNumbers,
+        ecmascript_version: EcmaScriptVersion,
         function_name: EcoString,
         function_arguments: Vec<Option<&'module EcoString>>,
         tracker: &'module mut UsageTracker,
@@ -151,9 +165,13 @@ impl<'module, 'a> Generator<'module, 'a> {
             src_path,
             project_root,
             line_numbers,
+            ecmascript_version,
             function_name,
             function_arguments,
             tail_recursion_used: false,
+            mutual_recursion: None,
+            mutual_tail_call_used: false,
+            tail_call_made: false,
             current_scope_vars,
             function_position: Position::Tail,
             scope_position: Position::Tail,
@@ -161,6 +179,12 @@ impl<'module, 'a> Generator<'module, 'a> {
         }
     }
 
+    /// Generates the function as the function with the given index of a group
+    /// of mutually recursive functions.
+    pub fn set_mutual_recursion(&mut self, group: &'module MutualRecursion<'a>, index: usize) {
+        self.mutual_recursion = Some((group, index));
+    }
+
     pub fn local_var(&mut self, name: &EcoString) -> EcoString {
         match self.current_scope_vars.get(name) {
             None => {
@@ -211,7 +235,20 @@ impl<'module, 'a> Generator<'module, 'a> {
             Statement::Assignment(assignment) => self.assignment(assignment),
             Statement::Use(_use) => self.expression(&_use.call),
         }?;
-        Ok(self.add_statement_level(expression_doc))
+        let document = self.add_statement_level(expression_doc);
+        Ok(self.record_coverage(statement.location(), document))
+    }
+
+    /// When instrumented for coverage each statement, and each case clause
+    /// body that is a single expression, first records that its line has
+    /// been run.
+    fn record_coverage(&mut self, location: SrcSpan, document: Document<'a>) -> Document<'a> {
+        let Some(coverage_lines) = &mut self.tracker.coverage_lines else {
+            return document;
+        };
+        let line_number = self.line_numbers.line_number(location.start);
+        let _ = coverage_lines.insert(line_number);
+        docvec!["$Coverage[", line_number, "]++;", line(), document].force_break()
     }
 
     fn add_statement_level(&mut self, expression: Document<'a>) -> Document<'a> {
@@ -230,8 +267,8 @@ impl<'module, 'a> Generator<'module, 'a> {
         let document = match expression {
             TypedExpr::String { value, .. } => Ok(string(value)),
 
-            TypedExpr::Int { value, .. } => Ok(int(value)),
-            TypedExpr::Float { value, .. } => Ok(float(value)),
+            TypedExpr::Int { value, .. } => Ok(int(value, self.ecmascript_version)),
+            TypedExpr::Float { value, .. } => Ok(float(value, self.ecmascript_version)),
 
             TypedExpr::List { elements, tail, .. } => {
                 self.not_in_tail_position(Some(Ordering::Strict), |this| match tail {
@@ -610,9 +647,12 @@ impl<'module, 'a> Generator<'module, 'a> {
 
     fn variable(&mut self, name: &'a EcoString, constructor: &'a ValueConstructor) -> Output<'a> {
         match &constructor.variant {
-            ValueConstructorVariant::LocalConstant { literal } => {
-                constant_expression(Context::Function, self.tracker, literal)
-            }
+            ValueConstructorVariant::LocalConstant { literal } => constant_expression(
+                Context::Function,
+                self.tracker,
+                self.ecmascript_version,
+                literal,
+            ),
             ValueConstructorVariant::Record { arity, .. } => {
                 let type_ = constructor.type_.clone();
                 let tracker = &mut self.tracker;
@@ -684,7 +724,8 @@ impl<'module, 'a> Generator<'module, 'a> {
             TypedExpr::Block { statements, .. } => self.statements(statements),
             _ => {
                 let expression_document = self.expression(expression)?;
-                Ok(self.add_statement_level(expression_document))
+                let document = self.add_statement_level(expression_document);
+                Ok(self.record_coverage(expression.location(), document))
             }
         }
     }
@@ -831,6 +872,34 @@ impl<'module, 'a> Generator<'module, 'a> {
             pattern::assign_subjects(self, subject_values)
                 .into_iter()
                 .unzip();
+
+        let doc = match subjects.as_slice() {
+            [subject] if is_literal_dispatch(clauses) => self.case_switch(subject, clauses)?,
+            _ => self.case_if_chain(&subjects, clauses)?,
+        };
+
+        // If there is a subject name given create a variable to hold it for
+        // use in patterns
+        let subject_assignments: Vec<_> = subject_assignments
+            .into_iter()
+            .zip(subject_values)
+            .flat_map(|(assignment_name, value)| assignment_name.map(|name| (name, value)))
+            .map(|(name, value)| {
+                let value = self.not_in_tail_position(Some(Ordering::Strict), |this| {
+                    this.wrap_expression(value)
+                })?;
+                Ok(docvec!["let ", name, " = ", value, ";", line()])
+            })
+            .try_collect()?;
+
+        Ok(docvec![subject_assignments, doc].force_break())
+    }
+
+    fn case_if_chain(
+        &mut self,
+        subjects: &[Document<'a>],
+        clauses: &'a [TypedClause],
+    ) -> Output<'a> {
         let mut generator = pattern::Generator::new(self);
 
         let mut doc = nil();
@@ -854,10 +923,8 @@ impl<'module, 'a> Generator<'module, 'a> {
             for multipatterns in multipatterns {
                 let scope = generator.expression_generator.current_scope_vars.clone();
                 let mut compiled =
-                    generator.generate(&subjects, multipatterns, clause.guard.as_ref())?;
-                let consequence = generator
-                    .expression_generator
-                    .expression_flattening_blocks(&clause.then)?;
+                    generator.generate(subjects, multipatterns, clause.guard.as_ref())?;
+                let consequence = generator.expression_generator.case_consequence(clause)?;
 
                 // We've seen one more clause
                 clause_number += 1;
@@ -914,21 +981,95 @@ impl<'module, 'a> Generator<'module, 'a> {
             }
         }
 
-        // If there is a subject name given create a variable to hold it for
-        // use in patterns
-        let subject_assignments: Vec<_> = subject_assignments
-            .into_iter()
-            .zip(subject_values)
-            .flat_map(|(assignment_name, value)| assignment_name.map(|name| (name, value)))
-            .map(|(name, value)| {
-                let value = self.not_in_tail_position(Some(Ordering::Strict), |this| {
-                    this.wrap_expression(value)
-                })?;
-                Ok(docvec!["let ", name, " = ", value, ";", line()])
-            })
-            .try_collect()?;
+        Ok(doc)
+    }
 
-        Ok(docvec![subject_assignments, doc].force_break())
+    /// Compiles a case that only matches a single subject against literals
+    /// into a `switch`, so the matching arm is found without comparing the
+    /// subject against each literal in turn. The final clause is the catch
+    /// all and becomes the `default` of the switch.
+    fn case_switch(&mut self, subject: &Document<'a>, clauses: &'a [TypedClause]) -> Output<'a> {
+        let mut generator = pattern::Generator::new(self);
+        let ecmascript_version = generator.expression_generator.ecmascript_version;
+        let mut cases = Vec::with_capacity(clauses.len());
+
+        for (index, clause) in clauses.iter().enumerate() {
+            let scope = generator.expression_generator.current_scope_vars.clone();
+            let mut compiled = generator.generate(
+                std::slice::from_ref(subject),
+                &clause.pattern,
+                clause.guard.as_ref(),
+            )?;
+            let tail_call_made = std::mem::take(&mut generator.expression_generator.tail_call_made);
+            let consequence = generator.expression_generator.case_consequence(clause)?;
+            let makes_tail_call = generator.expression_generator.tail_call_made;
+            generator.expression_generator.tail_call_made |= tail_call_made;
+            generator.expression_generator.current_scope_vars = scope;
+
+            let body = if compiled.has_assignments() {
+                let assignments = generator
+                    .expression_generator
+                    .pattern_take_assignments_doc(&mut compiled);
+                docvec![assignments, line(), consequence]
+            } else {
+                consequence
+            };
+
+            let labels = std::iter::once(&clause.pattern)
+                .chain(&clause.alternative_patterns)
+                .flatten()
+                .map(|pattern| match pattern {
+                    Pattern::Int { value, .. } => {
+                        docvec!["case ", int(value, ecmascript_version), ":"]
+                    }
+                    Pattern::Float { value, .. } => {
+                        docvec!["case ", float(value, ecmascript_version), ":"]
+                    }
+                    Pattern::String { value, .. } => docvec!["case ", string(value), ":"],
+                    _ => "default:".to_doc(),
+                });
+
+            // An arm that doesn't return has to `break` so it doesn't fall
+            // through to the next one. In tail position that's only the case
+            // for tail calls, which continue the enclosing loop instead.
+            let falls_through =
+                makes_tail_call || !generator.expression_generator.scope_position.is_tail();
+            let body = if falls_through && index + 1 < clauses.len() {
+                docvec![body, line(), "break;"]
+            } else {
+                body
+            };
+
+            cases.push(docvec![
+                join(labels, line()),
+                " {",
+                docvec![line(), body].nest(INDENT),
+                line(),
+                "}"
+            ]);
+        }
+
+        Ok(docvec![
+            "switch (",
+            subject.clone(),
+            ") {",
+            docvec![line(), join(cases, line())].nest(INDENT),
+            line(),
+            "}"
+        ])
+    }
+
+    fn case_consequence(&mut self, clause: &'a TypedClause) -> Output<'a> {
+        let consequence = self.expression_flattening_blocks(&clause.then)?;
+        // A case in statement position has its value discarded, so each
+        // consequence is a statement needing a semicolon like any other in a
+        // block.
+        Ok(match self.scope_position {
+            Position::NotTail(_) if final_statement_requires_semicolon(&clause.then) => {
+                docvec![consequence, ";"]
+            }
+            Position::Tail | Position::NotTail(_) | Position::Assign(_) => consequence,
+        })
     }
 
     fn assignment_no_match(
@@ -971,6 +1112,13 @@ impl<'module, 'a> Generator<'module, 'a> {
         fun: &'a TypedExpr,
         arguments: Vec<Document<'a>>,
     ) -> Output<'a> {
+        // Tail calls between mutually recursive functions are compiled to an
+        // assignment of the called function's arguments, continuing the loop
+        // the functions are generated in.
+        if let Some((group, index)) = self.mutual_tail_call_target(fun) {
+            return Ok(self.mutual_tail_call(group, index, arguments));
+        }
+
         match fun {
             // Qualified record construction
             TypedExpr::ModuleSelect {
@@ -1012,6 +1160,7 @@ impl<'module, 'a> Generator<'module, 'a> {
                 // Record that tail recursion is happening so that we know to
                 // render the loop at the top level of the function.
                 self.tail_recursion_used = true;
+                self.tail_call_made = true;
 
                 for (i, (element, argument)) in arguments
                     .into_iter()
@@ -1052,6 +1201,52 @@ impl<'module, 'a> Generator<'module, 'a> {
         }
     }
 
+    fn mutual_tail_call_target(
+        &self,
+        fun: &TypedExpr,
+    ) -> Option<(&'module MutualRecursion<'a>, usize)> {
+        let (group, _) = self.mutual_recursion?;
+        match fun {
+            TypedExpr::Var {
+                constructor:
+                    ValueConstructor {
+                        variant: ValueConstructorVariant::ModuleFn { module, name, .. },
+                        ..
+                    },
+                ..
+            } if self.function_position.is_tail() && *module == self.module_name => {
+                Some((group, group.index_of(name)?))
+            }
+            _ => None,
+        }
+    }
+
+    fn mutual_tail_call(
+        &mut self,
+        group: &MutualRecursion<'a>,
+        index: usize,
+        arguments: Vec<Document<'a>>,
+    ) -> Document<'a> {
+        let mut docs = Vec::with_capacity(arguments.len() + 1);
+        self.tail_call_made = true;
+        if self
+            .mutual_recursion
+            .is_some_and(|(_, current)| current != index)
+        {
+            self.mutual_tail_call_used = true;
+            docs.push(docvec!["loop$function = ", index, ";"]);
+        }
+        for (element, argument) in arguments.into_iter().zip(&group.function(index).arguments) {
+            // Values given for discarded arguments are still rendered as they
+            // may have side effects.
+            docs.push(match argument.get_variable_name() {
+                Some(name) => docvec![loop_variable(group, index, name), " = ", element, ";"],
+                None => docvec![element, ";"],
+            });
+        }
+        join(docs, line())
+    }
+
     fn fn_(&mut self, arguments: &'a [TypedArg], body: &'a [TypedStatement]) -> Output<'a> {
         // New function, this is now the tail position
         let function_position = std::mem::replace(&mut self.function_position, Position::Tail);
@@ -1067,6 +1262,7 @@ impl<'module, 'a> Generator<'module, 'a> {
         // mistakenly trigger tail call optimisation
         let mut name = None;
         std::mem::swap(&mut self.function_name, &mut name);
+        let mutual_recursion = self.mutual_recursion.take();
 
         // Generate the function body
         let result = self.statements(body);
@@ -1076,6 +1272,7 @@ impl<'module, 'a> Generator<'module, 'a> {
         self.scope_position = scope_position;
         self.current_scope_vars = scope;
         std::mem::swap(&mut self.function_name, &mut name);
+        self.mutual_recursion = mutual_recursion;
 
         Ok(docvec![
             docvec![
@@ -1420,7 +1617,47 @@ impl<'module, 'a> Generator<'module, 'a> {
     }
 }
 
-pub fn int(value: &str) -> Document<'_> {
+/// A `case` with at least this many literal patterns is compiled to a `switch`
+/// rather than a chain of `if` statements.
+const SWITCH_MINIMUM_LITERAL_PATTERNS: usize = 16;
+
+/// Whether a case can be compiled to a `switch`: it has a single subject, it
+/// has enough literal patterns to be worth it, and every clause but the last
+/// only matches literals without a guard, while the last one matches anything.
+fn is_literal_dispatch(clauses: &[TypedClause]) -> bool {
+    let Some((catch_all, literal_clauses)) = clauses.split_last() else {
+        return false;
+    };
+    let is_literal = |pattern: &TypedPattern| {
+        matches!(
+            pattern,
+            Pattern::Int { .. } | Pattern::Float { .. } | Pattern::String { .. }
+        )
+    };
+    let is_catch_all = matches!(
+        catch_all.pattern.as_slice(),
+        [Pattern::Discard { .. } | Pattern::Variable { .. }]
+    ) && catch_all.alternative_patterns.is_empty()
+        && catch_all.guard.is_none();
+
+    let mut literal_patterns = 0;
+    for clause in literal_clauses {
+        if clause.guard.is_some() {
+            return false;
+        }
+        for multipattern in std::iter::once(&clause.pattern).chain(&clause.alternative_patterns) {
+            match multipattern.as_slice() {
+                [pattern] if is_literal(pattern) => literal_patterns += 1,
+                _ => return false,
+            }
+        }
+    }
+    is_catch_all && literal_patterns >= SWITCH_MINIMUM_LITERAL_PATTERNS
+}
+
+pub fn int(value: &str, ecmascript_version: EcmaScriptVersion) -> Document<'_> {
+    let value = numeric_separators_for(value, ecmascript_version);
+    let value = value.as_str();
     let mut out = EcoString::with_capacity(value.len());
 
     if value.starts_with('-') {
@@ -1452,7 +1689,13 @@ pub fn int(value: &str) -> Document<'_> {
     out.to_doc()
 }
 
-pub fn float(value: &str) -> Document<'_> {
+/// Float literals are emitted as written in the source, only normalising the
+/// leading zeros, so JavaScript parses them to the same double the Erlang
+/// target does. Printing a float is left to the runtime, which uses the
+/// shortest representation that round-trips.
+pub fn float(value: &str, ecmascript_version: EcmaScriptVersion) -> Document<'_> {
+    let value = numeric_separators_for(value, ecmascript_version);
+    let value = value.as_str();
     let mut out = EcoString::with_capacity(value.len());
 
     if value.starts_with('-') {
@@ -1471,25 +1714,32 @@ pub fn float(value: &str) -> Document<'_> {
     out.to_doc()
 }
 
+/// Numeric separators, such as in `1_000`, are kept as written unless the
+/// version of ECMAScript being targeted doesn't support them.
+fn numeric_separators_for(value: &str, ecmascript_version: EcmaScriptVersion) -> EcoString {
+    if ecmascript_version.supports_numeric_separators() {
+        value.into()
+    } else {
+        value.replace('_', "").into()
+    }
+}
+
 pub(crate) fn guard_constant_expression<'a>(
     assignments: &mut Vec<Assignment<'a>>,
     tracker: &mut UsageTracker,
+    ecmascript_version: EcmaScriptVersion,
     expression: &'a TypedConstant,
 ) -> Output<'a> {
     match expression {
-        Constant::Tuple { elements, .. } => array(
-            elements
-                .iter()
-                .map(|element| guard_constant_expression(assignments, tracker, element)),
-        ),
+        Constant::Tuple { elements, .. } => array(elements.iter().map(|element| {
+            guard_constant_expression(assignments, tracker, ecmascript_version, element)
+        })),
 
         Constant::List { elements, .. } => {
             tracker.list_used = true;
-            list(
-                elements
-                    .iter()
-                    .map(|element| guard_constant_expression(assignments, tracker, element)),
-            )
+            list(elements.iter().map(|element| {
+                guard_constant_expression(assignments, tracker, ecmascript_version, element)
+            }))
         }
         Constant::Record { type_, name, .. } if type_.is_bool() && name == "True" => {
             Ok("true".to_doc())
@@ -1533,7 +1783,9 @@ pub(crate) fn guard_constant_expression<'a>(
 
             let field_values: Vec<_> = args
                 .iter()
-                .map(|arg| guard_constant_expression(assignments, tracker, &arg.value))
+                .map(|arg| {
+                    guard_constant_expression(assignments, tracker, ecmascript_version, &arg.value)
+                })
                 .try_collect()?;
             Ok(construct_record(
                 module.as_ref().map(|(module, _)| module.as_str()),
@@ -1543,7 +1795,7 @@ pub(crate) fn guard_constant_expression<'a>(
         }
 
         Constant::BitArray { segments, .. } => bit_array(tracker, segments, |tracker, constant| {
-            guard_constant_expression(assignments, tracker, constant)
+            guard_constant_expression(assignments, tracker, ecmascript_version, constant)
         }),
 
         Constant::Var { name, .. } => Ok(assignments
@@ -1552,7 +1804,9 @@ pub(crate) fn guard_constant_expression<'a>(
             .map(|assignment| assignment.subject.clone())
             .unwrap_or_else(|| maybe_escape_identifier(name).to_doc())),
 
-        expression => constant_expression(Context::Function, tracker, expression),
+        expression => {
+            constant_expression(Context::Function, tracker, ecmascript_version, expression)
+        }
     }
 }
 
@@ -1563,36 +1817,61 @@ pub(crate) fn guard_constant_expression<'a>(
 /// Based on the context we might want to annotate pure function calls as
 /// "@__PURE__".
 ///
-pub enum Context {
-    Constant,
+pub enum Context<'a> {
+    Constant {
+        shadowed_imports: &'a HashMap<EcoString, ShadowedImport>,
+    },
     Function,
 }
 
+impl Context<'_> {
+    /// The reference to use for a value imported unqualified, if it is
+    /// shadowed by a definition in the module.
+    fn shadowed_import(
+        &self,
+        name: &str,
+        constructor: Option<&ValueConstructor>,
+    ) -> Option<EcoString> {
+        let Context::Constant { shadowed_imports } = self else {
+            return None;
+        };
+        let import = shadowed_imports.get(name)?;
+        match &constructor?.variant {
+            ValueConstructorVariant::ModuleFn { module, .. }
+            | ValueConstructorVariant::ModuleConstant { module, .. }
+                if *module == import.module =>
+            {
+                Some(import.reference.clone())
+            }
+            _ => None,
+        }
+    }
+}
+
 pub(crate) fn constant_expression<'a>(
-    context: Context,
+    context: Context<'_>,
     tracker: &mut UsageTracker,
+    ecmascript_version: EcmaScriptVersion,
     expression: &'a TypedConstant,
 ) -> Output<'a> {
     match expression {
-        Constant::Int { value, .. } => Ok(int(value)),
-        Constant::Float { value, .. } => Ok(float(value)),
+        Constant::Int { value, .. } => Ok(int(value, ecmascript_version)),
+        Constant::Float { value, .. } => Ok(float(value, ecmascript_version)),
         Constant::String { value, .. } => Ok(string(value)),
         Constant::Tuple { elements, .. } => array(
             elements
                 .iter()
-                .map(|element| constant_expression(context, tracker, element)),
+                .map(|element| constant_expression(context, tracker, ecmascript_version, element)),
         ),
 
         Constant::List { elements, .. } => {
             tracker.list_used = true;
-            let list = list(
-                elements
-                    .iter()
-                    .map(|element| constant_expression(context, tracker, element)),
-            )?;
+            let list = list(elements.iter().map(|element| {
+                constant_expression(context, tracker, ecmascript_version, element)
+            }))?;
 
             match context {
-                Context::Constant => Ok(docvec!["/* @__PURE__ */ ", list]),
+                Context::Constant { .. } => Ok(docvec!["/* @__PURE__ */ ", list]),
                 Context::Function => Ok(list),
             }
         }
@@ -1639,7 +1918,7 @@ pub(crate) fn constant_expression<'a>(
 
             let field_values: Vec<_> = args
                 .iter()
-                .map(|arg| constant_expression(context, tracker, &arg.value))
+                .map(|arg| constant_expression(context, tracker, ecmascript_version, &arg.value))
                 .try_collect()?;
 
             let constructor = construct_record(
@@ -1648,24 +1927,32 @@ pub(crate) fn constant_expression<'a>(
                 field_values,
             );
             match context {
-                Context::Constant => Ok(docvec!["/* @__PURE__ */ ", constructor]),
+                Context::Constant { .. } => Ok(docvec!["/* @__PURE__ */ ", constructor]),
                 Context::Function => Ok(constructor),
             }
         }
 
         Constant::BitArray { segments, .. } => {
             let bit_array = bit_array(tracker, segments, |tracker, expr| {
-                constant_expression(context, tracker, expr)
+                constant_expression(context, tracker, ecmascript_version, expr)
             })?;
             match context {
-                Context::Constant => Ok(docvec!["/* @__PURE__ */ ", bit_array]),
+                Context::Constant { .. } => Ok(docvec!["/* @__PURE__ */ ", bit_array]),
                 Context::Function => Ok(bit_array),
             }
         }
 
-        Constant::Var { name, module, .. } => Ok({
+        Constant::Var {
+            name,
+            module,
+            constructor,
+            ..
+        } => Ok({
             match module {
-                None => maybe_escape_identifier(name).to_doc(),
+                None => match context.shadowed_import(name, constructor.as_deref()) {
+                    Some(reference) => reference.to_doc(),
+                    None => maybe_escape_identifier(name).to_doc(),
+                },
                 Some((module, _)) => {
                     // JS keywords can be accessed here, but we must escape anyway
                     // as we escape when exporting such names in the first place,
@@ -1676,8 +1963,8 @@ pub(crate) fn constant_expression<'a>(
         }),
 
         Constant::StringConcatenation { left, right, .. } => {
-            let left = constant_expression(context, tracker, left)?;
-            let right = constant_expression(context, tracker, right)?;
+            let left = constant_expression(context, tracker, ecmascript_version, left)?;
+            let right = constant_expression(context, tracker, ecmascript_version, right)?;
             Ok(docvec![left, " + ", right])
         }
 
@@ -1980,40 +2267,51 @@ pub fn is_js_scalar(t: Arc<Type>) -> bool {
 
 fn requires_semicolon(statement: &TypedStatement) -> bool {
     match statement {
-        Statement::Expression(
-            TypedExpr::Int { .. }
-            | TypedExpr::Fn { .. }
-            | TypedExpr::Var { .. }
-            | TypedExpr::List { .. }
-            | TypedExpr::Call { .. }
-            | TypedExpr::Echo { .. }
-            | TypedExpr::Float { .. }
-            | TypedExpr::String { .. }
-            | TypedExpr::BinOp { .. }
-            | TypedExpr::Tuple { .. }
-            | TypedExpr::NegateInt { .. }
-            | TypedExpr::BitArray { .. }
-            | TypedExpr::TupleIndex { .. }
-            | TypedExpr::NegateBool { .. }
-            | TypedExpr::RecordAccess { .. }
-            | TypedExpr::ModuleSelect { .. }
-            | TypedExpr::Block { .. },
-        ) => true,
-
-        Statement::Expression(
-            TypedExpr::Todo { .. }
-            | TypedExpr::Case { .. }
-            | TypedExpr::Panic { .. }
-            | TypedExpr::Pipeline { .. }
-            | TypedExpr::RecordUpdate { .. }
-            | TypedExpr::Invalid { .. },
-        ) => false,
-
+        Statement::Expression(expression) => expression_requires_semicolon(expression),
         Statement::Assignment(_) => false,
         Statement::Use(_) => false,
     }
 }
 
+fn expression_requires_semicolon(expression: &TypedExpr) -> bool {
+    match expression {
+        TypedExpr::Int { .. }
+        | TypedExpr::Fn { .. }
+        | TypedExpr::Var { .. }
+        | TypedExpr::List { .. }
+        | TypedExpr::Call { .. }
+        | TypedExpr::Echo { .. }
+        | TypedExpr::Float { .. }
+        | TypedExpr::String { .. }
+        | TypedExpr::BinOp { .. }
+        | TypedExpr::Tuple { .. }
+        | TypedExpr::NegateInt { .. }
+        | TypedExpr::BitArray { .. }
+        | TypedExpr::TupleIndex { .. }
+        | TypedExpr::NegateBool { .. }
+        | TypedExpr::RecordAccess { .. }
+        | TypedExpr::ModuleSelect { .. }
+        | TypedExpr::Block { .. } => true,
+
+        TypedExpr::Todo { .. }
+        | TypedExpr::Case { .. }
+        | TypedExpr::Panic { .. }
+        | TypedExpr::Pipeline { .. }
+        | TypedExpr::RecordUpdate { .. }
+        | TypedExpr::Invalid { .. } => false,
+    }
+}
+
+/// Whether the last statement run when evaluating an expression needs a
+/// semicolon if its value is discarded, looking inside blocks as they are
+/// flattened into the surrounding statements.
+fn final_statement_requires_semicolon(expression: &TypedExpr) -> bool {
+    match expression {
+        TypedExpr::Block { statements, .. } => requires_semicolon(statements.last()),
+        _ => expression_requires_semicolon(expression),
+    }
+}
+
 /// Wrap a document in an immediately invoked function expression
 fn immediately_invoked_function_expression_document(document: Document<'_>) -> Document<'_> {
     docvec![
@@ -0,0 +1,210 @@
+//! JavaScript engines don't eliminate tail calls, so functions that call each
+//! other in tail position, such as `is_even` calling `is_odd` and `is_odd`
+//! calling `is_even`, would run out of stack given a large enough input.
+//!
+//! To avoid this the bodies of a group of mutually recursive functions are
+//! generated together inside a single loop, named after the first function of
+//! the group, with each function becoming a wrapper that starts the loop at its
+//! own body:
+//!
+//! ```javascript
+//! export function is_odd(n) {
+//!   return loop$is_odd(0, n);
+//! }
+//!
+//! function loop$is_odd(loop$function, loop$is_odd$n, loop$is_even$n) {
+//!   while (true) {
+//!     if (loop$function === 0) {
+//!       let n = loop$is_odd$n;
+//!       ...
+//!     } else {
+//!       let n = loop$is_even$n;
+//!       ...
+//!     }
+//!   }
+//! }
+//!
+//! export function is_even(n) {
+//!   return loop$is_odd(1, undefined, n);
+//! }
+//! ```
+//!
+//! A tail call to another function of the group then assigns its arguments and
+//! which body to run next, rather than pushing a new stack frame.
+
+use std::{collections::HashMap, sync::Arc};
+
+use ecow::{EcoString, eco_format};
+use itertools::Itertools;
+use petgraph::Graph;
+use vec1::Vec1;
+
+use crate::{
+    ast::{
+        Definition, FunctionLiteralKind, SrcSpan, TypeAst, TypedArg, TypedExpr, TypedFunction,
+        TypedModule, TypedStatement,
+        visit::{self, Visit},
+    },
+    build::Target,
+    type_::{Type, TypedCallArg, ValueConstructor, ValueConstructorVariant},
+};
+
+/// Module functions that call each other, directly or indirectly. The
+/// functions are in the order they appear in the typed module's definitions,
+/// which is the order they were type checked in rather than the order they
+/// were written in.
+#[derive(Debug, Clone)]
+pub(crate) struct MutualRecursion<'a> {
+    pub functions: Vec<&'a TypedFunction>,
+}
+
+impl<'a> MutualRecursion<'a> {
+    /// The index of the function with the given name within the group.
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.functions
+            .iter()
+            .position(|function| function_name(function) == name)
+    }
+
+    pub fn function(&self, index: usize) -> &'a TypedFunction {
+        self.functions
+            .get(index)
+            .expect("Index of a function in a mutually recursive group")
+    }
+
+    pub fn name_of(&self, index: usize) -> &'a EcoString {
+        function_name(self.function(index))
+    }
+
+    /// The name of the function holding the loop the bodies of the functions
+    /// are generated in.
+    pub fn loop_name(&self) -> EcoString {
+        eco_format!("loop${}", self.name_of(0))
+    }
+}
+
+/// The name of the loop variable holding the given argument of the function
+/// with the given index within a group.
+pub(crate) fn loop_variable(
+    group: &MutualRecursion<'_>,
+    index: usize,
+    argument: &str,
+) -> EcoString {
+    eco_format!("loop${}${argument}", group.name_of(index))
+}
+
+/// Finds the groups of mutually recursive functions in a module, in the order
+/// their first function appears in the typed module's definitions. As type
+/// checking reorders functions this need not be the order they were written
+/// in. Only functions that are generated as JavaScript functions can be part
+/// of a group.
+pub(crate) fn groups(module: &TypedModule) -> Vec<MutualRecursion<'_>> {
+    let functions: Vec<_> = module
+        .definitions
+        .iter()
+        .filter_map(|definition| match definition {
+            Definition::Function(function) => Some(function),
+            _ => None,
+        })
+        .filter(|function| {
+            function.external_javascript.is_none()
+                && function.implementations.supports(Target::JavaScript)
+        })
+        .collect();
+
+    // Nodes are added in the same order as the functions, so the index of a
+    // node is the index of its function.
+    let mut graph = Graph::<(), ()>::with_capacity(functions.len(), functions.len());
+    let nodes: HashMap<_, _> = functions
+        .iter()
+        .map(|function| (function_name(function), graph.add_node(())))
+        .collect();
+
+    for function in &functions {
+        let mut calls = CalledFunctions {
+            module: &module.name,
+            names: vec![],
+        };
+        calls.visit_typed_function(function);
+        let Some(&from) = nodes.get(function_name(function)) else {
+            continue;
+        };
+        for &to in calls.names.iter().filter_map(|name| nodes.get(name)) {
+            let _ = graph.update_edge(from, to, ());
+        }
+    }
+
+    let mut groups: Vec<Vec<usize>> = petgraph::algo::tarjan_scc(&graph)
+        .into_iter()
+        .filter(|component| component.len() > 1)
+        .map(|component| {
+            component
+                .into_iter()
+                .map(|node| node.index())
+                .sorted()
+                .collect()
+        })
+        .collect();
+    groups.sort_unstable_by_key(|indexes| indexes.first().copied());
+
+    groups
+        .into_iter()
+        .map(|indexes| MutualRecursion {
+            functions: indexes
+                .into_iter()
+                .filter_map(|index| functions.get(index).copied())
+                .collect(),
+        })
+        .collect()
+}
+
+fn function_name(function: &TypedFunction) -> &EcoString {
+    let (_, name) = function
+        .name
+        .as_ref()
+        .expect("A module's function must be named");
+    name
+}
+
+/// Collects the names of the module's functions that a function calls.
+/// Calls made within anonymous functions are not included, as those are not
+/// in tail position of the function itself.
+struct CalledFunctions<'a> {
+    module: &'a EcoString,
+    names: Vec<&'a EcoString>,
+}
+
+impl<'a> Visit<'a> for CalledFunctions<'a> {
+    fn visit_typed_expr_call(
+        &mut self,
+        location: &'a SrcSpan,
+        type_: &'a Arc<Type>,
+        fun: &'a TypedExpr,
+        args: &'a [TypedCallArg],
+    ) {
+        if let TypedExpr::Var {
+            constructor:
+                ValueConstructor {
+                    variant: ValueConstructorVariant::ModuleFn { module, name, .. },
+                    ..
+                },
+            ..
+        } = fun
+            && module == self.module
+        {
+            self.names.push(name);
+        }
+        visit::visit_typed_expr_call(self, location, type_, fun, args);
+    }
+
+    fn visit_typed_expr_fn(
+        &mut self,
+        _location: &'a SrcSpan,
+        _type_: &'a Arc<Type>,
+        _kind: &'a FunctionLiteralKind,
+        _args: &'a [TypedArg],
+        _body: &'a Vec1<TypedStatement>,
+        _return_annotation: &'a Option<TypeAst>,
+    ) {
+    }
+}
@@ -517,6 +517,7 @@ impl<'module_ctx, 'expression_gen, 'a> Generator<'module_ctx, 'expression_gen, '
                 return expression::guard_constant_expression(
                     &mut self.assignments,
                     self.expression_generator.tracker,
+                    self.expression_generator.ecmascript_version,
                     constant,
                 );
             }
@@ -545,11 +546,17 @@ impl<'module_ctx, 'expression_gen, 'a> Generator<'module_ctx, 'expression_gen, '
                 Ok(())
             }
             Pattern::Int { value, .. } => {
-                self.push_equality_check(subject.clone(), expression::int(value));
+                self.push_equality_check(
+                    subject.clone(),
+                    expression::int(value, self.expression_generator.ecmascript_version),
+                );
                 Ok(())
             }
             Pattern::Float { value, .. } => {
-                self.push_equality_check(subject.clone(), expression::float(value));
+                self.push_equality_check(
+                    subject.clone(),
+                    expression::float(value, self.expression_generator.ecmascript_version),
+                );
                 Ok(())
             }
 
@@ -1,6 +1,7 @@
 use crate::{
     analyse::TargetSupport,
-    build::{Origin, Target},
+    build::{EcmaScriptVersion, Origin, Target},
+    codegen::DEFAULT_LINE_LENGTH,
     config::PackageConfig,
     javascript::*,
     uid::UniqueIdGenerator,
@@ -15,6 +16,7 @@ mod bools;
 mod case;
 mod case_clause_guards;
 mod consts;
+mod coverage;
 mod custom_types;
 mod echo;
 mod externals;
@@ -190,6 +192,42 @@ pub fn compile(src: &str, deps: Vec<(&str, &str, &str)>) -> TypedModule {
 }
 
 pub fn compile_js(src: &str, deps: Vec<(&str, &str, &str)>) -> Result<String, crate::Error> {
+    compile_js_for_ecmascript_version(src, deps, EcmaScriptVersion::default())
+}
+
+pub fn compile_js_for_ecmascript_version(
+    src: &str,
+    deps: Vec<(&str, &str, &str)>,
+    ecmascript_version: EcmaScriptVersion,
+) -> Result<String, crate::Error> {
+    compile_js_with(src, deps, ecmascript_version, DEFAULT_LINE_LENGTH, false)
+}
+
+pub fn compile_js_with_line_length(
+    src: &str,
+    deps: Vec<(&str, &str, &str)>,
+    line_length: isize,
+) -> Result<String, crate::Error> {
+    compile_js_with(src, deps, EcmaScriptVersion::default(), line_length, false)
+}
+
+pub fn compile_js_with_coverage(src: &str) -> Result<String, crate::Error> {
+    compile_js_with(
+        src,
+        vec![],
+        EcmaScriptVersion::default(),
+        DEFAULT_LINE_LENGTH,
+        true,
+    )
+}
+
+fn compile_js_with(
+    src: &str,
+    deps: Vec<(&str, &str, &str)>,
+    ecmascript_version: EcmaScriptVersion,
+    line_length: isize,
+    coverage: bool,
+) -> Result<String, crate::Error> {
     let ast = compile(src, deps);
     let line_numbers = LineNumbers::new(src);
     let stdlib_package = StdlibPackage::Present;
@@ -200,6 +238,9 @@ pub fn compile_js(src: &str, deps: Vec<(&str, &str, &str)>) -> Result<String, cr
         target_support: TargetSupport::Enforced,
         typescript: TypeScriptDeclarations::None,
         stdlib_package,
+        ecmascript_version,
+        line_length,
+        coverage,
         path: Utf8Path::new("src/module.gleam"),
         project_root: "project/root".into(),
     })?;
@@ -249,3 +249,17 @@ pub fn expect(value, message) {
 "#
     );
 }
+
+#[test]
+fn result_argument() {
+    // The value is only bound if it matches, otherwise an error with the
+    // value and location of the assertion is thrown.
+    assert_js!(
+        r#"
+pub fn unwrap(result: Result(Int, Nil)) -> Int {
+  let assert Ok(x) = result
+  x
+}
+"#
+    );
+}
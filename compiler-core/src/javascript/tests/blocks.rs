@@ -277,3 +277,29 @@ pub fn main() {
 "
     )
 }
+
+#[test]
+fn discarded_expressions_are_not_returned() {
+    assert_js!(
+        r#"
+fn log(x) {
+  x
+}
+
+fn go(a) {
+  log("start")
+  a == "ok"
+  Nil
+  {
+    log("nested")
+    a
+  }
+  case a {
+    "ok" -> log("ok")
+    _ -> log("error")
+  }
+  log("end")
+}
+"#,
+    );
+}
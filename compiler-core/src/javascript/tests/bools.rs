@@ -47,6 +47,34 @@ fn go() {
     );
 }
 
+#[test]
+fn operators_with_block_on_the_right() {
+    assert_js!(
+        r#"
+fn go(x, f) {
+  x && {
+    let assert True = f()
+    True
+  }
+}
+"#,
+    );
+}
+
+#[test]
+fn operators_with_case_on_the_right() {
+    assert_js!(
+        r#"
+fn go(x, f) {
+  x || case f() {
+    True -> False
+    False -> True
+  }
+}
+"#,
+    );
+}
+
 #[test]
 fn assigning() {
     assert_js!(
@@ -336,3 +336,267 @@ fn go(x) {
 "
     );
 }
+
+#[test]
+fn tuple_of_result_and_list_pattern() {
+    assert_js!(
+        r#"
+pub fn go(x: #(Result(Int, Nil), List(String))) {
+  case x {
+    #(Ok(x), [first, ..]) -> #(x, first)
+    #(Ok(x), []) -> #(x, "empty")
+    #(Error(_), _) -> #(0, "error")
+  }
+}
+"#
+    )
+}
+
+#[test]
+fn nested_tuple_list_string_and_constructor_pattern() {
+    assert_js!(
+        r#"
+pub fn go(x) {
+  case x {
+    #(Ok(#("a" <> rest, [Ok(n), ..])), _) -> rest <> ":" <> n
+    #(Ok(#(_, [Error(Nil)])), [first, ..]) -> first
+    _ -> "no match"
+  }
+}
+"#
+    )
+}
+
+#[test]
+fn case_on_bool() {
+    assert_js!(
+        r#"
+pub fn main(x) {
+  case x {
+    True -> "yes"
+    False -> "no"
+  }
+}
+"#
+    )
+}
+
+#[test]
+fn case_on_bool_with_false_first() {
+    assert_js!(
+        r#"
+pub fn main(x) {
+  case x {
+    False -> "no"
+    True -> "yes"
+  }
+}
+"#
+    )
+}
+
+#[test]
+fn many_literal_clauses_compile_to_a_switch() {
+    assert_js!(
+        r#"
+pub fn name(n) {
+  case n {
+    1 -> "one"
+    2 -> "two"
+    3 -> "three"
+    4 -> "four"
+    5 -> "five"
+    6 -> "six"
+    7 -> "seven"
+    8 -> "eight"
+    9 -> "nine"
+    10 -> "ten"
+    11 -> "eleven"
+    12 -> "twelve"
+    13 -> "thirteen"
+    14 -> "fourteen"
+    15 -> "fifteen"
+    16 -> "sixteen"
+    17 -> "seventeen"
+    18 -> "eighteen"
+    19 -> "nineteen"
+    20 -> "twenty"
+    21 -> "twenty one"
+    22 -> "twenty two"
+    23 -> "twenty three"
+    24 -> "twenty four"
+    25 -> "twenty five"
+    26 -> "twenty six"
+    27 -> "twenty seven"
+    28 -> "twenty eight"
+    29 -> "twenty nine"
+    30 -> "thirty"
+    _ -> "many"
+  }
+}
+"#
+    )
+}
+
+#[test]
+fn many_string_clauses_with_alternatives_compile_to_a_switch() {
+    assert_js!(
+        r#"
+pub fn code(colour) {
+  let code = case colour {
+    "red" | "RED" -> 0
+    "orange" | "ORANGE" -> 1
+    "yellow" | "YELLOW" -> 2
+    "green" | "GREEN" -> 3
+    "blue" | "BLUE" -> 4
+    "indigo" | "INDIGO" -> 5
+    "violet" | "VIOLET" -> 6
+    "black" | "BLACK" -> 7
+    "white" | "WHITE" -> 8
+    "grey" | "GREY" -> 9
+    "pink" | "PINK" -> 10
+    "brown" | "BROWN" -> 11
+    "cyan" | "CYAN" -> 12
+    "magenta" | "MAGENTA" -> 13
+    "teal" | "TEAL" -> 14
+    "navy" | "NAVY" -> 15
+    "olive" | "OLIVE" -> 16
+    "maroon" | "MAROON" -> 17
+    other -> string_length(other)
+  }
+  code + 1
+}
+
+@external(javascript, "./ffi.mjs", "length")
+fn string_length(string: String) -> Int
+"#
+    )
+}
+
+#[test]
+fn tail_calls_in_a_switch_break_out_of_it() {
+    assert_js!(
+        r#"
+pub fn count(n, acc) {
+  case n {
+    0 -> acc
+    1 -> count(n - 1, acc + 1)
+    2 -> count(n - 1, acc + 2)
+    3 -> count(n - 1, acc + 3)
+    4 -> count(n - 1, acc + 4)
+    5 -> count(n - 1, acc + 5)
+    6 -> count(n - 1, acc + 6)
+    7 -> count(n - 1, acc + 7)
+    8 -> count(n - 1, acc + 8)
+    9 -> count(n - 1, acc + 9)
+    10 -> count(n - 1, acc + 10)
+    11 -> count(n - 1, acc + 11)
+    12 -> count(n - 1, acc + 12)
+    13 -> count(n - 1, acc + 13)
+    14 -> count(n - 1, acc + 14)
+    15 -> count(n - 1, acc + 15)
+    16 -> count(n - 1, acc + 16)
+    _ -> count(n - 1, acc)
+  }
+}
+"#
+    )
+}
+
+#[test]
+fn mutual_tail_calls_in_a_switch_break_out_of_it() {
+    assert_js!(
+        r#"
+pub fn f(n) {
+  case n {
+    0 -> "zero"
+    1 -> g(100)
+    2 -> "two"
+    3 -> "three"
+    4 -> "four"
+    5 -> "five"
+    6 -> "six"
+    7 -> "seven"
+    8 -> "eight"
+    9 -> "nine"
+    10 -> "ten"
+    11 -> "eleven"
+    12 -> "twelve"
+    13 -> "thirteen"
+    14 -> f(n - 1)
+    15 -> "fifteen"
+    16 -> "sixteen"
+    _ -> g(-1)
+  }
+}
+
+pub fn g(n) {
+  case n {
+    100 -> "hundred"
+    _ -> case n < 0 {
+      True -> "default arm"
+      False -> f(n)
+    }
+  }
+}
+"#
+    )
+}
+
+#[test]
+fn few_literal_clauses_do_not_compile_to_a_switch() {
+    assert_js!(
+        r#"
+pub fn name(n) {
+  case n {
+    1 -> "one"
+    2 -> "two"
+    _ -> "many"
+  }
+}
+"#
+    )
+}
+
+#[test]
+fn many_literal_clauses_with_a_guard_do_not_compile_to_a_switch() {
+    assert_js!(
+        r#"
+pub fn name(n, x) {
+  case n {
+    1 if x -> "one"
+    2 -> "two"
+    3 -> "three"
+    4 -> "four"
+    5 -> "five"
+    6 -> "six"
+    7 -> "seven"
+    8 -> "eight"
+    9 -> "nine"
+    10 -> "ten"
+    11 -> "eleven"
+    12 -> "twelve"
+    13 -> "thirteen"
+    14 -> "fourteen"
+    15 -> "fifteen"
+    16 -> "sixteen"
+    17 -> "seventeen"
+    18 -> "eighteen"
+    19 -> "nineteen"
+    20 -> "twenty"
+    21 -> "twenty one"
+    22 -> "twenty two"
+    23 -> "twenty three"
+    24 -> "twenty four"
+    25 -> "twenty five"
+    26 -> "twenty six"
+    27 -> "twenty seven"
+    28 -> "twenty eight"
+    29 -> "twenty nine"
+    30 -> "thirty"
+    _ -> "many"
+  }
+}
+"#
+    )
+}
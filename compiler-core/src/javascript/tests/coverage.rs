@@ -0,0 +1,89 @@
+use crate::javascript::tests::compile_js_with_coverage;
+
+macro_rules! assert_js_with_coverage {
+    ($src:expr $(,)?) => {{
+        let output = compile_js_with_coverage($src).expect("compilation failed");
+        insta::assert_snapshot!(
+            insta::internals::AutoName,
+            format!(
+                "----- SOURCE CODE\n{}\n\n----- COMPILED JAVASCRIPT\n{output}",
+                $src
+            ),
+            $src
+        );
+    }};
+}
+
+#[test]
+fn each_statement_records_its_line() {
+    assert_js_with_coverage!(
+        r#"
+pub fn main(x) {
+  let y = x + 1
+  y * 2
+}
+"#
+    );
+}
+
+#[test]
+fn statements_in_case_clauses_record_their_lines() {
+    assert_js_with_coverage!(
+        r#"
+pub fn classify(n) {
+  case n > 0 {
+    True -> {
+      let word = "positive"
+      word
+    }
+    False -> "not positive"
+  }
+}
+"#
+    );
+}
+
+#[test]
+fn single_expression_bodies_record_their_lines() {
+    assert_js_with_coverage!(
+        r#"
+pub fn double(n) { n * 2 }
+
+pub fn sign(n) {
+  case n > 0 {
+    True -> 1
+    False ->
+      -1
+  }
+}
+"#
+    );
+}
+
+#[test]
+fn anonymous_functions_record_their_lines() {
+    assert_js_with_coverage!(
+        r#"
+pub fn main() {
+  let add = fn(a, b) {
+    let sum = a + b
+    sum
+  }
+  add(1, 2)
+}
+"#
+    );
+}
+
+#[test]
+fn modules_without_functions_are_not_instrumented() {
+    assert_js_with_coverage!(
+        r#"
+pub type Wibble {
+  Wibble
+}
+
+pub const wobble = 1
+"#
+    );
+}
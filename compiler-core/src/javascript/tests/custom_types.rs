@@ -688,3 +688,22 @@ pub fn main() {
 "#
     );
 }
+
+// Updating a record calls its constructor directly, reusing the unchanged
+// fields of the original, rather than spreading it into a plain object. A
+// plain object would no longer be an instance of the record's class, which
+// pattern matching relies on.
+#[test]
+fn record_update_of_single_field() {
+    assert_js!(
+        r#"
+pub type Foo {
+  Foo(a: Int, b: Int, c: Int, d: Int, e: Int, x: Int)
+}
+
+pub fn update(old: Foo) -> Foo {
+  Foo(..old, x: 1)
+}
+"#
+    );
+}
@@ -1,3 +1,4 @@
+use crate::javascript::tests::compile_js_with_line_length;
 use crate::{assert_js, assert_ts_def};
 
 #[test]
@@ -111,3 +112,18 @@ fn go(func) {
 "#,
     );
 }
+
+#[test]
+fn long_lines_wrap_at_configured_length() {
+    let src = r#"
+pub fn main() {
+  [1_000, 2_000, 3_000, 4_000, 5_000]
+}
+"#;
+    let output = compile_js_with_line_length(src, vec![], 30).expect("compilation failed");
+    insta::assert_snapshot!(
+        insta::internals::AutoName,
+        format!("----- SOURCE CODE\n{src}\n\n----- COMPILED JAVASCRIPT\n{output}"),
+        src
+    );
+}
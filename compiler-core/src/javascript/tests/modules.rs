@@ -209,3 +209,36 @@ pub const variable = rocket_ship.class
 "#,
     );
 }
+
+#[test]
+fn re_exported_unqualified_import() {
+    assert_js!(
+        ("rocket_ship", r#"pub fn launch(x) { x }"#),
+        r#"
+import rocket_ship.{launch}
+pub const launch = launch
+"#,
+    );
+}
+
+#[test]
+fn re_exported_aliased_unqualified_import_of_discarded_module() {
+    assert_js!(
+        ("rocket_ship", r#"pub const speed = 100"#),
+        r#"
+import rocket_ship.{speed as velocity} as _
+pub const velocity = velocity
+"#,
+    );
+}
+
+#[test]
+fn unqualified_import_shadowed_by_function() {
+    assert_js!(
+        ("rocket_ship", r#"pub fn launch(x) { x }"#),
+        r#"
+import rocket_ship.{launch}
+pub fn launch() { 1 }
+"#,
+    );
+}
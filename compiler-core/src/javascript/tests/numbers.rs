@@ -1,4 +1,6 @@
 use crate::assert_js;
+use crate::build::EcmaScriptVersion;
+use crate::javascript::tests::compile_js_for_ecmascript_version;
 
 #[test]
 fn int_literals() {
@@ -50,6 +52,20 @@ fn go() {
     );
 }
 
+#[test]
+fn float_literals_are_emitted_unchanged() {
+    assert_js!(
+        r#"
+fn go() {
+    0.1
+    1.0e21
+    5.0e-324
+    -2.2250738585072014e-308
+}
+"#,
+    );
+}
+
 #[test]
 fn int_operators() {
     assert_js!(
@@ -313,3 +329,38 @@ fn main(x) {
 "#
     );
 }
+
+#[test]
+fn int_and_float_division_of_variables() {
+    // Both are JavaScript numbers, so the types are used to pick truncating
+    // division for ints and float division for floats.
+    assert_js!(
+        r#"
+pub fn go(a: Int, b: Int, x: Float, y: Float) {
+  #(a / b, x /. y)
+}
+"#
+    );
+}
+
+#[test]
+fn numeric_separators_are_removed_for_es2020() {
+    // Numeric separators were added in ES2021.
+    let src = r#"
+const thousand = 1_000
+const pi = 3.141_592
+
+pub fn go(x) {
+  let assert 1_000_000 = x
+  let assert 0.000_1 = 0.000_1
+  #(thousand, pi, 0xFF_FF, 1_0.0e1_0)
+}
+"#;
+    let output = compile_js_for_ecmascript_version(src, vec![], EcmaScriptVersion::Es2020)
+        .expect("compilation failed");
+    insta::assert_snapshot!(
+        insta::internals::AutoName,
+        format!("----- SOURCE CODE\n{src}\n\n----- COMPILED JAVASCRIPT\n{output}"),
+        src
+    );
+}
@@ -47,6 +47,25 @@ pub fn main(x) {
     );
 }
 
+#[test]
+fn tco_closures_capture_their_own_iteration() {
+    // The loop variables are declared with `let` inside the loop body so that
+    // each closure captures the values of its own iteration.
+    assert_js!(
+        r#"
+pub fn main(x, closures) {
+  case x {
+    0 -> closures
+    _ -> {
+      let doubled = x * 2
+      main(x - 1, [fn() { #(x, doubled) }, ..closures])
+    }
+  }
+}
+"#
+    );
+}
+
 // https://github.com/gleam-lang/gleam/issues/2400
 #[test]
 fn shadowing_so_not_recursive() {
@@ -59,3 +78,89 @@ pub fn map(map) {
 "#
     );
 }
+
+#[test]
+fn mutual_tco() {
+    assert_js!(
+        r#"
+pub fn is_even(n) {
+  case n {
+    0 -> True
+    _ -> is_odd(n - 1)
+  }
+}
+
+pub fn is_odd(n) {
+  case n {
+    0 -> False
+    _ -> is_even(n - 1)
+  }
+}
+"#
+    );
+}
+
+#[test]
+fn mutual_tco_with_different_arguments() {
+    assert_js!(
+        r#"
+pub fn count(list, total) {
+  case list {
+    [] -> total
+    [_, ..rest] -> skip(rest, total + 1, Nil)
+  }
+}
+
+fn skip(list, total, _) {
+  case list {
+    [] -> total
+    [first, ..] if first > 10 -> count(list, total)
+    [_, ..rest] -> skip(rest, total, Nil)
+  }
+}
+
+fn unrelated() {
+  count([], 0)
+}
+"#
+    );
+}
+
+#[test]
+fn not_mutual_tco_when_calls_are_not_in_tail_position() {
+    assert_js!(
+        r#"
+pub fn wibble(n) {
+  case n {
+    0 -> 0
+    _ -> 1 + wobble(n - 1)
+  }
+}
+
+pub fn wobble(n) {
+  case n {
+    0 -> 0
+    _ -> 1 + wibble(n - 1)
+  }
+}
+"#
+    );
+}
+
+#[test]
+fn mutual_tco_calls_in_closures_are_not_tail_calls() {
+    assert_js!(
+        r#"
+pub fn wibble(n: Int) -> fn() -> Int {
+  case n {
+    0 -> fn() { wobble(1)() + 1 }
+    _ -> wobble(n - 1)
+  }
+}
+
+pub fn wobble(n: Int) -> fn() -> Int {
+  wibble(n)
+}
+"#
+    );
+}
@@ -74,6 +74,11 @@ where
             compile: build::Compile::All,
             root_target_support: TargetSupport::Enforced,
             no_print_progress: false,
+            compile_tests: true,
+            changed_modules: None,
+            features: vec![],
+            javascript_target_version: None,
+            coverage: false,
         };
         let mut project_compiler = ProjectCompiler::new(
             config,
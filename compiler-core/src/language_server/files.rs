@@ -88,6 +88,10 @@ where
         self.io.copy_dir(from, to)
     }
 
+    fn rename_directory(&self, from: &Utf8Path, to: &Utf8Path) -> Result<()> {
+        self.io.rename_directory(from, to)
+    }
+
     fn hardlink(&self, from: &Utf8Path, to: &Utf8Path) -> Result<()> {
         self.io.hardlink(from, to)
     }
@@ -33,7 +33,9 @@ use crate::{
         files::FileSystemProxy, progress::ProgressReporter,
     },
     line_numbers::LineNumbers,
-    manifest::{Base16Checksum, Manifest, ManifestPackage, ManifestPackageSource},
+    manifest::{
+        Base16Checksum, ChecksumAlgorithm, Manifest, ManifestPackage, ManifestPackageSource,
+    },
     paths::ProjectPaths,
     requirement::Requirement,
 };
@@ -66,6 +68,8 @@ impl LanguageServerTestIO {
             actions: Default::default(),
             paths: ProjectPaths::at_filesystem_root(),
             manifest: Manifest {
+                checksum_algorithm: ChecksumAlgorithm::Sha256,
+                published_before: None,
                 requirements: HashMap::new(),
                 packages: vec![],
             },
@@ -177,6 +181,10 @@ impl FileSystemWriter for LanguageServerTestIO {
         self.io.copy_dir(from, to)
     }
 
+    fn rename_directory(&self, from: &Utf8Path, to: &Utf8Path) -> Result<()> {
+        self.io.rename_directory(from, to)
+    }
+
     fn hardlink(&self, from: &Utf8Path, to: &Utf8Path) -> Result<()> {
         self.io.hardlink(from, to)
     }
@@ -371,6 +379,7 @@ fn add_path_dep<B>(engine: &mut LanguageServerEngine<LanguageServerTestIO, B>, n
             otp_app: None,
             requirements: vec![],
             source: ManifestPackageSource::Local { path: path.clone() },
+            retired: false,
         },
     )
 }
@@ -101,7 +101,7 @@ pub use error::{Error, Result};
 pub use warning::Warning;
 
 const GLEAM_CORE_PACKAGE_NAME: &str = "";
-const STDLIB_PACKAGE_NAME: &str = "gleam_stdlib";
+pub const STDLIB_PACKAGE_NAME: &str = "gleam_stdlib";
 
 mod schema_capnp {
     #![allow(
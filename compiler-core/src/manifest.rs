@@ -1,12 +1,14 @@
 use std::collections::HashMap;
 
 use crate::Result;
+use crate::dependency::Timestamp;
 use crate::io::{make_relative, ordered_map};
 use crate::requirement::Requirement;
 use camino::{Utf8Path, Utf8PathBuf};
-use ecow::EcoString;
+use ecow::{EcoString, eco_format};
 use hexpm::version::Version;
 use itertools::Itertools;
+use strum::EnumString;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq, Eq)]
 pub struct Manifest {
@@ -14,6 +16,14 @@ pub struct Manifest {
     pub requirements: HashMap<EcoString, Requirement>,
     #[serde(serialize_with = "sorted_vec")]
     pub packages: Vec<ManifestPackage>,
+    /// The algorithm used for the checksums of the Hex packages. Manifests
+    /// written before this was recorded use the default.
+    #[serde(default)]
+    pub checksum_algorithm: ChecksumAlgorithm,
+    /// When set, only versions published at or before this time were
+    /// considered when resolving the packages.
+    #[serde(default)]
+    pub published_before: Option<Timestamp>,
 }
 
 impl Manifest {
@@ -26,6 +36,8 @@ impl Manifest {
         let Self {
             requirements,
             packages,
+            checksum_algorithm,
+            published_before,
         } = self;
 
         buffer.push_str(
@@ -35,6 +47,20 @@ impl Manifest {
 ",
         );
 
+        // The default algorithm is not written so that existing manifests are
+        // unchanged.
+        if *checksum_algorithm != ChecksumAlgorithm::default() {
+            buffer.push_str("checksum_algorithm = \"");
+            buffer.push_str(checksum_algorithm.as_str());
+            buffer.push_str("\"\n\n");
+        }
+
+        if let Some(published_before) = published_before {
+            buffer.push_str("published_before = \"");
+            buffer.push_str(&published_before.to_string());
+            buffer.push_str("\"\n\n");
+        }
+
         // Packages
         buffer.push_str("packages = [\n");
         for ManifestPackage {
@@ -44,6 +70,7 @@ impl Manifest {
             otp_app,
             build_tools,
             requirements,
+            retired,
         } in packages.iter().sorted_by(|a, b| a.name.cmp(&b.name))
         {
             buffer.push_str(r#"  {"#);
@@ -98,6 +125,10 @@ impl Manifest {
                 }
             };
 
+            if *retired {
+                buffer.push_str(", retired = true");
+            }
+
             buffer.push_str(" },\n");
         }
         buffer.push_str("]\n\n");
@@ -113,9 +144,196 @@ impl Manifest {
 
         buffer
     }
+
+    /// A flat, human friendly rendering of the resolved packages, one per line
+    /// in alphabetical order, annotated with the direct dependencies that
+    /// caused each package to be included. This is derived from the manifest
+    /// and is intended to make dependency changes easy to review in diffs.
+    pub fn to_deps_lock(&self, root_path: &Utf8Path) -> String {
+        let mut buffer = String::from(
+            "# This file was generated by Gleam from manifest.toml
+# It is for reviewing dependency changes only, manifest.toml is used to build
+
+",
+        );
+
+        let packages: HashMap<&str, &ManifestPackage> = self
+            .packages
+            .iter()
+            .map(|package| (package.name.as_str(), package))
+            .collect();
+
+        // For each package, the direct dependencies that pulled it in.
+        let mut pulled_in_by: HashMap<&str, Vec<&str>> = HashMap::new();
+        for direct in self.requirements.keys().sorted() {
+            let mut stack = vec![direct.as_str()];
+            let mut seen = std::collections::HashSet::new();
+            while let Some(name) = stack.pop() {
+                if !seen.insert(name) {
+                    continue;
+                }
+                let Some(package) = packages.get(name) else {
+                    continue;
+                };
+                stack.extend(package.requirements.iter().map(|name| name.as_str()));
+                if name != direct {
+                    pulled_in_by.entry(name).or_default().push(direct);
+                }
+            }
+        }
+
+        for package in self.packages.iter().sorted_by(|a, b| a.name.cmp(&b.name)) {
+            buffer.push_str(&package.name);
+            buffer.push(' ');
+            buffer.push_str(&package.version.to_string());
+            buffer.push(' ');
+            match &package.source {
+                ManifestPackageSource::Hex { outer_checksum } => {
+                    buffer.push_str(self.checksum_algorithm.as_str());
+                    buffer.push(':');
+                    buffer.push_str(&outer_checksum.to_string());
+                }
+                ManifestPackageSource::Git { repo, commit } => {
+                    buffer.push_str("git:");
+                    buffer.push_str(repo);
+                    buffer.push('@');
+                    buffer.push_str(commit);
+                }
+                ManifestPackageSource::Local { path } => {
+                    buffer.push_str("local:");
+                    buffer.push_str(&make_relative(root_path, path).as_str().replace('\\', "/"));
+                }
+            }
+
+            let mut reasons = vec![];
+            if self.requirements.contains_key(&package.name) {
+                reasons.push("direct dependency".to_string());
+            }
+            if let Some(direct) = pulled_in_by.get(package.name.as_str()) {
+                reasons.push(format!("via {}", direct.join(", ")));
+            }
+            if !reasons.is_empty() {
+                buffer.push_str(" # ");
+                buffer.push_str(&reasons.join("; "));
+            }
+            buffer.push('\n');
+        }
+
+        buffer
+    }
+
+    /// The longest of the shortest chains of requirements from the direct
+    /// dependencies to each package, starting with a direct dependency. Ties
+    /// are broken by package name so that the same chain is always returned.
+    pub fn deepest_dependency_chain(&self) -> Vec<EcoString> {
+        let packages: HashMap<&str, &ManifestPackage> = self
+            .packages
+            .iter()
+            .map(|package| (package.name.as_str(), package))
+            .collect();
+
+        // Walk the requirements breadth first so each package is reached by
+        // its shortest chain, recording the package it was first reached from.
+        let mut reached_from: HashMap<&str, Option<&str>> = HashMap::new();
+        let mut queue: std::collections::VecDeque<&str> = self
+            .requirements
+            .keys()
+            .map(|name| name.as_str())
+            .sorted()
+            .collect();
+        for name in &queue {
+            let _ = reached_from.insert(name, None);
+        }
+        let mut deepest = None;
+        while let Some(name) = queue.pop_front() {
+            deepest = Some(name);
+            let Some(package) = packages.get(name) else {
+                continue;
+            };
+            for requirement in package.requirements.iter().sorted() {
+                if !reached_from.contains_key(requirement.as_str()) {
+                    let _ = reached_from.insert(requirement, Some(name));
+                    queue.push_back(requirement);
+                }
+            }
+        }
+
+        let mut chain = vec![];
+        let mut current = deepest;
+        while let Some(name) = current {
+            chain.push(EcoString::from(name));
+            current = reached_from.get(name).copied().flatten();
+        }
+        chain.reverse();
+        chain
+    }
+
+    /// The version a package has been resolved to, if it is one of the
+    /// packages in the manifest.
+    pub fn package_version(&self, name: &str) -> Option<&Version> {
+        self.packages
+            .iter()
+            .find(|package| package.name == name)
+            .map(|package| &package.version)
+    }
+
+    /// The resolved packages as a flat list of `name==version` lines sorted by
+    /// name, for tools that don't understand the manifest's format. The
+    /// separator between the name and the version can be changed.
+    pub fn to_requirements_lock(&self, separator: &str) -> String {
+        self.packages
+            .iter()
+            .sorted_by(|a, b| a.name.cmp(&b.name))
+            .map(|package| format!("{}{separator}{}\n", package.name, package.version))
+            .collect()
+    }
+
+    /// The names and versions of the Hex packages that have no checksum
+    /// recorded, such as those locked by a manifest written before checksums
+    /// were recorded. They can't be verified when they are downloaded.
+    pub fn packages_without_checksums(&self) -> Vec<EcoString> {
+        self.packages
+            .iter()
+            .filter(|package| match &package.source {
+                ManifestPackageSource::Hex { outer_checksum } => outer_checksum.0.is_empty(),
+                ManifestPackageSource::Git { .. } | ManifestPackageSource::Local { .. } => false,
+            })
+            .sorted_by(|a, b| a.name.cmp(&b.name))
+            .map(|package| eco_format!("{} {}", package.name, package.version))
+            .collect()
+    }
+}
+
+/// A hash algorithm that package checksums can be recorded with.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, EnumString, serde::Serialize, serde::Deserialize,
+)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumAlgorithm {
+    /// The algorithm Hex uses for the outer checksum of package tarballs.
+    #[default]
+    Sha256,
+    /// An algorithm this version of Gleam does not know, such as one recorded
+    /// by a newer version. Checksums recorded with it cannot be verified.
+    #[strum(disabled)]
+    #[serde(other)]
+    Unknown,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+impl ChecksumAlgorithm {
+    /// The names of the algorithms that can be selected by the user.
+    pub const SUPPORTED: &'static [&'static str] = &["sha256"];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Sha256 => "sha256",
+            ChecksumAlgorithm::Unknown => "unknown",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Base16Checksum(pub Vec<u8>);
 
 impl ToString for Base16Checksum {
@@ -156,6 +374,11 @@ pub struct ManifestPackage {
     pub requirements: Vec<EcoString>,
     #[serde(flatten)]
     pub source: ManifestPackageSource,
+    /// Whether this version had been retired on Hex when it was resolved.
+    /// Retired versions are only used if they were already locked or if no
+    /// other version satisfies the requirements.
+    #[serde(default)]
+    pub retired: bool,
 }
 
 impl ManifestPackage {
@@ -191,7 +414,11 @@ impl ManifestPackage {
 #[serde(tag = "source")]
 pub enum ManifestPackageSource {
     #[serde(rename = "hex")]
-    Hex { outer_checksum: Base16Checksum },
+    Hex {
+        /// Empty if no checksum was recorded for the package.
+        #[serde(default)]
+        outer_checksum: Base16Checksum,
+    },
     #[serde(rename = "git")]
     Git { repo: EcoString, commit: EcoString },
     #[serde(rename = "local")]
@@ -231,6 +458,8 @@ mod tests {
     #[test]
     fn manifest_toml_format() {
         let manifest = Manifest {
+            checksum_algorithm: ChecksumAlgorithm::Sha256,
+            published_before: None,
             requirements: [
                 ("zzz".into(), Requirement::hex("> 0.0.0")),
                 ("aaa".into(), Requirement::hex("> 0.0.0")),
@@ -256,6 +485,7 @@ mod tests {
                     source: ManifestPackageSource::Hex {
                         outer_checksum: Base16Checksum(vec![1, 22]),
                     },
+                    retired: false,
                 },
                 ManifestPackage {
                     name: "aaa".into(),
@@ -266,6 +496,7 @@ mod tests {
                     source: ManifestPackageSource::Hex {
                         outer_checksum: Base16Checksum(vec![3, 22]),
                     },
+                    retired: false,
                 },
                 ManifestPackage {
                     name: "zzz".into(),
@@ -276,6 +507,7 @@ mod tests {
                     source: ManifestPackageSource::Hex {
                         outer_checksum: Base16Checksum(vec![3, 22]),
                     },
+                    retired: false,
                 },
                 ManifestPackage {
                     name: "awsome_local2".into(),
@@ -287,6 +519,7 @@ mod tests {
                         repo: "https://github.com/gleam-lang/gleam.git".into(),
                         commit: "bd9fe02f72250e6a136967917bcb1bdccaffa3c8".into(),
                     },
+                    retired: false,
                 },
                 ManifestPackage {
                     name: "awsome_local1".into(),
@@ -297,6 +530,7 @@ mod tests {
                     source: ManifestPackageSource::Local {
                         path: PACKAGE.into(),
                     },
+                    retired: false,
                 },
                 ManifestPackage {
                     name: "gleeunit".into(),
@@ -307,6 +541,7 @@ mod tests {
                     source: ManifestPackageSource::Hex {
                         outer_checksum: Base16Checksum(vec![3, 46]),
                     },
+                    retired: false,
                 },
             ],
         };
@@ -341,6 +576,8 @@ zzz = { version = "> 0.0.0" }
     #[test]
     fn manifest_toml_format_with_unc() {
         let manifest = Manifest {
+            checksum_algorithm: ChecksumAlgorithm::Sha256,
+            published_before: None,
             requirements: [
                 ("zzz".into(), Requirement::hex("> 0.0.0")),
                 ("aaa".into(), Requirement::hex("> 0.0.0")),
@@ -366,6 +603,7 @@ zzz = { version = "> 0.0.0" }
                     source: ManifestPackageSource::Hex {
                         outer_checksum: Base16Checksum(vec![1, 22]),
                     },
+                    retired: false,
                 },
                 ManifestPackage {
                     name: "aaa".into(),
@@ -376,6 +614,7 @@ zzz = { version = "> 0.0.0" }
                     source: ManifestPackageSource::Hex {
                         outer_checksum: Base16Checksum(vec![3, 22]),
                     },
+                    retired: false,
                 },
                 ManifestPackage {
                     name: "zzz".into(),
@@ -386,6 +625,7 @@ zzz = { version = "> 0.0.0" }
                     source: ManifestPackageSource::Hex {
                         outer_checksum: Base16Checksum(vec![3, 22]),
                     },
+                    retired: false,
                 },
                 ManifestPackage {
                     name: "awsome_local2".into(),
@@ -397,6 +637,7 @@ zzz = { version = "> 0.0.0" }
                         repo: "https://github.com/gleam-lang/gleam.git".into(),
                         commit: "bd9fe02f72250e6a136967917bcb1bdccaffa3c8".into(),
                     },
+                    retired: false,
                 },
                 ManifestPackage {
                     name: "awsome_local1".into(),
@@ -407,6 +648,7 @@ zzz = { version = "> 0.0.0" }
                     source: ManifestPackageSource::Local {
                         path: PACKAGE_WITH_UNC.into(),
                     },
+                    retired: false,
                 },
                 ManifestPackage {
                     name: "gleeunit".into(),
@@ -417,6 +659,7 @@ zzz = { version = "> 0.0.0" }
                     source: ManifestPackageSource::Hex {
                         outer_checksum: Base16Checksum(vec![3, 46]),
                     },
+                    retired: false,
                 },
             ],
         };
@@ -447,6 +690,298 @@ zzz = { version = "> 0.0.0" }
         );
     }
 
+    #[test]
+    fn manifest_without_checksum_algorithm_uses_default() {
+        let manifest: Manifest = toml::from_str(
+            r#"
+packages = [
+  { name = "zzz", version = "0.4.0", build_tools = ["mix"], requirements = [], source = "hex", outer_checksum = "0316" },
+]
+
+[requirements]
+zzz = { version = "> 0.0.0" }
+"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.checksum_algorithm, ChecksumAlgorithm::Sha256);
+    }
+
+    #[test]
+    fn requirements_lock_format() {
+        let package = |name: &str, version: &str| ManifestPackage {
+            name: name.into(),
+            version: Version::parse(version).unwrap(),
+            build_tools: ["gleam".into()].into(),
+            otp_app: None,
+            requirements: vec![],
+            source: ManifestPackageSource::Hex {
+                outer_checksum: Base16Checksum(vec![1, 22]),
+            },
+            retired: false,
+        };
+        let manifest = Manifest {
+            checksum_algorithm: ChecksumAlgorithm::Sha256,
+            published_before: None,
+            requirements: HashMap::new(),
+            packages: vec![
+                package("wobble", "0.4.0"),
+                package("gleam_stdlib", "0.17.1"),
+                package("wibble", "1.0.0-rc1"),
+            ],
+        };
+        assert_eq!(
+            manifest.to_requirements_lock("=="),
+            "gleam_stdlib==0.17.1
+wibble==1.0.0-rc1
+wobble==0.4.0
+"
+        );
+        assert_eq!(
+            manifest.to_requirements_lock(" "),
+            "gleam_stdlib 0.17.1
+wibble 1.0.0-rc1
+wobble 0.4.0
+"
+        );
+    }
+
+    #[test]
+    fn manifest_without_outer_checksum() {
+        let manifest: Manifest = toml::from_str(
+            r#"
+packages = [
+  { name = "wibble", version = "1.0.0", build_tools = ["gleam"], requirements = [], source = "hex" },
+  { name = "zzz", version = "0.4.0", build_tools = ["mix"], requirements = [], source = "hex", outer_checksum = "0316" },
+]
+
+[requirements]
+wibble = { version = ">= 1.0.0" }
+zzz = { version = "> 0.0.0" }
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            manifest.packages_without_checksums(),
+            vec![EcoString::from("wibble 1.0.0")]
+        );
+    }
+
+    #[test]
+    fn manifest_with_unknown_checksum_algorithm() {
+        let manifest: Manifest = toml::from_str(
+            r#"
+checksum_algorithm = "blake3"
+
+packages = [
+  { name = "zzz", version = "0.4.0", build_tools = ["mix"], requirements = [], source = "hex", outer_checksum = "0316" },
+]
+
+[requirements]
+zzz = { version = "> 0.0.0" }
+"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.checksum_algorithm, ChecksumAlgorithm::Unknown);
+        assert_eq!(manifest.packages.len(), 1);
+    }
+
+    #[test]
+    fn manifest_toml_format_with_non_default_checksum_algorithm() {
+        let manifest = Manifest {
+            checksum_algorithm: ChecksumAlgorithm::Unknown,
+            published_before: None,
+            requirements: HashMap::new(),
+            packages: vec![],
+        };
+        let toml = manifest.to_toml(HOME.into());
+        assert!(toml.contains("checksum_algorithm = \"unknown\"\n"));
+        let read: Manifest = toml::from_str(&toml).unwrap();
+        assert_eq!(read, manifest);
+    }
+
+    #[test]
+    fn manifest_toml_format_with_published_before() {
+        let manifest = Manifest {
+            checksum_algorithm: ChecksumAlgorithm::Sha256,
+            published_before: Some("2024-06-01T12:30:00Z".parse().unwrap()),
+            requirements: HashMap::new(),
+            packages: vec![],
+        };
+        let toml = manifest.to_toml(HOME.into());
+        assert!(toml.contains("published_before = \"2024-06-01T12:30:00Z\"\n"));
+        let read: Manifest = toml::from_str(&toml).unwrap();
+        assert_eq!(read, manifest);
+    }
+
+    #[test]
+    fn manifest_toml_format_with_retired_package() {
+        let manifest = Manifest {
+            checksum_algorithm: ChecksumAlgorithm::Sha256,
+            published_before: None,
+            requirements: HashMap::new(),
+            packages: vec![ManifestPackage {
+                name: "wibble".into(),
+                retired: true,
+                ..Default::default()
+            }],
+        };
+        let toml = manifest.to_toml(HOME.into());
+        assert!(toml.contains(", retired = true },\n"));
+        let read: Manifest = toml::from_str(&toml).unwrap();
+        assert_eq!(read, manifest);
+    }
+
+    #[test]
+    fn deepest_dependency_chain() {
+        let manifest = Manifest {
+            checksum_algorithm: ChecksumAlgorithm::Sha256,
+            published_before: None,
+            requirements: [
+                ("wibble".into(), Requirement::hex(">= 1.0.0")),
+                ("wobble".into(), Requirement::hex(">= 1.0.0")),
+            ]
+            .into(),
+            packages: vec![
+                ManifestPackage {
+                    name: "wibble".into(),
+                    requirements: vec!["wubble".into()],
+                    ..Default::default()
+                },
+                ManifestPackage {
+                    name: "wobble".into(),
+                    requirements: vec!["wubble".into(), "jelly".into()],
+                    ..Default::default()
+                },
+                ManifestPackage {
+                    name: "wubble".into(),
+                    requirements: vec!["jelly".into()],
+                    ..Default::default()
+                },
+                ManifestPackage {
+                    name: "jelly".into(),
+                    requirements: vec!["wibble".into()],
+                    ..Default::default()
+                },
+            ],
+        };
+        // `jelly` is required directly by `wobble`, so its longer chain
+        // through `wubble` is not the deepest.
+        assert_eq!(
+            manifest.deepest_dependency_chain(),
+            vec![EcoString::from("wobble"), EcoString::from("jelly")]
+        );
+    }
+
+    #[test]
+    fn deepest_dependency_chain_without_dependencies() {
+        let manifest = Manifest {
+            checksum_algorithm: ChecksumAlgorithm::Sha256,
+            published_before: None,
+            requirements: HashMap::new(),
+            packages: vec![],
+        };
+        assert!(manifest.deepest_dependency_chain().is_empty());
+    }
+
+    #[test]
+    fn package_version() {
+        let manifest = Manifest {
+            checksum_algorithm: ChecksumAlgorithm::Sha256,
+            published_before: None,
+            requirements: HashMap::new(),
+            packages: vec![
+                ManifestPackage {
+                    name: "gleam_stdlib".into(),
+                    version: Version::new(0, 25, 0),
+                    ..Default::default()
+                },
+                ManifestPackage {
+                    name: "gleeunit".into(),
+                    ..Default::default()
+                },
+            ],
+        };
+        assert_eq!(
+            manifest.package_version("gleam_stdlib"),
+            Some(&Version::new(0, 25, 0))
+        );
+        assert_eq!(manifest.package_version("wibble"), None);
+    }
+
+    #[test]
+    fn deps_lock() {
+        let manifest = Manifest {
+            checksum_algorithm: ChecksumAlgorithm::Sha256,
+            published_before: None,
+            requirements: [
+                ("gleeunit".into(), Requirement::hex(">= 1.0.0")),
+                ("gleam_json".into(), Requirement::hex(">= 1.0.0")),
+                ("gleam_stdlib".into(), Requirement::hex(">= 0.25.0")),
+            ]
+            .into(),
+            packages: vec![
+                ManifestPackage {
+                    name: "thoas".into(),
+                    version: Version::new(1, 2, 0),
+                    source: ManifestPackageSource::Hex {
+                        outer_checksum: Base16Checksum(vec![3, 22]),
+                    },
+                    ..Default::default()
+                },
+                ManifestPackage {
+                    name: "gleeunit".into(),
+                    requirements: vec!["gleam_stdlib".into()],
+                    source: ManifestPackageSource::Git {
+                        repo: "https://github.com/gleam-lang/gleeunit".into(),
+                        commit: "bd512ab".into(),
+                    },
+                    ..Default::default()
+                },
+                ManifestPackage {
+                    name: "gleam_stdlib".into(),
+                    version: Version::new(0, 25, 0),
+                    source: ManifestPackageSource::Hex {
+                        outer_checksum: Base16Checksum(vec![1, 255]),
+                    },
+                    ..Default::default()
+                },
+                ManifestPackage {
+                    name: "gleam_json".into(),
+                    requirements: vec!["gleam_stdlib".into(), "thoas".into()],
+                    source: ManifestPackageSource::Local {
+                        path: PACKAGE.into(),
+                    },
+                    ..Default::default()
+                },
+            ],
+        };
+
+        let lock = manifest.to_deps_lock(HOME.into());
+        let lines = lock
+            .lines()
+            .filter(|line| !line.starts_with('#') && !line.is_empty())
+            .collect_vec();
+        let names = lines
+            .iter()
+            .map(|line| line.split(' ').next().unwrap_or_default())
+            .collect_vec();
+        assert_eq!(
+            names,
+            vec!["gleam_json", "gleam_stdlib", "gleeunit", "thoas"]
+        );
+        assert_eq!(
+            lock,
+            "# This file was generated by Gleam from manifest.toml
+# It is for reviewing dependency changes only, manifest.toml is used to build
+
+gleam_json 1.0.0 local:../path/to/package # direct dependency
+gleam_stdlib 0.25.0 sha256:01FF # direct dependency; via gleam_json, gleeunit
+gleeunit 1.0.0 git:https://github.com/gleam-lang/gleeunit@bd512ab # direct dependency
+thoas 1.2.0 sha256:0316 # via gleam_json
+"
+        );
+    }
+
     impl Default for ManifestPackage {
         fn default() -> Self {
             Self {
@@ -458,6 +993,7 @@ zzz = { version = "> 0.0.0" }
                 source: ManifestPackageSource::Hex {
                     outer_checksum: Base16Checksum(vec![]),
                 },
+                retired: false,
             }
         }
     }
@@ -8,7 +8,9 @@ use hexpm::version::Identifier;
 use crate::{
     analyse::TargetSupport,
     build::{Module, Origin, Package, Target},
-    config::{Docs, ErlangConfig, GleamVersion, JavaScriptConfig, PackageConfig, Repository},
+    config::{
+        Docs, ErlangConfig, GleamVersion, JavaScriptConfig, PackageConfig, PackageKind, Repository,
+    },
     line_numbers::LineNumbers,
     type_::PRELUDE_MODULE_NAME,
     uid::UniqueIdGenerator,
@@ -167,6 +169,16 @@ fn package_from_module(module: Module) -> Package {
             erlang: ErlangConfig::default(),
             javascript: JavaScriptConfig::default(),
             target: Target::Erlang,
+            run_target: None,
+            deps_lock: false,
+            max_dependency_depth: None,
+            warn_unbounded_requirements: false,
+            max_resolution_steps: None,
+            published_before: None,
+            generated_line_length: None,
+            dependency_policy: None,
+            kind: PackageKind::Library,
+            features: vec![],
             internal_modules: Some(vec![
                 GlobBuilder::new("internals/*")
                     .build()
@@ -52,7 +52,7 @@
 pub mod error;
 pub mod extra;
 pub mod lexer;
-mod token;
+pub mod token;
 
 use crate::Warning;
 use crate::analyse::Inferred;
@@ -122,11 +122,14 @@ struct Attributes {
     external_erlang: Option<(EcoString, EcoString, SrcSpan)>,
     external_javascript: Option<(EcoString, EcoString, SrcSpan)>,
     internal: InternalAttribute,
+    allow_deprecated: bool,
 }
 
 impl Attributes {
     fn has_function_only(&self) -> bool {
-        self.external_erlang.is_some() || self.external_javascript.is_some()
+        self.external_erlang.is_some()
+            || self.external_javascript.is_some()
+            || self.allow_deprecated
     }
 
     fn has_external_for(&self, target: Target) -> bool {
@@ -2009,6 +2012,7 @@ where
             deprecation: std::mem::take(&mut attributes.deprecated),
             external_erlang: attributes.external_erlang.take(),
             external_javascript: attributes.external_javascript.take(),
+            allow_deprecated: attributes.allow_deprecated,
             implementations: Implementations {
                 gleam: true,
                 can_run_on_erlang: true,
@@ -2305,6 +2309,7 @@ where
                             || attributes.external_javascript.is_some()
                             || attributes.target.is_some()
                             || attributes.internal != InternalAttribute::Missing
+                            || attributes.allow_deprecated
                         {
                             return parse_error(
                                 ParseErrorType::UnknownAttributeRecordVariant,
@@ -3769,6 +3774,10 @@ functions are declared separately from types.";
                 self.parse_deprecated_attribute(start, end, attributes)
             }
             "internal" => self.parse_internal_attribute(start, end, attributes),
+            "allow" => {
+                let _ = self.expect_one(&Token::LeftParen)?;
+                self.parse_allow_attribute(start, end, attributes)
+            }
             _ => parse_error(ParseErrorType::UnknownAttribute, SrcSpan { start, end }),
         }?;
 
@@ -3841,6 +3850,27 @@ functions are declared separately from types.";
         Ok(end)
     }
 
+    fn parse_allow_attribute(
+        &mut self,
+        start: u32,
+        end: u32,
+        attributes: &mut Attributes,
+    ) -> Result<u32, ParseError> {
+        let (name_start, name, name_end) = self.expect_name()?;
+        if name != "deprecated" {
+            return parse_error(
+                ParseErrorType::UnknownAllowedWarning,
+                SrcSpan::new(name_start, name_end),
+            );
+        }
+        let (_, end_paren) = self.expect_one(&Token::RightParen)?;
+        if attributes.allow_deprecated {
+            return parse_error(ParseErrorType::DuplicateAttribute, SrcSpan::new(start, end));
+        }
+        attributes.allow_deprecated = true;
+        Ok(end_paren)
+    }
+
     fn parse_internal_attribute(
         &mut self,
         start: u32,
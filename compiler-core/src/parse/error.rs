@@ -27,6 +27,7 @@ pub enum LexicalErrorType {
     MissingExponent,     // 1.0e, for example, where there is no exponent
     UnexpectedStringEnd, // Unterminated string literal
     UnrecognizedToken { tok: char },
+    NonAsciiName { tok: char }, // café, names can only use ASCII letters
     InvalidTripleEqual,
 }
 
@@ -244,7 +245,11 @@ utf16_codepoint, utf32_codepoint, signed, unsigned, big, little, native, size, u
             ),
             ParseErrorType::UnknownAttribute => (
                 "I don't recognise this attribute",
-                vec!["Try `deprecated`, `external` or `target` instead.".into()],
+                vec!["Try `allow`, `deprecated`, `external` or `target` instead.".into()],
+            ),
+            ParseErrorType::UnknownAllowedWarning => (
+                "I don't recognise this warning",
+                vec!["Try `deprecated` instead.".into()],
             ),
             ParseErrorType::DuplicateAttribute => (
                 "Duplicate attribute",
@@ -377,6 +382,7 @@ pub enum ParseErrorType {
     TooManyArgHoles, // a function call can have at most 1 arg hole
     DuplicateAttribute, // an attribute was used more than once
     UnknownAttribute, // an attribute was used that is not known
+    UnknownAllowedWarning, // in "@allow(...)" a warning was named that is not known
     UnknownTarget, // an unknown target was used
     ListSpreadWithoutElements, // Pointless spread: `[..xs]`
     ListSpreadFollowedByElements, // trying to append something after the spread: `[..xs, x]`
@@ -453,6 +459,13 @@ impl LexicalError {
                 "I can't figure out what to do with this character",
                 vec!["Hint: Is it a typo?".into()],
             ),
+            LexicalErrorType::NonAsciiName { .. } => (
+                "Names can only use ASCII letters",
+                vec![
+                    "Hint: Names are written with the letters a to z and A to Z,".into(),
+                    "numbers, and underscores.".into(),
+                ],
+            ),
             LexicalErrorType::InvalidUnicodeEscape(
                 InvalidUnicodeEscapeError::MissingOpeningBrace,
             ) => (
@@ -449,8 +449,13 @@ where
             }
             c => {
                 let location = self.get_pos();
+                let error = if c.is_alphabetic() {
+                    LexicalErrorType::NonAsciiName { tok: c }
+                } else {
+                    LexicalErrorType::UnrecognizedToken { tok: c }
+                };
                 return Err(LexicalError {
-                    error: LexicalErrorType::UnrecognizedToken { tok: c },
+                    error,
                     location: SrcSpan {
                         start: location,
                         end: location,
@@ -737,6 +737,41 @@ pub fn main() -> Nil {
     );
 }
 
+#[test]
+fn multiple_allow_attributes() {
+    assert_module_error!(
+        r#"
+@allow(deprecated)
+@allow(deprecated)
+pub fn main() -> Nil {
+  Nil
+}
+"#
+    );
+}
+
+#[test]
+fn allow_unknown_warning() {
+    assert_module_error!(
+        r#"
+@allow(unused)
+pub fn main() -> Nil {
+  Nil
+}
+"#
+    );
+}
+
+#[test]
+fn allow_attribute_with_non_fn_definition() {
+    assert_module_error!(
+        r#"
+@allow(deprecated)
+pub type Fun
+"#
+    );
+}
+
 #[test]
 fn attributes_with_no_definition() {
     assert_module_error!(
@@ -1766,3 +1801,23 @@ fn nested_tuple_access_after_function() {
 fn case_expression_without_body() {
     assert_parse!("case a");
 }
+
+#[test]
+fn non_ascii_function_name() {
+    assert_module_error!("pub fn café() { 1 }");
+}
+
+#[test]
+fn non_ascii_variable_name() {
+    assert_error!("let ñ = 1");
+}
+
+#[test]
+fn non_ascii_type_name() {
+    assert_module_error!("pub type Ñandu { Ñandu }");
+}
+
+#[test]
+fn non_letter_character_is_still_unrecognised() {
+    assert_error!("1 § 2");
+}
@@ -3,6 +3,7 @@ use crate::build::{Mode, Target};
 use camino::{Utf8Path, Utf8PathBuf};
 
 pub const ARTEFACT_DIRECTORY_NAME: &str = "_gleam_artefacts";
+pub const GENERATED_DIRECTORY_NAME: &str = "_gleam_generated";
 
 #[derive(Debug, Clone)]
 pub struct ProjectPaths {
@@ -40,6 +41,10 @@ impl ProjectPaths {
         self.root.join("manifest.toml")
     }
 
+    pub fn deps_lock(&self) -> Utf8PathBuf {
+        self.root.join("deps.lock")
+    }
+
     pub fn src_directory(&self) -> Utf8PathBuf {
         self.root.join("src")
     }
@@ -74,6 +79,11 @@ impl ProjectPaths {
             .join(format!("{package_name}-{version}.tar"))
     }
 
+    pub fn build_export_docs_tarball(&self, package_name: &str, version: &str) -> Utf8PathBuf {
+        self.build_directory()
+            .join(format!("{package_name}-{version}-docs.tar.gz"))
+    }
+
     pub fn build_directory_for_mode(&self, mode: Mode) -> Utf8PathBuf {
         self.build_directory().join(mode.to_string())
     }
@@ -82,6 +92,15 @@ impl ProjectPaths {
         self.build_directory().join("erlang-shipment")
     }
 
+    pub fn javascript_bundle_directory(&self) -> Utf8PathBuf {
+        self.build_directory().join("javascript-bundle")
+    }
+
+    /// The LCOV report written by `gleam test --coverage`.
+    pub fn coverage_report(&self) -> Utf8PathBuf {
+        self.build_directory().join("coverage").join("lcov.info")
+    }
+
     pub fn build_documentation_directory(&self, package: &str) -> Utf8PathBuf {
         self.build_directory_for_mode(Mode::Dev)
             .join("docs")
@@ -126,6 +145,12 @@ pub fn global_package_cache_package_tarball(package_name: &str, version: &str) -
     global_packages_cache().join(format!("{package_name}-{version}.tar"))
 }
 
+/// The directory the compiled artefacts of Hex dependencies are kept in so
+/// they can be shared by every project.
+pub fn global_dependency_cache() -> Utf8PathBuf {
+    default_global_gleam_cache().join("compiled")
+}
+
 pub fn global_hexpm_credentials_path() -> Utf8PathBuf {
     global_hexpm_cache().join("credentials")
 }
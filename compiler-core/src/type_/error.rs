@@ -54,6 +54,24 @@ impl Problems {
         std::mem::take(&mut self.errors)
     }
 
+    /// The number of warnings registered so far.
+    ///
+    pub fn warnings_count(&self) -> usize {
+        self.warnings.len()
+    }
+
+    /// Remove any deprecation warnings registered after the first `count`
+    /// warnings, used for code marked with `@allow(deprecated)`.
+    ///
+    pub fn remove_deprecated_item_warnings_since(&mut self, count: usize) {
+        let mut index = 0;
+        self.warnings.retain(|warning| {
+            let keep = index < count || !matches!(warning, Warning::DeprecatedItem { .. });
+            index += 1;
+            keep
+        });
+    }
+
     /// Take all the warnings, leaving an empty vector in its place.
     ///
     pub fn take_warnings(&mut self) -> Vec<Warning> {
@@ -918,6 +936,29 @@ pub enum Warning {
     JavaScriptIntUnsafe {
         location: SrcSpan,
     },
+
+    /// When a function unconditionally calls itself with the same arguments
+    /// it was called with, so it can never return.
+    ///
+    /// ```gleam
+    /// fn wibble(x) {
+    ///   wibble(x)
+    /// }
+    /// ```
+    ///
+    InfiniteRecursion {
+        location: SrcSpan,
+    },
+
+    /// When a package is an application, a public function in its `src`
+    /// directory that no module in the package uses. Only emitted when
+    /// `kind = "application"` is set in `gleam.toml`, as the functions of a
+    /// library are used by other packages.
+    ///
+    UnusedPublicFunction {
+        location: SrcSpan,
+        name: EcoString,
+    },
 }
 
 #[derive(Debug, Eq, Copy, PartialEq, Clone, serde::Serialize, serde::Deserialize)]
@@ -1129,7 +1170,9 @@ impl Warning {
             | Warning::UnreachableCodeAfterPanic { location, .. }
             | Warning::RedundantPipeFunctionCapture { location, .. }
             | Warning::FeatureRequiresHigherGleamVersion { location, .. }
-            | Warning::JavaScriptIntUnsafe { location, .. } => *location,
+            | Warning::JavaScriptIntUnsafe { location, .. }
+            | Warning::InfiniteRecursion { location, .. }
+            | Warning::UnusedPublicFunction { location, .. } => *location,
         }
     }
 
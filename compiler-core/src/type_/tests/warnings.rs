@@ -944,6 +944,98 @@ pub fn cat_name(cat: Cat) {
     );
 }
 
+#[test]
+fn allow_deprecated_function() {
+    assert_no_warnings!(
+        r#"
+@deprecated("Don't use this!")
+pub fn a() {
+  Nil
+}
+
+@allow(deprecated)
+pub fn b() {
+  a()
+}
+        "#
+    );
+}
+
+#[test]
+fn allow_deprecated_imported_function() {
+    assert_no_warnings!(
+        (
+            "package",
+            "module",
+            r#"@deprecated("Don't use this!") pub fn a() { Nil }"#
+        ),
+        r#"
+import module
+
+@allow(deprecated)
+pub fn a() {
+  module.a()
+}
+"#
+    );
+}
+
+#[test]
+fn allow_deprecated_type_used_as_arg() {
+    assert_no_warnings!(
+        r#"
+@deprecated("Don't use this!")
+pub type Cat {
+    Cat(name: String)
+}
+
+@allow(deprecated)
+pub fn cat_name(cat: Cat) {
+  cat.name
+}
+        "#
+    );
+}
+
+#[test]
+fn allow_deprecated_only_applies_to_annotated_function() {
+    assert_warning!(
+        r#"
+@deprecated("Don't use this!")
+pub fn a() {
+  Nil
+}
+
+@allow(deprecated)
+pub fn b() {
+  a()
+}
+
+pub fn c() {
+  a()
+}
+        "#
+    );
+}
+
+#[test]
+fn allow_deprecated_keeps_other_warnings() {
+    assert_warning!(
+        r#"
+@deprecated("Don't use this!")
+pub fn a() {
+  Nil
+}
+
+@allow(deprecated)
+pub fn b() {
+  let x = 1
+  a()
+}
+        "#
+    );
+}
+
 #[test]
 fn deprecated_type_used_as_case_clause() {
     assert_warning!(
@@ -2791,7 +2883,8 @@ pub fn main() {
   Nil
 }
 
-fn wibble() { wibble() }
+@external(erlang, "wibble", "wibble")
+fn wibble() -> Int
 "#
     );
 }
@@ -2825,7 +2918,8 @@ pub fn main() {
     Nil
 }
 
-fn wibble() { wibble() }
+@external(erlang, "wibble", "wibble")
+fn wibble() -> Int
 "#
     );
 }
@@ -2843,7 +2937,8 @@ pub fn main() {
     Nil
 }
 
-fn wibble() { wibble() }
+@external(erlang, "wibble", "wibble")
+fn wibble() -> Int
 "#
     );
 }
@@ -3014,3 +3109,112 @@ pub fn main(a) {
 "
     );
 }
+
+#[test]
+fn unconditional_self_call_with_same_arguments() {
+    assert_warning!(
+        "
+pub fn loop(a: Int, b: String) -> Nil {
+  loop(a, b)
+}
+"
+    );
+}
+
+#[test]
+fn unconditional_self_call_after_other_statements() {
+    assert_warning!(
+        "
+pub fn loop(a: Int) -> Int {
+  let b = a + 1
+  let #(x, _) = #(b, [a])
+  let c = loop(a)
+  b + c + x
+}
+"
+    );
+}
+
+#[test]
+fn receive_loop_does_not_warn() {
+    assert_no_warnings!(
+        (
+            "thepackage",
+            "process",
+            "pub fn receive_forever(subject: Int) -> Int { subject }"
+        ),
+        "
+import process
+
+pub fn loop(subject: Int) -> Nil {
+  handle(process.receive_forever(subject))
+  loop(subject)
+}
+
+fn handle(message: Int) -> Nil {
+  let _ = message
+  Nil
+}
+"
+    );
+}
+
+#[test]
+fn self_call_after_echo_does_not_warn() {
+    assert_no_warnings!(
+        "
+pub fn loop(a: Int) -> Nil {
+  echo a
+  loop(a)
+}
+"
+    );
+}
+
+#[test]
+fn unconditional_self_call_with_labelled_arguments() {
+    assert_warning!(
+        "
+pub fn loop(first a: Int, second b: Int) -> Int {
+  loop(second: b, first: a)
+}
+"
+    );
+}
+
+#[test]
+fn recursion_with_base_case_does_not_warn() {
+    assert_no_warnings!(
+        "
+pub fn count_down(n: Int) -> Nil {
+  case n {
+    0 -> Nil
+    _ -> count_down(n - 1)
+  }
+}
+"
+    );
+}
+
+#[test]
+fn self_call_with_different_arguments_does_not_warn() {
+    assert_no_warnings!(
+        "
+pub fn loop(a: Int, b: Int) -> Int {
+  loop(b, a)
+}
+"
+    );
+}
+
+#[test]
+fn self_call_with_shadowed_argument_does_not_warn() {
+    assert_no_warnings!(
+        "
+pub fn loop(a: Int) -> Int {
+  let a = a - 1
+  loop(a)
+}
+"
+    );
+}
@@ -155,6 +155,43 @@ pub enum Warning {
 
     InvalidSource {
         path: Utf8PathBuf,
+        /// The name the module would have, given where the file is.
+        module_name: EcoString,
+        /// A path the file could be moved to so that it has a valid name.
+        suggested_path: Option<Utf8PathBuf>,
+    },
+
+    IncompatibleDuplicateRequirements {
+        package: EcoString,
+        dependency_range: EcoString,
+        dev_dependency_range: EcoString,
+    },
+
+    UnboundedRequirement {
+        package: EcoString,
+        range: EcoString,
+        suggestion: EcoString,
+    },
+
+    LocalStdlibOverride {
+        path: Utf8PathBuf,
+    },
+
+    DeepDependencyChain {
+        max_depth: usize,
+        chain: Vec<EcoString>,
+    },
+
+    InsecureRepositoryUrl {
+        url: String,
+    },
+
+    UnusedDependencies {
+        packages: Vec<EcoString>,
+    },
+
+    RetiredPackages {
+        packages: Vec<EcoString>,
     },
 
     DeprecatedSyntax {
@@ -220,17 +257,133 @@ pub enum DeprecatedSyntaxWarning {
 impl Warning {
     pub fn to_diagnostic(&self) -> Diagnostic {
         match self {
-            Warning::InvalidSource { path } => Diagnostic {
+            Warning::InvalidSource {
+                path,
+                module_name,
+                suggested_path,
+            } => Diagnostic {
                 title: "Invalid module name".into(),
-                text: "\
-Module names must begin with a lowercase letter and contain
-only lowercase alphanumeric characters or underscores."
-                    .into(),
+                text: wrap(&format!(
+                    "Gleam modules are named after their path, so the file \
+`{path}` would be the module `{module_name}`, which is not a valid module name. \
+Module names must begin with a lowercase letter and contain only lowercase \
+alphanumeric characters or underscores."
+                )),
+                level: diagnostic::Level::Warning,
+                location: None,
+                hint: Some(match suggested_path {
+                    Some(suggested_path) => format!(
+                        "Rename `{path}` to `{suggested_path}`, or remove this file \
+from the project source."
+                    ),
+                    None => format!(
+                        "Rename `{path}` to be valid, or remove this file from the project source."
+                    ),
+                }),
+            },
+
+            Warning::IncompatibleDuplicateRequirements {
+                package,
+                dependency_range,
+                dev_dependency_range,
+            } => Diagnostic {
+                title: "Incompatible requirements".into(),
+                text: wrap(&format!(
+                    "The package `{package}` is required with the version range \
+`{dependency_range}` in `dependencies` and `{dev_dependency_range}` in \
+`dev-dependencies`. No version can satisfy both of these ranges, so \
+dependency resolution will fail."
+                )),
+                level: diagnostic::Level::Warning,
+                location: None,
+                hint: Some(format!(
+                    "Remove one of the requirements for `{package}` from your gleam.toml."
+                )),
+            },
+
+            Warning::UnboundedRequirement {
+                package,
+                range,
+                suggestion,
+            } => Diagnostic {
+                title: "Unbounded version requirement".into(),
+                text: wrap(&format!(
+                    "The package `{package}` is required with the version range \
+`{range}`, which has no upper bound. Packages that depend on this one could get \
+a future major version of `{package}` with breaking changes."
+                )),
                 level: diagnostic::Level::Warning,
                 location: None,
                 hint: Some(format!(
-                    "Rename `{path}` to be valid, or remove this file from the project source."
+                    "Cap the range below the next major version, such as with \
+`{package} = \"{suggestion}\"` in your gleam.toml."
+                )),
+            },
+
+            Warning::LocalStdlibOverride { path } => Diagnostic {
+                title: "Using a local standard library".into(),
+                text: wrap(&format!(
+                    "The `GLEAM_STDLIB_PATH` environment variable is set, so \
+`gleam_stdlib` is being built from `{path}` instead of the version resolved \
+from Hex. This is intended for developing the standard library only."
                 )),
+                level: diagnostic::Level::Warning,
+                location: None,
+                hint: Some("Unset `GLEAM_STDLIB_PATH` to use the published version.".into()),
+            },
+
+            Warning::DeepDependencyChain { max_depth, chain } => Diagnostic {
+                title: "Deep dependency chain".into(),
+                text: wrap(&format!(
+                    "This chain of dependencies is {} packages deep, more than \
+the `max-dependency-depth` of {max_depth} set in gleam.toml:",
+                    chain.len()
+                )) + "\n\n    "
+                    + &chain.join(" -> "),
+                level: diagnostic::Level::Warning,
+                location: None,
+                hint: Some(
+                    "Deep dependency chains can be a sign of packages with too many \
+dependencies."
+                        .into(),
+                ),
+            },
+
+            Warning::InsecureRepositoryUrl { url } => Diagnostic {
+                title: "Fetching packages over plain HTTP".into(),
+                text: wrap(&format!(
+                    "Insecure HTTP has been allowed, so packages are being fetched \
+from {url} without encryption. Anyone between you and the repository could \
+read or alter the packages downloaded."
+                )),
+                level: diagnostic::Level::Warning,
+                location: None,
+                hint: Some("Use an HTTPS repository URL whenever possible.".into()),
+            },
+
+            Warning::UnusedDependencies { packages } => Diagnostic {
+                title: "Unused dependencies".into(),
+                text: wrap(
+                    "These dependencies have been downloaded but none of their \
+modules are imported by this project:",
+                ) + "\n\n    "
+                    + &packages.join("\n    "),
+                level: diagnostic::Level::Warning,
+                location: None,
+                hint: Some("Remove them with `gleam remove` if they are not needed.".into()),
+            },
+
+            Warning::RetiredPackages { packages } => Diagnostic {
+                title: "Retired package versions".into(),
+                text: wrap(
+                    "These packages are locked to versions that have been retired \
+on Hex, as they were already locked or no other version satisfies the \
+requirements:",
+                ) + "\n\n    "
+                    + &packages.join("\n    "),
+                level: diagnostic::Level::Warning,
+                location: None,
+                hint: Some("Run `gleam deps download --no-retired` to make this an error.".into()),
             },
 
             Warning::DeprecatedSyntax {
@@ -1144,6 +1297,44 @@ information.",
                         extra_labels: Vec::new(),
                     }),
                 },
+
+                type_::Warning::InfiniteRecursion { location } => Diagnostic {
+                    title: "Infinite recursion".into(),
+                    text: wrap(
+                        "This function calls itself with the same arguments it was called \
+with before doing anything that could stop the recursion, so it will never return.",
+                    ),
+                    hint: None,
+                    level: diagnostic::Level::Warning,
+                    location: Some(Location {
+                        path: path.to_path_buf(),
+                        src: src.clone(),
+                        label: diagnostic::Label {
+                            text: Some("This call never returns".into()),
+                            span: *location,
+                        },
+                        extra_labels: Vec::new(),
+                    }),
+                },
+
+                type_::Warning::UnusedPublicFunction { location, name } => Diagnostic {
+                    title: "Unused public function".into(),
+                    text: wrap(&format!(
+                        "This package is an application, so nothing outside of it can \
+use its functions, and no module in the package uses `{name}`."
+                    )),
+                    hint: Some("You can safely remove it.".into()),
+                    level: diagnostic::Level::Warning,
+                    location: Some(Location {
+                        path: path.to_path_buf(),
+                        src: src.clone(),
+                        label: diagnostic::Label {
+                            text: Some("This public function is never used".into()),
+                            span: *location,
+                        },
+                        extra_labels: Vec::new(),
+                    }),
+                },
             },
         }
     }
@@ -42,6 +42,21 @@ function echo$inspectString(str) {
   return new_str;
 }
 
+// Numbers are printed using JavaScript's shortest round-trippable
+// representation. Ints and Floats can't be told apart at runtime, so only
+// non-integral numbers are known to be Floats and have their exponent written
+// the way the Erlang target prints it: `1e-7` becomes `1.0e-7`.
+function echo$inspectNumber(n) {
+  const string = n.toString();
+  if (Number.isInteger(n)) return string;
+  const exponent = string.indexOf("e");
+  if (exponent === -1) return string;
+
+  let mantissa = string.slice(0, exponent);
+  if (!mantissa.includes(".")) mantissa += ".0";
+  return mantissa + "e" + string.slice(exponent + 1);
+}
+
 function echo$inspectDict(map) {
   let body = "dict.from_list([";
   let first = true;
@@ -87,7 +102,8 @@ function echo$inspect(v) {
   if (v === null) return "//js(null)";
   if (v === undefined) return "Nil";
   if (t === "string") return echo$inspectString(v);
-  if (t === "bigint" || t === "number") return v.toString();
+  if (t === "bigint") return v.toString();
+  if (t === "number") return echo$inspectNumber(v);
   if (Array.isArray(v)) return `#(${v.map(echo$inspect).join(", ")})`;
   if (v instanceof $List) return `[${v.toArray().map(echo$inspect).join(", ")}]`;
   if (v instanceof $UtfCodepoint) return `//utfcodepoint(${String.fromCodePoint(v.value)})`;
@@ -1,5 +1,5 @@
 -module('{{ application }}@@main').
--export([run/1]).
+-export([run/1, run_reloading/1]).
 
 -define(red, "\e[31;1m").
 -define(grey, "\e[90m").
@@ -17,6 +17,29 @@ run(Module) ->
             init:stop(1)
     end.
 
+%% Runs the module, loading any modules that are recompiled while it runs in
+%% place of their previous versions, so the program keeps its state.
+run_reloading(Module) ->
+    spawn(fun reload_modified_modules/0),
+    run(Module).
+
+reload_modified_modules() ->
+    timer:sleep(200),
+    lists:foreach(fun reload_module/1, code:modified_modules()),
+    reload_modified_modules().
+
+reload_module(Module) ->
+    code:purge(Module),
+    case code:load_file(Module) of
+        {module, Module} ->
+            Name = string:replace(atom_to_binary(Module), "@", "/", all),
+            io:format(standard_error, "~ts~ts~ts~n", [?grey, ["Reloaded ", Name], ?reset_all]);
+        % The module may still be being written by the compiler, in which case
+        % it is tried again next time.
+        {error, _} ->
+            ok
+    end.
+
 run_module(Module) ->
     try
         {ok, _} = application:ensure_all_started('{{ application }}'),
@@ -64,23 +64,26 @@ export function toList(elements, tail) {
 
 // @internal
 class ListIterator {
-  #current;
-
   constructor(current) {
-    this.#current = current;
+    this[listIteratorCurrent] = current;
   }
 
   next() {
-    if (this.#current instanceof Empty) {
+    if (this[listIteratorCurrent] instanceof Empty) {
       return { done: true };
     } else {
-      let { head, tail } = this.#current;
-      this.#current = tail;
+      let { head, tail } = this[listIteratorCurrent];
+      this[listIteratorCurrent] = tail;
       return { value: head, done: false };
     }
   }
 }
 
+// The prelude only uses ES2020 features, so symbols are used for the state of
+// classes instead of private fields. They are not listed by `Object.keys`, so
+// they are not compared or printed as Gleam values.
+const listIteratorCurrent = Symbol("current");
+
 export class Empty extends List {}
 
 export class NonEmpty extends List {
@@ -95,45 +98,6 @@ export class NonEmpty extends List {
  * A bit array is a contiguous sequence of bits similar to Erlang's Binary type.
  */
 export class BitArray {
-  /**
-   * The size in bits of this bit array's data.
-   *
-   * @type {number}
-   */
-  bitSize;
-
-  /**
-   * The size in bytes of this bit array's data. If this bit array doesn't store
-   * a whole number of bytes then this value is rounded up.
-   *
-   * @type {number}
-   */
-  byteSize;
-
-  /**
-   * The number of unused high bits in the first byte of this bit array's
-   * buffer prior to the start of its data. The value of any unused high bits is
-   * undefined.
-   *
-   * The bit offset will be in the range 0-7.
-   *
-   * @type {number}
-   */
-  bitOffset;
-
-  /**
-   * The raw bytes that hold this bit array's data.
-   *
-   * If `bitOffset` is not zero then there are unused high bits in the first
-   * byte of this buffer.
-   *
-   * If `bitOffset + bitSize` is not a multiple of 8 then there are unused low
-   * bits in the last byte of this buffer.
-   *
-   * @type {Uint8Array}
-   */
-  rawBuffer;
-
   /**
    * Constructs a new bit array from a `Uint8Array`, an optional size in
    * bits, and an optional bit offset.
@@ -155,8 +119,30 @@ export class BitArray {
       );
     }
 
+    /**
+     * The size in bits of this bit array's data.
+     *
+     * @type {number}
+     */
     this.bitSize = bitSize ?? buffer.length * 8;
+
+    /**
+     * The size in bytes of this bit array's data. If this bit array doesn't
+     * store a whole number of bytes then this value is rounded up.
+     *
+     * @type {number}
+     */
     this.byteSize = Math.trunc((this.bitSize + 7) / 8);
+
+    /**
+     * The number of unused high bits in the first byte of this bit array's
+     * buffer prior to the start of its data. The value of any unused high bits
+     * is undefined.
+     *
+     * The bit offset will be in the range 0-7.
+     *
+     * @type {number}
+     */
     this.bitOffset = bitOffset ?? 0;
 
     // Validate the bit size
@@ -176,6 +162,17 @@ export class BitArray {
       throw globalThis.Error("BitArray buffer length is invalid");
     }
 
+    /**
+     * The raw bytes that hold this bit array's data.
+     *
+     * If `bitOffset` is not zero then there are unused high bits in the first
+     * byte of this buffer.
+     *
+     * If `bitOffset + bitSize` is not a multiple of 8 then there are unused low
+     * bits in the last byte of this buffer.
+     *
+     * @type {Uint8Array}
+     */
     this.rawBuffer = buffer;
   }
 
@@ -359,7 +356,7 @@ function bitArrayPrintDeprecationWarning(name, message) {
  * @returns {BitArray}
  */
 export function bitArraySlice(bitArray, start, end) {
-  end ??= bitArray.bitSize;
+  end = end ?? bitArray.bitSize;
 
   bitArrayValidateRange(bitArray, start, end);
 
@@ -1340,7 +1337,7 @@ let utf8Encoder;
  * @returns {Uint8Array}
  */
 export function stringBits(string) {
-  utf8Encoder ??= new TextEncoder();
+  utf8Encoder = utf8Encoder ?? new TextEncoder();
   return utf8Encoder.encode(string);
 }
 
@@ -19,8 +19,12 @@ impl Telemetry for LogTelemetry {
         tracing::info!("Checking package: {}", name);
     }
 
-    fn downloading_package(&self, name: &str) {
-        tracing::info!("Downloading package: {}", name);
+    fn downloading_packages(&self, count: usize) {
+        tracing::info!("Downloading {} packages", count);
+    }
+
+    fn downloaded_package(&self, name: &str, version: &hexpm::version::Version) {
+        tracing::info!("Downloaded package: {} v{}", name, version);
     }
 
     fn running(&self, name: &str) {
@@ -50,6 +50,10 @@ impl FileSystemWriter for WasmFileSystem {
         Ok(())
     }
 
+    fn rename_directory(&self, _: &Utf8Path, _: &Utf8Path) -> Result<(), Error> {
+        Ok(())
+    }
+
     fn mkdir(&self, path: &Utf8Path) -> Result<(), Error> {
         tracing::trace!("mkdir {:?}", path);
         self.imfs.mkdir(path)
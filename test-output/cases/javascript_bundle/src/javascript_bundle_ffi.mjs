@@ -0,0 +1,3 @@
+export function print(value) {
+  console.log(value);
+}
@@ -0,0 +1,5 @@
+import { Square } from "./shapes.mjs";
+
+export function unit() {
+  return new Square(1);
+}
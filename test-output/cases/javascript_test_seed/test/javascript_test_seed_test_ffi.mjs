@@ -0,0 +1,7 @@
+export function test_seed() {
+  return process.env.GLEAM_TEST_SEED ?? "";
+}
+
+export function print(text) {
+  process.stdout.write(text);
+}
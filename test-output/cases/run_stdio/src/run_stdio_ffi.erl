@@ -0,0 +1,16 @@
+-module(run_stdio_ffi).
+-export([read_line/0, print/1, set_exit_code/1]).
+
+read_line() ->
+    case io:get_line("") of
+        Line when is_binary(Line) -> string:trim(Line, trailing, "\n");
+        _ -> <<>>
+    end.
+
+print(Text) ->
+    io:put_chars(Text),
+    nil.
+
+%% The exit code can't be set without stopping, so the program stops here.
+set_exit_code(Code) ->
+    erlang:halt(Code).
@@ -0,0 +1,13 @@
+import { readFileSync } from "node:fs";
+
+export function read_line() {
+  return readFileSync(0, "utf8").split("\n")[0];
+}
+
+export function print(text) {
+  process.stdout.write(text);
+}
+
+export function set_exit_code(code) {
+  process.exitCode = code;
+}
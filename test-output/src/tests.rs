@@ -1,2 +1,10 @@
 #[cfg(test)]
 mod echo;
+#[cfg(test)]
+mod javascript_bundle;
+#[cfg(test)]
+mod javascript_coverage;
+#[cfg(test)]
+mod javascript_test_seed;
+#[cfg(test)]
+mod run_stdio;
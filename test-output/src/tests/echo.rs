@@ -60,6 +60,8 @@ fn run_and_capture_output(
         Some(main_module.into()),
         Which::Src,
         true,
+        None,
+        false,
     )
     .expect("run setup");
 
@@ -151,6 +153,11 @@ fn echo_float() {
     assert_echo!(Target::JavaScript, "echo_float");
 }
 
+#[test]
+fn echo_float_exponent() {
+    assert_echo!(Target::JavaScript, "echo_float_exponent");
+}
+
 #[test]
 fn echo_function() {
     assert_echo!("echo_function");
@@ -0,0 +1,35 @@
+use std::process::Stdio;
+
+use camino::Utf8Path;
+use gleam_core::paths::ProjectPaths;
+
+use gleam_cli::{export, fs};
+
+#[test]
+fn bundle_runs_without_module_loader() {
+    let path = fs::canonicalise(Utf8Path::new("../test-output/cases/javascript_bundle"))
+        .expect("canonicalise path");
+    let paths = ProjectPaths::new(fs::get_project_root(path).expect("project root"));
+    fs::delete_directory(&paths.build_directory()).expect("delete build directory content");
+
+    export::javascript_bundle(&paths, None).expect("export bundle");
+
+    let bundle_path = paths
+        .javascript_bundle_directory()
+        .join("javascript_bundle.js");
+    let bundle = fs::read(&bundle_path).expect("read bundle");
+    assert!(
+        !bundle
+            .lines()
+            .any(|line| line.starts_with("import ") || line.starts_with("export "))
+    );
+    assert!(!bundle.contains("// javascript_bundle/unused.mjs"));
+
+    let output = std::process::Command::new("node")
+        .arg(bundle_path.as_str())
+        .stdin(Stdio::null())
+        .output()
+        .expect("run bundle");
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "11\n");
+}
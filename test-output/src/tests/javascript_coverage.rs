@@ -0,0 +1,69 @@
+use std::process::Stdio;
+
+use camino::Utf8Path;
+use gleam_core::{io::Command, paths::ProjectPaths};
+
+use gleam_cli::{
+    fs,
+    run::{self, Which},
+};
+
+#[test]
+fn tests_write_a_coverage_report_of_the_lines_they_run() {
+    let path = fs::canonicalise(Utf8Path::new("../test-output/cases/javascript_coverage"))
+        .expect("canonicalise path");
+    let paths = ProjectPaths::new(fs::get_project_root(path).expect("project root"));
+    fs::delete_directory(&paths.build_directory()).expect("delete build directory content");
+
+    let Command {
+        program,
+        args,
+        env,
+        cwd: _,
+        stdio: _,
+    } = run::setup(
+        &paths,
+        vec![],
+        None,
+        None,
+        None,
+        Which::Test {
+            seed: Some(1),
+            coverage: true,
+        },
+        true,
+        None,
+        false,
+    )
+    .expect("run setup");
+
+    let output = std::process::Command::new(&program)
+        .args(args)
+        .envs(env.iter().map(|pair| (&pair.0, &pair.1)))
+        .current_dir(paths.root())
+        .stdin(Stdio::null())
+        .output()
+        .expect("run tests");
+    assert!(output.status.success(), "{output:?}");
+
+    let report = fs::read(paths.coverage_report()).expect("read coverage report");
+    assert_eq!(
+        report,
+        "TN:
+SF:src/javascript_coverage.gleam
+DA:2,2
+DA:4,2
+DA:5,2
+DA:8,0
+DA:9,0
+DA:15,0
+DA:16,0
+DA:20,1
+DA:21,1
+DA:22,0
+LF:10
+LH:5
+end_of_record
+"
+    );
+}
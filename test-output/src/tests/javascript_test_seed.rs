@@ -0,0 +1,70 @@
+use std::process::Stdio;
+
+use camino::Utf8Path;
+use gleam_core::{io::Command, paths::ProjectPaths};
+
+use gleam_cli::{
+    fs,
+    run::{self, TEST_SEED_ENVIRONMENT_VARIABLE, Which},
+};
+
+/// Sets up `gleam test` with the given seed, returning the seed it gives to
+/// the test runner and the seed the tests have been run with.
+fn run_tests_with_seed(seed: Option<u64>) -> (String, String) {
+    let path = fs::canonicalise(Utf8Path::new("../test-output/cases/javascript_test_seed"))
+        .expect("canonicalise path");
+    let paths = ProjectPaths::new(fs::get_project_root(path).expect("project root"));
+    fs::delete_directory(&paths.build_directory()).expect("delete build directory content");
+
+    let Command {
+        program,
+        args,
+        env,
+        cwd: _,
+        stdio: _,
+    } = run::setup(
+        &paths,
+        vec![],
+        None,
+        None,
+        None,
+        Which::Test {
+            seed,
+            coverage: false,
+        },
+        true,
+        None,
+        false,
+    )
+    .expect("run setup");
+
+    let given = env
+        .iter()
+        .find(|(name, _)| name == TEST_SEED_ENVIRONMENT_VARIABLE)
+        .map(|(_, value)| value.clone())
+        .expect("test seed environment variable");
+
+    let output = std::process::Command::new(&program)
+        .args(args)
+        .envs(env.iter().map(|pair| (&pair.0, &pair.1)))
+        .current_dir(paths.root())
+        .stdin(Stdio::null())
+        .output()
+        .expect("run tests");
+    assert!(output.status.success(), "{output:?}");
+
+    (given, String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// Both runs are made by one test as they share the project's build directory.
+#[test]
+fn tests_are_run_with_the_given_seed_or_a_random_one() {
+    assert_eq!(
+        run_tests_with_seed(Some(18446744073709551615)),
+        ("18446744073709551615".into(), "18446744073709551615".into())
+    );
+
+    let (given, used) = run_tests_with_seed(None);
+    assert!(given.parse::<u64>().is_ok(), "{given}");
+    assert_eq!(used, given);
+}
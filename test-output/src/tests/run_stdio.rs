@@ -0,0 +1,123 @@
+use std::io::Write;
+use std::process::Stdio;
+
+use camino::Utf8Path;
+use gleam_core::{
+    build::{Mode, Target},
+    io::{self, Command, CommandExecutor},
+    paths::ProjectPaths,
+};
+
+use gleam_cli::{
+    fs::{self, ProjectIO},
+    run::{self, Which},
+};
+
+/// When set, `run_with_inherited_stdio` runs the program for this target
+/// rather than doing nothing, as it does when the tests are run.
+const TARGET_ENV_NAME: &str = "GLEAM_TEST_RUN_STDIO_TARGET";
+
+fn project_paths() -> ProjectPaths {
+    let path = fs::canonicalise(Utf8Path::new("../test-output/cases/run_stdio"))
+        .expect("canonicalise path");
+    ProjectPaths::new(fs::get_project_root(path).expect("project root"))
+}
+
+/// Runs the program as `gleam run` does, connected to the standard streams
+/// of this process, and exits with the program's exit code.
+#[test]
+fn run_with_inherited_stdio() {
+    let target = match std::env::var(TARGET_ENV_NAME).as_deref() {
+        Ok("erlang") => Target::Erlang,
+        Ok("javascript") => Target::JavaScript,
+        _ => return,
+    };
+    let paths = project_paths();
+    let command = run::setup(
+        &paths,
+        vec![],
+        Some(target),
+        None,
+        None,
+        Which::Src,
+        true,
+        None,
+        false,
+    )
+    .expect("run setup");
+    let command = Command {
+        cwd: Some(paths.root().to_path_buf()),
+        stdio: io::Stdio::Inherit,
+        ..command
+    };
+    let exit_code = ProjectIO::new().exec(command).expect("run program");
+    std::process::exit(exit_code);
+}
+
+/// Runs `run_with_inherited_stdio` in another process with the given input
+/// piped to it, returning the exit code and what was written to stdout.
+fn run_piped(target: Target, input: &str) -> (Option<i32>, String) {
+    let mut child = std::process::Command::new(std::env::current_exe().expect("test binary"))
+        .args([
+            "tests::run_stdio::run_with_inherited_stdio",
+            "--exact",
+            "--nocapture",
+            "--quiet",
+        ])
+        .env(TARGET_ENV_NAME, target.to_string())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("spawn test binary");
+    child
+        .stdin
+        .take()
+        .expect("stdin")
+        .write_all(input.as_bytes())
+        .expect("write stdin");
+    let output = child.wait_with_output().expect("run test binary");
+    (
+        output.status.code(),
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+    )
+}
+
+fn assert_stdio_is_connected(target: Target) {
+    let paths = project_paths();
+    fs::delete_directory(&paths.build_directory_for_target(Mode::Dev, target))
+        .expect("delete build directory content");
+
+    // The program reads from stdin and writes to stdout, as in a pipeline.
+    // The test harness writes a header to stdout before the program runs.
+    let (exit_code, stdout) = run_piped(target, "Lucy\n");
+    assert_eq!(exit_code, Some(0), "{stdout}");
+    assert!(stdout.ends_with("\nHello, Lucy!\n"), "{stdout}");
+
+    // Given no input the program exits with a nonzero code, which is returned
+    // to be the exit code of `gleam run`.
+    let (exit_code, stdout) = run_piped(target, "");
+    assert_eq!(exit_code, Some(3), "{stdout}");
+}
+
+#[test]
+fn run_connects_the_standard_streams_and_propagates_the_exit_code() {
+    assert_stdio_is_connected(Target::JavaScript);
+}
+
+#[test]
+fn run_connects_the_standard_streams_and_propagates_the_exit_code_on_erlang() {
+    // Erlang may not be installed where the tests are run.
+    let erlang_installed = ProjectIO::new()
+        .exec(Command {
+            program: "erl".into(),
+            args: vec!["-noshell".into(), "-eval".into(), "halt().".into()],
+            env: vec![],
+            cwd: None,
+            stdio: io::Stdio::Null,
+        })
+        .is_ok();
+    if !erlang_installed {
+        return;
+    }
+    assert_stdio_is_connected(Target::Erlang);
+}
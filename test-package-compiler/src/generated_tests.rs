@@ -89,6 +89,17 @@ fn erlang_import_shadowing_prelude() {
     );
 }
 
+#[rustfmt::skip]
+#[test]
+fn erlang_module_prefix() {
+    let output = crate::prepare("./cases/erlang_module_prefix");
+    insta::assert_snapshot!(
+        "erlang_module_prefix",
+        output,
+        "./cases/erlang_module_prefix",
+    );
+}
+
 #[rustfmt::skip]
 #[test]
 fn erlang_nested() {
@@ -309,6 +320,28 @@ fn unknown_module_field_in_import() {
     );
 }
 
+#[rustfmt::skip]
+#[test]
+fn unused_public_function_application() {
+    let output = crate::prepare("./cases/unused_public_function_application");
+    insta::assert_snapshot!(
+        "unused_public_function_application",
+        output,
+        "./cases/unused_public_function_application",
+    );
+}
+
+#[rustfmt::skip]
+#[test]
+fn unused_public_function_library() {
+    let output = crate::prepare("./cases/unused_public_function_library");
+    insta::assert_snapshot!(
+        "unused_public_function_library",
+        output,
+        "./cases/unused_public_function_library",
+    );
+}
+
 #[rustfmt::skip]
 #[test]
 fn variable_or_module() {
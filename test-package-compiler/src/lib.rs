@@ -28,10 +28,13 @@ pub fn prepare(path: &str) -> String {
                 include_dev_deps: true,
                 package_name_overrides: HashMap::new(),
             }),
+            module_prefix: config.erlang.module_prefix.clone(),
         },
         Target::JavaScript => TargetCodegenConfiguration::JavaScript {
             emit_typescript_definitions: config.javascript.typescript_declarations,
             prelude_location: Utf8PathBuf::from("../prelude.mjs"),
+            ecmascript_version: config.javascript.target_version,
+            coverage: false,
         },
     };
 
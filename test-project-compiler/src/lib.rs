@@ -30,6 +30,11 @@ pub fn prepare(path: &str, mode: Mode) -> String {
         warnings_as_errors: false,
         root_target_support: TargetSupport::Enforced,
         no_print_progress: true,
+        compile_tests: true,
+        changed_modules: None,
+        features: vec![],
+        javascript_target_version: None,
+        coverage: false,
     };
 
     let compiler = ProjectCompiler::new(
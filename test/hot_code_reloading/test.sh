@@ -0,0 +1,56 @@
+#!/bin/sh
+
+set -eu
+
+GLEAM_COMMAND=${GLEAM_COMMAND:-"cargo run --quiet --"}
+MESSAGE_MODULE=src/hot_code_reloading/message.gleam
+
+g() {
+  echo "Running: $GLEAM_COMMAND $@"
+  $GLEAM_COMMAND "$@"
+}
+
+set_message() {
+  printf 'pub fn message() -> String {\n  "%s"\n}\n' "$1" > $MESSAGE_MODULE
+}
+
+wait_for_output() {
+  for _ in $(seq 1 100); do
+    if grep -q "$1" output.txt; then
+      return 0
+    fi
+    sleep 0.2
+  done
+  echo "Expected output containing: $1"
+  cat output.txt
+  exit 1
+}
+
+echo Resetting the build directory to get to a known state
+rm -fr build output.txt
+set_message before
+trap 'set_message before; rm -f output.txt' EXIT
+
+echo Running the program with reloading
+g run --reload --no-print-progress > output.txt &
+PROGRAM=$!
+wait_for_output " before"
+
+echo Changing a module while the program is running
+sleep 1
+set_message after
+
+echo The program should pick up the new code and finish
+wait $PROGRAM
+wait_for_output " after"
+
+echo The program should have kept running rather than being restarted
+if grep -q "^0 after" output.txt; then
+  echo "Expected the program to keep its state"
+  cat output.txt
+  exit 1
+fi
+
+echo
+echo Success! 💖
+echo
@@ -1,7 +1,8 @@
 -module(ffi_erlang).
 
 -export([
-    to_string/1, append/2, print/1, file_exists/1, halt/1, to_dynamic/1
+    to_string/1, append/2, print/1, file_exists/1, halt/1, to_dynamic/1,
+    target/0
 ]).
 
 append(A, B) ->
@@ -23,3 +24,6 @@ halt(Code) ->
 
 to_dynamic(X) ->
     X.
+
+target() ->
+    <<"erlang">>.
@@ -50,3 +50,7 @@ export function halt(code) {
 export function toDynamic(a) {
   return a;
 }
+
+export function target() {
+  return "javascript";
+}